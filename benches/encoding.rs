@@ -0,0 +1,73 @@
+//! Compares encoding into a reused vector (which only allocates once) with
+//! making a new one on each call.
+//!
+//! Run with `cargo bench --features encoding`.
+#![feature(test)]
+
+extern crate test;
+
+use buffers::{base_buffers::HeapBuffer, collections::Vector};
+use test::{black_box, Bencher};
+
+const N: usize = 4096;
+
+fn input() -> Vector<u8, HeapBuffer<u8>> {
+    let mut vec = Vector::new();
+    for i in 0..N {
+        vec.push(i as u8);
+    }
+    vec
+}
+
+#[bench]
+fn hex_into_reused_vector(b: &mut Bencher) {
+    let input = input();
+    let mut out = Vector::<u8, HeapBuffer<u8>>::new();
+    b.iter(|| {
+        out.clear();
+        black_box(&input).encode_hex_into(&mut out);
+        black_box(out.len())
+    });
+}
+
+#[bench]
+fn hex_into_new_vector(b: &mut Bencher) {
+    let input = input();
+    b.iter(|| {
+        let mut out = Vector::<u8, HeapBuffer<u8>>::new();
+        black_box(&input).encode_hex_into(&mut out);
+        out
+    });
+}
+
+#[bench]
+fn hex_into_new_string(b: &mut Bencher) {
+    let input: Vec<u8> = input().iter_copied().collect();
+    b.iter(|| {
+        black_box(&input)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>()
+    });
+}
+
+#[bench]
+fn base64_into_reused_vector(b: &mut Bencher) {
+    let input = input();
+    let mut out = Vector::<u8, HeapBuffer<u8>>::new();
+    b.iter(|| {
+        out.clear();
+        black_box(&input).encode_base64_into(&mut out);
+        black_box(out.len())
+    });
+}
+
+#[bench]
+fn base64_into_new_vector(b: &mut Bencher) {
+    let input = input();
+    b.iter(|| {
+        let mut out = Vector::<u8, HeapBuffer<u8>>::new();
+        black_box(&input).encode_base64_into(&mut out);
+        out
+    });
+}
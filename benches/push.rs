@@ -0,0 +1,59 @@
+//! Compares pushing into vectors with different buffers. `DefaultBuffer` is a
+//! stack of composites on top of a `HeapBuffer`, so once inlined both should
+//! take about the same time (and be close to `Vec`).
+//!
+//! Run with `cargo bench`.
+#![feature(test)]
+
+extern crate test;
+
+use buffers::{base_buffers::HeapBuffer, collections::Vector, DefaultBuffer};
+use test::{black_box, Bencher};
+
+const N: u32 = 1024;
+
+#[bench]
+fn push_default_buffer(b: &mut Bencher) {
+    b.iter(|| {
+        let mut vec = Vector::<u32, DefaultBuffer<u32>>::new();
+        vec.reserve(N as usize);
+        for i in 0..N {
+            vec.push(black_box(i));
+        }
+        vec
+    });
+}
+
+#[bench]
+fn push_heap_buffer(b: &mut Bencher) {
+    b.iter(|| {
+        let mut vec = Vector::<u32, HeapBuffer<u32>>::new();
+        vec.reserve(N as usize);
+        for i in 0..N {
+            vec.push(black_box(i));
+        }
+        vec
+    });
+}
+
+#[bench]
+fn push_std_vec(b: &mut Bencher) {
+    b.iter(|| {
+        let mut vec = Vec::<u32>::with_capacity(N as usize);
+        for i in 0..N {
+            vec.push(black_box(i));
+        }
+        vec
+    });
+}
+
+#[bench]
+fn push_growing_default_buffer(b: &mut Bencher) {
+    b.iter(|| {
+        let mut vec = Vector::<u32, DefaultBuffer<u32>>::new();
+        for i in 0..N {
+            vec.push(black_box(i));
+        }
+        vec
+    });
+}
@@ -0,0 +1,52 @@
+//! Per-access cost of `SvoBuffer` (both while using the small buffer and after
+//! moving into the big one) compared with a plain `HeapBuffer`.
+//!
+//! Storing which buffer is in use in the big buffer's capacity didn't make
+//! accesses measurably faster than the previous `EitherBuffer` layout: both
+//! were within noise of each other (and of `HeapBuffer`).
+//!
+//! Run with `cargo bench`.
+#![feature(test)]
+
+extern crate test;
+
+use buffers::{base_buffers::HeapBuffer, composites::SvoBuffer, interface::Buffer};
+use test::{black_box, Bencher};
+
+const N: usize = 64;
+
+/// Writes and then reads every position of the buffer.
+fn write_and_read<B: Buffer<Element = u64>>(buffer: &mut B) -> u64 {
+    for i in 0..N {
+        // SAFETY: `i` < N <= capacity and it's empty.
+        unsafe { buffer.put(i, black_box(i as u64)) };
+    }
+    let mut sum = 0;
+    for i in 0..N {
+        // SAFETY: `i` was filled above.
+        sum += unsafe { buffer.take(i) };
+    }
+    sum
+}
+
+#[bench]
+fn access_heap_buffer(b: &mut Bencher) {
+    let mut buffer = HeapBuffer::<u64>::new();
+    // SAFETY: N > 0
+    unsafe { buffer.try_grow(N) }.unwrap();
+    b.iter(|| write_and_read(&mut buffer));
+}
+
+#[bench]
+fn access_svo_buffer_small(b: &mut Bencher) {
+    let mut buffer = SvoBuffer::<N, HeapBuffer<u64>>::new();
+    b.iter(|| write_and_read(&mut buffer));
+}
+
+#[bench]
+fn access_svo_buffer_big(b: &mut Bencher) {
+    let mut buffer = SvoBuffer::<1, HeapBuffer<u64>>::new();
+    // SAFETY: N > 1
+    unsafe { buffer.try_grow(N) }.unwrap();
+    b.iter(|| write_and_read(&mut buffer));
+}
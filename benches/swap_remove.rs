@@ -0,0 +1,91 @@
+//! Compares `swap_remove` on rows split into columns (`ArrayBuffer`, which
+//! overrides `Buffer::swap_remove_into` to move each column on its own) with
+//! the previous path (the default `swap_remove_into`: take the whole row, then
+//! move the last one into the hole).
+//!
+//! Filling the vector isn't timed, only the removals. Rows stored whole
+//! (`HeapBuffer`) are there as a reference.
+//!
+//! Run with `cargo bench --bench swap_remove`.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use buffers::{
+    base_buffers::HeapBuffer,
+    collections::Vector,
+    composites::ArrayBuffer,
+    interface::{Buffer, ResizeError},
+};
+
+const N: usize = 4096;
+const COLUMNS: usize = 8;
+const RUNS: usize = 200;
+
+type Row = [u64; COLUMNS];
+type Columns = ArrayBuffer<COLUMNS, HeapBuffer<u64>>;
+
+/// Forwards everything to `B` but `swap_remove_into`, so it uses the default
+/// one (what `Vector::swap_remove` did before it was added).
+#[derive(Default)]
+struct PreviousPath<B>(B);
+
+impl<B: Buffer> Buffer for PreviousPath<B> {
+    type Element = B::Element;
+
+    fn capacity(&self) -> usize {
+        self.0.capacity()
+    }
+
+    unsafe fn take(&mut self, index: usize) -> B::Element {
+        // SAFETY: Same requirements.
+        unsafe { self.0.take(index) }
+    }
+
+    unsafe fn put(&mut self, index: usize, value: B::Element) {
+        // SAFETY: Same requirements.
+        unsafe { self.0.put(index, value) }
+    }
+
+    unsafe fn try_grow(&mut self, target: usize) -> Result<(), ResizeError> {
+        // SAFETY: Same requirements.
+        unsafe { self.0.try_grow(target) }
+    }
+
+    unsafe fn try_shrink(&mut self, target: usize) -> Result<(), ResizeError> {
+        // SAFETY: Same requirements.
+        unsafe { self.0.try_shrink(target) }
+    }
+}
+
+/// Fills a vector (not timed) and then swap removes from the front until it's
+/// empty. Gives the time of the removals.
+fn time_removals<B: Buffer<Element = Row> + Default>() -> Duration {
+    let mut vec = Vector::<Row, B>::new();
+    vec.try_reserve_exact(N).unwrap();
+    for i in 0..N {
+        vec.push([black_box(i as u64); COLUMNS]);
+    }
+
+    let start = Instant::now();
+    while !vec.is_empty() {
+        black_box(vec.swap_remove(0));
+    }
+    start.elapsed()
+}
+
+/// Prints the median time per removal over [`RUNS`] runs.
+fn bench<B: Buffer<Element = Row> + Default>(name: &str) {
+    let mut runs: Vec<_> = (0..RUNS).map(|_| time_removals::<B>()).collect();
+    runs.sort();
+    let median = runs[RUNS / 2].as_nanos() as f64 / N as f64;
+    println!("{name:<30} {median:>6.2} ns/removal");
+}
+
+fn main() {
+    bench::<Columns>("columns (swap_remove_into)");
+    bench::<PreviousPath<Columns>>("columns (previous path)");
+    bench::<HeapBuffer<Row>>("rows");
+}
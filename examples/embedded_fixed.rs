@@ -0,0 +1,81 @@
+//! Sensor readings on a device without a heap: everything lives in
+//! `FixedVector`s (an `InlineBuffer` of a fixed capacity), so nothing is ever
+//! allocated. When a vector is full, pushing gives an error instead of
+//! allocating (or aborting), and the program decides what to do.
+//!
+//! The functions besides `main` only use `core` functionality, so the same
+//! code works in `no_std` firmware.
+
+use buffers::collections::FixedVector;
+
+/// Readings kept to compute the moving average.
+const WINDOW: usize = 4;
+
+/// Latest readings, oldest first.
+type Window = FixedVector<u16, WINDOW>;
+
+/// Adds a reading, dropping the oldest one when the window is full.
+fn record(window: &mut Window, reading: u16) {
+    if window.try_push(reading).is_err() {
+        window.remove(0);
+        window.push(reading);
+    }
+}
+
+/// Average of the readings in the window (0 if there are none).
+fn moving_average(window: &Window) -> u16 {
+    if window.is_empty() {
+        return 0;
+    }
+    let sum: u32 = window.as_slice().iter().map(|&r| u32::from(r)).sum();
+    (sum / window.len() as u32) as u16
+}
+
+/// Keeps the readings over `threshold` into `alarms`, as many as fit. Gives
+/// how many didn't fit.
+fn collect_alarms<const N: usize>(
+    readings: &[u16],
+    threshold: u16,
+    alarms: &mut FixedVector<u16, N>,
+) -> usize {
+    let mut lost = 0;
+    for &reading in readings.iter().filter(|&&r| r > threshold) {
+        if alarms.try_push(reading).is_err() {
+            lost += 1;
+        }
+    }
+    lost
+}
+
+fn main() {
+    let readings = [510, 520, 900, 530, 950, 990, 540];
+    let mut window = Window::new();
+    for reading in readings {
+        record(&mut window, reading);
+        println!("reading {reading}, average {}", moving_average(&window));
+    }
+
+    let mut alarms = FixedVector::<u16, 2>::new();
+    let lost = collect_alarms(&readings, 800, &mut alarms);
+    println!("alarms {:?} ({lost} lost)", alarms.as_slice());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_vectors_never_allocate() {
+        let mut window = Window::new();
+        for reading in [1, 2, 3, 4, 5, 6] {
+            record(&mut window, reading);
+        }
+        assert_eq!(window.as_slice(), [3, 4, 5, 6]);
+        assert_eq!(window.capacity(), WINDOW);
+        assert_eq!(moving_average(&window), 4);
+
+        let mut alarms = FixedVector::<u16, 1>::new();
+        assert_eq!(collect_alarms(&[10, 20, 30], 15, &mut alarms), 1);
+        assert_eq!(alarms.as_slice(), [20]);
+    }
+}
@@ -0,0 +1,63 @@
+//! Request handling where every request needs scratch buffers. They are lent
+//! by a `VectorPool`, so once the pool is warm no request allocates: the
+//! buffers go back into the pool (keeping their capacity) when dropped.
+//!
+//! Small pieces of data (like the headers) use a `SmallVector`, which keeps
+//! them inline until they don't fit.
+
+use buffers::{
+    base_buffers::HeapBuffer,
+    collections::{pool::VectorPool, SmallVector},
+};
+
+/// Pool of byte buffers shared by all the requests.
+type BufferPool = VectorPool<u8, HeapBuffer<u8>>;
+
+/// Parses a request like `"GET /path\nheader: value\n..."`, and answers with
+/// the path in uppercase and the number of headers.
+fn handle(pool: &BufferPool, request: &str) -> String {
+    let mut lines = request.lines();
+    let path = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap_or("/");
+
+    let mut headers = SmallVector::<&str, 8>::new();
+    headers.extend(lines.filter_map(|line| line.split_once(':').map(|(name, _)| name)));
+
+    let mut body = pool.get();
+    body.extend_from_slice(path.as_bytes());
+    body.as_mut_slice().make_ascii_uppercase();
+    body.extend_from_slice(format!(" ({} headers)", headers.len()).as_bytes());
+    String::from_utf8_lossy(body.as_slice()).into_owned()
+}
+
+fn main() {
+    let pool = BufferPool::new();
+    let requests = [
+        "GET /index\nhost: example.com\naccept: */*",
+        "GET /about\nhost: example.com",
+        "POST /form\nhost: example.com\ncontent-length: 3\ncontent-type: text/plain",
+    ];
+    for request in requests {
+        println!("{}", handle(&pool, request));
+    }
+    println!("{} buffer(s) idle in the pool", pool.idle());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffers_are_reused_between_requests() {
+        let pool = BufferPool::new();
+        assert_eq!(handle(&pool, "GET /a\nx: 1"), "/A (1 headers)");
+        assert_eq!(pool.idle(), 1);
+
+        let capacity = pool.get().capacity();
+        assert_eq!(handle(&pool, "GET /b"), "/B (0 headers)");
+        assert_eq!(pool.idle(), 1);
+        assert_eq!(pool.get().capacity(), capacity);
+    }
+}
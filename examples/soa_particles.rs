@@ -0,0 +1,72 @@
+//! Particle simulation stored as a structure of arrays: each field of the
+//! particles lives in its own contiguous column, so updating one field only
+//! touches the memory of that field.
+//!
+//! The composition is an `ArrayBuffer` (one lane per field) of growable heap
+//! buffers that grow exponentially.
+
+use buffers::{
+    base_buffers::HeapBuffer,
+    collections::Vector,
+    composites::{ArrayBuffer, ExponentialGrowthBuffer},
+};
+
+/// Each particle is `[x, y, speed_x, speed_y]`.
+type Particles = Vector<[f32; 4], ArrayBuffer<4, ExponentialGrowthBuffer<HeapBuffer<f32>>>>;
+
+/// Spawns `count` particles on a line, moving up at different speeds.
+fn spawn(count: usize) -> Particles {
+    let mut particles = Particles::new();
+    for i in 0..count {
+        let i = i as f32;
+        particles.push([i, 0.0, 0.0, 1.0 + i]);
+    }
+    particles
+}
+
+/// Moves every particle by its speed for `dt` seconds, with gravity.
+fn step(particles: &mut Particles, dt: f32) {
+    const GRAVITY: f32 = -9.8;
+    particles.for_each_row(|[x, y, speed_x, speed_y]| {
+        *speed_y += GRAVITY * dt;
+        *x += *speed_x * dt;
+        *y += *speed_y * dt;
+    });
+}
+
+/// Average height, reading only the `y` column.
+fn average_height(particles: &Particles) -> f32 {
+    let [_, ys, _, _] = particles.columns();
+    ys.iter().sum::<f32>() / ys.len() as f32
+}
+
+fn main() {
+    let mut particles = spawn(8);
+    for frame in 0..5 {
+        step(&mut particles, 0.1);
+        println!(
+            "frame {frame}: average height {:.3}",
+            average_height(&particles)
+        );
+    }
+    // Particles which fell below the ground are removed.
+    particles.retain(|[_, y, _, _]| *y >= 0.0);
+    println!("{} particles left", particles.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_are_stored_in_columns() {
+        let mut particles = spawn(3);
+        step(&mut particles, 1.0);
+
+        let [xs, ys, _, speeds_y] = particles.columns();
+        assert_eq!(xs, [0.0, 1.0, 2.0]);
+        assert_eq!(speeds_y, [-8.8, -7.8, -6.8]);
+        assert_eq!(ys, speeds_y);
+        assert_eq!(particles.capacity(), 4);
+    }
+}
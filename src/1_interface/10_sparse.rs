@@ -0,0 +1,219 @@
+use std::ops::Range;
+
+use super::Buffer;
+
+/// Trait for buffers which know which of their positions are filled.
+///
+/// Usually a collection keeps track of it (eg. a vector knows that everything
+/// before its length is filled), but some layouts need the buffer itself to do
+/// it, like a slab with holes or a buffer of optional values with a validity
+/// bitmap. This trait is the common way of asking them, so collections can
+/// visit only the filled positions without knowing how it's tracked.
+///
+/// Only [`SparseBuffer::is_filled`] is required. The rest have default
+/// implementations that check position by position, which buffers may override
+/// when they can find whole runs at once (eg. by checking a bitmap a word at a
+/// time).
+///
+/// Composites don't get it through [`super::indirect_buffer::IndirectBuffer`]
+/// (they could change which positions are filled), so each one which keeps
+/// the positions of its inner buffer implements it explicitly.
+///
+/// # Safety
+/// Unsafe code relies on it to take, drop or index only the filled positions,
+/// so it must be exact:
+///   1. [`SparseBuffer::is_filled`] must be `true` if and only if the position
+///      is filled (and thus `false` for positions out of the capacity).
+///   2. If [`SparseBuffer::next_filled_run`] is overridden, it must give the
+///      same runs as the default implementation.
+pub unsafe trait SparseBuffer: Buffer {
+    /// Whether the position `index` is filled. It's `false` for positions out
+    /// of the capacity.
+    fn is_filled(&self, index: usize) -> bool;
+
+    /// Finds the first run of contiguous filled positions which starts at
+    /// `from` or after it. Returns `None` if there are no more.
+    ///
+    /// The run is as long as possible: the position right after it is empty
+    /// (or out of the capacity).
+    fn next_filled_run(&self, from: usize) -> Option<Range<usize>> {
+        let capacity = self.capacity();
+        let start = (from..capacity).find(|&index| self.is_filled(index))?;
+        let end = (start + 1..capacity)
+            .find(|&index| !self.is_filled(index))
+            .unwrap_or(capacity);
+        Some(start..end)
+    }
+
+    /// Iterates over the runs of contiguous filled positions, in order.
+    fn filled_runs(&self) -> FilledRuns<'_, Self> {
+        FilledRuns {
+            buffer: self,
+            next: 0,
+        }
+    }
+
+    /// Iterates over the filled positions (and only those), in order.
+    fn filled_positions(&self) -> FilledPositions<'_, Self> {
+        FilledPositions {
+            runs: self.filled_runs(),
+            current: 0..0,
+        }
+    }
+}
+
+// SAFETY: A box keeps the positions of the buffer it holds.
+unsafe impl<B: SparseBuffer + ?Sized> SparseBuffer for Box<B> {
+    #[inline(always)]
+    fn is_filled(&self, index: usize) -> bool {
+        (**self).is_filled(index)
+    }
+
+    #[inline(always)]
+    fn next_filled_run(&self, from: usize) -> Option<Range<usize>> {
+        (**self).next_filled_run(from)
+    }
+}
+
+/// Iterator over the runs of filled positions of a [`SparseBuffer`]. See
+/// [`SparseBuffer::filled_runs`].
+pub struct FilledRuns<'a, B: SparseBuffer + ?Sized> {
+    buffer: &'a B,
+    // Where to start looking for the next run.
+    next: usize,
+}
+
+impl<B: SparseBuffer + ?Sized> Iterator for FilledRuns<'_, B> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let run = self.buffer.next_filled_run(self.next)?;
+        self.next = run.end;
+        Some(run)
+    }
+}
+
+/// Iterator over the filled positions of a [`SparseBuffer`]. See
+/// [`SparseBuffer::filled_positions`].
+pub struct FilledPositions<'a, B: SparseBuffer + ?Sized> {
+    runs: FilledRuns<'a, B>,
+    // Positions of the current run that still have to be yielded.
+    current: Range<usize>,
+}
+
+impl<B: SparseBuffer + ?Sized> Iterator for FilledPositions<'_, B> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current.is_empty() {
+            self.current = self.runs.next()?;
+        }
+        self.current.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        base_buffers::InlineBuffer,
+        composites::{AtLeastBuffer, ExponentialGrowthBuffer},
+        interface::resize_error::ResizeError,
+    };
+
+    use super::*;
+
+    /// Buffer that keeps track of which positions are filled.
+    struct TrackingBuffer {
+        buffer: InlineBuffer<u32, 8>,
+        filled: [bool; 8],
+    }
+
+    impl TrackingBuffer {
+        fn with_filled(positions: &[usize]) -> Self {
+            let mut buffer = Self {
+                buffer: InlineBuffer::new(),
+                filled: [false; 8],
+            };
+            for &index in positions {
+                // SAFETY: All positions in the test are valid and different.
+                unsafe { buffer.put(index, index as u32) };
+            }
+            buffer
+        }
+    }
+
+    impl Buffer for TrackingBuffer {
+        type Element = u32;
+
+        fn capacity(&self) -> usize {
+            self.buffer.capacity()
+        }
+
+        unsafe fn take(&mut self, index: usize) -> u32 {
+            self.filled[index] = false;
+            // SAFETY: Same requirements.
+            unsafe { self.buffer.take(index) }
+        }
+
+        unsafe fn put(&mut self, index: usize, value: u32) {
+            self.filled[index] = true;
+            // SAFETY: Same requirements.
+            unsafe { self.buffer.put(index, value) }
+        }
+
+        unsafe fn manually_drop(&mut self, index: usize) {
+            self.filled[index] = false;
+            // SAFETY: Same requirements.
+            unsafe { self.buffer.manually_drop(index) }
+        }
+
+        unsafe fn try_grow(&mut self, _target: usize) -> Result<(), ResizeError> {
+            Err(ResizeError::UnsupportedOperation)
+        }
+
+        unsafe fn try_shrink(&mut self, _target: usize) -> Result<(), ResizeError> {
+            Err(ResizeError::UnsupportedOperation)
+        }
+    }
+
+    // SAFETY: `filled` is updated every time a position is filled or emptied.
+    unsafe impl SparseBuffer for TrackingBuffer {
+        fn is_filled(&self, index: usize) -> bool {
+            self.filled.get(index).copied().unwrap_or(false)
+        }
+    }
+
+    #[test]
+    fn finds_the_runs_of_filled_positions() {
+        let buffer = TrackingBuffer::with_filled(&[0, 1, 4, 6, 7]);
+        assert_eq!(buffer.filled_runs().collect::<Vec<_>>(), [0..2, 4..5, 6..8]);
+        assert_eq!(buffer.next_filled_run(1), Some(1..2));
+        assert_eq!(buffer.next_filled_run(8), None);
+    }
+
+    #[test]
+    fn only_visits_filled_positions() {
+        let mut buffer = TrackingBuffer::with_filled(&[2, 3, 5]);
+        assert_eq!(buffer.filled_positions().collect::<Vec<_>>(), [2, 3, 5]);
+
+        // SAFETY: 3 is filled.
+        assert_eq!(unsafe { buffer.take(3) }, 3);
+        assert_eq!(buffer.filled_positions().collect::<Vec<_>>(), [2, 5]);
+    }
+
+    #[test]
+    fn composites_which_keep_the_positions_forward_it() {
+        let buffer = AtLeastBuffer::<8, _>::from(TrackingBuffer::with_filled(&[1, 2, 5]));
+        assert_eq!(buffer.filled_runs().collect::<Vec<_>>(), [1..3, 5..6]);
+        let buffer = Box::new(ExponentialGrowthBuffer::from(buffer));
+        assert!(buffer.is_filled(2));
+        assert!(!buffer.is_filled(3));
+    }
+
+    #[test]
+    fn empty_buffers_have_no_runs() {
+        let buffer = TrackingBuffer::with_filled(&[]);
+        assert_eq!(buffer.filled_runs().next(), None);
+        assert_eq!(buffer.filled_positions().next(), None);
+    }
+}
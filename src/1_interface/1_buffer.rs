@@ -1,8 +1,12 @@
+use std::alloc::Layout;
 use std::ops::Bound::*;
 use std::ops::Range;
 use std::ops::RangeBounds;
 
+use super::capacity_contract::CapacityContract;
+use super::caps::BufferCaps;
 use super::resize_error::ResizeError;
+use super::shift;
 
 /// Trait that represents a layout of data for a collection. This abstraction is
 /// very low level and only manages the "space" itself, and not the values which
@@ -34,9 +38,46 @@ pub trait Buffer {
     /// Type of elements this buffer holds.
     type Element;
 
+    /// What the capacity is after growing successfully (see
+    /// [`CapacityContract`]). Composites derive it from their inner buffers.
+    ///
+    /// By default it's [`CapacityContract::RoundedUp`], which is true of any
+    /// buffer. Collections check it in debug builds.
+    const CAPACITY_CONTRACT: CapacityContract = CapacityContract::RoundedUp;
+
     /// How many elements can this buffer contain.
     fn capacity(&self) -> usize;
 
+    /// Memory layout of each element. By default, the one of
+    /// [`Buffer::Element`].
+    #[inline]
+    fn element_layout(&self) -> Layout {
+        Layout::new::<Self::Element>()
+    }
+
+    /// How many bytes of memory this buffer uses to hold its positions (filled
+    /// or not), whether they are allocated or inline. It's meant to account
+    /// the memory used by collections.
+    ///
+    /// By default, it's the capacity times the size of an element. Buffers
+    /// that reserve more than that (eg. whole pages) or that are made of
+    /// several inner buffers give the sum of everything they hold.
+    #[inline]
+    fn allocated_bytes(&self) -> usize {
+        self.capacity().saturating_mul(self.element_layout().size())
+    }
+
+    /// Capabilities this buffer has right now, for code that cannot name the
+    /// marker traits (see [`BufferCaps`]). Composites combine the ones of
+    /// their inner buffers.
+    ///
+    /// By default it has none, which is always correct: it only means that
+    /// generic code takes the slowest path.
+    #[inline]
+    fn caps(&self) -> BufferCaps {
+        BufferCaps::empty()
+    }
+
     /// Reads the `index` position in the buffer, emptying it.
     ///
     /// # Safety
@@ -66,16 +107,89 @@ pub trait Buffer {
         let _ = unsafe { self.take(index) };
     }
 
+    /// Reads the `index` position and moves the value of `last_index` into it
+    /// (the "swap remove" of a collection). `last_index` ends up empty, or
+    /// `index` if they are the same.
+    ///
+    /// By default it's a [`Buffer::take`] followed by a move. Buffers made of
+    /// several inner ones (eg. columns) can do it in each of them instead of
+    /// moving the whole value twice.
+    ///
+    /// # Safety
+    ///   * `index` and `last_index` must be less than `capacity`.
+    ///   * Both positions must be filled.
+    unsafe fn swap_remove_into(&mut self, index: usize, last_index: usize) -> Self::Element {
+        // SAFETY: The caller ensures that it's valid and filled.
+        let value = unsafe { self.take(index) };
+        // Move only when necessary
+        if index != last_index {
+            // SAFETY: The caller ensures that it's valid and filled.
+            let last = unsafe { self.take(last_index) };
+            // SAFETY: `index` was just emptied.
+            unsafe { self.put(index, last) };
+        }
+        value
+    }
+
     /// Asks the buffer to grow.
     ///
     /// This operation may fail a number of ways depending on the implementation
     /// and `Self::Element`. See [`ResizeError`] for more details.
     ///
+    /// When it fails, the capacity must stay the same. Composites with several
+    /// inner buffers undo what they already grew (see
+    /// [`crate::composites::lanes`]).
+    ///
     /// # Safety
     ///   * Target size must be bigger than the current capacity (and thus, also
     ///     bigger than zero)
     unsafe fn try_grow(&mut self, target: usize) -> Result<(), ResizeError>;
 
+    /// Asks the buffer to grow to `preferred` positions, settling for `min`
+    /// if it cannot. Returns the capacity it reached, which is at least `min`.
+    ///
+    /// When it fails, the capacity stays the same (like
+    /// [`Buffer::try_grow`]), so callers which can make do with less may ask
+    /// for a smaller `min`.
+    ///
+    /// By default it tries to grow to `preferred` and then to `min`. Buffers
+    /// which know how much they can get may ask for it directly.
+    ///
+    /// # Safety
+    ///   * `min` must be bigger than the current capacity.
+    ///   * `preferred` must be at least `min`.
+    unsafe fn try_grow_at_least(
+        &mut self,
+        min: usize,
+        preferred: usize,
+    ) -> Result<usize, ResizeError> {
+        debug_assert!(preferred >= min, "The preferred capacity is too small");
+        // SAFETY: `preferred` is at least `min`, which the caller ensures is
+        // bigger than the capacity.
+        let result = unsafe { self.try_grow(preferred) };
+        if result.is_err() && preferred > min {
+            // SAFETY: The capacity didn't change when it failed.
+            unsafe { self.try_grow(min) }?;
+        } else {
+            result?;
+        }
+        Ok(self.capacity())
+    }
+
+    /// Capacity to ask for (with [`Buffer::try_grow`]) when at least `required`
+    /// positions are needed and it has `current`. It's the growth policy of the
+    /// buffer: collections use it when reserving (unless asked for an exact
+    /// size).
+    ///
+    /// It should return at least `required`. By default, it's exactly that.
+    /// Since it's a safe method, callers can't rely on it: they must not ask
+    /// for less than `required` even if it does.
+    #[inline]
+    fn preferred_grow_target(&self, current: usize, required: usize) -> usize {
+        let _ = current;
+        required
+    }
+
     /// Asks the buffer to shrink.
     ///
     /// This operation may fail a number of ways depending on the implementation
@@ -89,14 +203,46 @@ pub trait Buffer {
     /// Utility method which drops elements (and thus empties) a range of
     /// positions.
     ///
+    /// If dropping one of the values panics, the rest are still dropped (like
+    /// dropping a slice does). All positions end up empty either way.
+    ///
     /// # Safety
     ///   * All the positions in `values_range` must be valid and filled.
     unsafe fn manually_drop_range<R: RangeBounds<usize> + Clone>(&mut self, values_range: R) {
-        for index in clamp_buffer_range(self, values_range) {
-            // SAFETY: All positions should fulfill the requirements as per
-            // this function documentation.
-            unsafe { self.manually_drop(index) };
+        let mut guard = DropRangeGuard {
+            remaining: normalize_range(values_range, self.capacity()),
+            buffer: self,
+        };
+        guard.drop_remaining();
+    }
+
+    /// Utility method which fills all the positions of a range with clones of
+    /// `value`.
+    ///
+    /// If cloning panics, the clones already written are dropped, so all the
+    /// positions end up empty. Contiguous buffers write them straight into
+    /// memory instead (see
+    /// [`super::contiguous_memory::fill_range_in_place`]).
+    ///
+    /// # Safety
+    ///   * All the positions in `range` must be valid and empty.
+    unsafe fn fill_range<R: RangeBounds<usize> + Clone>(&mut self, range: R, value: &Self::Element)
+    where
+        Self::Element: Clone,
+    {
+        let range = normalize_range(range, self.capacity());
+        // Until it's done, the guard drops the values filled so far.
+        let mut guard = DropRangeGuard {
+            remaining: range.start..range.start,
+            buffer: self,
+        };
+        for index in range {
+            // SAFETY: `fill_range` requires all the positions in the range to
+            // be valid and empty.
+            unsafe { guard.buffer.put(index, value.clone()) };
+            guard.remaining.end = index + 1;
         }
+        guard.remaining.start = guard.remaining.end;
     }
 
     /// Utility method to move elements to the right by `positions`.
@@ -106,12 +252,9 @@ pub trait Buffer {
     ///   * `positions` positions after the `to_move` range must be valid and
     ///     empty.
     unsafe fn shift_right<R: RangeBounds<usize> + Clone>(&mut self, to_move: R, positions: usize) {
-        let range = clamp_buffer_range(self, to_move);
+        let range = normalize_range(to_move, self.capacity());
 
-        debug_assert!(range.end + positions <= self.capacity());
-
-        for old_pos in range.into_iter().rev() {
-            let new_pos = old_pos + positions;
+        for (old_pos, new_pos) in shift::right_moves(range, positions, self.capacity()) {
             // SAFETY: This function requirements ensure that `to_move` (`range`
             // after clamp) has all values be valid. We are moving values before
             // overriding, ensuring that the value is still valid.
@@ -132,12 +275,9 @@ pub trait Buffer {
     ///   * `positions` positions before the `to_move` range must be valid and
     ///     empty.
     unsafe fn shift_left<R: RangeBounds<usize> + Clone>(&mut self, to_move: R, positions: usize) {
-        let range = clamp_buffer_range(self, to_move);
-
-        debug_assert!(range.end >= positions);
+        let range = normalize_range(to_move, self.capacity());
 
-        for old_pos in range.into_iter() {
-            let new_pos = old_pos - positions;
+        for (old_pos, new_pos) in shift::left_moves(range, positions) {
             // SAFETY: This function requirements ensure that `to_move` (`range`
             // after clamp) has all values be valid. We are moving values before
             // overriding, ensuring that the value is still valid.
@@ -152,21 +292,66 @@ pub trait Buffer {
     }
 }
 
-/// Utility function that clamps a range into a buffer cappacity. Allows for
-/// open ended ranges in the ranged utility functions.
-fn clamp_buffer_range<B: Buffer + ?Sized, R: RangeBounds<usize> + Clone>(
-    buffer: &B,
-    range: R,
-) -> Range<usize> {
+/// Internal guard which drops a range of values. If dropping one of them
+/// panics, it keeps dropping the rest while unwinding. It's also used to drop
+/// the values written so far when filling a range panics.
+struct DropRangeGuard<'a, B: Buffer + ?Sized> {
+    buffer: &'a mut B,
+    remaining: Range<usize>,
+}
+
+impl<B: Buffer + ?Sized> DropRangeGuard<'_, B> {
+    fn drop_remaining(&mut self) {
+        // The index is removed from `remaining` before dropping, so a panicking
+        // value isn't dropped twice.
+        for index in self.remaining.by_ref() {
+            // SAFETY: `manually_drop_range` requires all the positions in the
+            // range to be valid and filled.
+            unsafe { self.buffer.manually_drop(index) };
+        }
+    }
+}
+
+impl<B: Buffer + ?Sized> Drop for DropRangeGuard<'_, B> {
+    fn drop(&mut self) {
+        // Only does something if a value panicked while being dropped.
+        self.drop_remaining();
+    }
+}
+
+/// Turns any range of positions (eg. `1..=3` or `2..`) into a [`Range`],
+/// using `capacity` as the end when it's unbounded. It allows for open ended
+/// ranges in the ranged utility functions.
+///
+/// It doesn't validate the range: the end may be bigger than `capacity`, and
+/// if the start is bigger than the end the range is empty.
+///
+/// # Panics
+/// If an excluded start or an included end is `usize::MAX` (like
+/// slicing does), since the range can't be represented.
+///
+/// # Example
+/// ```
+/// # use std::ops::Bound;
+/// # use buffers::interface::buffer::normalize_range;
+/// assert_eq!(normalize_range(1..=3, 10), 1..4);
+/// assert_eq!(normalize_range(2.., 10), 2..10);
+/// assert_eq!(normalize_range((Bound::Excluded(2), Bound::Unbounded), 10), 3..10);
+/// ```
+pub fn normalize_range<R: RangeBounds<usize>>(range: R, capacity: usize) -> Range<usize> {
     let start: usize = match range.start_bound() {
         Included(index) => *index,
-        Excluded(index) => *index + 1,
+        Excluded(index) => index
+            .checked_add(1)
+            .expect("attempted to index from after maximum usize"),
         Unbounded => 0,
     };
     let end: usize = match range.end_bound() {
-        Included(index) => *index + 1,
+        Included(index) => index
+            .checked_add(1)
+            .expect("attempted to index up to maximum usize"),
         Excluded(index) => *index,
-        Unbounded => buffer.capacity(),
+        Unbounded => capacity,
     };
     start..end
 }
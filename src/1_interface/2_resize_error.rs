@@ -1,5 +1,12 @@
+use std::{alloc::Layout, mem};
+
 /// Errors that may happen when attempting to resize a buffer.
+///
+/// More reasons may be added in the future, so matches on it need a wildcard
+/// arm.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
 pub enum ResizeError {
     /// The underlying mechanism to aquire memory cannot aquire more.
     ///
@@ -13,6 +20,25 @@ pub enum ResizeError {
     /// the architecture.
     TheoreticalLimitSurpassed,
 
+    /// The size in bytes of the requested number of elements overflows
+    /// `isize`, so no memory was requested (unlike
+    /// [`ResizeError::OutOfMemory`]). Retrying with the same number fails the
+    /// same way: cap the requests instead.
+    ///
+    /// For example: growing a [`crate::base_buffers::HeapBuffer<u64>`] to
+    /// `usize::MAX / 2` elements.
+    LayoutOverflow {
+        /// Number of elements that was requested.
+        requested: usize,
+    },
+
+    /// The number of elements requested doesn't even fit in a `usize`, so
+    /// nothing was requested to the buffer.
+    ///
+    /// For example: reserving `usize::MAX` more elements in a vector which
+    /// isn't empty.
+    CapacityOverflow,
+
     /// This buffer cannot perform the specified resizing operation due to its
     /// properties.
     ///
@@ -41,6 +67,33 @@ impl From<std::alloc::LayoutError> for ResizeError {
     }
 }
 
+/// Most bytes that a buffer may allocate, like in `std`: offsets inside an
+/// allocation must fit in an `isize`.
+const MAX_ALLOCATION_SIZE: usize = isize::MAX as usize;
+
+/// Internal utility that gives the size in bytes of an array of `requested`
+/// elements placed `stride` bytes apart, or [`ResizeError::LayoutOverflow`] if
+/// it's more than any allocation may have (`isize::MAX` bytes).
+///
+/// Every buffer that allocates an array should use it (directly or with
+/// [`array_layout`]), so all of them fail the same way.
+pub(crate) fn array_size(stride: usize, requested: usize) -> Result<usize, ResizeError> {
+    stride
+        .checked_mul(requested)
+        .filter(|&size| size <= MAX_ALLOCATION_SIZE)
+        .ok_or(ResizeError::LayoutOverflow { requested })
+}
+
+/// Internal utility that makes the layout of an array of `requested` values of
+/// `T`, or [`ResizeError::LayoutOverflow`] if it's too big (see
+/// [`array_size`]).
+pub(crate) fn array_layout<T>(requested: usize) -> Result<Layout, ResizeError> {
+    let size = array_size(mem::size_of::<T>(), requested)?;
+    // The size is a multiple of the alignment, so it never needs padding.
+    Layout::from_size_align(size, mem::align_of::<T>())
+        .map_err(|_| ResizeError::LayoutOverflow { requested })
+}
+
 /// Automatic transformation from [`std::alloc::AllocError`] to
 /// [`ResizeError`].
 ///
@@ -52,3 +105,35 @@ impl From<std::alloc::AllocError> for ResizeError {
         Self::UndistinguishableError
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arrays_can_have_up_to_isize_max_bytes() {
+        assert_eq!(
+            array_size(1, MAX_ALLOCATION_SIZE).unwrap(),
+            MAX_ALLOCATION_SIZE
+        );
+        assert_eq!(array_size(0, usize::MAX).unwrap(), 0);
+        assert!(matches!(
+            array_size(1, MAX_ALLOCATION_SIZE + 1),
+            Err(ResizeError::LayoutOverflow { requested }) if requested == MAX_ALLOCATION_SIZE + 1
+        ));
+        assert!(array_size(2, MAX_ALLOCATION_SIZE / 2 + 1).is_err());
+        assert!(array_size(3, usize::MAX / 2).is_err());
+    }
+
+    #[test]
+    fn array_layouts_match_std() {
+        for requested in [0, 1, 7, MAX_ALLOCATION_SIZE / 8] {
+            assert_eq!(
+                array_layout::<u64>(requested).unwrap(),
+                Layout::array::<u64>(requested).unwrap()
+            );
+        }
+        assert!(array_layout::<u64>(MAX_ALLOCATION_SIZE / 8 + 1).is_err());
+        assert!(array_layout::<()>(usize::MAX).is_ok());
+    }
+}
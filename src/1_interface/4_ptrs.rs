@@ -37,4 +37,18 @@ pub trait PtrBuffer: Buffer {
     /// Calling the function multiple times with the same `index` results in the
     /// same value.
     unsafe fn mut_ptr(&mut self, index: usize) -> Self::MutablePointer;
+
+    /// Get how many positions, starting at `index`, are stored next to each
+    /// other in the same allocation, so the pointer of `index` can reach all
+    /// of them. It's at least 1 and at most `capacity - index`.
+    ///
+    /// Positions that happen to be next to each other in memory but come from
+    /// different allocations (like two separate buffers) must not be counted.
+    /// By default it's 1, which is always correct.
+    ///
+    /// # Safety
+    ///   * `index` must be a valid position.
+    unsafe fn segment_len(&self, _index: usize) -> usize {
+        1
+    }
 }
@@ -1,6 +1,6 @@
-use std::ops::Bound::*;
 use std::ops::RangeBounds;
 
+use super::buffer::normalize_range;
 use super::ptrs::PtrBuffer;
 use super::Buffer;
 
@@ -8,7 +8,8 @@ use super::Buffer;
 /// memory block. It also adds utility functions based on that fact.
 ///
 /// To be such buffer it must ensure that:
-///   1. All elements have a distinct pointer.
+///   1. All elements have a distinct pointer (except for zero-sized types,
+///      where an array layout makes all of them share the same one).
 ///   2. All the memory is allocated contiguously, following an array layout.
 ///
 /// This is quite common but it cannot be assumed in the base trait.
@@ -25,13 +26,16 @@ pub trait ContiguousMemoryBuffer:
     ///  * `range` must be a range of valid positions.
     ///  * All positions in `range` must be filled.
     unsafe fn slice<R: RangeBounds<usize> + Clone>(&self, range: R) -> &[Self::Element] {
-        let (start, len) = start_len(self, range);
-        // SAFETY: `start` is part of `range` which must be valid.
-        let data = unsafe { self.ptr(start) };
-        // SAFETY: `len` is limited to capacity. Because all values must be
-        // filled, the values are valid. `ptr` ensures that the values are
-        // non-null, properly aligned, and valid.
-        unsafe { std::slice::from_raw_parts(data, len) }
+        let range = normalize_range(range, self.capacity());
+        if range.is_empty() {
+            return &[];
+        }
+        // SAFETY: The range isn't empty, so its start is a valid position.
+        let data = unsafe { self.ptr(range.start) };
+        // SAFETY: The memory is contiguous and all the positions in the range
+        // are valid and filled. `ptr` ensures that the values are non-null and
+        // properly aligned.
+        unsafe { std::slice::from_raw_parts(data, range.len()) }
     }
 
     /// Get the mutable slice of memory of the buffer specified by `range`.
@@ -43,34 +47,158 @@ pub trait ContiguousMemoryBuffer:
         &mut self,
         range: R,
     ) -> &mut [Self::Element] {
-        let (start, len) = start_len(self, range);
-        // SAFETY: `start` is part of `range` which must be valid.
-        let data = unsafe { self.mut_ptr(start) };
-        // SAFETY: `len` is limited to capacity. Because all values must be
-        // filled, the values are valid. `ptr` ensures that the values are
-        // non-null, properly aligned, and valid.
-        unsafe { std::slice::from_raw_parts_mut(data, len) }
+        let range = normalize_range(range, self.capacity());
+        if range.is_empty() {
+            return &mut [];
+        }
+        // SAFETY: The range isn't empty, so its start is a valid position.
+        let data = unsafe { self.mut_ptr(range.start) };
+        // SAFETY: The memory is contiguous and all the positions in the range
+        // are valid and filled. `mut_ptr` ensures that the values are non-null
+        // and properly aligned.
+        unsafe { std::slice::from_raw_parts_mut(data, range.len()) }
     }
 }
 
-/// Finds the start and length of a range for a specific buffer (allows open
-/// ranges).
-fn start_len<B: Buffer + ?Sized, R: RangeBounds<usize> + Clone>(
-    buffer: &B,
-    range: R,
-) -> (usize, usize) {
-    let start: usize = match range.start_bound() {
-        Included(index) => *index,
-        Excluded(index) => *index + 1,
-        Unbounded => 0,
-    };
-    let end: usize = match range.end_bound() {
-        Included(index) => *index + 1,
-        Excluded(index) => *index,
-        Unbounded => buffer.capacity(),
+/// Drops all the values in `values_range` at once (as a slice) instead of one
+/// by one. Contiguous buffers use it to implement
+/// [`Buffer::manually_drop_range`].
+///
+/// As with any slice, if dropping a value panics the rest are still dropped.
+///
+/// # Safety
+/// Same as [`Buffer::manually_drop_range`]:
+///   * All the positions in `values_range` must be valid and filled.
+pub unsafe fn drop_range_in_place<B, R>(buffer: &mut B, values_range: R)
+where
+    B: ContiguousMemoryBuffer + ?Sized,
+    R: RangeBounds<usize> + Clone,
+{
+    let range = normalize_range(values_range, buffer.capacity());
+    if range.is_empty() {
+        return;
+    }
+    // SAFETY: The range isn't empty so its start is a valid position.
+    let start = unsafe { buffer.mut_ptr(range.start) };
+    let values = std::ptr::slice_from_raw_parts_mut(start, range.len());
+    // SAFETY: The memory is contiguous and all the positions in the range are
+    // valid and filled, so it's a valid slice to drop.
+    unsafe { std::ptr::drop_in_place(values) };
+}
+
+/// Fills all the positions in `range` with clones of `value`, writing them
+/// straight into memory instead of one by one. Contiguous buffers use it to
+/// implement [`Buffer::fill_range`]. See [`fill_in_place`].
+///
+/// # Safety
+/// Same as [`Buffer::fill_range`]:
+///   * All the positions in `range` must be valid and empty.
+pub unsafe fn fill_range_in_place<B, R>(buffer: &mut B, range: R, value: &B::Element)
+where
+    B: ContiguousMemoryBuffer + ?Sized,
+    B::Element: Clone,
+    R: RangeBounds<usize> + Clone,
+{
+    let range = normalize_range(range, buffer.capacity());
+    if range.is_empty() {
+        return;
+    }
+    // SAFETY: The range isn't empty so its start is a valid position.
+    let start = unsafe { buffer.mut_ptr(range.start) };
+    let values = std::ptr::slice_from_raw_parts_mut(start, range.len());
+    // SAFETY: The memory is contiguous and all the positions in the range are
+    // valid and empty.
+    unsafe { fill_in_place(values, value) };
+}
+
+/// Writes clones of `value` into all of `values`. It's a plain loop over the
+/// memory, which the compiler turns into a `memset` for byte-sized `Copy`
+/// elements (eg. `u8`).
+///
+/// If cloning panics, the clones already written are dropped.
+///
+/// # Safety
+///   * `values` must be valid for writes and properly aligned.
+///   * It must not hold any value (it's overwritten without dropping).
+pub unsafe fn fill_in_place<T: Clone>(values: *mut [T], value: &T) {
+    let mut guard = FillGuard {
+        start: values.cast::<T>(),
+        written: 0,
     };
+    while guard.written < values.len() {
+        // SAFETY: The position is inside `values`.
+        let slot = unsafe { guard.start.add(guard.written) };
+        // SAFETY: It's valid for writes and it doesn't hold a value.
+        unsafe { slot.write(value.clone()) };
+        guard.written += 1;
+    }
+    std::mem::forget(guard);
+}
 
-    let size = if start <= end { 0 } else { end - start };
+/// Internal guard which drops the first `written` values if filling panics.
+struct FillGuard<T> {
+    start: *mut T,
+    written: usize,
+}
+
+impl<T> Drop for FillGuard<T> {
+    fn drop(&mut self) {
+        let written = std::ptr::slice_from_raw_parts_mut(self.start, self.written);
+        // SAFETY: The first `written` positions were filled.
+        unsafe { std::ptr::drop_in_place(written) };
+    }
+}
 
-    (start, size)
+#[cfg(test)]
+mod tests {
+    use std::ops::Bound;
+
+    use crate::base_buffers::{HeapBuffer, InlineBuffer};
+
+    use super::*;
+
+    fn filled_buffer() -> InlineBuffer<u32, 5> {
+        let mut buffer = InlineBuffer::new();
+        for index in 0..5 {
+            // SAFETY: All positions are valid and start empty.
+            unsafe { buffer.put(index, index as u32) };
+        }
+        buffer
+    }
+
+    #[test]
+    fn slices_follow_the_bounds() {
+        let buffer = filled_buffer();
+        // SAFETY: All positions are filled.
+        let slice = |range: (Bound<usize>, Bound<usize>)| unsafe { buffer.slice(range) };
+
+        use Bound::*;
+        assert_eq!(slice((Included(1), Excluded(3))), [1, 2]);
+        assert_eq!(slice((Included(1), Included(3))), [1, 2, 3]);
+        assert_eq!(slice((Excluded(1), Included(3))), [2, 3]);
+        assert_eq!(slice((Unbounded, Excluded(2))), [0, 1]);
+        assert_eq!(slice((Included(3), Unbounded)), [3, 4]);
+        assert_eq!(slice((Unbounded, Unbounded)), [0, 1, 2, 3, 4]);
+        assert_eq!(slice((Included(2), Excluded(2))), []);
+    }
+
+    #[test]
+    fn mutable_slices_follow_the_bounds() {
+        let mut buffer = filled_buffer();
+        // SAFETY: All positions are filled.
+        let slice = unsafe { buffer.mut_slice(2..=3) };
+        slice[0] = 7;
+        assert_eq!(slice, [7, 3]);
+        // SAFETY: All positions are filled.
+        assert_eq!(unsafe { buffer.slice(..) }, [0, 1, 7, 3, 4]);
+    }
+
+    #[test]
+    fn empty_slices_do_not_need_valid_positions() {
+        let mut buffer = HeapBuffer::<u32>::new();
+        // SAFETY: An empty range has no positions to be filled.
+        assert_eq!(unsafe { buffer.slice(..) }, []);
+        // SAFETY: Same as before.
+        assert_eq!(unsafe { buffer.mut_slice(0..0) }, []);
+    }
 }
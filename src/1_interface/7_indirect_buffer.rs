@@ -3,11 +3,15 @@ use std::ops::{Deref, DerefMut, RangeBounds};
 use crate::narrow_ref::{NarrowMutRef, NarrowRef};
 
 use super::buffer::Buffer;
+use super::capacity_contract::CapacityContract;
+use super::caps::BufferCaps;
 use super::contiguous_memory::ContiguousMemoryBuffer;
 use super::copy_value::CopyValueBuffer;
+use super::growable::GrowableBuffer;
 use super::ptrs::PtrBuffer;
 use super::refs::RefBuffer;
 use super::resize_error::ResizeError;
+use super::shrinkable::ShrinkableBuffer;
 
 /// Trait which by default forwards all behaviour into an inner buffer. This is
 /// perticularly useful to allow modifying a single function without having to
@@ -35,6 +39,10 @@ pub trait IndirectBuffer {
     where
         Self: 'a;
 
+    /// Same as [`Buffer::CAPACITY_CONTRACT`] but defaulting to the one of
+    /// [`IndirectBuffer::InnerBuffer`].
+    const CAPACITY_CONTRACT: CapacityContract = <Self::InnerBuffer as Buffer>::CAPACITY_CONTRACT;
+
     /// Aquire a constant reference into the inner buffer.
     fn inner(&self) -> Self::InnerBufferRef<'_>;
     /// Aquire a mutable reference into the inner buffer.
@@ -45,15 +53,31 @@ pub trait IndirectBuffer {
     ///
     /// # Safety
     /// Same as [`Buffer::capacity`].
+    #[inline(always)]
     fn capacity(&self) -> usize {
         self.inner().narrow_ref().capacity()
     }
 
+    /// Same as [`Buffer::allocated_bytes`] but default-implemented to pass it
+    /// to [`IndirectBuffer::inner`].
+    #[inline(always)]
+    fn allocated_bytes(&self) -> usize {
+        self.inner().narrow_ref().allocated_bytes()
+    }
+
+    /// Same as [`Buffer::caps`] but default-implemented to pass it to
+    /// [`IndirectBuffer::inner`].
+    #[inline(always)]
+    fn caps(&self) -> BufferCaps {
+        self.inner().narrow_ref().caps()
+    }
+
     /// Same as [`Buffer::take`] but default-implemented to pass it to
     /// [`IndirectBuffer::inner`].
     ///
     /// # Safety
     /// Same as [`Buffer::take`].
+    #[inline(always)]
     unsafe fn take(&mut self, index: usize) -> <Self::InnerBuffer as Buffer>::Element {
         let inner = self.inner_mut().narrow_mut_ref();
         // SAFETY: Just calls the inner function with the same requirements.
@@ -65,6 +89,7 @@ pub trait IndirectBuffer {
     ///
     /// # Safety
     /// Same as [`Buffer::put`].
+    #[inline(always)]
     unsafe fn put(&mut self, index: usize, value: <Self::InnerBuffer as Buffer>::Element) {
         let inner = self.inner_mut().narrow_mut_ref();
         // SAFETY: Just calls the inner function with the same requirements.
@@ -76,6 +101,7 @@ pub trait IndirectBuffer {
     ///
     /// # Safety
     /// Same as [`Buffer::manually_drop`].
+    #[inline(always)]
     unsafe fn manually_drop(&mut self, index: usize) {
         let inner = self.inner_mut().narrow_mut_ref();
         // SAFETY: Just calls the inner function with the same requirements.
@@ -87,28 +113,98 @@ pub trait IndirectBuffer {
     ///
     /// # Safety
     /// Same as [`Buffer::manually_drop_range`].
+    #[inline(always)]
     unsafe fn manually_drop_range<R: RangeBounds<usize> + Clone>(&mut self, values_range: R) {
         let inner = self.inner_mut().narrow_mut_ref();
         // SAFETY: Just calls the inner function with the same requirements.
         unsafe { inner.manually_drop_range(values_range) }
     }
 
+    /// Same as [`Buffer::fill_range`] but default-implemented to pass it to
+    /// [`IndirectBuffer::inner`].
+    ///
+    /// # Safety
+    /// Same as [`Buffer::fill_range`].
+    #[inline(always)]
+    unsafe fn fill_range<R: RangeBounds<usize> + Clone>(
+        &mut self,
+        range: R,
+        value: &<Self::InnerBuffer as Buffer>::Element,
+    ) where
+        <Self::InnerBuffer as Buffer>::Element: Clone,
+    {
+        let inner = self.inner_mut().narrow_mut_ref();
+        // SAFETY: Just calls the inner function with the same requirements.
+        unsafe { inner.fill_range(range, value) }
+    }
+
+    /// Same as [`Buffer::swap_remove_into`] but default-implemented to pass it
+    /// to [`IndirectBuffer::inner`].
+    ///
+    /// # Safety
+    /// Same as [`Buffer::swap_remove_into`].
+    #[inline(always)]
+    unsafe fn swap_remove_into(
+        &mut self,
+        index: usize,
+        last_index: usize,
+    ) -> <Self::InnerBuffer as Buffer>::Element {
+        let inner = self.inner_mut().narrow_mut_ref();
+        // SAFETY: Just calls the inner function with the same requirements.
+        unsafe { inner.swap_remove_into(index, last_index) }
+    }
+
     /// Same as [`Buffer::try_grow`] but default-implemented to pass it to
     /// [`IndirectBuffer::inner`].
     ///
     /// # Safety
     /// Same as [`Buffer::try_grow`].
+    #[inline(always)]
     unsafe fn try_grow(&mut self, target: usize) -> Result<(), ResizeError> {
         let inner = self.inner_mut().narrow_mut_ref();
         // SAFETY: Just calls the inner function with the same requirements.
         unsafe { inner.try_grow(target) }
     }
 
+    /// Same as [`Buffer::try_grow_at_least`], but by default it grows with
+    /// [`IndirectBuffer::try_grow`] instead of passing it to
+    /// [`IndirectBuffer::inner`], so composites which only change how they
+    /// grow keep doing so.
+    ///
+    /// # Safety
+    /// Same as [`Buffer::try_grow_at_least`].
+    unsafe fn try_grow_at_least(
+        &mut self,
+        min: usize,
+        preferred: usize,
+    ) -> Result<usize, ResizeError> {
+        debug_assert!(preferred >= min, "The preferred capacity is too small");
+        // SAFETY: Same requirements as `Buffer::try_grow_at_least`.
+        let result = unsafe { IndirectBuffer::try_grow(self, preferred) };
+        if result.is_err() && preferred > min {
+            // SAFETY: The capacity didn't change when it failed.
+            unsafe { IndirectBuffer::try_grow(self, min) }?;
+        } else {
+            result?;
+        }
+        Ok(IndirectBuffer::capacity(self))
+    }
+
+    /// Same as [`Buffer::preferred_grow_target`] but default-implemented to
+    /// pass it to [`IndirectBuffer::inner`].
+    #[inline(always)]
+    fn preferred_grow_target(&self, current: usize, required: usize) -> usize {
+        self.inner()
+            .narrow_ref()
+            .preferred_grow_target(current, required)
+    }
+
     /// Same as [`Buffer::try_shrink`] but default-implemented to pass it to
     /// [`IndirectBuffer::inner`].
     ///
     /// # Safety
     /// Same as [`Buffer::try_shrink`].
+    #[inline(always)]
     unsafe fn try_shrink(&mut self, target: usize) -> Result<(), ResizeError> {
         let inner = self.inner_mut().narrow_mut_ref();
         // SAFETY: Just calls the inner function with the same requirements.
@@ -120,6 +216,7 @@ pub trait IndirectBuffer {
     ///
     /// # Safety
     /// Same as [`Buffer::shift_right`].
+    #[inline(always)]
     unsafe fn shift_right<R: RangeBounds<usize> + Clone>(&mut self, to_move: R, positions: usize) {
         let inner = self.inner_mut().narrow_mut_ref();
         // SAFETY: Just calls the inner function with the same requirements.
@@ -131,6 +228,7 @@ pub trait IndirectBuffer {
     ///
     /// # Safety
     /// Same as [`Buffer::shift_left`].
+    #[inline(always)]
     unsafe fn shift_left<R: RangeBounds<usize> + Clone>(&mut self, to_move: R, positions: usize) {
         let inner = self.inner_mut().narrow_mut_ref();
         // SAFETY: Just calls the inner function with the same requirements.
@@ -142,45 +240,96 @@ pub trait IndirectBuffer {
 impl<IB: IndirectBuffer + ?Sized> Buffer for IB {
     type Element = <<Self as IndirectBuffer>::InnerBuffer as Buffer>::Element;
 
+    const CAPACITY_CONTRACT: CapacityContract = <Self as IndirectBuffer>::CAPACITY_CONTRACT;
+
+    #[inline(always)]
     fn capacity(&self) -> usize {
         <Self as IndirectBuffer>::capacity(self)
     }
 
+    #[inline(always)]
+    fn allocated_bytes(&self) -> usize {
+        <Self as IndirectBuffer>::allocated_bytes(self)
+    }
+
+    #[inline(always)]
+    fn caps(&self) -> BufferCaps {
+        <Self as IndirectBuffer>::caps(self)
+    }
+
+    #[inline(always)]
     unsafe fn take(&mut self, index: usize) -> Self::Element {
         // SAFETY: Just calls the inner function with the same requirements.
         unsafe { <Self as IndirectBuffer>::take(self, index) }
     }
 
+    #[inline(always)]
     unsafe fn put(&mut self, index: usize, value: Self::Element) {
         // SAFETY: Just calls the inner function with the same requirements.
         unsafe { <Self as IndirectBuffer>::put(self, index, value) }
     }
 
+    #[inline(always)]
     unsafe fn manually_drop(&mut self, index: usize) {
         // SAFETY: Just calls the inner function with the same requirements.
         unsafe { <Self as IndirectBuffer>::manually_drop(self, index) }
     }
 
+    #[inline(always)]
     unsafe fn manually_drop_range<R: RangeBounds<usize> + Clone>(&mut self, values_range: R) {
         // SAFETY: Just calls the inner function with the same requirements.
         unsafe { <Self as IndirectBuffer>::manually_drop_range(self, values_range) }
     }
 
+    #[inline(always)]
+    unsafe fn fill_range<R: RangeBounds<usize> + Clone>(&mut self, range: R, value: &Self::Element)
+    where
+        Self::Element: Clone,
+    {
+        // SAFETY: Just calls the inner function with the same requirements.
+        unsafe { <Self as IndirectBuffer>::fill_range(self, range, value) }
+    }
+
+    #[inline(always)]
+    unsafe fn swap_remove_into(&mut self, index: usize, last_index: usize) -> Self::Element {
+        // SAFETY: Just calls the inner function with the same requirements.
+        unsafe { <Self as IndirectBuffer>::swap_remove_into(self, index, last_index) }
+    }
+
+    #[inline(always)]
     unsafe fn try_grow(&mut self, target: usize) -> Result<(), ResizeError> {
         // SAFETY: Just calls the inner function with the same requirements.
         unsafe { <Self as IndirectBuffer>::try_grow(self, target) }
     }
 
+    #[inline(always)]
+    unsafe fn try_grow_at_least(
+        &mut self,
+        min: usize,
+        preferred: usize,
+    ) -> Result<usize, ResizeError> {
+        // SAFETY: Just calls the inner function with the same requirements.
+        unsafe { <Self as IndirectBuffer>::try_grow_at_least(self, min, preferred) }
+    }
+
+    #[inline(always)]
+    fn preferred_grow_target(&self, current: usize, required: usize) -> usize {
+        <Self as IndirectBuffer>::preferred_grow_target(self, current, required)
+    }
+
+    #[inline(always)]
     unsafe fn try_shrink(&mut self, target: usize) -> Result<(), ResizeError> {
         // SAFETY: Just calls the inner function with the same requirements.
         unsafe { <Self as IndirectBuffer>::try_shrink(self, target) }
     }
 
+    #[inline(always)]
     unsafe fn shift_right<R: RangeBounds<usize> + Clone>(&mut self, to_move: R, positions: usize) {
         // SAFETY: Just calls the inner function with the same requirements.
         unsafe { <Self as IndirectBuffer>::shift_right(self, to_move, positions) }
     }
 
+    #[inline(always)]
     unsafe fn shift_left<R: RangeBounds<usize> + Clone>(&mut self, to_move: R, positions: usize) {
         // SAFETY: Just calls the inner function with the same requirements.
         unsafe { <Self as IndirectBuffer>::shift_left(self, to_move, positions) }
@@ -193,6 +342,7 @@ where
     IB::InnerBuffer: CopyValueBuffer,
     <IB::InnerBuffer as Buffer>::Element: Copy,
 {
+    #[inline(always)]
     unsafe fn copy(&self, index: usize) -> Self::Element {
         // SAFETY: Just calls the inner function with the same requirements.
         unsafe { self.inner().narrow_ref().copy(index) }
@@ -208,17 +358,26 @@ where
     type ConstantPointer = B::ConstantPointer;
     type MutablePointer = B::MutablePointer;
 
+    #[inline(always)]
     unsafe fn ptr(&self, index: usize) -> Self::ConstantPointer {
         let inner = self.inner().narrow_ref();
         // SAFETY: Just calls the inner function with the same requirements.
         unsafe { inner.ptr(index) }
     }
 
+    #[inline(always)]
     unsafe fn mut_ptr(&mut self, index: usize) -> Self::MutablePointer {
         let inner = self.inner_mut().narrow_mut_ref();
         // SAFETY: Just calls the inner function with the same requirements.
         unsafe { inner.mut_ptr(index) }
     }
+
+    #[inline(always)]
+    unsafe fn segment_len(&self, index: usize) -> usize {
+        let inner = self.inner().narrow_ref();
+        // SAFETY: Just calls the inner function with the same requirements.
+        unsafe { inner.segment_len(index) }
+    }
 }
 impl<IB> RefBuffer for IB
 where
@@ -229,12 +388,14 @@ where
     type ConstantReference<'a> = <<IB as IndirectBuffer>::InnerBuffer as RefBuffer>::ConstantReference<'a> where Self: 'a;
     type MutableReference<'a> = <<IB as IndirectBuffer>::InnerBuffer as RefBuffer>::MutableReference<'a> where Self: 'a;
 
+    #[inline(always)]
     unsafe fn index<'a: 'b, 'b>(&'a self, index: usize) -> Self::ConstantReference<'b> {
         let inner = self.inner().narrow_ref();
         // SAFETY: Just calls the inner function with the same requirements.
         unsafe { inner.index(index) }
     }
 
+    #[inline(always)]
     unsafe fn mut_index<'a: 'b, 'b>(&'a mut self, index: usize) -> Self::MutableReference<'b> {
         let inner = self.inner_mut().narrow_mut_ref();
         // SAFETY: Just calls the inner function with the same requirements.
@@ -248,6 +409,20 @@ where
 {
 }
 
+impl<IB> GrowableBuffer for IB
+where
+    IB: IndirectBuffer + ?Sized,
+    IB::InnerBuffer: GrowableBuffer,
+{
+}
+
+impl<IB> ShrinkableBuffer for IB
+where
+    IB: IndirectBuffer + ?Sized,
+    IB::InnerBuffer: ShrinkableBuffer,
+{
+}
+
 /// Blanket implementation to anything that can mutably dereference into a
 /// buffer, as a way of forwarding. This includes `&mut T`, `Box<T>`, etc.
 impl<D> IndirectBuffer for D
@@ -260,10 +435,12 @@ where
     type InnerBufferRef<'a> = &'a Self::InnerBuffer where Self: 'a;
     type InnerBufferMutRef<'a> = &'a mut Self::InnerBuffer where Self: 'a;
 
+    #[inline(always)]
     fn inner(&self) -> Self::InnerBufferRef<'_> {
         self.deref()
     }
 
+    #[inline(always)]
     fn inner_mut(&mut self) -> Self::InnerBufferMutRef<'_> {
         self.deref_mut()
     }
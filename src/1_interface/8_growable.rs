@@ -0,0 +1,36 @@
+use super::Buffer;
+
+/// Marker trait for buffers which can grow as much as needed (until running out
+/// of memory or reaching the theoretical limit).
+///
+/// This means that [`Buffer::try_grow`] never fails because the operation is
+/// not supported ([`super::ResizeError::UnsupportedOperation`]), which allows
+/// collections to only expose the methods that expect growing to succeed (like
+/// `Vector::reserve`) when it's actually possible, at compile time.
+///
+/// Buffers that can never grow (like [`crate::base_buffers::InlineBuffer`])
+/// don't implement it, and composites implement it when all the buffers they
+/// may use do.
+pub trait GrowableBuffer: Buffer {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        base_buffers::HeapBuffer,
+        composites::{AtLeastBuffer, ExponentialGrowthBuffer, SvoBuffer},
+        DefaultBuffer,
+    };
+
+    use super::GrowableBuffer;
+
+    fn assert_growable<B: GrowableBuffer>() {}
+
+    #[test]
+    fn composites_forward_it() {
+        assert_growable::<DefaultBuffer<u32>>();
+        assert_growable::<DefaultBuffer<()>>();
+        assert_growable::<Box<HeapBuffer<u32>>>();
+        assert_growable::<ExponentialGrowthBuffer<SvoBuffer<4, HeapBuffer<u32>>>>();
+        assert_growable::<AtLeastBuffer<4, HeapBuffer<u32>>>();
+    }
+}
@@ -0,0 +1,38 @@
+use super::Buffer;
+
+/// Marker trait for buffers which can give back the capacity they no longer
+/// need.
+///
+/// This means that [`Buffer::try_shrink`] never fails because the operation is
+/// not supported ([`super::ResizeError::UnsupportedOperation`]). It may still
+/// succeed without changing the capacity when there is nothing to give back
+/// (like a [`crate::composites::SvoBuffer`] using its inline storage). This
+/// allows collections to only expose the methods that shrink (like
+/// `Vector::shrink_to_fit`) when they are meaningful.
+///
+/// Buffers that can never shrink (like [`crate::base_buffers::InlineBuffer`])
+/// don't implement it, and composites implement it when all the buffers they
+/// may use do.
+pub trait ShrinkableBuffer: Buffer {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        base_buffers::HeapBuffer,
+        composites::{AtLeastBuffer, ExponentialGrowthBuffer, SvoBuffer},
+        DefaultBuffer,
+    };
+
+    use super::ShrinkableBuffer;
+
+    fn assert_shrinkable<B: ShrinkableBuffer>() {}
+
+    #[test]
+    fn composites_forward_it() {
+        assert_shrinkable::<DefaultBuffer<u32>>();
+        assert_shrinkable::<DefaultBuffer<()>>();
+        assert_shrinkable::<Box<HeapBuffer<u32>>>();
+        assert_shrinkable::<ExponentialGrowthBuffer<SvoBuffer<4, HeapBuffer<u32>>>>();
+        assert_shrinkable::<AtLeastBuffer<4, HeapBuffer<u32>>>();
+    }
+}
@@ -23,3 +23,32 @@ pub mod contiguous_memory;
 
 #[path = "7_indirect_buffer.rs"]
 pub mod indirect_buffer;
+
+#[path = "8_growable.rs"]
+pub mod growable;
+
+#[path = "9_shrinkable.rs"]
+pub mod shrinkable;
+
+#[path = "10_sparse.rs"]
+pub mod sparse;
+
+#[path = "a_shift.rs"]
+pub mod shift;
+
+#[path = "b_stable.rs"]
+pub mod stable;
+
+#[path = "c_caps.rs"]
+pub mod caps;
+pub use self::caps::BufferCaps;
+
+#[path = "d_transparent.rs"]
+pub mod transparent;
+
+#[path = "e_capacity_contract.rs"]
+pub mod capacity_contract;
+pub use self::capacity_contract::CapacityContract;
+
+#[path = "f_out_of_line.rs"]
+pub mod out_of_line;
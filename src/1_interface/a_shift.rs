@@ -0,0 +1,194 @@
+//! Shared logic for shifting a range of positions (see
+//! [`super::Buffer::shift_right`] and [`super::Buffer::shift_left`]).
+//!
+//! Shifting is moving values into positions that may still be filled by other
+//! values of the same range. The only thing that makes it correct is the order
+//! of the moves, so it's defined once here and used by every implementation
+//! that moves values one by one.
+
+use std::ops::Range;
+
+/// Checks (in debug builds) that shifting `range` to the right by `positions`
+/// stays inside `capacity`.
+#[inline]
+pub fn check_right(range: &Range<usize>, positions: usize, capacity: usize) {
+    debug_assert!(
+        range.is_empty()
+            || range
+                .end
+                .checked_add(positions)
+                .is_some_and(|end| end <= capacity),
+        "Shifting {range:?} right by {positions} goes past the capacity ({capacity})"
+    );
+}
+
+/// Checks (in debug builds) that shifting `range` to the left by `positions`
+/// doesn't go before the first position.
+#[inline]
+pub fn check_left(range: &Range<usize>, positions: usize) {
+    debug_assert!(
+        range.is_empty() || range.start >= positions,
+        "Shifting {range:?} left by {positions} goes before the first position"
+    );
+}
+
+/// Moves (`(from, to)`) that shift `range` to the right by `positions`, in
+/// the order they must be done: from the last one, so a value is always moved
+/// out before another one is moved into its position.
+#[inline]
+pub fn right_moves(
+    range: Range<usize>,
+    positions: usize,
+    capacity: usize,
+) -> impl Iterator<Item = (usize, usize)> {
+    check_right(&range, positions, capacity);
+    range.rev().map(move |from| (from, from + positions))
+}
+
+/// Moves (`(from, to)`) that shift `range` to the left by `positions`, in the
+/// order they must be done: from the first one, so a value is always moved
+/// out before another one is moved into its position.
+#[inline]
+pub fn left_moves(range: Range<usize>, positions: usize) -> impl Iterator<Item = (usize, usize)> {
+    check_left(&range, positions);
+    range.map(move |from| (from, from - positions))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interface::{resize_error::ResizeError, Buffer};
+
+    use super::*;
+
+    /// Buffer which checks that every operation follows the rules (it panics
+    /// otherwise).
+    struct CheckedBuffer(Vec<Option<usize>>);
+
+    impl Buffer for CheckedBuffer {
+        type Element = usize;
+
+        fn capacity(&self) -> usize {
+            self.0.len()
+        }
+
+        unsafe fn take(&mut self, index: usize) -> usize {
+            self.0[index].take().expect("Taking an empty position")
+        }
+
+        unsafe fn put(&mut self, index: usize, value: usize) {
+            assert!(self.0[index].is_none(), "Putting into a filled position");
+            self.0[index] = Some(value);
+        }
+
+        unsafe fn try_grow(&mut self, _target: usize) -> Result<(), ResizeError> {
+            Err(ResizeError::UnsupportedOperation)
+        }
+
+        unsafe fn try_shrink(&mut self, _target: usize) -> Result<(), ResizeError> {
+            Err(ResizeError::UnsupportedOperation)
+        }
+    }
+
+    /// Reference model: the values in `range` end up `positions` away and
+    /// the positions they leave behind (and don't get filled) end up empty.
+    fn model(before: &[Option<usize>], range: Range<usize>, to: usize) -> Vec<Option<usize>> {
+        let mut after = before.to_vec();
+        for index in range.clone() {
+            after[index] = None;
+        }
+        for (offset, index) in range.clone().enumerate() {
+            after[to + offset] = before[index];
+        }
+        after
+    }
+
+    /// Buffer with `range` filled (each position with its index).
+    fn filled(capacity: usize, range: Range<usize>) -> CheckedBuffer {
+        let values = (0..capacity).map(|index| range.contains(&index).then_some(index));
+        CheckedBuffer(values.collect())
+    }
+
+    /// All the shifts in buffers up to `max_capacity`: every capacity, range
+    /// and number of positions that are valid.
+    fn all_shifts(max_capacity: usize) -> impl Iterator<Item = (usize, Range<usize>, usize)> {
+        (0..=max_capacity).flat_map(|capacity| {
+            (0..=capacity).flat_map(move |start| {
+                (start..=capacity).flat_map(move |end| {
+                    (0..=capacity).map(move |positions| (capacity, start..end, positions))
+                })
+            })
+        })
+    }
+
+    #[test]
+    fn shifting_right_matches_the_model() {
+        for (capacity, range, positions) in all_shifts(8) {
+            if range.end + positions > capacity {
+                continue;
+            }
+            let mut buffer = filled(capacity, range.clone());
+            let expected = model(&buffer.0, range.clone(), range.start + positions);
+            // SAFETY: The range is filled and there is space after it.
+            unsafe { buffer.shift_right(range.clone(), positions) };
+            assert_eq!(buffer.0, expected, "{range:?} >> {positions} in {capacity}");
+        }
+    }
+
+    #[test]
+    fn shifting_left_matches_the_model() {
+        for (capacity, range, positions) in all_shifts(8) {
+            if range.start < positions {
+                continue;
+            }
+            let mut buffer = filled(capacity, range.clone());
+            let expected = model(&buffer.0, range.clone(), range.start - positions);
+            // SAFETY: The range is filled and there is space before it.
+            unsafe { buffer.shift_left(range.clone(), positions) };
+            assert_eq!(buffer.0, expected, "{range:?} << {positions} in {capacity}");
+        }
+    }
+
+    #[test]
+    fn shifting_random_ranges_matches_the_model() {
+        // Simple xorshift, so the cases are the same on every run.
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut random = |max: usize| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % (max as u64 + 1)) as usize
+        };
+
+        for _ in 0..1000 {
+            let capacity = random(200);
+            let start = random(capacity);
+            let end = start + random(capacity - start);
+            let positions = random(capacity - (end - start));
+            let range = start..end;
+
+            let right = positions.min(capacity - end);
+            let mut buffer = filled(capacity, range.clone());
+            let expected = model(&buffer.0, range.clone(), start + right);
+            // SAFETY: The range is filled and there is space after it.
+            unsafe { buffer.shift_right(range.clone(), right) };
+            assert_eq!(buffer.0, expected);
+
+            let left = positions.min(start);
+            let mut buffer = filled(capacity, range.clone());
+            let expected = model(&buffer.0, range.clone(), start - left);
+            // SAFETY: The range is filled and there is space before it.
+            unsafe { buffer.shift_left(range.clone(), left) };
+            assert_eq!(buffer.0, expected);
+        }
+    }
+
+    #[test]
+    fn moves_come_in_a_safe_order() {
+        assert_eq!(
+            right_moves(1..3, 1, 4).collect::<Vec<_>>(),
+            [(2, 3), (1, 2)]
+        );
+        assert_eq!(left_moves(1..3, 1).collect::<Vec<_>>(), [(1, 0), (2, 1)]);
+        assert_eq!(left_moves(0..0, 3).count(), 0);
+    }
+}
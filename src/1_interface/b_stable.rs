@@ -0,0 +1,36 @@
+use super::Buffer;
+
+/// Marker trait for buffers whose values never move while they are filled,
+/// which makes it possible to pin them (see
+/// [`crate::collections::PinnedVector`]).
+///
+/// # Safety
+/// Implementors must ensure that, for as long as a position stays filled:
+///   1. Its value keeps the same address, even if the buffer itself is moved.
+///      This means the values can't be stored inside the buffer (like
+///      [`crate::base_buffers::InlineBuffer`] does).
+///   2. Growing or shrinking never moves it (they may fail instead).
+///   3. Its memory is not invalidated nor reused until the buffer is dropped,
+///      even if the buffer is leaked. This rules out borrowed memory (like
+///      [`crate::base_buffers::SliceBuffer`]), which could be reused after
+///      forgetting the buffer without dropping the values.
+///
+/// For example, `Box<InlineBuffer<T, N>>` fulfills all of them: the values are
+/// on the heap, it never grows and the memory is only released when dropping
+/// the box.
+pub unsafe trait StableElementsBuffer: Buffer {}
+
+#[cfg(test)]
+mod tests {
+    use crate::base_buffers::InlineBuffer;
+
+    use super::StableElementsBuffer;
+
+    fn assert_stable<B: StableElementsBuffer>() {}
+
+    #[test]
+    fn boxed_inline_buffers_are_stable() {
+        assert_stable::<Box<InlineBuffer<u32, 4>>>();
+        assert_stable::<Box<InlineBuffer<(), 4>>>();
+    }
+}
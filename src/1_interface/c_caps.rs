@@ -0,0 +1,146 @@
+use std::{
+    fmt,
+    ops::{BitAnd, BitOr, Not},
+};
+
+/// Set of capabilities a buffer has at runtime (see [`super::Buffer::caps`]).
+///
+/// It mirrors the marker traits ([`super::growable::GrowableBuffer`],
+/// [`super::shrinkable::ShrinkableBuffer`] and
+/// [`super::contiguous_memory::ContiguousMemoryBuffer`]) so generic code
+/// without those bounds can still choose the best strategy (eg. copying a
+/// slice instead of iterating the elements). Composites which switch between
+/// inner buffers report the one in use.
+///
+/// # Example
+/// ```
+/// # use buffers::interface::caps::BufferCaps;
+/// let caps = BufferCaps::GROW | BufferCaps::CONTIGUOUS;
+/// assert!(caps.contains(BufferCaps::GROW));
+/// assert!(!caps.contains(BufferCaps::SHRINK));
+/// assert_eq!(caps & !BufferCaps::GROW, BufferCaps::CONTIGUOUS);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct BufferCaps(u8);
+
+impl BufferCaps {
+    /// [`super::Buffer::try_grow`] may succeed.
+    pub const GROW: Self = Self(1 << 0);
+    /// [`super::Buffer::try_shrink`] may succeed.
+    pub const SHRINK: Self = Self(1 << 1);
+    /// The positions are stored next to each other in memory, so they can be
+    /// accessed as a slice.
+    pub const CONTIGUOUS: Self = Self(1 << 2);
+
+    const NAMES: [(Self, &'static str); 3] = [
+        (Self::GROW, "GROW"),
+        (Self::SHRINK, "SHRINK"),
+        (Self::CONTIGUOUS, "CONTIGUOUS"),
+    ];
+
+    /// No capabilities at all. It's always a correct (if pessimistic) answer.
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Every capability.
+    pub const fn all() -> Self {
+        Self(Self::GROW.0 | Self::SHRINK.0 | Self::CONTIGUOUS.0)
+    }
+
+    /// Raw bits of the set.
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Whether it has no capabilities.
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Whether it has all the capabilities of `other`.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Capabilities in either set.
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// Capabilities in both sets.
+    pub const fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    /// Capabilities of `self` which aren't in `other`.
+    pub const fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+}
+
+impl BitOr for BufferCaps {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        self.union(rhs)
+    }
+}
+
+impl BitAnd for BufferCaps {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        self.intersection(rhs)
+    }
+}
+
+impl Not for BufferCaps {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self::all().difference(self)
+    }
+}
+
+impl fmt::Debug for BufferCaps {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names = Self::NAMES
+            .iter()
+            .filter(|(flag, _)| self.contains(*flag))
+            .map(|(_, name)| *name)
+            .peekable();
+        f.write_str("BufferCaps(")?;
+        if names.peek().is_none() {
+            f.write_str("empty")?;
+        }
+        for (i, name) in names.enumerate() {
+            if i > 0 {
+                f.write_str(" | ")?;
+            }
+            f.write_str(name)?;
+        }
+        f.write_str(")")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn not_stays_within_the_known_capabilities() {
+        assert_eq!(!BufferCaps::empty(), BufferCaps::all());
+        assert_eq!(!BufferCaps::all(), BufferCaps::empty());
+        assert!((!BufferCaps::GROW).contains(BufferCaps::SHRINK | BufferCaps::CONTIGUOUS));
+    }
+
+    #[test]
+    fn debug_lists_the_capabilities() {
+        assert_eq!(format!("{:?}", BufferCaps::empty()), "BufferCaps(empty)");
+        assert_eq!(
+            format!("{:?}", BufferCaps::GROW | BufferCaps::CONTIGUOUS),
+            "BufferCaps(GROW | CONTIGUOUS)"
+        );
+    }
+}
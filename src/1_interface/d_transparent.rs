@@ -0,0 +1,76 @@
+use std::mem::{self, ManuallyDrop};
+
+use super::Buffer;
+
+/// Marker trait for composites which are a `#[repr(transparent)]` wrapper
+/// around another buffer and only change how it behaves (eg. how it grows),
+/// not what it holds. It makes it possible to add or remove the layer
+/// without moving the values (see [`crate::collections::Vector::wrap_buffer`]).
+///
+/// # Safety
+/// Implementors must ensure that:
+///   1. They are `#[repr(transparent)]` over [`TransparentBuffer::Inner`].
+///   2. Any value of the inner buffer is a valid wrapper, with the same
+///      capacity and the same filled positions (and the other way around).
+///   3. They don't implement [`Drop`]: dropping them only drops the inner
+///      buffer.
+///
+/// For example, [`crate::composites::ZstoBuffer`] is `#[repr(transparent)]`
+/// but it can't implement it: for zero-sized types it ignores its inner
+/// buffer, so their capacities are different.
+pub unsafe trait TransparentBuffer: Buffer {
+    /// Buffer that it wraps.
+    type Inner: Buffer<Element = Self::Element>;
+
+    /// Wraps `inner` without moving its values.
+    fn from_inner(inner: Self::Inner) -> Self
+    where
+        Self: Sized,
+    {
+        let inner = ManuallyDrop::new(inner);
+        // SAFETY: They have the same layout and any inner buffer is a valid
+        // wrapper (required by the trait). The original is never dropped.
+        unsafe { mem::transmute_copy(&*inner) }
+    }
+
+    /// Gives back the inner buffer with the same values.
+    fn into_inner(self) -> Self::Inner
+    where
+        Self: Sized,
+    {
+        let this = ManuallyDrop::new(self);
+        // SAFETY: They have the same layout and the wrapper doesn't need to
+        // be dropped (required by the trait). The original is never dropped.
+        unsafe { mem::transmute_copy(&*this) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::{align_of, size_of};
+
+    use crate::{
+        base_buffers::HeapBuffer,
+        composites::{AtLeastBuffer, ExponentialGrowthBuffer, ZstoBuffer},
+    };
+
+    /// Checks at compile time that `A` and `B` have the same layout.
+    macro_rules! assert_same_layout {
+        ($a:ty, $b:ty) => {
+            const _: () = assert!(size_of::<$a>() == size_of::<$b>());
+            const _: () = assert!(align_of::<$a>() == align_of::<$b>());
+        };
+    }
+
+    type Heap = HeapBuffer<u64>;
+
+    assert_same_layout!(ZstoBuffer<Heap>, Heap);
+    assert_same_layout!(ExponentialGrowthBuffer<Heap>, Heap);
+    assert_same_layout!(AtLeastBuffer<8, Heap>, Heap);
+    assert_same_layout!(
+        ExponentialGrowthBuffer<AtLeastBuffer<8, ZstoBuffer<Heap>>>,
+        Heap
+    );
+    #[cfg(feature = "array")]
+    assert_same_layout!(crate::composites::ArrayBuffer<3, Heap>, [Heap; 3]);
+}
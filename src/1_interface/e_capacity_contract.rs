@@ -0,0 +1,149 @@
+/// What a buffer's capacity is after growing successfully (see
+/// [`super::Buffer::CAPACITY_CONTRACT`]), so collections (and their users)
+/// know whether asking for an exact capacity gives exactly that.
+///
+/// # Example
+/// ```
+/// # use buffers::{base_buffers::HeapBuffer, composites::ExponentialGrowthBuffer, interface::{Buffer, CapacityContract}};
+/// assert_eq!(HeapBuffer::<u32>::CAPACITY_CONTRACT, CapacityContract::Exact);
+/// assert_eq!(
+///     ExponentialGrowthBuffer::<HeapBuffer<u32>>::CAPACITY_CONTRACT,
+///     CapacityContract::RoundedUp
+/// );
+/// assert!(CapacityContract::RoundedUp.allows(10, 16));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CapacityContract {
+    /// It's exactly the target: there is no surplus.
+    Exact,
+    /// It's at least the target, but it may be more (eg. rounded up to a
+    /// power of 2).
+    RoundedUp,
+    /// It's always [`usize::MAX`] (eg. for zero-sized types), so it never
+    /// needs to grow.
+    Unbounded,
+}
+
+impl CapacityContract {
+    /// Whether a buffer with this contract may end up with `capacity` after
+    /// growing to `target`.
+    pub const fn allows(self, target: usize, capacity: usize) -> bool {
+        match self {
+            Self::Exact => capacity == target,
+            Self::RoundedUp => capacity >= target,
+            Self::Unbounded => capacity == usize::MAX,
+        }
+    }
+
+    /// Contract of a composite which may grow its inner buffer past the
+    /// target (an unbounded buffer stays unbounded).
+    pub const fn rounded_up(self) -> Self {
+        match self {
+            Self::Unbounded => Self::Unbounded,
+            _ => Self::RoundedUp,
+        }
+    }
+
+    /// Contract of a composite which may use either of two buffers: the
+    /// weakest one that both follow.
+    pub const fn either(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Exact, Self::Exact) => Self::Exact,
+            (Self::Unbounded, Self::Unbounded) => Self::Unbounded,
+            _ => Self::RoundedUp,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        base_buffers::{
+            ExternBuffer, HeapBuffer, InlineBuffer, SharedSliceBuffer, SliceBuffer, ZstBuffer,
+        },
+        collections::Vector,
+        composites::{
+            either::EitherBuffer, AtLeastBuffer, ExponentialGrowthBuffer, SvoBuffer, ZstoBuffer,
+        },
+        interface::Buffer,
+        DefaultBuffer,
+    };
+
+    use super::CapacityContract::{self, *};
+
+    /// Checks at compile time the contract of a buffer.
+    macro_rules! assert_contract {
+        ($buffer:ty, $contract:pat) => {
+            const _: () = assert!(matches!(<$buffer as Buffer>::CAPACITY_CONTRACT, $contract));
+        };
+    }
+
+    // Base buffers.
+    assert_contract!(HeapBuffer<u32>, Exact);
+    assert_contract!(InlineBuffer<u32, 4>, Exact);
+    assert_contract!(ZstBuffer<()>, Unbounded);
+    assert_contract!(SliceBuffer<'static, u32>, Exact);
+    assert_contract!(SharedSliceBuffer<'static, u32>, Exact);
+    assert_contract!(ExternBuffer<u32>, Exact);
+    #[cfg(feature = "allocator")]
+    assert_contract!(crate::base_buffers::AllocatorBuffer<u32>, Exact);
+    #[cfg(all(feature = "guarded", unix))]
+    assert_contract!(crate::base_buffers::GuardedBuffer<u32>, Exact);
+
+    // Composites.
+    assert_contract!(DefaultBuffer<u32>, Exact);
+    assert_contract!(DefaultBuffer<()>, Unbounded);
+    assert_contract!(Box<HeapBuffer<u32>>, Exact);
+    assert_contract!(ExponentialGrowthBuffer<HeapBuffer<u32>>, RoundedUp);
+    assert_contract!(AtLeastBuffer<8, HeapBuffer<u32>>, RoundedUp);
+    assert_contract!(
+        ExponentialGrowthBuffer<ZstoBuffer<HeapBuffer<()>>>,
+        Unbounded
+    );
+    assert_contract!(SvoBuffer<4, HeapBuffer<u32>>, Exact);
+    assert_contract!(EitherBuffer<HeapBuffer<u32>, InlineBuffer<u32, 4>>, Exact);
+    assert_contract!(
+        EitherBuffer<HeapBuffer<u32>, ExponentialGrowthBuffer<HeapBuffer<u32>>>,
+        RoundedUp
+    );
+
+    #[test]
+    fn growing_exactly_follows_the_contract() {
+        fn reserve_exact<B: Buffer<Element = u32> + Default>(additional: usize) -> usize {
+            let mut vec = Vector::<u32, B>::new();
+            vec.try_push(1).unwrap();
+            vec.try_reserve_exact(additional).unwrap();
+            vec.capacity()
+        }
+
+        assert_eq!(reserve_exact::<HeapBuffer<u32>>(9), 10);
+        assert_eq!(reserve_exact::<DefaultBuffer<u32>>(299), 300);
+        assert_eq!(
+            reserve_exact::<ExponentialGrowthBuffer<HeapBuffer<u32>>>(9),
+            16
+        );
+        assert_eq!(reserve_exact::<AtLeastBuffer<32, HeapBuffer<u32>>>(9), 32);
+    }
+
+    #[test]
+    fn combined_contracts_allow_both() {
+        let all = [Exact, RoundedUp, Unbounded];
+        let cases = [(4, 4), (4, 8), (4, usize::MAX)];
+        for a in all {
+            for b in all {
+                let combined = a.either(b);
+                for (target, capacity) in cases {
+                    if a.allows(target, capacity) || b.allows(target, capacity) {
+                        assert!(combined.allows(target, capacity), "{a:?} {b:?}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rounding_up_allows_more() {
+        let rounded: [CapacityContract; 3] = [Exact, RoundedUp, Unbounded].map(|c| c.rounded_up());
+        assert_eq!(rounded, [RoundedUp, RoundedUp, Unbounded]);
+    }
+}
@@ -0,0 +1,33 @@
+use super::Buffer;
+
+/// Marker trait for buffers whose values aren't stored inside the buffer
+/// itself, so moving the buffer doesn't move them.
+///
+/// Anything that checks the addresses of the values once (like their
+/// alignment) can rely on them as long as the buffer isn't resized.
+///
+/// # Safety
+/// Implementors must ensure that moving the buffer keeps the address of every
+/// position. This means the values can't be stored inline (like
+/// [`crate::base_buffers::InlineBuffer`] does), but unlike
+/// [`super::stable::StableElementsBuffer`] they may move when resizing.
+pub unsafe trait OutOfLineBuffer: Buffer {}
+
+// SAFETY: The inner buffer is on the heap, so moving the box doesn't move it.
+unsafe impl<B: Buffer + ?Sized> OutOfLineBuffer for Box<B> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::base_buffers::{HeapBuffer, InlineBuffer, SliceBuffer};
+
+    use super::OutOfLineBuffer;
+
+    fn assert_out_of_line<B: OutOfLineBuffer>() {}
+
+    #[test]
+    fn buffers_pointing_elsewhere_are_out_of_line() {
+        assert_out_of_line::<HeapBuffer<u8>>();
+        assert_out_of_line::<SliceBuffer<'static, u8>>();
+        assert_out_of_line::<Box<InlineBuffer<u8, 4>>>();
+    }
+}
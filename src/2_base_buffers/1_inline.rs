@@ -1,8 +1,20 @@
 use crate::interface::{
-    contiguous_memory::ContiguousMemoryBuffer, copy_value::CopyValueBuffer, ptrs::PtrBuffer,
-    refs::RefBuffer, resize_error::ResizeError, Buffer,
+    buffer::normalize_range,
+    capacity_contract::CapacityContract,
+    caps::BufferCaps,
+    contiguous_memory::{fill_in_place, ContiguousMemoryBuffer},
+    copy_value::CopyValueBuffer,
+    ptrs::PtrBuffer,
+    refs::RefBuffer,
+    resize_error::ResizeError,
+    stable::StableElementsBuffer,
+    Buffer,
 };
-use std::mem::MaybeUninit;
+use std::mem::{self, MaybeUninit};
+use std::ops::RangeBounds;
+use std::ptr;
+
+use super::occupancy::Occupancy;
 
 /// Buffer based on an inline fixed-sized array. It cannot grow or shrink. This
 /// also means that the memory is contiguous and it can be used in the stack
@@ -10,36 +22,44 @@ use std::mem::MaybeUninit;
 ///
 /// It can also be combined with [`std::boxed::Box`] to move the array on the
 /// heap (since `Box<AnyBuffer>` is also a buffer).
+///
+/// With the `contract-checks` feature, debug builds check that positions are
+/// only read or emptied when filled and only filled when empty.
 pub struct InlineBuffer<T, const SIZE: usize> {
     array: [MaybeUninit<T>; SIZE],
+    occupancy: Occupancy,
 }
 
 impl<T, const SIZE: usize> InlineBuffer<T, SIZE> {
     /// Create a new empty inline buffer.
     pub fn new() -> Self {
         InlineBuffer {
-            array: MaybeUninit::uninit_array(),
+            array: [const { MaybeUninit::uninit() }; SIZE],
+            occupancy: Occupancy::new(SIZE),
         }
     }
 
-    /// Get a constant reference to an element in the specified `index` that may
-    /// or may not be initialized.
+    /// Makes a buffer with all its positions filled with the values of
+    /// `array`. It can be used in constants.
     ///
-    /// # SAFETY
-    ///   * `index` must be valid.
-    fn at(&self, index: usize) -> &MaybeUninit<T> {
-        debug_assert!(index < SIZE);
-        &self.array[index]
-    }
-
-    /// Get a mutable reference to an element in the specified `index` that may
-    /// or may not be initialized.
-    ///
-    /// # SAFETY
-    ///   * `index` must be valid.
-    fn mut_at(&mut self, index: usize) -> &mut MaybeUninit<T> {
-        debug_assert!(index < SIZE);
-        &mut self.array[index]
+    /// # Example
+    /// ```
+    /// # use buffers::{base_buffers::InlineBuffer, interface::copy_value::CopyValueBuffer};
+    /// const BUFFER: InlineBuffer<u8, 3> = InlineBuffer::from_array([1, 2, 3]);
+    /// // SAFETY: All the positions are filled.
+    /// assert_eq!(unsafe { BUFFER.copy(2) }, 3);
+    /// ```
+    pub const fn from_array(array: [T; SIZE]) -> Self {
+        let source = ptr::addr_of!(array).cast::<[MaybeUninit<T>; SIZE]>();
+        // SAFETY: `MaybeUninit<T>` has the same layout as `T`, so the arrays
+        // have the same layout too. The original is forgotten right after,
+        // so the values are moved.
+        let values = unsafe { source.read() };
+        mem::forget(array);
+        InlineBuffer {
+            array: values,
+            occupancy: Occupancy::unknown(),
+        }
     }
 
     /// Internal utility that reads `index`. Used both for copying and for
@@ -49,40 +69,104 @@ impl<T, const SIZE: usize> InlineBuffer<T, SIZE> {
     ///   * `index` must be less than `capacity`.
     ///   * The `index` position must be filled.
     unsafe fn read(&self, index: usize) -> T {
-        // SAFETY: `index` is unsafe with requirements that ensures that
-        // [`PtrBuffer::ptr`] can be used.
-        let ptr = unsafe { self.ptr(index) };
+        // SAFETY: Same requirements.
+        let ptr = unsafe { self.slot(index) };
         // SAFETY: if `index` is a valid position, `ptr` is valid to read from.
         unsafe { ptr.read() }
     }
+
+    /// Internal utility that gets a pointer to `index` (without handing it
+    /// out, unlike [`PtrBuffer::ptr`]).
+    ///
+    /// # Safety
+    /// `index` must be less than `capacity`.
+    unsafe fn slot(&self, index: usize) -> *const T {
+        debug_assert!(index < SIZE);
+        // The pointer comes from the whole array (instead of the position) so it
+        // can be used to reach other positions, as contiguous memory allows.
+        let start = self.array.as_ptr().cast::<T>();
+        // SAFETY: `index` is a valid position, so it's inside the array.
+        unsafe { start.add(index) }
+    }
+
+    /// Mutable version of [`InlineBuffer::slot`].
+    ///
+    /// # Safety
+    /// `index` must be less than `capacity`.
+    unsafe fn slot_mut(&mut self, index: usize) -> *mut T {
+        debug_assert!(index < SIZE);
+        // Same as `slot`, it comes from the whole array.
+        let start = self.array.as_mut_ptr().cast::<T>();
+        // SAFETY: `index` is a valid position, so it's inside the array.
+        unsafe { start.add(index) }
+    }
 }
 
 impl<T, const SIZE: usize> Buffer for InlineBuffer<T, SIZE> {
     type Element = T;
+    const CAPACITY_CONTRACT: CapacityContract = CapacityContract::Exact;
 
     fn capacity(&self) -> usize {
         SIZE
     }
 
+    #[inline]
+    fn caps(&self) -> BufferCaps {
+        BufferCaps::CONTIGUOUS
+    }
+
     unsafe fn take(&mut self, index: usize) -> T {
+        self.occupancy.empty(index);
         // SAFETY: it has the same requirements
         unsafe { self.read(index) }
     }
 
     unsafe fn put(&mut self, index: usize, value: T) {
+        self.occupancy.fill(index);
         // SAFETY: `index` is unsafe with requirements that ensures that
-        // [`PtrBuffer::ptr`] can be used.
-        let ptr = unsafe { self.mut_ptr(index) };
+        // it's a valid position.
+        let ptr = unsafe { self.slot_mut(index) };
         // SAFETY: if `index` is an empty position, `ptr` is valid to write to.
         unsafe { ptr.write(value) }
     }
 
     unsafe fn manually_drop(&mut self, index: usize) {
+        self.occupancy.empty(index);
         // SAFETY: `index` is unsafe with requirements that ensures that
-        // [`PtrBuffer::ptr`] can be used.
-        let ptr = unsafe { self.mut_ptr(index) };
+        // it's a valid position.
+        let ptr = unsafe { self.slot_mut(index) };
         // SAFETY: if `index` is a valid position, `ptr` is valid to drop.
-        unsafe { std::ptr::drop_in_place(ptr) };
+        unsafe { ptr::drop_in_place(ptr) };
+    }
+
+    unsafe fn manually_drop_range<R: RangeBounds<usize> + Clone>(&mut self, values_range: R) {
+        let range = normalize_range(values_range, SIZE);
+        self.occupancy.empty_range(range.clone());
+        if range.is_empty() {
+            return;
+        }
+        // SAFETY: The range isn't empty so its start is a valid position.
+        let start = unsafe { self.slot_mut(range.start) };
+        let values = ptr::slice_from_raw_parts_mut(start, range.len());
+        // SAFETY: All the positions in the range are valid and filled, so it's
+        // a valid slice to drop.
+        unsafe { ptr::drop_in_place(values) };
+    }
+
+    unsafe fn fill_range<R: RangeBounds<usize> + Clone>(&mut self, range: R, value: &T)
+    where
+        T: Clone,
+    {
+        let range = normalize_range(range, SIZE);
+        if range.is_empty() {
+            return;
+        }
+        // SAFETY: The range isn't empty so its start is a valid position.
+        let start = unsafe { self.slot_mut(range.start) };
+        let values = ptr::slice_from_raw_parts_mut(start, range.len());
+        // SAFETY: All the positions in the range are valid and empty.
+        unsafe { fill_in_place(values, value) };
+        self.occupancy.fill_range(range);
     }
 
     unsafe fn try_grow(&mut self, _target: usize) -> Result<(), ResizeError> {
@@ -96,6 +180,7 @@ impl<T, const SIZE: usize> Buffer for InlineBuffer<T, SIZE> {
 
 impl<T: Copy, const SIZE: usize> CopyValueBuffer for InlineBuffer<T, SIZE> {
     unsafe fn copy(&self, index: usize) -> T {
+        self.occupancy.check_filled(index);
         // SAFETY: it has the same requirements
         unsafe { self.read(index) }
     }
@@ -106,13 +191,20 @@ impl<T, const SIZE: usize> PtrBuffer for InlineBuffer<T, SIZE> {
     type MutablePointer = *mut T;
 
     unsafe fn ptr(&self, index: usize) -> *const T {
-        debug_assert!(index < SIZE);
-        self.at(index).as_ptr()
+        self.occupancy.escape();
+        // SAFETY: Same requirements.
+        unsafe { self.slot(index) }
     }
 
     unsafe fn mut_ptr(&mut self, index: usize) -> *mut T {
-        debug_assert!(index < SIZE);
-        self.mut_at(index).as_mut_ptr()
+        self.occupancy.escape();
+        // SAFETY: Same requirements.
+        unsafe { self.slot_mut(index) }
+    }
+
+    unsafe fn segment_len(&self, index: usize) -> usize {
+        // All the positions are in a single allocation.
+        self.capacity() - index
     }
 }
 
@@ -125,26 +217,33 @@ impl<T, const SIZE: usize> RefBuffer for InlineBuffer<T, SIZE> {
         Self: 'a;
 
     unsafe fn index<'a: 'b, 'b>(&'a self, index: usize) -> &'b T {
+        self.occupancy.check_filled(index);
         // SAFETY: `index` is unsafe with requirements that ensures that
-        // [`PtrBuffer::ptr`] can be used.
-        let ptr = unsafe { self.ptr(index) };
-        // SAFETY: [`PtrBuffer::ptr`] ensures that the pointer can be
-        // derefferenced.
+        // it's a valid position.
+        let ptr = unsafe { self.slot(index) };
+        // SAFETY: The position is valid and filled, so the pointer can be
+        // dereferenced.
         unsafe { &*ptr }
     }
 
     unsafe fn mut_index<'a: 'b, 'b>(&'a mut self, index: usize) -> &'b mut T {
+        self.occupancy.check_filled(index);
         // SAFETY: `mut_index` is unsafe with requirements that ensures that
-        // [`PtrBuffer::mut_ptr`] can be used.
-        let ptr = unsafe { self.mut_ptr(index) };
-        // SAFETY: [`PtrBuffer::mut_ptr`] ensures that the pointer can be
-        // derefferenced.
+        // it's a valid position.
+        let ptr = unsafe { self.slot_mut(index) };
+        // SAFETY: The position is valid and filled, so the pointer can be
+        // dereferenced.
         unsafe { &mut *ptr }
     }
 }
 
 impl<T, const SIZE: usize> ContiguousMemoryBuffer for InlineBuffer<T, SIZE> {}
 
+// SAFETY: The values are on the heap (so moving the box doesn't move them), it
+// never grows nor shrinks, and the memory is only released when the box is
+// dropped.
+unsafe impl<T, const SIZE: usize> StableElementsBuffer for Box<InlineBuffer<T, SIZE>> {}
+
 impl<T, const SIZE: usize> Default for InlineBuffer<T, SIZE> {
     fn default() -> Self {
         Self::new()
@@ -159,6 +258,12 @@ mod tests {
 
     use super::*;
 
+    // Compile-time check: it must be covariant like an array.
+    #[allow(dead_code)]
+    fn is_covariant<'a>(buffer: InlineBuffer<&'static str, 2>) -> InlineBuffer<&'a str, 2> {
+        buffer
+    }
+
     #[test]
     fn inline_buffer_should_have_the_capacity_of_its_size() {
         let vec = InlineBuffer::<u32, 123>::new();
@@ -174,7 +279,9 @@ mod tests {
     fn inline_buffer_should_can_read_previously_written_values() {
         let mut vec = InlineBuffer::<u32, 123>::new();
         for x in 1..3 {
+            // SAFETY: 0 is a valid position, and it's empty.
             unsafe { vec.put(0, x) };
+            // SAFETY: 0 is a valid position, and it was just filled.
             let r = unsafe { vec.take(0) };
 
             assert_eq!(x, r)
@@ -185,9 +292,11 @@ mod tests {
     fn inline_buffer_should_be_able_to_read_multiple_values() {
         let mut vec = InlineBuffer::<usize, 123>::new();
         for x in 1..3 {
+            // SAFETY: `x` is a valid position, and it's empty.
             unsafe { vec.put(x, x * 2) };
         }
         for x in 1..3 {
+            // SAFETY: `x` is a valid position, and it was filled above.
             let r = unsafe { vec.take(x) };
             assert_eq!(r, x * 2)
         }
@@ -198,9 +307,11 @@ mod tests {
         let counter = AtomicI64::new(0);
         let mut buffer = InlineBuffer::<LifeCounter<'_>, 1>::new();
 
+        // SAFETY: 0 is a valid position, and it's empty.
         unsafe { buffer.put(0, LifeCounter::new(&counter)) };
         assert_eq!(counter.load(Ordering::SeqCst), 1);
 
+        // SAFETY: 0 is a valid position, and it was just filled.
         unsafe { buffer.manually_drop(0) };
         assert_eq!(counter.load(Ordering::SeqCst), 0);
     }
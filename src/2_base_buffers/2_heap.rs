@@ -1,20 +1,37 @@
 use std::{
-    alloc::Layout,
     marker::PhantomData,
+    mem::{self, ManuallyDrop},
+    ops::RangeBounds,
     ptr::{self, NonNull},
 };
 
+use super::occupancy::Occupancy;
 use crate::interface::{
-    contiguous_memory::ContiguousMemoryBuffer, copy_value::CopyValueBuffer, ptrs::PtrBuffer,
-    refs::RefBuffer, resize_error::ResizeError, Buffer,
+    buffer::normalize_range,
+    capacity_contract::CapacityContract,
+    caps::BufferCaps,
+    contiguous_memory::{fill_in_place, ContiguousMemoryBuffer},
+    copy_value::CopyValueBuffer,
+    growable::GrowableBuffer,
+    out_of_line::OutOfLineBuffer,
+    ptrs::PtrBuffer,
+    refs::RefBuffer,
+    resize_error::{array_layout, ResizeError},
+    shrinkable::ShrinkableBuffer,
+    Buffer,
 };
 
 /// Buffer implementation using a heap-allocated contiguous array.
 ///
 /// This implementation uses the allocation functions on [`std::alloc`].
+///
+/// With the `contract-checks` feature, debug builds check that positions are
+/// only read or emptied when filled and only filled when empty (which makes it
+/// bigger).
 pub struct HeapBuffer<T> {
     buffer_start: NonNull<T>,
     cap: usize,
+    occupancy: Occupancy,
     _marker: PhantomData<T>,
 }
 
@@ -29,10 +46,38 @@ impl<T> HeapBuffer<T> {
         Self {
             buffer_start: NonNull::dangling(),
             cap: 0,
+            occupancy: Occupancy::new(0),
             _marker: PhantomData,
         }
     }
 
+    /// Internal utility that takes the allocation of `vec` (both use the
+    /// global allocator with the same layout). Returns the buffer and how many
+    /// positions (from the start) are filled.
+    ///
+    /// Zero-sized elements have no allocation to take, so `vec` is given back.
+    pub(crate) fn from_vec(vec: Vec<T>) -> Result<(Self, usize), Vec<T>> {
+        if mem::size_of::<T>() == 0 {
+            return Err(vec);
+        }
+        let mut vec = ManuallyDrop::new(vec);
+        let len = vec.len();
+        let cap = vec.capacity();
+        let buffer_start = NonNull::new(vec.as_mut_ptr()).expect("Vec pointers are never null");
+
+        let mut occupancy = Occupancy::new(cap);
+        for index in 0..len {
+            occupancy.fill(index);
+        }
+        let buffer = Self {
+            buffer_start,
+            cap,
+            occupancy,
+            _marker: PhantomData,
+        };
+        Ok((buffer, len))
+    }
+
     /// Internal utility that reads `index`. Used both for copying and for
     /// extracting the value.
     ///
@@ -40,13 +85,26 @@ impl<T> HeapBuffer<T> {
     ///   * `index` must be less than `capacity`.
     ///   * The `index` position must be filled.
     unsafe fn read(&self, index: usize) -> T {
-        // SAFETY: `index` is unsafe with requirements that ensures that
-        // [`PtrBuffer::ptr`] can be used.
-        let ptr = unsafe { self.ptr(index) };
+        // SAFETY: Same requirements.
+        let ptr = unsafe { self.slot(index) };
         // SAFETY: if `index` is a valid position, `ptr` is valid to read from.
         unsafe { ptr.read() }
     }
 
+    /// Internal utility that gets a pointer to `index` (without handing it
+    /// out, unlike [`PtrBuffer::ptr`]).
+    ///
+    /// # Safety
+    /// `index` must be less than `capacity`.
+    unsafe fn slot(&self, index: usize) -> *mut T {
+        debug_assert!(index < self.capacity());
+        let ptr = self.buffer_start.as_ptr();
+
+        // SAFETY: `ptr` is at the start, `ptr.add(index)` points to the array's
+        // position, which is valid.
+        unsafe { ptr.add(index) }
+    }
+
     /// Internal function that allocates a new array into the heap.
     ///
     /// # Safety
@@ -99,35 +157,74 @@ impl<T> HeapBuffer<T> {
 
 impl<T> Buffer for HeapBuffer<T> {
     type Element = T;
+    const CAPACITY_CONTRACT: CapacityContract = CapacityContract::Exact;
 
     fn capacity(&self) -> usize {
         self.cap
     }
 
+    #[inline]
+    fn caps(&self) -> BufferCaps {
+        BufferCaps::all()
+    }
+
     unsafe fn take(&mut self, index: usize) -> T {
+        self.occupancy.empty(index);
         // SAFETY: it has the same requirements
         unsafe { self.read(index) }
     }
 
     unsafe fn put(&mut self, index: usize, value: T) {
+        self.occupancy.fill(index);
         // SAFETY: [`Buffer::put`] ensures that the position is valid and empty.
-        let dst = unsafe { self.mut_ptr(index) };
-        // SAFETY: [`PtrBuffer::mut_ptr`] ensures that the pointer is valid.
-        // [`Buffer::put`] ensures that the position is empty.
+        let dst = unsafe { self.slot(index) };
+        // SAFETY: The pointer is valid. [`Buffer::put`] ensures that the
+        // position is empty.
         unsafe { ptr::write(dst, value) };
     }
 
     unsafe fn manually_drop(&mut self, index: usize) {
+        self.occupancy.empty(index);
         // SAFETY: [`Buffer::manually_drop`] ensures that the position is valid
         // and filled.
-        let to_drop = unsafe { self.mut_ptr(index) };
-        // SAFETY: [`PtrBuffer::mut_ptr`] ensures that the pointer is valid.
-        // [`Buffer::manually_drop`] ensures that the position is filled.
+        let to_drop = unsafe { self.slot(index) };
+        // SAFETY: The pointer is valid. [`Buffer::manually_drop`] ensures that
+        // the position is filled.
         unsafe { ptr::drop_in_place(to_drop) };
     }
 
+    unsafe fn manually_drop_range<R: RangeBounds<usize> + Clone>(&mut self, values_range: R) {
+        let range = normalize_range(values_range, self.cap);
+        self.occupancy.empty_range(range.clone());
+        if range.is_empty() {
+            return;
+        }
+        // SAFETY: The range isn't empty so its start is a valid position.
+        let start = unsafe { self.slot(range.start) };
+        let values = ptr::slice_from_raw_parts_mut(start, range.len());
+        // SAFETY: All the positions in the range are valid and filled, so it's
+        // a valid slice to drop.
+        unsafe { ptr::drop_in_place(values) };
+    }
+
+    unsafe fn fill_range<R: RangeBounds<usize> + Clone>(&mut self, range: R, value: &T)
+    where
+        T: Clone,
+    {
+        let range = normalize_range(range, self.cap);
+        if range.is_empty() {
+            return;
+        }
+        // SAFETY: The range isn't empty so its start is a valid position.
+        let start = unsafe { self.slot(range.start) };
+        let values = ptr::slice_from_raw_parts_mut(start, range.len());
+        // SAFETY: All the positions in the range are valid and empty.
+        unsafe { fill_in_place(values, value) };
+        self.occupancy.fill_range(range);
+    }
+
     unsafe fn try_grow(&mut self, target: usize) -> Result<(), ResizeError> {
-        if self.cap == 0 {
+        let result = if self.cap == 0 {
             // SAFETY: `self.cap` is checked in the conditional.
             // [`Buffer::try_grow`] ensures that `target` > `self.cap` (which is
             // 0)
@@ -138,11 +235,13 @@ impl<T> Buffer for HeapBuffer<T> {
             // [`Buffer::try_grow`] ensures that `target` > `self.cap` (which
             // implies `target` != `self.cap`)
             unsafe { self.resize_array(target) }
-        }
+        };
+        self.occupancy.resize(self.cap);
+        result
     }
 
     unsafe fn try_shrink(&mut self, target: usize) -> Result<(), ResizeError> {
-        if target == 0 {
+        let result = if target == 0 {
             // SAFETY: [`Buffer::try_shrink`] ensures `target` < `self.cap`.
             // This means that `self.cap` > 0 (conditional) and thus
             // `self.buffer_start` is not dangling.
@@ -154,12 +253,15 @@ impl<T> Buffer for HeapBuffer<T> {
             // that `target` != `self.cap`. Also `self.cap` > 0 (conditional)
             // and thus `self.buffer_start` is not dangling.
             unsafe { self.resize_array(target) }
-        }
+        };
+        self.occupancy.resize(self.cap);
+        result
     }
 }
 
 impl<T: Copy> CopyValueBuffer for HeapBuffer<T> {
     unsafe fn copy(&self, index: usize) -> T {
+        self.occupancy.check_filled(index);
         // SAFETY: it has the same requirements
         unsafe { self.read(index) }
     }
@@ -170,23 +272,20 @@ impl<T> PtrBuffer for HeapBuffer<T> {
     type MutablePointer = *mut T;
 
     unsafe fn ptr(&self, index: usize) -> *const T {
-        debug_assert!(index < self.capacity());
-        let ptr = self.buffer_start.as_ptr();
-
-        // SAFETY: `ptr` is at the start, `ptr.add(index)` points to the array's
-        // position. [`PtrBuffer::ptr`] requires that the index is valid and
-        // filled. Thus the pointer also is.
-        unsafe { ptr.add(index) }
+        self.occupancy.escape();
+        // SAFETY: Same requirements.
+        unsafe { self.slot(index) }
     }
 
     unsafe fn mut_ptr(&mut self, index: usize) -> *mut T {
-        debug_assert!(index < self.capacity());
-        let ptr = self.buffer_start.as_ptr();
+        self.occupancy.escape();
+        // SAFETY: Same requirements.
+        unsafe { self.slot(index) }
+    }
 
-        // SAFETY: `ptr` is at the start, `ptr.add(index)` points to the array's
-        // position. [`PtrBuffer::mut_ptr`] requires that the index is valid and
-        // filled. Thus the pointer also is.
-        unsafe { ptr.add(index) }
+    unsafe fn segment_len(&self, index: usize) -> usize {
+        // All the positions are in a single allocation.
+        self.capacity() - index
     }
 }
 
@@ -199,19 +298,19 @@ impl<T> RefBuffer for HeapBuffer<T> {
         Self: 'a;
 
     unsafe fn index<'a: 'b, 'b>(&'a self, index: usize) -> &'b T {
-        // SAFETY: [`RefBuffer::index`] has at least the same requirements as
-        // [`PtrBuffer::ptr`].
-        let ptr = unsafe { self.ptr(index) };
-        // SAFETY: [`PtrBuffer::ptr`] requires that the pointer can be
+        self.occupancy.check_filled(index);
+        // SAFETY: [`RefBuffer::index`] requires the position to be valid.
+        let ptr = unsafe { self.slot(index) };
+        // SAFETY: The position is valid and filled, so the pointer can be
         // dereferenced.
         unsafe { &*ptr }
     }
 
     unsafe fn mut_index<'a: 'b, 'b>(&'a mut self, index: usize) -> &'b mut T {
-        // SAFETY: [`RefBuffer::mut_index`] has at least the same requirements
-        // as [`PtrBuffer::mut_ptr`].
-        let ptr = unsafe { self.mut_ptr(index) };
-        // SAFETY: [`PtrBuffer::mut_ptr`] requires that the pointer can be
+        self.occupancy.check_filled(index);
+        // SAFETY: [`RefBuffer::mut_index`] requires the position to be valid.
+        let ptr = unsafe { self.slot(index) };
+        // SAFETY: The position is valid and filled, so the pointer can be
         // dereferenced.
         unsafe { &mut *ptr }
     }
@@ -219,12 +318,26 @@ impl<T> RefBuffer for HeapBuffer<T> {
 
 impl<T> ContiguousMemoryBuffer for HeapBuffer<T> {}
 
+// SAFETY: The values are in their own heap allocation.
+unsafe impl<T> OutOfLineBuffer for HeapBuffer<T> {}
+
+impl<T> GrowableBuffer for HeapBuffer<T> {}
+
+impl<T> ShrinkableBuffer for HeapBuffer<T> {}
+
 impl<T> Default for HeapBuffer<T> {
     fn default() -> Self {
         Self::new()
     }
 }
 
+// SAFETY: The buffer uniquely owns its allocation (like a `Box<[T]>`), so it
+// can be sent to another thread as long as the values can.
+unsafe impl<T: Send> Send for HeapBuffer<T> {}
+
+// SAFETY: Shared references only give shared access to the values.
+unsafe impl<T: Sync> Sync for HeapBuffer<T> {}
+
 // SAFETY: As a buffer it's not its responsabilities to clean the values that it
 // saves. The container should use [`Buffer::manually_drop`] and
 // [`Buffer::manually_drop_range`] to properly drop the values it contains.
@@ -246,7 +359,7 @@ unsafe impl<#[may_dangle] T> Drop for HeapBuffer<T> {
 ///   * `size` must be bigger than zero.
 unsafe fn try_array_alloc<T>(size: usize) -> Result<NonNull<T>, ResizeError> {
     debug_assert!(size > 0);
-    let layout = Layout::array::<T>(size)?;
+    let layout = array_layout::<T>(size)?;
     // SAFETY: Because `try_array_alloc` ensures that `size` > 0, `layout` is
     // valid to allocate.
     let ptr = unsafe { std::alloc::alloc(layout) };
@@ -267,8 +380,8 @@ unsafe fn try_array_realloc<T>(
     debug_assert!(new_size > 0);
     debug_assert!(old_size != new_size);
 
-    let old_layout = Layout::array::<T>(old_size)?;
-    let new_layout = Layout::array::<T>(new_size)?;
+    let old_layout = array_layout::<T>(old_size)?;
+    let new_layout = array_layout::<T>(new_size)?;
 
     let old_ptr = old_ptr.as_ptr() as *mut u8;
 
@@ -292,7 +405,7 @@ unsafe fn try_array_realloc<T>(
 ///   * `ptr` must point the head of the array to deallocate.
 unsafe fn deallocate<T>(ptr: NonNull<T>, size: usize) -> Result<(), ResizeError> {
     debug_assert!(size > 0);
-    let layout = Layout::array::<T>(size)?;
+    let layout = array_layout::<T>(size)?;
     let ptr = ptr.as_ptr();
     let ptr = ptr as *mut u8;
 
@@ -308,8 +421,25 @@ unsafe fn deallocate<T>(ptr: NonNull<T>, size: usize) -> Result<(), ResizeError>
 
 #[cfg(test)]
 mod tests {
+    use std::sync::atomic::{AtomicI64, Ordering};
+
+    use crate::test_utils::{panic::catch_panic_unwind_silent, panicking::PanickingElement};
+
     use super::*;
 
+    // Compile-time checks: it must be covariant like `Vec`.
+    #[allow(dead_code)]
+    fn is_covariant<'a>(buffer: HeapBuffer<&'static str>) -> HeapBuffer<&'a str> {
+        buffer
+    }
+
+    #[test]
+    fn is_send_and_sync_when_its_elements_are() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<HeapBuffer<u32>>();
+        assert_send_sync::<HeapBuffer<String>>();
+    }
+
     #[test]
     fn can_grow_from_default() {
         const TARGET: usize = 1;
@@ -331,15 +461,26 @@ mod tests {
 
         let mut buffer = HeapBuffer::<i32>::new();
 
-        // SAFETY: 0 < TARGET1 < TARGET2
-        unsafe {
-            buffer.try_grow(TARGET1).unwrap();
-            buffer.try_grow(TARGET2).unwrap();
-        }
+        // SAFETY: 0 < TARGET1
+        unsafe { buffer.try_grow(TARGET1).unwrap() };
+        // SAFETY: TARGET1 < TARGET2
+        unsafe { buffer.try_grow(TARGET2).unwrap() };
 
         assert!(buffer.capacity() >= TARGET2);
     }
 
+    #[test]
+    fn overflowing_the_layout_is_not_out_of_memory() {
+        let requested = usize::MAX / 4;
+        let mut buffer = HeapBuffer::<u64>::new();
+        // SAFETY: 0 < `requested`
+        let result = unsafe { buffer.try_grow(requested) };
+        assert!(
+            matches!(result, Err(ResizeError::LayoutOverflow { requested: r }) if r == requested)
+        );
+        assert_eq!(buffer.capacity(), 0);
+    }
+
     #[test]
     fn can_shrink() {
         const TARGET1: usize = 64;
@@ -347,11 +488,10 @@ mod tests {
 
         let mut buffer = HeapBuffer::<i32>::new();
 
-        // SAFETY: 0 < TARGET2 < TARGET1
-        unsafe {
-            buffer.try_grow(TARGET1).unwrap();
-            buffer.try_shrink(TARGET2).unwrap();
-        }
+        // SAFETY: 0 < TARGET1
+        unsafe { buffer.try_grow(TARGET1).unwrap() };
+        // SAFETY: TARGET2 < TARGET1 and there are no values.
+        unsafe { buffer.try_shrink(TARGET2).unwrap() };
 
         assert!(buffer.capacity() < TARGET1);
         assert!(buffer.capacity() >= TARGET2);
@@ -364,13 +504,37 @@ mod tests {
 
         let mut buffer = HeapBuffer::<i32>::new();
 
-        // SAFETY: 0 == TARGET2 < TARGET1
-        unsafe {
-            buffer.try_grow(TARGET1).unwrap();
-            buffer.try_shrink(TARGET2).unwrap();
-        }
+        // SAFETY: 0 < TARGET1
+        unsafe { buffer.try_grow(TARGET1).unwrap() };
+        // SAFETY: 0 == TARGET2 < TARGET1 and there are no values.
+        unsafe { buffer.try_shrink(TARGET2).unwrap() };
 
         assert!(buffer.capacity() < TARGET1);
         assert!(buffer.capacity() == TARGET2);
     }
+
+    #[test]
+    fn dropping_a_range_drops_the_rest_when_one_panics() {
+        let counter = AtomicI64::new(0);
+        let mut buffer = HeapBuffer::new();
+        // SAFETY: 0 < 4
+        unsafe { buffer.try_grow(4).unwrap() };
+        for i in 0..4 {
+            let value = PanickingElement::new(&counter, i);
+            let value = if i == 1 {
+                value.panicking_on_drop()
+            } else {
+                value
+            };
+            // SAFETY: `i` < 4 and it's empty.
+            unsafe { buffer.put(i as usize, value) };
+        }
+
+        let result = catch_panic_unwind_silent(std::panic::AssertUnwindSafe(|| {
+            // SAFETY: All positions are filled.
+            unsafe { buffer.manually_drop_range(..) };
+        }));
+        assert!(result.is_err());
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+    }
 }
@@ -1,8 +1,10 @@
 use std::marker::PhantomData;
 
 use crate::interface::{
-    copy_value::CopyValueBuffer, ptrs::PtrBuffer, refs::RefBuffer, resize_error::ResizeError,
-    Buffer,
+    capacity_contract::CapacityContract, caps::BufferCaps,
+    contiguous_memory::ContiguousMemoryBuffer, copy_value::CopyValueBuffer,
+    growable::GrowableBuffer, ptrs::PtrBuffer, refs::RefBuffer, resize_error::ResizeError,
+    shrinkable::ShrinkableBuffer, Buffer,
 };
 
 /// Buffer optimized for zero-sized types.
@@ -43,11 +45,17 @@ impl<T> ZstBuffer<T> {
 
 impl<T> Buffer for ZstBuffer<T> {
     type Element = T;
+    const CAPACITY_CONTRACT: CapacityContract = CapacityContract::Unbounded;
 
     fn capacity(&self) -> usize {
         usize::MAX
     }
 
+    #[inline]
+    fn caps(&self) -> BufferCaps {
+        BufferCaps::all()
+    }
+
     unsafe fn take(&mut self, index: usize) -> T {
         // SAFETY: it has the same requirements
         unsafe { self.read(index) }
@@ -66,7 +74,8 @@ impl<T> Buffer for ZstBuffer<T> {
     }
 
     unsafe fn try_shrink(&mut self, _target: usize) -> Result<(), ResizeError> {
-        Err(ResizeError::UnsupportedOperation)
+        // There is no memory to give back.
+        Ok(())
     }
 }
 
@@ -94,6 +103,11 @@ impl<T> PtrBuffer for ZstBuffer<T> {
     unsafe fn mut_ptr(&mut self, _index: usize) -> *mut Self::Element {
         std::ptr::NonNull::dangling().as_ptr()
     }
+
+    unsafe fn segment_len(&self, index: usize) -> usize {
+        // Zero-sized values share the same (dangling) pointer.
+        self.capacity() - index
+    }
 }
 
 impl<T> RefBuffer for ZstBuffer<T> {
@@ -122,3 +136,11 @@ impl<T> RefBuffer for ZstBuffer<T> {
         unsafe { &mut *ptr }
     }
 }
+
+impl<T> ContiguousMemoryBuffer for ZstBuffer<T> {}
+
+/// Its capacity is already the maximum, so it never needs to grow.
+impl<T> GrowableBuffer for ZstBuffer<T> {}
+
+/// It doesn't use any memory, so shrinking trivially succeeds.
+impl<T> ShrinkableBuffer for ZstBuffer<T> {}
@@ -1,8 +1,14 @@
-use std::mem::MaybeUninit;
+use std::{mem::MaybeUninit, ops::RangeBounds};
 
 use crate::interface::{
-    contiguous_memory::ContiguousMemoryBuffer, copy_value::CopyValueBuffer, ptrs::PtrBuffer,
-    refs::RefBuffer, Buffer, ResizeError,
+    capacity_contract::CapacityContract,
+    caps::BufferCaps,
+    contiguous_memory::{drop_range_in_place, fill_range_in_place, ContiguousMemoryBuffer},
+    copy_value::CopyValueBuffer,
+    out_of_line::OutOfLineBuffer,
+    ptrs::PtrBuffer,
+    refs::RefBuffer,
+    Buffer, ResizeError,
 };
 
 /// Buffer which works on top of a mutable slice of maybe-uninit values.
@@ -13,6 +19,16 @@ use crate::interface::{
 /// It's worth noting that, like any other buffer, it doesn't have information
 /// about which values are set and which are not. This information needs to be
 /// handled separately (eg. giving the current size to a vector).
+///
+/// Like any mutable reference, it's invariant in `T`. Otherwise shorter-lived
+/// values could be written into the slice:
+/// ```compile_fail
+/// # use std::mem::MaybeUninit;
+/// # use buffers::base_buffers::SliceBuffer;
+/// fn shorten<'a>(buffer: SliceBuffer<'a, &'static str>) -> SliceBuffer<'a, &'a str> {
+///     buffer
+/// }
+/// ```
 #[repr(transparent)]
 pub struct SliceBuffer<'a, T> {
     slice: &'a mut [MaybeUninit<T>],
@@ -27,6 +43,45 @@ impl<'a, T> SliceBuffer<'a, T> {
         Self { slice }
     }
 
+    /// Makes a `SliceBuffer` on top of a slice of initialized values, so all
+    /// its positions start filled.
+    ///
+    /// Only `Copy` values are allowed: taking or dropping them leaves their
+    /// bytes in place, so the slice still has valid values when it's given
+    /// back.
+    ///
+    /// # Safety
+    /// Uninitialized values must never be written into the buffer (eg. through
+    /// [`crate::collections::Vector::spare_capacity_mut`]). The slice would
+    /// have an invalid value once the borrow ends.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::base_buffers::SliceBuffer;
+    /// # use buffers::interface::Buffer;
+    /// let mut array = [1, 2, 3];
+    /// // SAFETY: Nothing uninitialized is written.
+    /// let mut buffer = unsafe { SliceBuffer::from_initialized_slice(&mut array) };
+    /// // SAFETY: Position 1 is filled.
+    /// let value = unsafe { buffer.take(1) };
+    /// // SAFETY: Position 1 was just emptied.
+    /// unsafe { buffer.put(1, value * 10) };
+    /// drop(buffer);
+    /// assert_eq!(array, [1, 20, 3]);
+    /// ```
+    pub unsafe fn from_initialized_slice(slice: &'a mut [T]) -> Self
+    where
+        T: Copy,
+    {
+        let len = slice.len();
+        let ptr = slice.as_mut_ptr().cast::<MaybeUninit<T>>();
+        // SAFETY: `MaybeUninit<T>` has the same layout as `T`, and the slice
+        // keeps the same borrow. The caller ensures that it only ever holds
+        // initialized values.
+        let slice = unsafe { std::slice::from_raw_parts_mut(ptr, len) };
+        Self { slice }
+    }
+
     /// Internal utility that reads `index`. Used both for copying and for
     /// extracting the value.
     ///
@@ -42,11 +97,17 @@ impl<'a, T> SliceBuffer<'a, T> {
 
 impl<'a, T> Buffer for SliceBuffer<'a, T> {
     type Element = T;
+    const CAPACITY_CONTRACT: CapacityContract = CapacityContract::Exact;
 
     fn capacity(&self) -> usize {
         self.slice.len()
     }
 
+    #[inline]
+    fn caps(&self) -> BufferCaps {
+        BufferCaps::CONTIGUOUS
+    }
+
     unsafe fn take(&mut self, index: usize) -> Self::Element {
         // SAFETY: same requirements
         unsafe { self.read(index) }
@@ -62,6 +123,19 @@ impl<'a, T> Buffer for SliceBuffer<'a, T> {
         unsafe { self.slice[index].assume_init_drop() }
     }
 
+    unsafe fn manually_drop_range<R: RangeBounds<usize> + Clone>(&mut self, values_range: R) {
+        // SAFETY: Same requirements as [`Buffer::manually_drop_range`].
+        unsafe { drop_range_in_place(self, values_range) }
+    }
+
+    unsafe fn fill_range<R: RangeBounds<usize> + Clone>(&mut self, range: R, value: &Self::Element)
+    where
+        Self::Element: Clone,
+    {
+        // SAFETY: Same requirements as [`Buffer::fill_range`].
+        unsafe { fill_range_in_place(self, range, value) }
+    }
+
     unsafe fn try_grow(&mut self, _target: usize) -> Result<(), ResizeError> {
         Err(ResizeError::UnsupportedOperation)
     }
@@ -83,11 +157,25 @@ impl<'a, T> PtrBuffer for SliceBuffer<'a, T> {
     type MutablePointer = *mut T;
 
     unsafe fn ptr(&self, index: usize) -> Self::ConstantPointer {
-        self.slice[index].as_ptr()
+        debug_assert!(index < self.slice.len());
+        // The pointer comes from the whole slice (instead of the position) so it
+        // can be used to reach other positions, as contiguous memory allows.
+        let start = self.slice.as_ptr().cast::<T>();
+        // SAFETY: `index` is a valid position, so it's inside the slice.
+        unsafe { start.add(index) }
     }
 
     unsafe fn mut_ptr(&mut self, index: usize) -> Self::MutablePointer {
-        self.slice[index].as_mut_ptr()
+        debug_assert!(index < self.slice.len());
+        // Same as `ptr`, it comes from the whole slice.
+        let start = self.slice.as_mut_ptr().cast::<T>();
+        // SAFETY: `index` is a valid position, so it's inside the slice.
+        unsafe { start.add(index) }
+    }
+
+    unsafe fn segment_len(&self, index: usize) -> usize {
+        // All the positions are in a single allocation.
+        self.capacity() - index
     }
 }
 
@@ -120,6 +208,9 @@ impl<'a, T> RefBuffer for SliceBuffer<'a, T> {
 
 impl<'a, T> ContiguousMemoryBuffer for SliceBuffer<'a, T> {}
 
+// SAFETY: The values are in the borrowed slice.
+unsafe impl<'a, T> OutOfLineBuffer for SliceBuffer<'a, T> {}
+
 #[cfg(test)]
 mod tests {
     use std::mem::MaybeUninit;
@@ -130,14 +221,33 @@ mod tests {
 
     #[test]
     fn can_be_constructed_from_slice() {
-        let mut array = MaybeUninit::<u32>::uninit_array::<10>();
+        let mut array = [const { MaybeUninit::<u32>::uninit() }; 10];
         let slice = &mut array[..];
 
         let mut buffer = SliceBuffer::from_slice(slice);
 
         const VALUE: u32 = 123;
+        // SAFETY: 0 is a valid position, and it's empty.
         unsafe { buffer.put(0, VALUE) };
+        // SAFETY: 0 is a valid position, and it was just filled.
         let result = unsafe { buffer.take(0) };
         assert_eq!(result, VALUE);
     }
+
+    #[test]
+    fn initialized_slices_start_filled() {
+        let mut array = [1u32, 2, 3];
+
+        // SAFETY: Nothing uninitialized is written.
+        let mut buffer = unsafe { SliceBuffer::from_initialized_slice(&mut array) };
+        assert_eq!(buffer.capacity(), 3);
+        // SAFETY: 2 is filled.
+        assert_eq!(unsafe { buffer.take(2) }, 3);
+        // SAFETY: 0 and 1 are filled and 2 was just emptied.
+        unsafe { buffer.shift_right(0..2, 1) };
+        // SAFETY: 0 was just emptied.
+        unsafe { buffer.put(0, 7) };
+
+        assert_eq!(array, [7, 1, 2]);
+    }
 }
@@ -1,12 +1,22 @@
 use std::{
-    alloc::{Allocator, Global, Layout},
+    alloc::{Allocator, Global},
     marker::PhantomData,
+    ops::RangeBounds,
     ptr::NonNull,
 };
 
 use crate::interface::{
-    contiguous_memory::ContiguousMemoryBuffer, copy_value::CopyValueBuffer, ptrs::PtrBuffer,
-    refs::RefBuffer, resize_error::ResizeError, Buffer,
+    capacity_contract::CapacityContract,
+    caps::BufferCaps,
+    contiguous_memory::{drop_range_in_place, fill_range_in_place, ContiguousMemoryBuffer},
+    copy_value::CopyValueBuffer,
+    growable::GrowableBuffer,
+    out_of_line::OutOfLineBuffer,
+    ptrs::PtrBuffer,
+    refs::RefBuffer,
+    resize_error::{array_layout, ResizeError},
+    shrinkable::ShrinkableBuffer,
+    Buffer,
 };
 
 /// Buffer that dynamically allocates using an [`Allocator`].
@@ -40,6 +50,11 @@ impl<T, A: Allocator> AllocatorBuffer<T, A> {
         }
     }
 
+    /// Allocator it uses.
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
     unsafe fn read(&self, index: usize) -> T {
         // SAFETY: [`Buffer::take`] ensures that the position is valid and
         // filled.
@@ -58,11 +73,17 @@ impl<T, A: Allocator> AllocatorBuffer<T, A> {
 
 impl<T, A: Allocator> Buffer for AllocatorBuffer<T, A> {
     type Element = T;
+    const CAPACITY_CONTRACT: CapacityContract = CapacityContract::Exact;
 
     fn capacity(&self) -> usize {
         self.cap
     }
 
+    #[inline]
+    fn caps(&self) -> BufferCaps {
+        BufferCaps::all()
+    }
+
     unsafe fn take(&mut self, index: usize) -> T {
         // SAFETY: it has the same requirements
         unsafe { self.read(index) }
@@ -85,6 +106,19 @@ impl<T, A: Allocator> Buffer for AllocatorBuffer<T, A> {
         unsafe { std::ptr::drop_in_place(ptr) };
     }
 
+    unsafe fn manually_drop_range<R: RangeBounds<usize> + Clone>(&mut self, values_range: R) {
+        // SAFETY: Same requirements as [`Buffer::manually_drop_range`].
+        unsafe { drop_range_in_place(self, values_range) }
+    }
+
+    unsafe fn fill_range<R: RangeBounds<usize> + Clone>(&mut self, range: R, value: &Self::Element)
+    where
+        Self::Element: Clone,
+    {
+        // SAFETY: Same requirements as [`Buffer::fill_range`].
+        unsafe { fill_range_in_place(self, range, value) }
+    }
+
     unsafe fn try_grow(&mut self, target: usize) -> Result<(), ResizeError> {
         let ptr = if self.cap > 0 {
             // SAFETY: `self.cap` is checked in the conditional.
@@ -151,6 +185,11 @@ impl<T, A: Allocator> PtrBuffer for AllocatorBuffer<T, A> {
         // filled. Thus the pointer also is.
         unsafe { ptr.add(index) }
     }
+
+    unsafe fn segment_len(&self, index: usize) -> usize {
+        // All the positions are in a single allocation.
+        self.capacity() - index
+    }
 }
 
 impl<T, A: Allocator> RefBuffer for AllocatorBuffer<T, A> {
@@ -182,12 +221,27 @@ impl<T, A: Allocator> RefBuffer for AllocatorBuffer<T, A> {
 
 impl<T, A: Allocator> ContiguousMemoryBuffer for AllocatorBuffer<T, A> {}
 
+// SAFETY: The values are in memory given by the allocator.
+unsafe impl<T, A: Allocator> OutOfLineBuffer for AllocatorBuffer<T, A> {}
+
+impl<T, A: Allocator> GrowableBuffer for AllocatorBuffer<T, A> {}
+
+impl<T, A: Allocator> ShrinkableBuffer for AllocatorBuffer<T, A> {}
+
 impl<T, A: Allocator + Default> Default for AllocatorBuffer<T, A> {
     fn default() -> Self {
         Self::new()
     }
 }
 
+// SAFETY: The buffer uniquely owns its allocation (like a `Box<[T], A>`), so it
+// can be sent to another thread as long as the values and the allocator can.
+unsafe impl<T: Send, A: Allocator + Send> Send for AllocatorBuffer<T, A> {}
+
+// SAFETY: Shared references only give shared access to the values and the
+// allocator.
+unsafe impl<T: Sync, A: Allocator + Sync> Sync for AllocatorBuffer<T, A> {}
+
 // SAFETY: As a buffer it's not its responsabilities to clean the values that it
 // saves. The container should use [`Buffer::manually_drop`] and
 // [`Buffer::manually_drop_range`] to properly drop the values it contains.
@@ -211,7 +265,7 @@ unsafe impl<#[may_dangle] T, A: Allocator> Drop for AllocatorBuffer<T, A> {
 ///   * `size` must be bigger than zero.
 unsafe fn try_allocate<T, A: Allocator>(alloc: &A, size: usize) -> Result<NonNull<T>, ResizeError> {
     debug_assert!(size > 0);
-    let new_layout = Layout::array::<T>(size)?;
+    let new_layout = array_layout::<T>(size)?;
 
     let new_ptr = alloc.allocate(new_layout)?;
 
@@ -235,8 +289,8 @@ unsafe fn try_grow<T, A: Allocator>(
 ) -> Result<NonNull<T>, ResizeError> {
     debug_assert!(new_size > old_size);
 
-    let old_layout = Layout::array::<T>(old_size)?;
-    let new_layout = Layout::array::<T>(new_size)?;
+    let old_layout = array_layout::<T>(old_size)?;
+    let new_layout = array_layout::<T>(new_size)?;
 
     // SAFETY:
     //  * `old_ptr` should be currently managed by `alloc` (precondition).
@@ -267,8 +321,8 @@ unsafe fn try_shrink<T, A: Allocator>(
     debug_assert!(new_size > 0);
     debug_assert!(new_size < old_size);
 
-    let old_layout = Layout::array::<T>(old_size)?;
-    let new_layout = Layout::array::<T>(new_size)?;
+    let old_layout = array_layout::<T>(old_size)?;
+    let new_layout = array_layout::<T>(new_size)?;
 
     // SAFETY:
     //  * `old_ptr` should be currently managed by `alloc` (precondition).
@@ -293,7 +347,7 @@ unsafe fn try_deallocate<T, A: Allocator>(
     old_ptr: NonNull<T>,
     old_size: usize,
 ) -> Result<(), ResizeError> {
-    let old_layout = Layout::array::<T>(old_size)?;
+    let old_layout = array_layout::<T>(old_size)?;
 
     // SAFETY:
     //  * `old_ptr` should be currently managed by `alloc` (precondition).
@@ -306,6 +360,35 @@ unsafe fn try_deallocate<T, A: Allocator>(
 mod tests {
     use super::*;
 
+    // Compile-time checks: it must be covariant like `Vec`.
+    #[allow(dead_code)]
+    fn is_covariant<'a>(buffer: AllocatorBuffer<&'static str>) -> AllocatorBuffer<&'a str> {
+        buffer
+    }
+
+    #[test]
+    fn overflowing_the_layout_is_not_out_of_memory() {
+        let mut buffer = AllocatorBuffer::<u32>::new();
+        // SAFETY: 0 < 1
+        unsafe { buffer.try_grow(1).unwrap() };
+        // SAFETY: 1 < `usize::MAX`
+        let result = unsafe { buffer.try_grow(usize::MAX) };
+        assert!(matches!(
+            result,
+            Err(ResizeError::LayoutOverflow {
+                requested: usize::MAX
+            })
+        ));
+        assert_eq!(buffer.capacity(), 1);
+    }
+
+    #[test]
+    fn is_send_and_sync_when_its_elements_and_allocator_are() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<AllocatorBuffer<u32>>();
+        assert_send_sync::<AllocatorBuffer<String, Global>>();
+    }
+
     #[test]
     fn can_grow_from_default() {
         const TARGET: usize = 1;
@@ -327,11 +410,10 @@ mod tests {
 
         let mut buffer = AllocatorBuffer::<i32, Global>::new();
 
-        // SAFETY: 0 < TARGET1 < TARGET2
-        unsafe {
-            buffer.try_grow(TARGET1).unwrap();
-            buffer.try_grow(TARGET2).unwrap();
-        }
+        // SAFETY: 0 < TARGET1
+        unsafe { buffer.try_grow(TARGET1).unwrap() };
+        // SAFETY: TARGET1 < TARGET2
+        unsafe { buffer.try_grow(TARGET2).unwrap() };
 
         assert!(buffer.capacity() >= TARGET2);
     }
@@ -343,11 +425,10 @@ mod tests {
 
         let mut buffer = AllocatorBuffer::<i32, Global>::new();
 
-        // SAFETY: 0 < TARGET2 < TARGET1
-        unsafe {
-            buffer.try_grow(TARGET1).unwrap();
-            buffer.try_shrink(TARGET2).unwrap();
-        }
+        // SAFETY: 0 < TARGET1
+        unsafe { buffer.try_grow(TARGET1).unwrap() };
+        // SAFETY: TARGET2 < TARGET1 and there are no values.
+        unsafe { buffer.try_shrink(TARGET2).unwrap() };
 
         assert!(buffer.capacity() < TARGET1);
         assert!(buffer.capacity() >= TARGET2);
@@ -360,11 +441,10 @@ mod tests {
 
         let mut buffer = AllocatorBuffer::<i32, Global>::new();
 
-        // SAFETY: 0 == TARGET2 < TARGET1
-        unsafe {
-            buffer.try_grow(TARGET1).unwrap();
-            buffer.try_shrink(TARGET2).unwrap();
-        }
+        // SAFETY: 0 < TARGET1
+        unsafe { buffer.try_grow(TARGET1).unwrap() };
+        // SAFETY: 0 == TARGET2 < TARGET1 and there are no values.
+        unsafe { buffer.try_shrink(TARGET2).unwrap() };
 
         assert!(buffer.capacity() < TARGET1);
         assert!(buffer.capacity() == TARGET2);
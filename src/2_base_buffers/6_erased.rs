@@ -0,0 +1,476 @@
+use std::{
+    alloc::Layout,
+    ops::{Range, RangeBounds},
+    ptr::{self, NonNull},
+};
+
+use crate::interface::{
+    buffer::normalize_range,
+    resize_error::{array_size, ResizeError},
+    shift,
+};
+
+/// Runtime description of the elements of an [`ErasedBuffer`]: their memory
+/// layout and how to drop them.
+#[derive(Clone, Copy, Debug)]
+pub struct ErasedLayout {
+    layout: Layout,
+    drop: Option<unsafe fn(*mut u8)>,
+}
+
+impl ErasedLayout {
+    /// Makes the layout of `T`.
+    ///
+    /// ```
+    /// # use buffers::base_buffers::erased::ErasedLayout;
+    /// let layout = ErasedLayout::of::<String>();
+    /// assert_eq!(layout.layout(), std::alloc::Layout::new::<String>());
+    /// ```
+    pub fn of<T>() -> Self {
+        Self {
+            layout: Layout::new::<T>(),
+            drop: if std::mem::needs_drop::<T>() {
+                Some(drop_erased::<T>)
+            } else {
+                None
+            },
+        }
+    }
+
+    /// Makes a layout given the memory layout of the elements and the
+    /// function which drops one of them in place (`None` if they don't need
+    /// dropping).
+    ///
+    /// # Safety
+    /// `drop` must be sound to call with a pointer to any value stored in a
+    /// buffer which uses this layout.
+    pub unsafe fn new(layout: Layout, drop: Option<unsafe fn(*mut u8)>) -> Self {
+        Self { layout, drop }
+    }
+
+    /// Memory layout of a single element.
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// Distance in bytes between two consecutive elements.
+    fn stride(&self) -> usize {
+        self.layout.pad_to_align().size()
+    }
+
+    /// Memory layout of an array of `len` elements.
+    fn array(&self, len: usize) -> Result<Layout, ResizeError> {
+        let size = array_size(self.stride(), len)?;
+        Ok(Layout::from_size_align(size, self.layout.align())?)
+    }
+}
+
+/// Buffer which stores elements the type of which is only known at runtime
+/// (described by an [`ErasedLayout`]), using a heap-allocated contiguous
+/// array.
+///
+/// Because the element type isn't known at compile time it cannot implement
+/// [`crate::interface::Buffer`]. Instead it has the same methods, but values
+/// are moved in and out through pointers. This is useful to make
+/// heterogeneous collections (like the columns of an ECS) where each one
+/// stores a different type.
+///
+/// It's neither [`Send`] nor [`Sync`], since it cannot know if its elements
+/// are.
+///
+/// # Example
+/// ```
+/// # use buffers::base_buffers::erased::{ErasedBuffer, ErasedLayout};
+/// let mut buffer = ErasedBuffer::new(ErasedLayout::of::<String>());
+/// // SAFETY: 4 is bigger than the capacity (0).
+/// unsafe { buffer.try_grow(4) }.unwrap();
+/// // SAFETY: The buffer stores strings and 0 is valid and empty.
+/// unsafe { buffer.put_as(0, String::from("value")) };
+/// // SAFETY: The buffer stores strings and 0 is filled.
+/// assert_eq!(unsafe { buffer.take_as::<String>(0) }, "value");
+/// ```
+pub struct ErasedBuffer {
+    buffer_start: NonNull<u8>,
+    cap: usize,
+    layout: ErasedLayout,
+}
+
+impl ErasedBuffer {
+    /// Makes a new empty buffer for elements with the given layout.
+    pub fn new(layout: ErasedLayout) -> Self {
+        Self {
+            buffer_start: dangling(layout.layout),
+            cap: if layout.stride() == 0 { usize::MAX } else { 0 },
+            layout,
+        }
+    }
+
+    /// Layout of the elements.
+    pub fn layout(&self) -> ErasedLayout {
+        self.layout
+    }
+
+    /// Same as [`crate::interface::Buffer::capacity`].
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// Same as [`crate::interface::Buffer::allocated_bytes`].
+    pub fn allocated_bytes(&self) -> usize {
+        self.cap * self.layout.stride()
+    }
+
+    /// Same as [`crate::interface::ptrs::PtrBuffer::ptr`], but untyped.
+    ///
+    /// # Safety
+    /// `index` must be less than the capacity.
+    pub unsafe fn ptr(&self, index: usize) -> *const u8 {
+        debug_assert!(index < self.capacity());
+        // SAFETY: `index` is valid, so it's in the same allocation.
+        unsafe { self.buffer_start.as_ptr().add(index * self.layout.stride()) }
+    }
+
+    /// Same as [`crate::interface::ptrs::PtrBuffer::mut_ptr`], but untyped.
+    ///
+    /// # Safety
+    /// `index` must be less than the capacity.
+    pub unsafe fn mut_ptr(&mut self, index: usize) -> *mut u8 {
+        debug_assert!(index < self.capacity());
+        // SAFETY: `index` is valid, so it's in the same allocation.
+        unsafe { self.buffer_start.as_ptr().add(index * self.layout.stride()) }
+    }
+
+    /// Same as [`crate::interface::Buffer::put`], but it moves the value
+    /// that `value` points to into the buffer.
+    ///
+    /// # Safety
+    ///   * `index` must be less than the capacity.
+    ///   * The `index` position must be empty.
+    ///   * `value` must point to a valid value described by the layout, which
+    ///     is moved (it must not be used or dropped afterwards).
+    pub unsafe fn put(&mut self, index: usize, value: *const u8) {
+        // SAFETY: `index` is valid.
+        let dst = unsafe { self.mut_ptr(index) };
+        // SAFETY: Both point to (different) values of the layout's size.
+        unsafe { ptr::copy_nonoverlapping(value, dst, self.layout.layout.size()) };
+    }
+
+    /// Same as [`crate::interface::Buffer::take`], but it moves the value
+    /// into `dst`.
+    ///
+    /// # Safety
+    ///   * `index` must be less than the capacity.
+    ///   * The `index` position must be filled.
+    ///   * `dst` must be valid to write a value described by the layout.
+    pub unsafe fn take(&mut self, index: usize, dst: *mut u8) {
+        // SAFETY: `index` is valid.
+        let src = unsafe { self.ptr(index) };
+        // SAFETY: Both point to (different) values of the layout's size.
+        unsafe { ptr::copy_nonoverlapping(src, dst, self.layout.layout.size()) };
+    }
+
+    /// Same as [`ErasedBuffer::put`], but it moves `count` consecutive values
+    /// at once (in a single copy).
+    ///
+    /// # Safety
+    ///   * Positions `index..index + count` must be valid and empty.
+    ///   * `values` must point to `count` consecutive valid values described
+    ///     by the layout (like an array), which are moved.
+    pub unsafe fn put_many(&mut self, index: usize, values: *const u8, count: usize) {
+        if count == 0 {
+            return;
+        }
+        // SAFETY: `index` is valid, since the whole range is.
+        let dst = unsafe { self.mut_ptr(index) };
+        // SAFETY: Both point to (different) arrays of `count` values.
+        unsafe { ptr::copy_nonoverlapping(values, dst, count * self.layout.stride()) };
+    }
+
+    /// Typed version of [`ErasedBuffer::put`].
+    ///
+    /// # Safety
+    ///   * `T` must be the type described by the layout.
+    ///   * `index` must be less than the capacity.
+    ///   * The `index` position must be empty.
+    pub unsafe fn put_as<T>(&mut self, index: usize, value: T) {
+        debug_assert!(Layout::new::<T>() == self.layout.layout);
+        // SAFETY: `index` is valid.
+        let dst = unsafe { self.mut_ptr(index) };
+        // SAFETY: The position is valid, empty and the type matches.
+        unsafe { dst.cast::<T>().write(value) };
+    }
+
+    /// Typed version of [`ErasedBuffer::take`].
+    ///
+    /// # Safety
+    ///   * `T` must be the type described by the layout.
+    ///   * `index` must be less than the capacity.
+    ///   * The `index` position must be filled.
+    pub unsafe fn take_as<T>(&mut self, index: usize) -> T {
+        debug_assert!(Layout::new::<T>() == self.layout.layout);
+        // SAFETY: `index` is valid.
+        let src = unsafe { self.ptr(index) };
+        // SAFETY: The position is valid, filled and the type matches.
+        unsafe { src.cast::<T>().read() }
+    }
+
+    /// Same as [`crate::interface::Buffer::manually_drop`].
+    ///
+    /// # Safety
+    ///   * `index` must be less than the capacity.
+    ///   * The `index` position must be filled.
+    pub unsafe fn manually_drop(&mut self, index: usize) {
+        if let Some(drop) = self.layout.drop {
+            // SAFETY: `index` is valid.
+            let value = unsafe { self.mut_ptr(index) };
+            // SAFETY: The position is filled, and the layout ensures that its
+            // drop function can be used with it.
+            unsafe { drop(value) };
+        }
+    }
+
+    /// Same as [`crate::interface::Buffer::manually_drop_range`].
+    ///
+    /// # Safety
+    /// All the positions in `values_range` must be valid and filled.
+    pub unsafe fn manually_drop_range<R: RangeBounds<usize>>(&mut self, values_range: R) {
+        if self.layout.drop.is_none() {
+            return;
+        }
+
+        /// Keeps dropping the rest if dropping a value panics.
+        struct Guard<'a> {
+            buffer: &'a mut ErasedBuffer,
+            remaining: Range<usize>,
+        }
+        impl Guard<'_> {
+            fn drop_remaining(&mut self) {
+                // The index is removed from `remaining` before dropping, so a
+                // panicking value isn't dropped twice.
+                for index in self.remaining.by_ref() {
+                    // SAFETY: The whole range is valid and filled.
+                    unsafe { self.buffer.manually_drop(index) };
+                }
+            }
+        }
+        impl Drop for Guard<'_> {
+            fn drop(&mut self) {
+                // Only does something if a value panicked while being dropped.
+                self.drop_remaining();
+            }
+        }
+
+        let mut guard = Guard {
+            remaining: self.clamp(values_range),
+            buffer: self,
+        };
+        guard.drop_remaining();
+    }
+
+    /// Same as [`crate::interface::Buffer::try_grow`].
+    ///
+    /// # Safety
+    /// `target` must be bigger than the current capacity.
+    pub unsafe fn try_grow(&mut self, target: usize) -> Result<(), ResizeError> {
+        debug_assert!(target > self.cap);
+        // SAFETY: `target` is different and bigger than zero.
+        unsafe { self.resize(target) }
+    }
+
+    /// Same as [`crate::interface::Buffer::try_shrink`].
+    ///
+    /// # Safety
+    ///   * `target` must be smaller than the current capacity.
+    ///   * Positions from `target` to the capacity must be empty.
+    pub unsafe fn try_shrink(&mut self, target: usize) -> Result<(), ResizeError> {
+        debug_assert!(target < self.cap);
+        if self.layout.stride() == 0 {
+            // There is no memory to give back.
+            return Ok(());
+        }
+        // SAFETY: `target` is different from the capacity.
+        unsafe { self.resize(target) }
+    }
+
+    /// Same as [`crate::interface::Buffer::shift_right`].
+    ///
+    /// # Safety
+    ///   * All positions in `to_move` must be valid.
+    ///   * `positions` positions after the `to_move` range must be valid and
+    ///     empty.
+    pub unsafe fn shift_right<R: RangeBounds<usize>>(&mut self, to_move: R, positions: usize) {
+        let range = self.clamp(to_move);
+        shift::check_right(&range, positions, self.capacity());
+        if range.is_empty() {
+            return;
+        }
+        // SAFETY: The range is valid.
+        let src = unsafe { self.mut_ptr(range.start) };
+        // SAFETY: The destination is valid too, in the same allocation.
+        let dst = unsafe { src.add(positions * self.layout.stride()) };
+        // SAFETY: Both ranges are valid (they may overlap).
+        unsafe { ptr::copy(src, dst, range.len() * self.layout.stride()) };
+    }
+
+    /// Same as [`crate::interface::Buffer::shift_left`].
+    ///
+    /// # Safety
+    ///   * All positions in `to_move` must be valid.
+    ///   * `positions` positions before the `to_move` range must be valid and
+    ///     empty.
+    pub unsafe fn shift_left<R: RangeBounds<usize>>(&mut self, to_move: R, positions: usize) {
+        let range = self.clamp(to_move);
+        shift::check_left(&range, positions);
+        if range.is_empty() {
+            return;
+        }
+        // SAFETY: The destination range is valid.
+        let dst = unsafe { self.mut_ptr(range.start - positions) };
+        // SAFETY: The range is valid too, in the same allocation.
+        let src = unsafe { dst.add(positions * self.layout.stride()) };
+        // SAFETY: Both ranges are valid (they may overlap).
+        unsafe { ptr::copy(src, dst, range.len() * self.layout.stride()) };
+    }
+
+    /// Internal utility that makes a [`Range`] out of any range, using the
+    /// capacity as the end of unbounded ones.
+    fn clamp<R: RangeBounds<usize>>(&self, range: R) -> Range<usize> {
+        normalize_range(range, self.capacity())
+    }
+
+    /// Internal function that allocates, reallocates or deallocates the array
+    /// so it has `target` positions.
+    ///
+    /// # Safety
+    ///   * `target` must be different than the capacity.
+    ///   * The elements must not be zero-sized.
+    unsafe fn resize(&mut self, target: usize) -> Result<(), ResizeError> {
+        debug_assert!(target != self.cap);
+        let new_layout = self.layout.array(target)?;
+        let ptr = if self.cap == 0 {
+            // SAFETY: `target` != 0 and the elements aren't zero-sized.
+            unsafe { std::alloc::alloc(new_layout) }
+        } else {
+            let old_layout = self.layout.array(self.cap)?;
+            if target == 0 {
+                // SAFETY: It was allocated with the same layout.
+                unsafe { std::alloc::dealloc(self.buffer_start.as_ptr(), old_layout) };
+                self.buffer_start = dangling(self.layout.layout);
+                self.cap = 0;
+                return Ok(());
+            }
+            // SAFETY: It was allocated with `old_layout` and the new size is
+            // bigger than zero.
+            unsafe {
+                std::alloc::realloc(self.buffer_start.as_ptr(), old_layout, new_layout.size())
+            }
+        };
+        self.buffer_start = NonNull::new(ptr).ok_or(ResizeError::OutOfMemory)?;
+        self.cap = target;
+        Ok(())
+    }
+}
+
+impl Drop for ErasedBuffer {
+    fn drop(&mut self) {
+        if self.cap != 0 && self.layout.stride() != 0 {
+            // SAFETY: The capacity isn't 0. Like other buffers, it doesn't drop
+            // its values.
+            let _ = unsafe { self.resize(0) };
+        }
+    }
+}
+
+/// Internal utility that drops a value of type `T` in place.
+///
+/// # Safety
+/// `ptr` must point to a valid `T`.
+unsafe fn drop_erased<T>(ptr: *mut u8) {
+    // SAFETY: The caller ensures it's a valid `T`.
+    unsafe { ptr.cast::<T>().drop_in_place() }
+}
+
+/// Internal utility that makes a dangling pointer, aligned for `layout`.
+fn dangling(layout: Layout) -> NonNull<u8> {
+    NonNull::new(ptr::without_provenance_mut(layout.align())).expect("Alignment is never zero")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicI64, Ordering};
+
+    use crate::test_utils::life_counter::LifeCounter;
+
+    use super::*;
+
+    #[test]
+    fn stores_values_of_a_runtime_layout() {
+        let mut buffer = ErasedBuffer::new(ErasedLayout::of::<u16>());
+        // SAFETY: 4 is bigger than 0.
+        unsafe { buffer.try_grow(4) }.unwrap();
+        for i in 0..3u16 {
+            let value = i * 10;
+            // SAFETY: `i` is valid and empty. `value` is a `u16`.
+            unsafe { buffer.put(i as usize, (&value as *const u16).cast()) };
+        }
+        // [0, 10, 20, _] -> [0, _, 10, 20]
+        // SAFETY: `1..3` is filled and 3 is empty.
+        unsafe { buffer.shift_right(1..3, 1) };
+        // SAFETY: It's filled with a `u16`.
+        assert_eq!(unsafe { buffer.take_as::<u16>(3) }, 20);
+
+        let mut value = 0u16;
+        // SAFETY: It's filled with a `u16`, which is moved into `value`.
+        unsafe { buffer.take(2, (&mut value as *mut u16).cast()) };
+        assert_eq!(value, 10);
+    }
+
+    #[test]
+    fn drops_values_with_the_layout_drop_function() {
+        let counter = AtomicI64::new(0);
+        let mut buffer = ErasedBuffer::new(ErasedLayout::of::<LifeCounter<'_>>());
+        // SAFETY: 3 is bigger than 0.
+        unsafe { buffer.try_grow(3) }.unwrap();
+        for i in 0..3 {
+            // SAFETY: It stores `LifeCounter`s and `i` is valid and empty.
+            unsafe { buffer.put_as(i, LifeCounter::new(&counter)) };
+        }
+        // SAFETY: 0 is filled.
+        unsafe { buffer.manually_drop(0) };
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+        // SAFETY: `1..3` are filled.
+        unsafe { buffer.manually_drop_range(1..) };
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn cannot_grow_past_isize_max_bytes() {
+        let mut buffer = ErasedBuffer::new(ErasedLayout::of::<u32>());
+        let requested = isize::MAX as usize / 4 + 1;
+        // SAFETY: It's bigger than 0.
+        let result = unsafe { buffer.try_grow(requested) };
+        assert!(
+            matches!(result, Err(ResizeError::LayoutOverflow { requested: r }) if r == requested)
+        );
+        assert_eq!(buffer.capacity(), 0);
+    }
+
+    #[test]
+    fn can_shrink_to_nothing() {
+        let mut buffer = ErasedBuffer::new(ErasedLayout::of::<u64>());
+        // SAFETY: 8 is bigger than 0.
+        unsafe { buffer.try_grow(8) }.unwrap();
+        // SAFETY: 2 is smaller than 8 and everything is empty.
+        unsafe { buffer.try_shrink(2) }.unwrap();
+        assert_eq!(buffer.capacity(), 2);
+        // SAFETY: 0 is smaller than 2 and everything is empty.
+        unsafe { buffer.try_shrink(0) }.unwrap();
+        assert_eq!(buffer.capacity(), 0);
+    }
+
+    #[test]
+    fn zero_sized_elements_never_allocate() {
+        let buffer = ErasedBuffer::new(ErasedLayout::of::<()>());
+        assert_eq!(buffer.capacity(), usize::MAX);
+    }
+}
@@ -0,0 +1,410 @@
+use std::{
+    marker::PhantomData,
+    mem,
+    ops::RangeBounds,
+    ptr::{self, NonNull},
+};
+
+use crate::interface::{
+    capacity_contract::CapacityContract,
+    caps::BufferCaps,
+    contiguous_memory::{drop_range_in_place, fill_range_in_place, ContiguousMemoryBuffer},
+    copy_value::CopyValueBuffer,
+    growable::GrowableBuffer,
+    out_of_line::OutOfLineBuffer,
+    ptrs::PtrBuffer,
+    refs::RefBuffer,
+    resize_error::{array_size, ResizeError},
+    shrinkable::ShrinkableBuffer,
+    Buffer,
+};
+
+/// Buffer for debugging (like Electric Fence) that maps its elements right
+/// before an inaccessible guard page, so writing (or reading) past the end
+/// faults immediately instead of corrupting other memory. Optionally there is
+/// another guard page before the start.
+///
+/// Every allocation takes whole pages (plus the guards) directly from the
+/// system, and growing always moves the elements to a new mapping: it's slow
+/// and wasteful, only meant to find bugs in unsafe code.
+///
+/// It requires the `guarded` feature and a Unix system.
+///
+/// The front guard only catches going before the first element when the
+/// elements take whole pages: otherwise there is some unused memory between
+/// it and the first element. Types aligned to more than a page can't be
+/// mapped (it fails with [`ResizeError::UnsupportedOperation`]), since the
+/// elements wouldn't end right at the back guard.
+///
+/// # Example
+/// ```
+/// # use buffers::{base_buffers::GuardedBuffer, collections::Vector};
+/// let mut vec = Vector::from_buffer(GuardedBuffer::<u64>::with_front_guard());
+/// vec.extend_from_slice(&[1, 2, 3]);
+/// assert_eq!(vec.as_slice(), [1, 2, 3]);
+/// // Writing through a pointer past `vec.capacity()` would crash.
+/// ```
+pub struct GuardedBuffer<T> {
+    ptr: NonNull<T>,
+    cap: usize,
+    mapping: Option<Mapping>,
+    front_guard: bool,
+    _marker: PhantomData<T>,
+}
+
+impl<T> GuardedBuffer<T> {
+    /// Makes an empty buffer with a guard page after the elements.
+    pub fn new() -> Self {
+        Self {
+            ptr: NonNull::dangling(),
+            cap: 0,
+            mapping: None,
+            front_guard: false,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Makes an empty buffer with guard pages both before and after the
+    /// elements. Going before the first element is only caught if they take
+    /// whole pages.
+    pub fn with_front_guard() -> Self {
+        Self {
+            front_guard: true,
+            ..Self::new()
+        }
+    }
+
+    /// Internal utility that gets a pointer to `index`.
+    ///
+    /// # Safety
+    /// `index` must be less than `capacity`.
+    unsafe fn slot(&self, index: usize) -> *mut T {
+        debug_assert!(index < self.cap);
+        // SAFETY: The position is inside the mapping.
+        unsafe { self.ptr.as_ptr().add(index) }
+    }
+
+    /// Internal utility that moves the first `keep` positions to a new mapping
+    /// (or none, if `target` is 0) for `target` elements.
+    ///
+    /// # Safety
+    /// `keep` must be at most `target` and `self.cap`.
+    unsafe fn remap(&mut self, target: usize, keep: usize) -> Result<(), ResizeError> {
+        debug_assert!(keep <= target && keep <= self.cap);
+        let bytes = array_size(mem::size_of::<T>(), target)?;
+
+        let (ptr, mapping) = if bytes == 0 {
+            (NonNull::dangling(), None)
+        } else if mem::align_of::<T>() > page_size() {
+            // Ending at the (page-aligned) guard would misalign the elements.
+            return Err(ResizeError::UnsupportedOperation);
+        } else {
+            let mapping = Mapping::new(bytes, self.front_guard)?;
+            // The elements end right at the back guard.
+            let ptr = mapping.accessible_end().wrapping_sub(bytes).cast::<T>();
+            let ptr = NonNull::new(ptr).ok_or(ResizeError::OutOfMemory)?;
+            (ptr, Some(mapping))
+        };
+
+        // SAFETY: Both have space for `keep` elements and they are different
+        // mappings, so they don't overlap (zero-sized values need no copy).
+        unsafe { ptr::copy_nonoverlapping(self.ptr.as_ptr(), ptr.as_ptr(), keep) };
+        self.ptr = ptr;
+        self.cap = target;
+        // The old mapping (if any) is unmapped when dropped.
+        self.mapping = mapping;
+        Ok(())
+    }
+}
+
+impl<T> Buffer for GuardedBuffer<T> {
+    type Element = T;
+    const CAPACITY_CONTRACT: CapacityContract = CapacityContract::Exact;
+
+    fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// Whole pages are mapped, but the guard pages don't use any memory.
+    fn allocated_bytes(&self) -> usize {
+        let guards = 1 + self.front_guard as usize;
+        self.mapping
+            .as_ref()
+            .map_or(0, |mapping| mapping.len - guards * page_size())
+    }
+
+    #[inline]
+    fn caps(&self) -> BufferCaps {
+        BufferCaps::all()
+    }
+
+    unsafe fn take(&mut self, index: usize) -> T {
+        // SAFETY: [`Buffer::take`] ensures that the position is valid.
+        let ptr = unsafe { self.slot(index) };
+        // SAFETY: [`Buffer::take`] ensures that the position is filled.
+        unsafe { ptr::read(ptr) }
+    }
+
+    unsafe fn put(&mut self, index: usize, value: T) {
+        // SAFETY: [`Buffer::put`] ensures that the position is valid.
+        let ptr = unsafe { self.slot(index) };
+        // SAFETY: [`Buffer::put`] ensures that the position is empty.
+        unsafe { ptr::write(ptr, value) };
+    }
+
+    unsafe fn manually_drop(&mut self, index: usize) {
+        // SAFETY: [`Buffer::manually_drop`] ensures that the position is
+        // valid.
+        let ptr = unsafe { self.slot(index) };
+        // SAFETY: [`Buffer::manually_drop`] ensures that the position is
+        // filled.
+        unsafe { ptr::drop_in_place(ptr) };
+    }
+
+    unsafe fn manually_drop_range<R: RangeBounds<usize> + Clone>(&mut self, values_range: R) {
+        // SAFETY: Same requirements as [`Buffer::manually_drop_range`].
+        unsafe { drop_range_in_place(self, values_range) }
+    }
+
+    unsafe fn fill_range<R: RangeBounds<usize> + Clone>(&mut self, range: R, value: &Self::Element)
+    where
+        Self::Element: Clone,
+    {
+        // SAFETY: Same requirements as [`Buffer::fill_range`].
+        unsafe { fill_range_in_place(self, range, value) }
+    }
+
+    unsafe fn try_grow(&mut self, target: usize) -> Result<(), ResizeError> {
+        // SAFETY: [`Buffer::try_grow`] ensures that `target` > `self.cap`.
+        unsafe { self.remap(target, self.cap) }
+    }
+
+    unsafe fn try_shrink(&mut self, target: usize) -> Result<(), ResizeError> {
+        // SAFETY: [`Buffer::try_shrink`] ensures that `target` < `self.cap`
+        // and that only the first `target` positions may be filled.
+        unsafe { self.remap(target, target) }
+    }
+}
+
+impl<T: Copy> CopyValueBuffer for GuardedBuffer<T> {
+    unsafe fn copy(&self, index: usize) -> T {
+        // SAFETY: [`CopyValueBuffer::copy`] ensures that the position is
+        // valid.
+        let ptr = unsafe { self.slot(index) };
+        // SAFETY: [`CopyValueBuffer::copy`] ensures that the position is
+        // filled.
+        unsafe { ptr::read(ptr) }
+    }
+}
+
+impl<T> PtrBuffer for GuardedBuffer<T> {
+    type ConstantPointer = *const T;
+    type MutablePointer = *mut T;
+
+    unsafe fn ptr(&self, index: usize) -> *const T {
+        // SAFETY: Same requirements.
+        unsafe { self.slot(index) }
+    }
+
+    unsafe fn mut_ptr(&mut self, index: usize) -> *mut T {
+        // SAFETY: Same requirements.
+        unsafe { self.slot(index) }
+    }
+
+    unsafe fn segment_len(&self, index: usize) -> usize {
+        // All the positions are in a single allocation.
+        self.capacity() - index
+    }
+}
+
+impl<T> RefBuffer for GuardedBuffer<T> {
+    type ConstantReference<'a> = &'a T
+    where
+        Self: 'a;
+    type MutableReference<'a> = &'a mut T
+    where
+        Self: 'a;
+
+    unsafe fn index<'a: 'b, 'b>(&'a self, index: usize) -> &'b T {
+        // SAFETY: [`RefBuffer::index`] ensures that the position is valid.
+        let ptr = unsafe { self.slot(index) };
+        // SAFETY: [`RefBuffer::index`] ensures that the position is filled.
+        unsafe { &*ptr }
+    }
+
+    unsafe fn mut_index<'a: 'b, 'b>(&'a mut self, index: usize) -> &'b mut T {
+        // SAFETY: [`RefBuffer::mut_index`] ensures that the position is
+        // valid.
+        let ptr = unsafe { self.slot(index) };
+        // SAFETY: [`RefBuffer::mut_index`] ensures that the position is
+        // filled.
+        unsafe { &mut *ptr }
+    }
+}
+
+impl<T> ContiguousMemoryBuffer for GuardedBuffer<T> {}
+
+// SAFETY: The values are in their own mapping.
+unsafe impl<T> OutOfLineBuffer for GuardedBuffer<T> {}
+
+impl<T> GrowableBuffer for GuardedBuffer<T> {}
+
+impl<T> ShrinkableBuffer for GuardedBuffer<T> {}
+
+impl<T> Default for GuardedBuffer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// SAFETY: The buffer uniquely owns its mapping (like a `Box<[T]>`).
+unsafe impl<T: Send> Send for GuardedBuffer<T> {}
+
+// SAFETY: Shared references only give shared access to the values.
+unsafe impl<T: Sync> Sync for GuardedBuffer<T> {}
+
+/// Internal type. Pages mapped from the system, with the guards around the
+/// accessible part. They are unmapped when dropped.
+struct Mapping {
+    start: *mut u8,
+    len: usize,
+    accessible_end: *mut u8,
+}
+
+impl Mapping {
+    /// Maps enough pages for `bytes`, plus a guard page after them (and
+    /// another before if `front_guard`).
+    fn new(bytes: usize, front_guard: bool) -> Result<Self, ResizeError> {
+        let page = page_size();
+        let pages = bytes.div_ceil(page);
+        let guards = 1 + front_guard as usize;
+        let len = pages
+            .checked_add(guards)
+            .and_then(|total| total.checked_mul(page))
+            .ok_or(ResizeError::TheoreticalLimitSurpassed)?;
+
+        // SAFETY: Anonymous private mappings don't alias any existing memory.
+        let start = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_NONE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if start == libc::MAP_FAILED {
+            return Err(ResizeError::OutOfMemory);
+        }
+        let start = start.cast::<u8>();
+        let mapping = Self {
+            start,
+            len,
+            accessible_end: start.wrapping_add(len - page),
+        };
+
+        let accessible = mapping.accessible_end.wrapping_sub(pages * page);
+        // SAFETY: The accessible pages are inside the mapping, which is only
+        // owned by `mapping`.
+        let result = unsafe {
+            libc::mprotect(
+                accessible.cast(),
+                pages * page,
+                libc::PROT_READ | libc::PROT_WRITE,
+            )
+        };
+        if result == 0 {
+            Ok(mapping)
+        } else {
+            Err(ResizeError::OutOfMemory)
+        }
+    }
+
+    /// Address where the back guard page starts.
+    fn accessible_end(&self) -> *mut u8 {
+        self.accessible_end
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        // SAFETY: The whole mapping is owned by this value, and it's not used
+        // after this.
+        unsafe { libc::munmap(self.start.cast(), self.len) };
+    }
+}
+
+/// Internal utility that gives the size of the memory pages.
+fn page_size() -> usize {
+    // SAFETY: Querying the page size has no requirements.
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    usize::try_from(size).unwrap_or(4096)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::collections::Vector;
+
+    use super::*;
+
+    #[test]
+    fn allocated_bytes_are_whole_pages_without_the_guards() {
+        let mut buffer = GuardedBuffer::<u8>::with_front_guard();
+        assert_eq!(buffer.allocated_bytes(), 0);
+        // SAFETY: It's bigger than its capacity (0).
+        unsafe { buffer.try_grow(page_size() + 1) }.unwrap();
+        assert_eq!(buffer.allocated_bytes(), 2 * page_size());
+    }
+
+    #[test]
+    fn the_elements_end_at_the_guard_page() {
+        let mut vec = Vector::from_buffer(GuardedBuffer::<u32>::new());
+        vec.extend_from_slice(&[1, 2, 3]);
+        let end = |vec: &Vector<u32, GuardedBuffer<u32>>| {
+            vec.as_slice().as_ptr().wrapping_add(vec.capacity()) as usize
+        };
+        assert_eq!(end(&vec) % page_size(), 0);
+
+        vec.shrink_to_fit();
+        assert_eq!(vec.capacity(), 3);
+        assert_eq!(end(&vec) % page_size(), 0);
+        assert_eq!(vec.as_slice(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn cannot_map_past_isize_max_bytes() {
+        let mut vec = Vector::from_buffer(GuardedBuffer::<u16>::new());
+        let result = vec.try_reserve_exact(isize::MAX as usize / 2 + 1);
+        assert!(matches!(result, Err(ResizeError::LayoutOverflow { .. })));
+        assert_eq!(vec.capacity(), 0);
+    }
+
+    #[test]
+    fn types_aligned_to_more_than_a_page_cannot_be_mapped() {
+        #[repr(align(65536))]
+        struct Big(#[allow(dead_code)] u8);
+
+        let mut vec = Vector::from_buffer(GuardedBuffer::<Big>::new());
+        let result = vec.try_reserve_exact(1);
+        if mem::align_of::<Big>() > page_size() {
+            assert!(matches!(result, Err(ResizeError::UnsupportedOperation)));
+            assert_eq!(vec.capacity(), 0);
+        } else {
+            vec.push(Big(1));
+            assert!(vec.as_slice().as_ptr().is_aligned());
+        }
+    }
+
+    #[test]
+    fn can_grow_with_a_front_guard() {
+        let mut vec = Vector::from_buffer(GuardedBuffer::<String>::with_front_guard());
+        for i in 0..2000 {
+            vec.push(i.to_string());
+        }
+        assert_eq!(vec.as_slice()[1999], "1999");
+        vec.clear();
+        vec.shrink_to_fit();
+        assert_eq!(vec.capacity(), 0);
+    }
+}
@@ -22,3 +22,28 @@ pub use slice::SliceBuffer;
 pub mod allocator;
 #[cfg(feature = "allocator")]
 pub use allocator::AllocatorBuffer;
+
+#[path = "6_erased.rs"]
+pub mod erased;
+pub use erased::ErasedBuffer;
+
+#[cfg(all(unix, feature = "guarded"))]
+#[path = "7_guarded.rs"]
+pub mod guarded;
+#[cfg(all(unix, feature = "guarded"))]
+pub use guarded::GuardedBuffer;
+
+#[path = "a_occupancy.rs"]
+mod occupancy;
+
+#[cfg(feature = "allocator")]
+#[path = "b_allocators.rs"]
+pub mod allocators;
+
+#[path = "c_shared_slice.rs"]
+pub mod shared_slice;
+pub use shared_slice::SharedSliceBuffer;
+
+#[path = "d_external.rs"]
+pub mod external;
+pub use external::ExternBuffer;
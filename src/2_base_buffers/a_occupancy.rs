@@ -0,0 +1,264 @@
+//! Lightweight tracking of which positions of a base buffer are filled, to
+//! catch misuses of the [`crate::interface::Buffer`] contract (eg. putting a
+//! value into a filled position, which leaks the old one).
+//!
+//! It only exists with the `contract-checks` feature in builds with debug
+//! assertions. Otherwise [`Occupancy`] is zero-sized and does nothing, so the
+//! buffers keep their layout.
+//!
+//! Positions can also be changed through raw pointers (eg. copying values in
+//! bulk), which cannot be tracked. Once a pointer is handed out, every position
+//! is considered unknown (and not checked) until it's put or taken again.
+
+#[cfg(all(debug_assertions, feature = "contract-checks"))]
+pub use enabled::Occupancy;
+
+#[cfg(not(all(debug_assertions, feature = "contract-checks")))]
+pub use disabled::Occupancy;
+
+#[cfg(all(debug_assertions, feature = "contract-checks"))]
+mod enabled {
+    use std::{
+        ops::Range,
+        sync::atomic::{AtomicBool, Ordering},
+    };
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum State {
+        Empty,
+        Filled,
+        Unknown,
+    }
+
+    /// State of each position of a buffer.
+    pub struct Occupancy {
+        states: Vec<State>,
+        /// Set when a pointer is handed out (which only needs a shared
+        /// reference). The states are forgotten on the next change.
+        escaped: AtomicBool,
+        /// Whether nothing is tracked at all (see [`Occupancy::unknown`]).
+        untracked: bool,
+    }
+
+    impl Occupancy {
+        /// Makes the tracking of a buffer with `capacity` empty positions.
+        pub fn new(capacity: usize) -> Self {
+            Self {
+                states: vec![State::Empty; capacity],
+                escaped: AtomicBool::new(false),
+                untracked: false,
+            }
+        }
+
+        /// Makes the tracking of a buffer whose positions are unknown (and
+        /// not checked, not even their bounds), which can be done at compile
+        /// time. Only for buffers that never resize.
+        pub const fn unknown() -> Self {
+            Self {
+                states: Vec::new(),
+                escaped: AtomicBool::new(false),
+                untracked: true,
+            }
+        }
+
+        /// Notes that a pointer was handed out.
+        pub fn escape(&self) {
+            self.escaped.store(true, Ordering::Relaxed);
+        }
+
+        /// Checks that `index` is empty and marks it as filled.
+        pub fn fill(&mut self, index: usize) {
+            if self.untracked {
+                return;
+            }
+            self.sync();
+            let state = &mut self.states[index];
+            assert!(
+                *state != State::Filled,
+                "Putting a value into position {index}, which is filled"
+            );
+            *state = State::Filled;
+        }
+
+        /// Checks that `index` is filled and marks it as empty.
+        pub fn empty(&mut self, index: usize) {
+            if self.untracked {
+                return;
+            }
+            self.sync();
+            let state = &mut self.states[index];
+            assert!(
+                *state != State::Empty,
+                "Taking or dropping the value of position {index}, which is empty"
+            );
+            *state = State::Empty;
+        }
+
+        /// Same as [`Occupancy::fill`] for all the positions in `range`.
+        pub fn fill_range(&mut self, range: Range<usize>) {
+            for index in range {
+                self.fill(index);
+            }
+        }
+
+        /// Same as [`Occupancy::empty`] for all the positions in `range`.
+        pub fn empty_range(&mut self, range: Range<usize>) {
+            for index in range {
+                self.empty(index);
+            }
+        }
+
+        /// Checks that `index` is filled.
+        pub fn check_filled(&self, index: usize) {
+            if !self.untracked && !self.escaped.load(Ordering::Relaxed) {
+                assert!(
+                    self.states[index] != State::Empty,
+                    "Reading the value of position {index}, which is empty"
+                );
+            }
+        }
+
+        /// Changes the number of positions. The removed ones must be empty and
+        /// the new ones start empty.
+        pub fn resize(&mut self, capacity: usize) {
+            if self.untracked {
+                return;
+            }
+            self.sync();
+            if let Some(index) = self
+                .states
+                .iter()
+                .skip(capacity)
+                .position(|&state| state == State::Filled)
+            {
+                panic!(
+                    "Shrinking to {capacity} while position {} is filled",
+                    capacity + index
+                );
+            }
+            self.states.resize(capacity, State::Empty);
+        }
+
+        /// Internal utility that forgets the states if a pointer was handed
+        /// out.
+        fn sync(&mut self) {
+            if std::mem::take(self.escaped.get_mut()) {
+                self.states.fill(State::Unknown);
+            }
+        }
+    }
+}
+
+#[cfg(not(all(debug_assertions, feature = "contract-checks")))]
+mod disabled {
+    use std::ops::Range;
+
+    /// Does nothing (see the [module](super) documentation).
+    pub struct Occupancy;
+
+    impl Occupancy {
+        #[inline(always)]
+        pub fn new(_capacity: usize) -> Self {
+            Self
+        }
+
+        #[inline(always)]
+        pub const fn unknown() -> Self {
+            Self
+        }
+
+        #[inline(always)]
+        pub fn escape(&self) {}
+
+        #[inline(always)]
+        pub fn fill(&mut self, _index: usize) {}
+
+        #[inline(always)]
+        pub fn empty(&mut self, _index: usize) {}
+
+        #[inline(always)]
+        pub fn fill_range(&mut self, _range: Range<usize>) {}
+
+        #[inline(always)]
+        pub fn empty_range(&mut self, _range: Range<usize>) {}
+
+        #[inline(always)]
+        pub fn check_filled(&self, _index: usize) {}
+
+        #[inline(always)]
+        pub fn resize(&mut self, _capacity: usize) {}
+    }
+}
+
+#[cfg(all(test, debug_assertions, feature = "contract-checks"))]
+mod tests {
+    use crate::{
+        base_buffers::{HeapBuffer, InlineBuffer},
+        interface::{ptrs::PtrBuffer, Buffer},
+        test_utils::panic::assert_panic,
+    };
+
+    #[test]
+    fn putting_into_a_filled_position_panics() {
+        let mut buffer = InlineBuffer::<u32, 2>::new();
+        // SAFETY: 0 is valid and empty.
+        unsafe { buffer.put(0, 1) };
+        // SAFETY: Not really: this is what's being checked.
+        assert_panic(move || unsafe { buffer.put(0, 2) });
+    }
+
+    #[test]
+    fn taking_from_an_empty_position_panics() {
+        let mut buffer = HeapBuffer::<u32>::new();
+        // SAFETY: 2 is bigger than its capacity (0).
+        unsafe { buffer.try_grow(2) }.unwrap();
+        // SAFETY: 1 is valid and empty.
+        unsafe { buffer.put(1, 1) };
+        // SAFETY: 1 was just filled.
+        assert_eq!(unsafe { buffer.take(1) }, 1);
+        // SAFETY: Not really: this is what's being checked.
+        assert_panic(move || unsafe { buffer.take(1) });
+    }
+
+    #[test]
+    fn positions_out_of_the_capacity_panic() {
+        let mut buffer = InlineBuffer::<u32, 2>::new();
+        // SAFETY: Not really: this is what's being checked.
+        assert_panic(move || unsafe { buffer.put(2, 1) });
+    }
+
+    #[test]
+    fn buffers_made_from_arrays_are_not_tracked() {
+        let mut buffer = InlineBuffer::from_array([1u32, 2]);
+        // SAFETY: 1 is filled.
+        assert_eq!(unsafe { buffer.take(1) }, 2);
+        // SAFETY: 1 was just emptied.
+        unsafe { buffer.put(1, 3) };
+        // SAFETY: 1 is filled.
+        assert_eq!(unsafe { buffer.take(1) }, 3);
+    }
+
+    #[test]
+    fn shrinking_over_filled_positions_panics() {
+        let mut buffer = HeapBuffer::<u32>::new();
+        // SAFETY: 4 is bigger than its capacity (0).
+        unsafe { buffer.try_grow(4) }.unwrap();
+        // SAFETY: 3 is valid and empty.
+        unsafe { buffer.put(3, 1) };
+        // SAFETY: Not really: this is what's being checked.
+        assert_panic(move || unsafe { buffer.try_shrink(2) });
+    }
+
+    #[test]
+    fn positions_reached_through_pointers_are_not_checked() {
+        let mut buffer = InlineBuffer::<u32, 2>::new();
+        // SAFETY: 0 is valid and empty.
+        let ptr = unsafe { buffer.mut_ptr(0) };
+        // SAFETY: The pointer is valid for writes.
+        unsafe { ptr.write(1) };
+        // SAFETY: 0 was filled through the pointer.
+        assert_eq!(unsafe { buffer.take(0) }, 1);
+        // SAFETY: Not really: once taken, it's tracked again.
+        assert_panic(move || unsafe { buffer.take(0) });
+    }
+}
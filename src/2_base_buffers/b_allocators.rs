@@ -0,0 +1,525 @@
+//! Ready-made [`Allocator`]s to use with [`super::AllocatorBuffer`].
+//!
+//! [`GlobalAllocator`] turns a [`GlobalAlloc`] into an [`Allocator`]. With
+//! the `jemalloc` and `mimalloc` features there are aliases for those
+//! allocators ([`Jemalloc`] and [`Mimalloc`]), so `AllocatorBuffer<T,
+//! Jemalloc>` works out of the box.
+//!
+//! Growing and shrinking use [`GlobalAlloc::realloc`], so allocators that can
+//! resize in place (like jemalloc and mimalloc, within a size class) don't
+//! copy the elements.
+//!
+//! [`TrackingAllocator`] wraps another allocator to count (and optionally
+//! limit) the bytes allocated through it. [`BudgetedBuffer`] uses one and
+//! reports going over its limit as running out of memory.
+
+use std::{
+    alloc::{AllocError, Allocator, Global, GlobalAlloc, Layout, System},
+    mem,
+    ptr::{self, NonNull},
+};
+
+use crate::{
+    interface::{indirect_buffer::IndirectBuffer, resize_error::ResizeError, Buffer},
+    sync::{AtomicUsize, Ordering},
+};
+
+use super::AllocatorBuffer;
+
+/// [`Allocator`] on top of a [`GlobalAlloc`].
+///
+/// Unlike a [`GlobalAlloc`], an [`Allocator`] must keep its memory valid
+/// when it's moved or copied (buffers keep it by value), so it can only be
+/// made with [`GlobalAllocator::new`] (which is `unsafe`), or with
+/// [`Default`] for the allocators which are known to be fine ([`System`],
+/// jemalloc and mimalloc).
+#[derive(Clone, Copy, Debug)]
+pub struct GlobalAllocator<G: GlobalAlloc>(G);
+
+impl<G: GlobalAlloc> GlobalAllocator<G> {
+    /// Makes an [`Allocator`] which uses `alloc`.
+    ///
+    /// # Safety
+    /// The memory allocated through `alloc` must stay valid (until it's
+    /// deallocated) when `alloc` is moved, copied or dropped, and any copy
+    /// must be able to deallocate it. Stateless allocators (which only call
+    /// the system or a process-wide allocator) are fine, while one that keeps
+    /// its arena inline is not.
+    pub const unsafe fn new(alloc: G) -> Self {
+        Self(alloc)
+    }
+
+    /// Underlying [`GlobalAlloc`].
+    pub fn get(&self) -> &G {
+        &self.0
+    }
+
+    /// Internal utility that allocates (or gives a dangling pointer for
+    /// zero-sized layouts).
+    fn alloc_with(
+        &self,
+        layout: Layout,
+        alloc: unsafe fn(&G, Layout) -> *mut u8,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        if layout.size() == 0 {
+            return Ok(NonNull::slice_from_raw_parts(layout.dangling_ptr(), 0));
+        }
+        // SAFETY: The layout has a non-zero size.
+        let ptr = unsafe { alloc(&self.0, layout) };
+        let ptr = NonNull::new(ptr).ok_or(AllocError)?;
+        Ok(NonNull::slice_from_raw_parts(ptr, layout.size()))
+    }
+
+    /// Internal utility that resizes an allocation (both growing and
+    /// shrinking).
+    ///
+    /// # Safety
+    ///   * `ptr` must have been allocated by this allocator with `old_layout`.
+    unsafe fn reallocate(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        if old_layout.size() == 0 {
+            return self.allocate(new_layout);
+        }
+        if new_layout.size() == 0 {
+            // SAFETY: Same requirements.
+            unsafe { self.deallocate(ptr, old_layout) };
+            return Ok(NonNull::slice_from_raw_parts(new_layout.dangling_ptr(), 0));
+        }
+
+        if old_layout.align() == new_layout.align() {
+            // SAFETY: `ptr` was allocated with `old_layout`, whose alignment is
+            // the same, and the new size is not zero.
+            let new = unsafe { self.0.realloc(ptr.as_ptr(), old_layout, new_layout.size()) };
+            let new = NonNull::new(new).ok_or(AllocError)?;
+            return Ok(NonNull::slice_from_raw_parts(new, new_layout.size()));
+        }
+
+        // `realloc` cannot change the alignment, so it's moved manually.
+        let new = self.allocate(new_layout)?;
+        let count = old_layout.size().min(new_layout.size());
+        // SAFETY: Both allocations are valid for at least `count` bytes and
+        // they are different, so they don't overlap.
+        unsafe { ptr::copy_nonoverlapping(ptr.as_ptr(), new.cast().as_ptr(), count) };
+        // SAFETY: Same requirements.
+        unsafe { self.deallocate(ptr, old_layout) };
+        Ok(new)
+    }
+}
+
+// SAFETY: The memory comes from the [`GlobalAlloc`], which keeps it valid until
+// it's deallocated even if `self` is moved or copied (required by
+// `GlobalAllocator::new`). Zero-sized layouts never reach it.
+unsafe impl<G: GlobalAlloc> Allocator for GlobalAllocator<G> {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.alloc_with(layout, G::alloc)
+    }
+
+    fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.alloc_with(layout, G::alloc_zeroed)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        if layout.size() != 0 {
+            // SAFETY: [`Allocator::deallocate`] ensures that `ptr` was
+            // allocated by this allocator with `layout`.
+            unsafe { self.0.dealloc(ptr.as_ptr(), layout) };
+        }
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        // SAFETY: [`Allocator::grow`] has the same requirements.
+        unsafe { self.reallocate(ptr, old_layout, new_layout) }
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        // SAFETY: [`Allocator::shrink`] has the same requirements.
+        unsafe { self.reallocate(ptr, old_layout, new_layout) }
+    }
+}
+
+impl Default for GlobalAllocator<System> {
+    fn default() -> Self {
+        // SAFETY: The system allocator is stateless.
+        unsafe { Self::new(System) }
+    }
+}
+
+/// Allocator using jemalloc (through `tikv-jemallocator`).
+///
+/// It requires the `jemalloc` feature.
+#[cfg(feature = "jemalloc")]
+pub type Jemalloc = GlobalAllocator<tikv_jemallocator::Jemalloc>;
+
+#[cfg(feature = "jemalloc")]
+impl Default for Jemalloc {
+    fn default() -> Self {
+        // SAFETY: It's stateless: all the instances use the same jemalloc.
+        unsafe { GlobalAllocator::new(tikv_jemallocator::Jemalloc) }
+    }
+}
+
+/// Allocator using mimalloc.
+///
+/// It requires the `mimalloc` feature.
+#[cfg(feature = "mimalloc")]
+pub type Mimalloc = GlobalAllocator<mimalloc::MiMalloc>;
+
+#[cfg(feature = "mimalloc")]
+impl Default for Mimalloc {
+    fn default() -> Self {
+        // SAFETY: It's stateless: all the instances use the same mimalloc.
+        unsafe { GlobalAllocator::new(mimalloc::MiMalloc) }
+    }
+}
+
+/// Allocator that keeps track of how many bytes are allocated through it (and
+/// the most there have been at once), and can limit them.
+///
+/// Allocations that would go over the limit fail without reaching the inner
+/// allocator, which makes the buffer return an error instead of aborting the
+/// process. As the allocator API cannot tell why it failed, an
+/// [`AllocatorBuffer`] returns [`ResizeError::UndistinguishableError`]. Use
+/// [`BudgetedBuffer`] to get [`ResizeError::OutOfMemory`] instead.
+///
+/// Use a reference (which is also an allocator) to share it between
+/// collections or to check the usage of one.
+///
+/// # Example
+/// ```
+/// # #![feature(allocator_api)]
+/// # use std::alloc::Global;
+/// # use buffers::{base_buffers::{allocators::TrackingAllocator, AllocatorBuffer}, collections::Vector};
+/// let budget = TrackingAllocator::with_limit(Global, 64);
+/// let mut vec = Vector::<u32, _>::from_buffer(AllocatorBuffer::with_allocator(&budget));
+///
+/// vec.try_reserve_exact(10).unwrap();
+/// assert_eq!(budget.used(), 40);
+/// assert!(vec.try_reserve_exact(20).is_err());
+///
+/// vec.shrink_to(2);
+/// assert_eq!(budget.used(), 8);
+/// assert_eq!(budget.peak(), 40);
+/// ```
+#[derive(Debug)]
+pub struct TrackingAllocator<A: Allocator = Global> {
+    inner: A,
+    limit: usize,
+    used: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl<A: Allocator> TrackingAllocator<A> {
+    /// Wraps `inner` without limiting it.
+    pub fn new(inner: A) -> Self {
+        Self::with_limit(inner, usize::MAX)
+    }
+
+    /// Wraps `inner` allowing up to `limit` bytes at once.
+    pub fn with_limit(inner: A, limit: usize) -> Self {
+        Self {
+            inner,
+            limit,
+            used: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+        }
+    }
+
+    /// Bytes allocated at the moment.
+    pub fn used(&self) -> usize {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    /// Most bytes that were allocated at once.
+    pub fn peak(&self) -> usize {
+        self.peak.load(Ordering::Relaxed)
+    }
+
+    /// Maximum number of bytes allowed at once.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Bytes that can still be allocated.
+    pub fn remaining(&self) -> usize {
+        self.limit.saturating_sub(self.used())
+    }
+
+    /// Allocator that it wraps.
+    pub fn inner(&self) -> &A {
+        &self.inner
+    }
+
+    /// Internal utility that counts `bytes` more as used, if they fit.
+    fn reserve(&self, bytes: usize) -> Result<(), AllocError> {
+        let limit = self.limit;
+        let previous = self
+            .used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                used.checked_add(bytes).filter(|&total| total <= limit)
+            })
+            .map_err(|_| AllocError)?;
+        self.peak.fetch_max(previous + bytes, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Internal utility that counts `bytes` less as used.
+    fn release(&self, bytes: usize) {
+        self.used.fetch_sub(bytes, Ordering::Relaxed);
+    }
+
+    /// Internal utility that makes an allocation counting its bytes.
+    fn tracked(
+        &self,
+        bytes: usize,
+        alloc: impl FnOnce() -> Result<NonNull<[u8]>, AllocError>,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        self.reserve(bytes)?;
+        alloc().inspect_err(|_| self.release(bytes))
+    }
+}
+
+impl<A: Allocator + Default> Default for TrackingAllocator<A> {
+    fn default() -> Self {
+        Self::new(A::default())
+    }
+}
+
+// SAFETY: All the memory comes from `inner`, which keeps it valid. It only
+// adds the bookkeeping.
+unsafe impl<A: Allocator> Allocator for TrackingAllocator<A> {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.tracked(layout.size(), || self.inner.allocate(layout))
+    }
+
+    fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.tracked(layout.size(), || self.inner.allocate_zeroed(layout))
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        // SAFETY: [`Allocator::deallocate`] has the same requirements.
+        unsafe { self.inner.deallocate(ptr, layout) };
+        self.release(layout.size());
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let extra = new_layout.size() - old_layout.size();
+        self.tracked(extra, || {
+            // SAFETY: [`Allocator::grow`] has the same requirements.
+            unsafe { self.inner.grow(ptr, old_layout, new_layout) }
+        })
+    }
+
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let extra = new_layout.size() - old_layout.size();
+        self.tracked(extra, || {
+            // SAFETY: [`Allocator::grow_zeroed`] has the same requirements.
+            unsafe { self.inner.grow_zeroed(ptr, old_layout, new_layout) }
+        })
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        // SAFETY: [`Allocator::shrink`] has the same requirements.
+        let shrunk = unsafe { self.inner.shrink(ptr, old_layout, new_layout)? };
+        self.release(old_layout.size() - new_layout.size());
+        Ok(shrunk)
+    }
+}
+
+/// [`AllocatorBuffer`] which allocates through a (shared) [`TrackingAllocator`]
+/// and reports going over its limit as [`ResizeError::OutOfMemory`].
+///
+/// # Example
+/// ```
+/// # #![feature(allocator_api)]
+/// # use std::alloc::Global;
+/// # use buffers::{base_buffers::allocators::{BudgetedBuffer, TrackingAllocator}, collections::Vector};
+/// # use buffers::interface::resize_error::ResizeError;
+/// let budget = TrackingAllocator::with_limit(Global, 64);
+/// let mut vec = Vector::<u32, _>::from_buffer(BudgetedBuffer::new(&budget));
+///
+/// vec.try_reserve_exact(10).unwrap();
+/// let result = vec.try_reserve_exact(20);
+/// assert!(matches!(result, Err(ResizeError::OutOfMemory)));
+/// ```
+pub struct BudgetedBuffer<'a, T, A: Allocator = Global> {
+    inner: AllocatorBuffer<T, &'a TrackingAllocator<A>>,
+}
+
+impl<'a, T, A: Allocator> BudgetedBuffer<'a, T, A> {
+    /// Makes an empty buffer which allocates through `budget`.
+    pub fn new(budget: &'a TrackingAllocator<A>) -> Self {
+        Self {
+            inner: AllocatorBuffer::with_allocator(budget),
+        }
+    }
+
+    /// Allocator which keeps the budget.
+    pub fn budget(&self) -> &'a TrackingAllocator<A> {
+        self.inner.allocator()
+    }
+}
+
+impl<'a, T, A: Allocator> IndirectBuffer for BudgetedBuffer<'a, T, A> {
+    type InnerBuffer = AllocatorBuffer<T, &'a TrackingAllocator<A>>;
+    type InnerBufferRef<'b> = &'b Self::InnerBuffer where Self: 'b;
+    type InnerBufferMutRef<'b> = &'b mut Self::InnerBuffer where Self: 'b;
+
+    fn inner(&self) -> &Self::InnerBuffer {
+        &self.inner
+    }
+
+    fn inner_mut(&mut self) -> &mut Self::InnerBuffer {
+        &mut self.inner
+    }
+
+    /// Same as the inner buffer, but if it fails because the extra bytes
+    /// don't fit in the budget, it's [`ResizeError::OutOfMemory`].
+    unsafe fn try_grow(&mut self, target: usize) -> Result<(), ResizeError> {
+        let capacity = self.inner.capacity();
+        // SAFETY: Same requirements.
+        let result = unsafe { self.inner.try_grow(target) };
+        match result {
+            Err(ResizeError::UndistinguishableError) => {
+                let extra = (target - capacity).saturating_mul(mem::size_of::<T>());
+                if extra > self.budget().remaining() {
+                    Err(ResizeError::OutOfMemory)
+                } else {
+                    Err(ResizeError::UndistinguishableError)
+                }
+            }
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::alloc::System;
+
+    use super::*;
+
+    /// Checks that resizing within the allocator's size class (a small one)
+    /// doesn't move the elements.
+    #[cfg(any(feature = "jemalloc", feature = "mimalloc"))]
+    fn resizes_in_place<A: Allocator + Default>() {
+        use crate::{base_buffers::AllocatorBuffer, collections::Vector};
+
+        let mut vec = Vector::<u8, AllocatorBuffer<u8, A>>::new();
+        vec.reserve_exact(5);
+        vec.extend_from_slice(&[1, 2, 3, 4, 5]);
+        let ptr = vec.as_slice().as_ptr();
+
+        vec.reserve_exact(3);
+        assert_eq!(vec.capacity(), 8);
+        assert_eq!(vec.as_slice().as_ptr(), ptr);
+
+        vec.truncate(3);
+        vec.shrink_to(6);
+        assert_eq!(vec.capacity(), 6);
+        assert_eq!(vec.as_slice().as_ptr(), ptr);
+        assert_eq!(vec.as_slice(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn can_change_the_alignment() {
+        let alloc = GlobalAllocator::<System>::default();
+        let old = Layout::from_size_align(24, 8).unwrap();
+        let new = Layout::from_size_align(48, 64).unwrap();
+        let ptr = alloc.allocate(old).unwrap().cast::<u8>();
+        // SAFETY: The allocation has 24 bytes.
+        unsafe { ptr.write_bytes(7, 24) };
+
+        // SAFETY: `ptr` was allocated with `old`, which is smaller than `new`.
+        let grown = unsafe { alloc.grow(ptr, old, new) }.unwrap().cast::<u8>();
+        assert_eq!(grown.as_ptr() as usize % 64, 0);
+        let last = grown.as_ptr().wrapping_add(23);
+        // SAFETY: The first 24 bytes were copied.
+        assert_eq!(unsafe { last.read() }, 7);
+
+        // SAFETY: `grown` was allocated with `new`.
+        let empty = unsafe { alloc.shrink(grown, new, Layout::new::<()>()) }.unwrap();
+        assert_eq!(empty.len(), 0);
+    }
+
+    #[test]
+    fn tracking_counts_every_collection() {
+        use crate::{base_buffers::AllocatorBuffer, collections::Vector};
+
+        let tracker = TrackingAllocator::<Global>::with_limit(Global, 100);
+        let mut a = Vector::<u64, _>::from_buffer(AllocatorBuffer::with_allocator(&tracker));
+        let mut b = Vector::<u8, _>::from_buffer(AllocatorBuffer::with_allocator(&tracker));
+        a.try_reserve_exact(8).unwrap();
+        b.try_reserve_exact(30).unwrap();
+        assert_eq!(tracker.used(), 94);
+
+        assert!(b.try_reserve_exact(37).is_err());
+        assert_eq!(tracker.remaining(), 6);
+        b.try_reserve_exact(36).unwrap();
+        drop(a);
+        assert_eq!(tracker.used(), 36);
+        drop(b);
+        assert_eq!(tracker.used(), 0);
+        assert_eq!(tracker.peak(), 100);
+    }
+
+    #[test]
+    fn budgets_run_out_of_memory() {
+        use crate::collections::Vector;
+
+        let budget = TrackingAllocator::<Global>::with_limit(Global, 16);
+        let mut a = Vector::<u32, _>::from_buffer(BudgetedBuffer::new(&budget));
+        let mut b = Vector::<u32, _>::from_buffer(BudgetedBuffer::new(&budget));
+        a.try_reserve_exact(3).unwrap();
+        assert!(matches!(
+            b.try_reserve_exact(2),
+            Err(ResizeError::OutOfMemory)
+        ));
+        assert!(matches!(
+            b.try_reserve_exact(usize::MAX / 2),
+            Err(ResizeError::LayoutOverflow { .. })
+        ));
+        b.try_reserve_exact(1).unwrap();
+        assert_eq!(budget.remaining(), 0);
+    }
+
+    #[cfg(feature = "jemalloc")]
+    #[test]
+    fn jemalloc_resizes_in_place() {
+        resizes_in_place::<Jemalloc>();
+    }
+
+    #[cfg(feature = "mimalloc")]
+    #[test]
+    fn mimalloc_resizes_in_place() {
+        resizes_in_place::<Mimalloc>();
+    }
+}
@@ -0,0 +1,165 @@
+use std::ops::RangeBounds;
+
+use crate::interface::{
+    copy_value::CopyValueBuffer, ptrs::PtrBuffer, refs::RefBuffer, Buffer, CapacityContract,
+    ResizeError,
+};
+
+/// Read-only buffer on top of a shared slice of values. It's the immutable
+/// counterpart of [`super::SliceBuffer`]: all its positions are always filled
+/// with the values of the slice, which are borrowed.
+///
+/// It only implements the parts of the interface that read: the values can be
+/// copied ([`CopyValueBuffer`]), pointed to ([`PtrBuffer`]) and referenced
+/// ([`RefBuffer`]), but even the "mutable" pointers and references are shared
+/// ones. Dropping values does nothing (they belong to the slice), so a
+/// collection can still forget some of them (eg. truncating a vector), but
+/// anything that moves or writes a value panics, and it cannot be resized.
+///
+/// This is useful to use collection APIs over borrowed data (eg. while
+/// parsing). See [`crate::collections::Vector::from_shared_slice`].
+///
+/// # Example
+/// ```
+/// # use buffers::{base_buffers::SharedSliceBuffer, interface::refs::RefBuffer};
+/// let tokens = ["let", "x", "=", "1"];
+/// let buffer = SharedSliceBuffer::new(&tokens);
+/// // SAFETY: All the positions of the slice are filled.
+/// assert_eq!(unsafe { *buffer.index(1) }, "x");
+/// ```
+#[repr(transparent)]
+pub struct SharedSliceBuffer<'a, T> {
+    slice: &'a [T],
+}
+
+impl<'a, T> SharedSliceBuffer<'a, T> {
+    /// Makes a buffer on top of `slice`. All its positions are filled.
+    pub fn new(slice: &'a [T]) -> Self {
+        Self { slice }
+    }
+
+    /// The whole slice (the values of all the positions).
+    pub fn as_slice(&self) -> &'a [T] {
+        self.slice
+    }
+}
+
+impl<'a, T> Buffer for SharedSliceBuffer<'a, T> {
+    type Element = T;
+    const CAPACITY_CONTRACT: CapacityContract = CapacityContract::Exact;
+
+    fn capacity(&self) -> usize {
+        self.slice.len()
+    }
+
+    /// # Panics
+    /// Always: the values are borrowed, so they cannot be moved out.
+    unsafe fn take(&mut self, _index: usize) -> T {
+        panic!("Cannot take values out of a read-only SharedSliceBuffer")
+    }
+
+    /// # Panics
+    /// Always: the slice cannot be written.
+    unsafe fn put(&mut self, _index: usize, _value: T) {
+        panic!("Cannot put values into a read-only SharedSliceBuffer")
+    }
+
+    unsafe fn manually_drop(&mut self, _index: usize) {
+        // The values belong to the slice.
+    }
+
+    unsafe fn manually_drop_range<R: RangeBounds<usize> + Clone>(&mut self, _values_range: R) {
+        // The values belong to the slice.
+    }
+
+    unsafe fn try_grow(&mut self, _target: usize) -> Result<(), ResizeError> {
+        Err(ResizeError::UnsupportedOperation)
+    }
+
+    unsafe fn try_shrink(&mut self, _target: usize) -> Result<(), ResizeError> {
+        Err(ResizeError::UnsupportedOperation)
+    }
+}
+
+impl<'a, T: Copy> CopyValueBuffer for SharedSliceBuffer<'a, T> {
+    unsafe fn copy(&self, index: usize) -> T {
+        self.slice[index]
+    }
+}
+
+impl<'a, T> PtrBuffer for SharedSliceBuffer<'a, T> {
+    type ConstantPointer = *const T;
+    /// The slice is shared, so it can't give mutable pointers.
+    type MutablePointer = *const T;
+
+    unsafe fn ptr(&self, index: usize) -> *const T {
+        debug_assert!(index < self.slice.len());
+        // SAFETY: `index` is a valid position, so it's inside the slice.
+        unsafe { self.slice.as_ptr().add(index) }
+    }
+
+    unsafe fn mut_ptr(&mut self, index: usize) -> *const T {
+        // SAFETY: Same requirements.
+        unsafe { self.ptr(index) }
+    }
+
+    unsafe fn segment_len(&self, index: usize) -> usize {
+        // All the positions are in a single allocation.
+        self.capacity() - index
+    }
+}
+
+impl<'a, T> RefBuffer for SharedSliceBuffer<'a, T> {
+    type ConstantReference<'b> = &'b T
+    where
+        Self: 'b;
+    /// The slice is shared, so it can't give mutable references.
+    type MutableReference<'b> = &'b T
+    where
+        Self: 'b;
+
+    unsafe fn index<'x: 'y, 'y>(&'x self, index: usize) -> &'y T {
+        &self.slice[index]
+    }
+
+    unsafe fn mut_index<'x: 'y, 'y>(&'x mut self, index: usize) -> &'y T {
+        &self.slice[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{collections::Vector, test_utils::panic::assert_panic};
+
+    use super::*;
+
+    // Compile-time check: it must be covariant like a shared reference.
+    #[allow(dead_code)]
+    fn is_covariant<'a>(
+        buffer: SharedSliceBuffer<'a, &'static str>,
+    ) -> SharedSliceBuffer<'a, &'a str> {
+        buffer
+    }
+
+    #[test]
+    fn reads_the_borrowed_values() {
+        let values = [String::from("a"), String::from("b")];
+        let buffer = SharedSliceBuffer::new(&values);
+        assert_eq!(buffer.capacity(), 2);
+        // SAFETY: All the positions are filled.
+        assert_eq!(unsafe { buffer.index(1) }, "b");
+        // SAFETY: All the positions are valid.
+        assert_eq!(unsafe { buffer.ptr(0) }, values.as_ptr());
+    }
+
+    #[test]
+    fn writing_panics_but_forgetting_works() {
+        let values = [1u8, 2, 3];
+        let mut vec = Vector::from_shared_slice(&values);
+        vec.truncate(2);
+        assert_eq!(vec.as_slice(), [1, 2]);
+        assert_panic(std::panic::AssertUnwindSafe(|| vec.push(3)));
+        assert_panic(std::panic::AssertUnwindSafe(|| vec.pop()));
+        assert_eq!(values, [1, 2, 3]);
+    }
+}
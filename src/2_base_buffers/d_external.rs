@@ -0,0 +1,283 @@
+use std::{
+    ffi::c_void,
+    marker::PhantomData,
+    ops::RangeBounds,
+    ptr::{self, NonNull},
+};
+
+use crate::interface::{
+    capacity_contract::CapacityContract,
+    caps::BufferCaps,
+    contiguous_memory::{drop_range_in_place, fill_range_in_place, ContiguousMemoryBuffer},
+    copy_value::CopyValueBuffer,
+    out_of_line::OutOfLineBuffer,
+    ptrs::PtrBuffer,
+    refs::RefBuffer,
+    shrinkable::ShrinkableBuffer,
+    Buffer, ResizeError,
+};
+
+/// Function which frees memory allocated outside of Rust (eg. C's `free`).
+pub type ExternFree = unsafe extern "C" fn(*mut c_void);
+
+/// Buffer which owns memory allocated outside of Rust (eg. by a C library).
+/// When dropped (or shrunk to nothing), it frees it with the function it was
+/// given.
+///
+/// It can't grow (the memory wasn't allocated by Rust) and only shrinks to 0.
+/// It's meant to wrap data that comes from C into a vector without copying
+/// it (see [`crate::collections::Vector::from_extern`]).
+pub struct ExternBuffer<T> {
+    ptr: NonNull<T>,
+    cap: usize,
+    free: Option<ExternFree>,
+    _marker: PhantomData<T>,
+}
+
+impl<T> ExternBuffer<T> {
+    /// Makes a buffer which owns the memory of `capacity` values starting at
+    /// `ptr` and frees it with `free` (called once with `ptr`). All its
+    /// positions start empty.
+    ///
+    /// # Safety
+    ///   * `ptr` must be valid to read and write `capacity` values of `T`,
+    ///     properly aligned.
+    ///   * The memory must not be used by anything else while the buffer
+    ///     owns it.
+    ///   * `free` must be sound to call with `ptr` when the buffer is
+    ///     dropped, from any thread the buffer is sent to.
+    pub unsafe fn from_raw_parts(ptr: NonNull<T>, capacity: usize, free: ExternFree) -> Self {
+        Self {
+            ptr,
+            cap: capacity,
+            free: Some(free),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Gives back the ownership of the memory: its pointer, its capacity and
+    /// the function that frees it (`None` if it was already freed, by
+    /// shrinking to 0). Filled positions are leaked.
+    pub fn into_raw_parts(self) -> (NonNull<T>, usize, Option<ExternFree>) {
+        let mut this = std::mem::ManuallyDrop::new(self);
+        (this.ptr, this.cap, this.free.take())
+    }
+
+    /// Internal utility that gives the pointer to the `index` position.
+    ///
+    /// # Safety
+    /// `index` must be less than the capacity.
+    unsafe fn slot(&self, index: usize) -> *mut T {
+        debug_assert!(index < self.cap);
+        // SAFETY: The position is inside the memory it owns.
+        unsafe { self.ptr.as_ptr().add(index) }
+    }
+
+    /// Internal utility that frees the memory (if it wasn't already).
+    fn free(&mut self) {
+        self.cap = 0;
+        if let Some(free) = self.free.take() {
+            // SAFETY: The caller of `from_raw_parts` ensured that it can be
+            // called, and it was taken so it's only called once.
+            unsafe { free(self.ptr.as_ptr().cast()) };
+        }
+    }
+}
+
+impl<T> Buffer for ExternBuffer<T> {
+    type Element = T;
+    const CAPACITY_CONTRACT: CapacityContract = CapacityContract::Exact;
+
+    fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    #[inline]
+    fn caps(&self) -> BufferCaps {
+        BufferCaps::SHRINK | BufferCaps::CONTIGUOUS
+    }
+
+    unsafe fn take(&mut self, index: usize) -> T {
+        // SAFETY: [`Buffer::take`] ensures that the position is valid.
+        let src = unsafe { self.slot(index) };
+        // SAFETY: [`Buffer::take`] ensures that the position is filled.
+        unsafe { ptr::read(src) }
+    }
+
+    unsafe fn put(&mut self, index: usize, value: T) {
+        // SAFETY: [`Buffer::put`] ensures that the position is valid.
+        let dst = unsafe { self.slot(index) };
+        // SAFETY: [`Buffer::put`] ensures that the position is empty.
+        unsafe { ptr::write(dst, value) };
+    }
+
+    unsafe fn manually_drop(&mut self, index: usize) {
+        // SAFETY: [`Buffer::manually_drop`] ensures that the position is valid.
+        let to_drop = unsafe { self.slot(index) };
+        // SAFETY: [`Buffer::manually_drop`] ensures that the position is
+        // filled.
+        unsafe { ptr::drop_in_place(to_drop) };
+    }
+
+    unsafe fn manually_drop_range<R: RangeBounds<usize> + Clone>(&mut self, values_range: R) {
+        // SAFETY: Same requirements as [`Buffer::manually_drop_range`].
+        unsafe { drop_range_in_place(self, values_range) }
+    }
+
+    unsafe fn fill_range<R: RangeBounds<usize> + Clone>(&mut self, range: R, value: &T)
+    where
+        T: Clone,
+    {
+        // SAFETY: Same requirements as [`Buffer::fill_range`].
+        unsafe { fill_range_in_place(self, range, value) }
+    }
+
+    unsafe fn try_grow(&mut self, _target: usize) -> Result<(), ResizeError> {
+        Err(ResizeError::UnsupportedOperation)
+    }
+
+    /// Only shrinking to 0 is supported, which frees the memory.
+    unsafe fn try_shrink(&mut self, target: usize) -> Result<(), ResizeError> {
+        if target == 0 {
+            self.free();
+            Ok(())
+        } else {
+            Err(ResizeError::UnsupportedOperation)
+        }
+    }
+}
+
+impl<T: Copy> CopyValueBuffer for ExternBuffer<T> {
+    unsafe fn copy(&self, index: usize) -> T {
+        // SAFETY: [`CopyValueBuffer::copy`] ensures that the position is valid.
+        let src = unsafe { self.slot(index) };
+        // SAFETY: [`CopyValueBuffer::copy`] ensures that the position is
+        // filled.
+        unsafe { ptr::read(src) }
+    }
+}
+
+impl<T> PtrBuffer for ExternBuffer<T> {
+    type ConstantPointer = *const T;
+    type MutablePointer = *mut T;
+
+    unsafe fn ptr(&self, index: usize) -> *const T {
+        // SAFETY: Same requirements.
+        unsafe { self.slot(index) }
+    }
+
+    unsafe fn mut_ptr(&mut self, index: usize) -> *mut T {
+        // SAFETY: Same requirements.
+        unsafe { self.slot(index) }
+    }
+
+    unsafe fn segment_len(&self, index: usize) -> usize {
+        // All the positions are in a single allocation.
+        self.capacity() - index
+    }
+}
+
+impl<T> RefBuffer for ExternBuffer<T> {
+    type ConstantReference<'a> = &'a T
+    where
+        Self: 'a;
+    type MutableReference<'a> = &'a mut T
+    where
+        Self: 'a;
+
+    unsafe fn index<'a: 'b, 'b>(&'a self, index: usize) -> &'b T {
+        // SAFETY: [`RefBuffer::index`] requires the position to be valid.
+        let ptr = unsafe { self.slot(index) };
+        // SAFETY: The position is valid and filled, so the pointer can be
+        // dereferenced.
+        unsafe { &*ptr }
+    }
+
+    unsafe fn mut_index<'a: 'b, 'b>(&'a mut self, index: usize) -> &'b mut T {
+        // SAFETY: [`RefBuffer::mut_index`] requires the position to be valid.
+        let ptr = unsafe { self.slot(index) };
+        // SAFETY: The position is valid and filled, so the pointer can be
+        // dereferenced.
+        unsafe { &mut *ptr }
+    }
+}
+
+impl<T> ContiguousMemoryBuffer for ExternBuffer<T> {}
+
+// SAFETY: The values are in the memory it was given.
+unsafe impl<T> OutOfLineBuffer for ExternBuffer<T> {}
+
+impl<T> ShrinkableBuffer for ExternBuffer<T> {}
+
+// SAFETY: The buffer uniquely owns its memory and `free` can be called from
+// any thread (required by `from_raw_parts`), so it can be sent to another
+// thread as long as the values can.
+unsafe impl<T: Send> Send for ExternBuffer<T> {}
+
+// SAFETY: Shared references only give shared access to the values.
+unsafe impl<T: Sync> Sync for ExternBuffer<T> {}
+
+// SAFETY: As a buffer it's not its responsabilities to clean the values that it
+// saves, it only frees the memory.
+unsafe impl<#[may_dangle] T> Drop for ExternBuffer<T> {
+    fn drop(&mut self) {
+        self.free();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        alloc::{self, Layout},
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use crate::collections::Vector;
+
+    use super::*;
+
+    const CAPACITY: usize = 4;
+
+    static FREED: AtomicUsize = AtomicUsize::new(0);
+
+    fn layout() -> Layout {
+        Layout::array::<String>(CAPACITY).unwrap()
+    }
+
+    /// Stands for the function of a C library.
+    unsafe extern "C" fn free_strings(ptr: *mut c_void) {
+        FREED.fetch_add(1, Ordering::SeqCst);
+        // SAFETY: It was allocated with the same layout in `allocate`.
+        unsafe { alloc::dealloc(ptr.cast(), layout()) };
+    }
+
+    fn allocate() -> NonNull<String> {
+        // SAFETY: The layout isn't zero-sized.
+        let ptr = unsafe { alloc::alloc(layout()) };
+        NonNull::new(ptr.cast()).unwrap()
+    }
+
+    #[test]
+    fn frees_when_dropped_or_shrunk_to_nothing() {
+        // SAFETY: The memory was just allocated for `CAPACITY` strings and
+        // `free_strings` frees it.
+        let buffer = unsafe { ExternBuffer::from_raw_parts(allocate(), CAPACITY, free_strings) };
+        let mut vec = Vector::from_buffer(buffer);
+        vec.push("a".to_string());
+        assert!(vec.try_reserve(CAPACITY).is_err());
+        drop(vec);
+        assert_eq!(FREED.load(Ordering::SeqCst), 1);
+
+        // SAFETY: Same as before.
+        let buffer = unsafe { ExternBuffer::from_raw_parts(allocate(), CAPACITY, free_strings) };
+        let mut vec = Vector::from_buffer(buffer);
+        vec.push("b".to_string());
+        vec.shrink_to_fit();
+        assert_eq!(vec.capacity(), CAPACITY);
+        vec.clear_and_shrink();
+        assert_eq!(vec.capacity(), 0);
+        assert_eq!(FREED.load(Ordering::SeqCst), 2);
+        drop(vec);
+        assert_eq!(FREED.load(Ordering::SeqCst), 2);
+    }
+}
@@ -1,15 +1,374 @@
-use crate::{base_buffers::zst::ZstBuffer, interface::Buffer, never::PhantomNever};
+use std::ops::RangeBounds;
 
-use super::conditional::{ConditionalBuffer, Selector};
+use crate::{
+    base_buffers::zst::ZstBuffer,
+    interface::{
+        capacity_contract::CapacityContract, caps::BufferCaps,
+        contiguous_memory::ContiguousMemoryBuffer, copy_value::CopyValueBuffer,
+        growable::GrowableBuffer, ptrs::PtrBuffer, refs::RefBuffer, resize_error::ResizeError,
+        shrinkable::ShrinkableBuffer, Buffer,
+    },
+};
 
-/// Composite buffer that automatically uses a ZstBuffer when T is a ZST. It
+/// Composite buffer that automatically uses a [`ZstBuffer`] when T is a ZST. It
 /// uses `B` otherwise.
-pub type ZstoBuffer<B> =
-    ConditionalBuffer<ZstBuffer<<B as Buffer>::Element>, B, ZstSelector<<B as Buffer>::Element>>;
-
-/// Internal type. [`Selector`] that detects if T is a ZST.
-#[doc(hidden)]
-pub struct ZstSelector<T>(PhantomNever<T>);
-impl<T> Selector for ZstSelector<T> {
-    const SELECT_A: bool = std::mem::size_of::<T>() == 0;
+///
+/// Because [`ZstBuffer`] doesn't have any state, it only needs to hold `B`.
+/// Note that `B` is still constructed even for zero-sized types (it just never
+/// gets used).
+///
+/// It's `#[repr(transparent)]`, so it has the same layout as `B`.
+#[repr(transparent)]
+pub struct ZstoBuffer<B: Buffer>(B);
+
+impl<B: Buffer> ZstoBuffer<B> {
+    /// Whether the elements are zero-sized types, in which case `B` is ignored.
+    const IS_ZST: bool = std::mem::size_of::<B::Element>() == 0;
+
+    /// Make a new [`ZstoBuffer<B>`] given the underlying buffer `B`.
+    pub fn from(buffer: B) -> Self {
+        Self(buffer)
+    }
+
+    /// Gives back the underlying buffer. For zero-sized types it doesn't hold
+    /// anything (they are never stored in it).
+    pub fn into_inner(self) -> B {
+        self.0
+    }
+
+    /// Internal utility to get the buffer used for zero-sized types.
+    ///
+    /// [`ZstBuffer`] is stateless so it can be made whenever necessary.
+    #[inline(always)]
+    fn zst() -> ZstBuffer<B::Element> {
+        debug_assert!(Self::IS_ZST);
+        ZstBuffer::new()
+    }
+}
+
+impl<B: Buffer + Default> Default for ZstoBuffer<B> {
+    fn default() -> Self {
+        Self::from(Default::default())
+    }
+}
+
+impl<B: Buffer> Buffer for ZstoBuffer<B> {
+    type Element = B::Element;
+
+    const CAPACITY_CONTRACT: CapacityContract = if Self::IS_ZST {
+        CapacityContract::Unbounded
+    } else {
+        B::CAPACITY_CONTRACT
+    };
+
+    #[inline(always)]
+    fn capacity(&self) -> usize {
+        if Self::IS_ZST {
+            Self::zst().capacity()
+        } else {
+            self.0.capacity()
+        }
+    }
+
+    #[inline]
+    fn allocated_bytes(&self) -> usize {
+        if Self::IS_ZST {
+            0
+        } else {
+            self.0.allocated_bytes()
+        }
+    }
+
+    #[inline]
+    fn caps(&self) -> BufferCaps {
+        if Self::IS_ZST {
+            Self::zst().caps()
+        } else {
+            self.0.caps()
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn take(&mut self, index: usize) -> Self::Element {
+        if Self::IS_ZST {
+            // SAFETY: Forwarding call to the ZST buffer.
+            unsafe { Self::zst().take(index) }
+        } else {
+            // SAFETY: Forwarding call to inner buffer.
+            unsafe { self.0.take(index) }
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn put(&mut self, index: usize, value: Self::Element) {
+        if Self::IS_ZST {
+            // SAFETY: Forwarding call to the ZST buffer.
+            unsafe { Self::zst().put(index, value) }
+        } else {
+            // SAFETY: Forwarding call to inner buffer.
+            unsafe { self.0.put(index, value) }
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn manually_drop(&mut self, index: usize) {
+        if Self::IS_ZST {
+            // SAFETY: Forwarding call to the ZST buffer.
+            unsafe { Self::zst().manually_drop(index) }
+        } else {
+            // SAFETY: Forwarding call to inner buffer.
+            unsafe { self.0.manually_drop(index) }
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn manually_drop_range<R: RangeBounds<usize> + Clone>(&mut self, values_range: R) {
+        if Self::IS_ZST {
+            // SAFETY: Forwarding call to the ZST buffer.
+            unsafe { Self::zst().manually_drop_range(values_range) }
+        } else {
+            // SAFETY: Forwarding call to inner buffer.
+            unsafe { self.0.manually_drop_range(values_range) }
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn fill_range<R: RangeBounds<usize> + Clone>(&mut self, range: R, value: &Self::Element)
+    where
+        Self::Element: Clone,
+    {
+        if Self::IS_ZST {
+            // SAFETY: Forwarding call to the ZST buffer.
+            unsafe { Self::zst().fill_range(range, value) }
+        } else {
+            // SAFETY: Forwarding call to inner buffer.
+            unsafe { self.0.fill_range(range, value) }
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn swap_remove_into(&mut self, index: usize, last_index: usize) -> Self::Element {
+        if Self::IS_ZST {
+            // SAFETY: Forwarding call to the ZST buffer.
+            unsafe { Self::zst().swap_remove_into(index, last_index) }
+        } else {
+            // SAFETY: Forwarding call to inner buffer.
+            unsafe { self.0.swap_remove_into(index, last_index) }
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn try_grow(&mut self, target: usize) -> Result<(), ResizeError> {
+        if Self::IS_ZST {
+            // SAFETY: Forwarding call to the ZST buffer.
+            unsafe { Self::zst().try_grow(target) }
+        } else {
+            // SAFETY: Forwarding call to inner buffer.
+            unsafe { self.0.try_grow(target) }
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn try_grow_at_least(
+        &mut self,
+        min: usize,
+        preferred: usize,
+    ) -> Result<usize, ResizeError> {
+        if Self::IS_ZST {
+            // SAFETY: Forwarding call to the ZST buffer.
+            unsafe { Self::zst().try_grow_at_least(min, preferred) }
+        } else {
+            // SAFETY: Forwarding call to inner buffer.
+            unsafe { self.0.try_grow_at_least(min, preferred) }
+        }
+    }
+
+    #[inline(always)]
+    fn preferred_grow_target(&self, current: usize, required: usize) -> usize {
+        if Self::IS_ZST {
+            required
+        } else {
+            self.0.preferred_grow_target(current, required)
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn try_shrink(&mut self, target: usize) -> Result<(), ResizeError> {
+        if Self::IS_ZST {
+            // SAFETY: Forwarding call to the ZST buffer.
+            unsafe { Self::zst().try_shrink(target) }
+        } else {
+            // SAFETY: Forwarding call to inner buffer.
+            unsafe { self.0.try_shrink(target) }
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn shift_right<R: RangeBounds<usize> + Clone>(&mut self, to_move: R, positions: usize) {
+        if Self::IS_ZST {
+            // SAFETY: Forwarding call to the ZST buffer.
+            unsafe { Self::zst().shift_right(to_move, positions) }
+        } else {
+            // SAFETY: Forwarding call to inner buffer.
+            unsafe { self.0.shift_right(to_move, positions) }
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn shift_left<R: RangeBounds<usize> + Clone>(&mut self, to_move: R, positions: usize) {
+        if Self::IS_ZST {
+            // SAFETY: Forwarding call to the ZST buffer.
+            unsafe { Self::zst().shift_left(to_move, positions) }
+        } else {
+            // SAFETY: Forwarding call to inner buffer.
+            unsafe { self.0.shift_left(to_move, positions) }
+        }
+    }
+}
+
+impl<B> CopyValueBuffer for ZstoBuffer<B>
+where
+    B: CopyValueBuffer,
+    B::Element: Copy,
+{
+    #[inline(always)]
+    unsafe fn copy(&self, index: usize) -> Self::Element {
+        if Self::IS_ZST {
+            // SAFETY: Forwarding call to the ZST buffer.
+            unsafe { Self::zst().copy(index) }
+        } else {
+            // SAFETY: Forwarding call to inner buffer.
+            unsafe { self.0.copy(index) }
+        }
+    }
+}
+
+impl<B> PtrBuffer for ZstoBuffer<B>
+where
+    B: PtrBuffer<
+        ConstantPointer = *const <B as Buffer>::Element,
+        MutablePointer = *mut <B as Buffer>::Element,
+    >,
+{
+    type ConstantPointer = *const B::Element;
+    type MutablePointer = *mut B::Element;
+
+    #[inline(always)]
+    unsafe fn ptr(&self, index: usize) -> Self::ConstantPointer {
+        if Self::IS_ZST {
+            // SAFETY: Forwarding call to the ZST buffer.
+            unsafe { Self::zst().ptr(index) }
+        } else {
+            // SAFETY: Forwarding call to inner buffer.
+            unsafe { self.0.ptr(index) }
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn mut_ptr(&mut self, index: usize) -> Self::MutablePointer {
+        if Self::IS_ZST {
+            // SAFETY: Forwarding call to the ZST buffer.
+            unsafe { Self::zst().mut_ptr(index) }
+        } else {
+            // SAFETY: Forwarding call to inner buffer.
+            unsafe { self.0.mut_ptr(index) }
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn segment_len(&self, index: usize) -> usize {
+        if Self::IS_ZST {
+            // SAFETY: Forwarding call to the ZST buffer.
+            unsafe { Self::zst().segment_len(index) }
+        } else {
+            // SAFETY: Forwarding call to inner buffer.
+            unsafe { self.0.segment_len(index) }
+        }
+    }
+}
+
+impl<B> RefBuffer for ZstoBuffer<B>
+where
+    B: PtrBuffer<
+        ConstantPointer = *const <B as Buffer>::Element,
+        MutablePointer = *mut <B as Buffer>::Element,
+    >,
+{
+    type ConstantReference<'a> = &'a B::Element
+    where
+        Self: 'a;
+    type MutableReference<'a> = &'a mut B::Element
+    where
+        Self: 'a;
+
+    #[inline(always)]
+    unsafe fn index<'a: 'b, 'b>(&'a self, index: usize) -> &'b B::Element {
+        // SAFETY: [`RefBuffer::index`] has at least the same requirements as
+        // [`PtrBuffer::ptr`].
+        let ptr = unsafe { self.ptr(index) };
+        // SAFETY: [`PtrBuffer::ptr`] requires that the pointer can be
+        // dereferenced.
+        unsafe { &*ptr }
+    }
+
+    #[inline(always)]
+    unsafe fn mut_index<'a: 'b, 'b>(&'a mut self, index: usize) -> &'b mut B::Element {
+        // SAFETY: [`RefBuffer::mut_index`] has at least the same requirements
+        // as [`PtrBuffer::mut_ptr`].
+        let ptr = unsafe { self.mut_ptr(index) };
+        // SAFETY: [`PtrBuffer::mut_ptr`] requires that the pointer can be
+        // dereferenced.
+        unsafe { &mut *ptr }
+    }
+}
+
+impl<B: ContiguousMemoryBuffer> ContiguousMemoryBuffer for ZstoBuffer<B> {}
+
+impl<B: GrowableBuffer> GrowableBuffer for ZstoBuffer<B> {}
+
+impl<B: ShrinkableBuffer> ShrinkableBuffer for ZstoBuffer<B> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        base_buffers::{HeapBuffer, InlineBuffer},
+        collections::Vector,
+        composites::{AtLeastBuffer, ExponentialGrowthBuffer, SvoBuffer},
+        DefaultBuffer,
+    };
+
+    use super::*;
+
+    fn assert_all_capabilities<B>()
+    where
+        B: ContiguousMemoryBuffer + CopyValueBuffer + RefBuffer,
+        B::Element: Copy,
+    {
+    }
+
+    #[test]
+    fn forwards_all_capabilities() {
+        assert_all_capabilities::<DefaultBuffer<u32>>();
+        assert_all_capabilities::<DefaultBuffer<()>>();
+        assert_all_capabilities::<ZstoBuffer<InlineBuffer<u32, 1>>>();
+        assert_all_capabilities::<ZstoBuffer<ExponentialGrowthBuffer<HeapBuffer<u32>>>>();
+        assert_all_capabilities::<ZstoBuffer<AtLeastBuffer<4, SvoBuffer<2, HeapBuffer<u32>>>>>();
+    }
+
+    #[test]
+    fn zst_never_uses_the_inner_buffer() {
+        let mut vec = Vector::<(), ZstoBuffer<InlineBuffer<(), 0>>>::new();
+        vec.push(());
+        vec.push(());
+        assert_eq!(vec.len(), 2);
+        assert_eq!(vec.capacity(), usize::MAX);
+    }
+
+    #[test]
+    fn borrowed_elements_can_be_referenced() {
+        let value = String::from("value");
+        let mut vec = Vector::<&String>::new();
+        vec.push(&value);
+        assert_eq!(*vec.index(0), "value");
+    }
 }
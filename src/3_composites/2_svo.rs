@@ -1,219 +1,483 @@
-use std::ops::RangeBounds;
+use std::{
+    mem::{self, MaybeUninit},
+    ops::RangeBounds,
+    ptr,
+};
 
 use crate::{
-    base_buffers::inline::InlineBuffer,
+    base_buffers::HeapBuffer,
     interface::{
-        contiguous_memory::ContiguousMemoryBuffer, copy_value::CopyValueBuffer, ptrs::PtrBuffer,
-        refs::RefBuffer, resize_error::ResizeError, Buffer,
+        contiguous_memory::{drop_range_in_place, fill_range_in_place, ContiguousMemoryBuffer},
+        copy_value::CopyValueBuffer,
+        growable::GrowableBuffer,
+        ptrs::PtrBuffer,
+        refs::RefBuffer,
+        resize_error::ResizeError,
+        shrinkable::ShrinkableBuffer,
+        Buffer, BufferCaps, CapacityContract,
     },
 };
 
-use super::either::EitherBuffer;
-
 /// Buffer composite that adds small vector optimization (SVO) to a given
 /// buffer. This means that it starts working with an inline buffer (which is
 /// usually left on the stack) but can automatically grow into an arbitrary
 /// bigger buffer (usually a heap-allocated one which can grow).
-pub struct SvoBuffer<const SMALL_SIZE: usize, B>
+///
+/// Which one is in use is encoded in the capacity of the big buffer: it's used
+/// only when it's bigger than the small one. There is no other state to check
+/// (or to keep in sync), and every access starts by picking the start of the
+/// memory in use with a single comparison.
+///
+/// # Stack usage
+/// The small buffer is part of this buffer, so it takes at least
+/// `SMALL_SIZE * size_of::<T>()` bytes wherever it's placed (usually the
+/// stack), and moving it copies all of them. With a big `SMALL_SIZE` (like the
+/// one of [`crate::DefaultBuffer`]) this can overflow small stacks. In that case
+/// use [`SvoBuffer::new_boxed_inline`] (see [`BoxedInline`]).
+///
+/// `T` is always `B`'s element and never needs to be specified. It's only
+/// there so the inline buffer doesn't use a projection (`B::Element`), which
+/// would make this buffer invariant.
+pub struct SvoBuffer<const SMALL_SIZE: usize, B, T = <B as Buffer>::Element>
 where
-    B: ContiguousMemoryBuffer + Default,
+    B: ContiguousMemoryBuffer<Element = T>,
 {
-    inner: EitherBuffer<InlineBuffer<B::Element, SMALL_SIZE>, B>,
+    small: [MaybeUninit<T>; SMALL_SIZE],
+    big: B,
 }
 
-impl<const SMALL_SIZE: usize, B> SvoBuffer<SMALL_SIZE, B>
+/// [`SvoBuffer`] on top of a [`HeapBuffer`] whose small buffer lives on the heap,
+/// for `N`s big enough to threaten the stack. Make it with
+/// [`SvoBuffer::new_boxed_inline`].
+pub type BoxedInline<const N: usize, T> = Box<SvoBuffer<N, HeapBuffer<T>>>;
+
+impl<const SMALL_SIZE: usize, B, T> SvoBuffer<SMALL_SIZE, B, T>
 where
-    B: ContiguousMemoryBuffer + Default,
+    B: ContiguousMemoryBuffer<Element = T> + Default,
 {
     /// Creates a new empty buffer
     pub fn new() -> Self {
         Default::default()
     }
 
+    /// Creates a new empty buffer directly on the heap, so the small buffer
+    /// never goes through the stack. It still avoids allocating when growing
+    /// up to `SMALL_SIZE`, but it's allocated once.
+    ///
+    /// Note that anything that makes the buffer with [`Default`] (eg.
+    /// [`crate::collections::Vector::new`]) makes it on the stack first, even
+    /// when boxed. Give this one to [`crate::collections::Vector::from_buffer`]
+    /// instead.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::{base_buffers::HeapBuffer, collections::Vector, composites::SvoBuffer};
+    /// let buffer = SvoBuffer::<100_000, HeapBuffer<u64>>::new_boxed_inline();
+    /// let mut vec = Vector::from_buffer(buffer);
+    /// vec.push(1);
+    /// assert_eq!(vec.capacity(), 100_000);
+    /// ```
+    pub fn new_boxed_inline() -> Box<Self> {
+        let mut boxed = Box::<Self>::new_uninit();
+        let this = boxed.as_mut_ptr();
+        // SAFETY: The pointer is valid (it comes from the box), and only the
+        // address of the field is taken.
+        let big = unsafe { &raw mut (*this).big };
+        // SAFETY: The field is valid for writes and uninitialized.
+        unsafe { big.write(B::default()) };
+        // SAFETY: The big buffer was just initialized, and the small one is
+        // made of maybe-uninit values (which are always initialized).
+        unsafe { boxed.assume_init() }
+    }
+}
+
+impl<const SMALL_SIZE: usize, B, T> SvoBuffer<SMALL_SIZE, B, T>
+where
+    B: ContiguousMemoryBuffer<Element = T>,
+{
+    /// Makes an empty buffer which grows into `big`, for big buffers that
+    /// cannot be made with [`Default`] (eg. an
+    /// [`crate::base_buffers::AllocatorBuffer`] with a given allocator).
+    ///
+    /// `big` must not hold any values. If it's already bigger than the small
+    /// buffer, it's used from the start.
+    pub fn with_big(big: B) -> Self {
+        Self {
+            small: [const { MaybeUninit::uninit() }; SMALL_SIZE],
+            big,
+        }
+    }
+
+    /// Makes a buffer which uses `big` from the start, keeping whatever it
+    /// holds. It's given back if it's not bigger than the small buffer (it
+    /// wouldn't be used).
+    pub fn from_big(big: B) -> Result<Self, B> {
+        if big.capacity() > SMALL_SIZE {
+            Ok(Self {
+                small: [const { MaybeUninit::uninit() }; SMALL_SIZE],
+                big,
+            })
+        } else {
+            Err(big)
+        }
+    }
+
+    /// Gives back the big buffer (with whatever it holds) if it's in use.
+    /// Otherwise the values are in the small buffer, so it gives back itself.
+    pub fn into_big(self) -> Result<B, Self> {
+        if self.is_big() {
+            Ok(self.big)
+        } else {
+            Err(self)
+        }
+    }
+
     /// Internal only.
     ///
-    /// Move all data from the small vector into the big one.
+    /// Whether the values are in the big buffer (instead of the small one).
+    #[inline(always)]
+    fn is_big(&self) -> bool {
+        self.big.capacity() > SMALL_SIZE
+    }
+
+    /// Internal only.
+    ///
+    /// Pointer to the start of the memory in use.
+    #[inline(always)]
+    fn start(&self) -> *const T {
+        if self.is_big() {
+            // SAFETY: The big buffer has a capacity bigger than `SMALL_SIZE`,
+            // so `0` is a valid index.
+            unsafe { self.big.ptr(0) }
+        } else {
+            self.small.as_ptr().cast()
+        }
+    }
+
+    /// Internal only.
+    ///
+    /// Mutable pointer to the start of the memory in use.
+    #[inline(always)]
+    fn mut_start(&mut self) -> *mut T {
+        if self.is_big() {
+            // SAFETY: The big buffer has a capacity bigger than `SMALL_SIZE`,
+            // so `0` is a valid index.
+            unsafe { self.big.mut_ptr(0) }
+        } else {
+            self.small.as_mut_ptr().cast()
+        }
+    }
+
+    /// Internal only.
+    ///
+    /// Move all data from the small buffer into the big one.
     ///
     /// # SAFETY
     ///   * `target` > `SMALL_SIZE`
+    ///   * The small buffer is in use.
+    #[cold]
     unsafe fn move_into_big(&mut self, target: usize) -> Result<(), ResizeError> {
-        let EitherBuffer::First(ref current_buf) = self.inner else {
-            // SAFETY: This is only called when we grow from small to big.
-            // This means that we always have an inline buffer at this point
-            unreachable!()
-        };
-
-        let mut new_buf: B = Default::default();
-        if new_buf.capacity() < target {
-            // SAFETY: The conditional checks that `new_buffer` actually needs
-            // to grow.
-            unsafe { new_buf.try_grow(target)? };
-        }
+        debug_assert!(target > SMALL_SIZE && !self.is_big());
+        // SAFETY: The big buffer isn't in use, so its capacity is at most
+        // `SMALL_SIZE` which is smaller than `target`.
+        unsafe { self.big.try_grow(target)? };
 
-        // SAFETY: `current_buf.capacity()` > 0; thus `0` is a valid index.
-        let src = unsafe { current_buf.ptr(0) };
-        // SAFETY: `new_buf.capacity()` > 0; thus `0` is a valid index.
-        let dst = unsafe { new_buf.mut_ptr(0) };
+        let src = self.small.as_ptr().cast::<T>();
+        // SAFETY: `self.big.capacity()` >= `target` > 0; thus `0` is a valid
+        // index.
+        let dst = unsafe { self.big.mut_ptr(0) };
 
         // SAFETY:
         //   * Both buffers have contiguous memory.
-        //   * `new_buf.capacity()` > `current_buf.capacity()`.
+        //   * The big buffer has now more than `SMALL_SIZE` positions.
         //   * They do not reuse the same memory.
-        unsafe { std::ptr::copy_nonoverlapping(src, dst, current_buf.capacity()) };
-
-        self.inner = EitherBuffer::Second(new_buf);
+        unsafe { ptr::copy_nonoverlapping(src, dst, SMALL_SIZE) };
         Ok(())
     }
+
+    /// Internal only.
+    ///
+    /// Move the first `target` positions back into the small buffer and
+    /// release the big one.
+    ///
+    /// # SAFETY
+    ///   * `target` <= `SMALL_SIZE`
+    ///   * The big buffer is in use and positions from `target` are empty.
+    #[cold]
+    unsafe fn move_into_small(&mut self, target: usize) -> Result<(), ResizeError> {
+        debug_assert!(target <= SMALL_SIZE && self.is_big());
+        // SAFETY: The big buffer has more than `SMALL_SIZE` positions, so `0`
+        // is a valid index.
+        let src = unsafe { self.big.ptr(0) };
+        let dst = self.small.as_mut_ptr().cast::<T>();
+
+        // SAFETY:
+        //   * Both buffers have contiguous memory with at least `target`
+        //     positions.
+        //   * They do not reuse the same memory.
+        unsafe { ptr::copy_nonoverlapping(src, dst, target) };
+
+        // If it fails the big buffer is still in use, and it still has the
+        // values (what was copied is just ignored).
+        // SAFETY: 0 is smaller than its capacity and all the values are
+        // considered moved.
+        unsafe { self.big.try_shrink(0) }
+    }
 }
 
-impl<const SMALL_SIZE: usize, B> Default for SvoBuffer<SMALL_SIZE, B>
+impl<const SMALL_SIZE: usize, B, T> Default for SvoBuffer<SMALL_SIZE, B, T>
 where
-    B: ContiguousMemoryBuffer + Default,
+    B: ContiguousMemoryBuffer<Element = T> + Default,
 {
     fn default() -> Self {
-        Self {
-            inner: Default::default(),
-        }
+        Self::with_big(Default::default())
     }
 }
 
-impl<const SMALL_SIZE: usize, B> Buffer for SvoBuffer<SMALL_SIZE, B>
+impl<const SMALL_SIZE: usize, B, T> Buffer for SvoBuffer<SMALL_SIZE, B, T>
 where
-    B: ContiguousMemoryBuffer + Default,
+    B: ContiguousMemoryBuffer<Element = T>,
 {
     type Element = B::Element;
 
+    const CAPACITY_CONTRACT: CapacityContract = B::CAPACITY_CONTRACT;
+
+    #[inline(always)]
     fn capacity(&self) -> usize {
-        self.inner.capacity()
+        std::cmp::max(SMALL_SIZE, self.big.capacity())
+    }
+
+    /// The small buffer is always there, so it counts even when the big one
+    /// is in use.
+    #[inline]
+    fn allocated_bytes(&self) -> usize {
+        mem::size_of::<[MaybeUninit<T>; SMALL_SIZE]>() + self.big.allocated_bytes()
+    }
+
+    /// The ones of the big buffer, which is the one that grows and shrinks.
+    /// Both are always contiguous.
+    #[inline]
+    fn caps(&self) -> BufferCaps {
+        self.big.caps() | BufferCaps::CONTIGUOUS
     }
 
+    #[inline(always)]
     unsafe fn take(&mut self, index: usize) -> Self::Element {
-        // SAFETY: Forwarding call to inner buffer.
-        unsafe { self.inner.take(index) }
+        // SAFETY: [`Buffer::take`] requires the position to be valid.
+        let ptr = unsafe { self.mut_ptr(index) };
+        // SAFETY: [`Buffer::take`] requires the position to be filled.
+        unsafe { ptr.read() }
     }
 
+    #[inline(always)]
     unsafe fn put(&mut self, index: usize, value: Self::Element) {
-        // SAFETY: Forwarding call to inner buffer.
-        unsafe { self.inner.put(index, value) }
+        // SAFETY: [`Buffer::put`] requires the position to be valid.
+        let ptr = unsafe { self.mut_ptr(index) };
+        // SAFETY: [`Buffer::put`] requires the position to be empty.
+        unsafe { ptr.write(value) }
     }
 
+    #[inline(always)]
     unsafe fn manually_drop(&mut self, index: usize) {
-        // SAFETY: Forwarding call to inner buffer.
-        unsafe { self.inner.manually_drop(index) }
+        // SAFETY: [`Buffer::manually_drop`] requires the position to be valid.
+        let ptr = unsafe { self.mut_ptr(index) };
+        // SAFETY: [`Buffer::manually_drop`] requires the position to be
+        // filled.
+        unsafe { ptr::drop_in_place(ptr) }
     }
 
+    #[inline(always)]
     unsafe fn manually_drop_range<R: RangeBounds<usize> + Clone>(&mut self, values_range: R) {
-        // SAFETY: Forwarding call to inner buffer.
-        unsafe { self.inner.manually_drop_range(values_range) }
+        // SAFETY: Same requirements as [`Buffer::manually_drop_range`].
+        unsafe { drop_range_in_place(self, values_range) }
+    }
+
+    #[inline(always)]
+    unsafe fn fill_range<R: RangeBounds<usize> + Clone>(&mut self, range: R, value: &Self::Element)
+    where
+        Self::Element: Clone,
+    {
+        // SAFETY: Same requirements as [`Buffer::fill_range`].
+        unsafe { fill_range_in_place(self, range, value) }
     }
+
+    #[inline]
     unsafe fn try_grow(&mut self, target: usize) -> Result<(), ResizeError> {
-        match self.inner {
-            EitherBuffer::First(_) => {
-                // SAFETY: `target` > `self.capacity()` = `SMALL_SIZE`
-                unsafe { self.move_into_big(target) }
-            }
-            EitherBuffer::Second(ref mut buf) => {
-                // SAFETY: Forwarding call to big buffer.
-                unsafe { buf.try_grow(target) }
-            }
+        if self.is_big() {
+            // SAFETY: Forwarding call to big buffer.
+            unsafe { self.big.try_grow(target) }
+        } else {
+            // SAFETY: `target` > `self.capacity()` = `SMALL_SIZE`
+            unsafe { self.move_into_big(target) }
         }
     }
+
+    #[inline]
+    fn preferred_grow_target(&self, current: usize, required: usize) -> usize {
+        // Growing always ends up in the big buffer, so it's its policy.
+        self.big.preferred_grow_target(current, required)
+    }
+
+    #[inline]
     unsafe fn try_shrink(&mut self, target: usize) -> Result<(), ResizeError> {
-        match self.inner {
-            EitherBuffer::First(_) => Ok(()),
-            EitherBuffer::Second(ref mut buf) => {
-                // SAFETY: Forwarding call to big buffer.
-                unsafe { buf.try_shrink(target) }
-            }
+        if !self.is_big() {
+            Ok(())
+        } else if target > SMALL_SIZE {
+            // SAFETY: Forwarding call to big buffer.
+            unsafe { self.big.try_shrink(target) }
+        } else {
+            // SAFETY: `target` fits in the small buffer and
+            // [`Buffer::try_shrink`] requires positions from `target` to be
+            // empty.
+            unsafe { self.move_into_small(target) }
         }
     }
 }
 
-impl<const SMALL_SIZE: usize, B> CopyValueBuffer for SvoBuffer<SMALL_SIZE, B>
+impl<const SMALL_SIZE: usize, B, T> CopyValueBuffer for SvoBuffer<SMALL_SIZE, B, T>
 where
-    B: ContiguousMemoryBuffer + CopyValueBuffer + Default,
+    B: ContiguousMemoryBuffer<Element = T>,
     Self::Element: Copy,
 {
+    #[inline(always)]
     unsafe fn copy(&self, index: usize) -> Self::Element {
-        // SAFETY: Forwarding call to inner buffer.
-        unsafe { self.inner.copy(index) }
+        // SAFETY: [`CopyValueBuffer::copy`] requires the position to be valid.
+        let ptr = unsafe { self.ptr(index) };
+        // SAFETY: [`CopyValueBuffer::copy`] requires the position to be
+        // filled, and it's `Copy`.
+        unsafe { ptr.read() }
     }
 }
 
-impl<const SMALL_SIZE: usize, B> PtrBuffer for SvoBuffer<SMALL_SIZE, B>
+impl<const SMALL_SIZE: usize, B, T> PtrBuffer for SvoBuffer<SMALL_SIZE, B, T>
 where
-    B: ContiguousMemoryBuffer + Default,
+    B: ContiguousMemoryBuffer<Element = T>,
 {
-    type ConstantPointer = B::ConstantPointer;
-    type MutablePointer = B::MutablePointer;
+    type ConstantPointer = *const T;
+    type MutablePointer = *mut T;
 
+    #[inline(always)]
     unsafe fn ptr(&self, index: usize) -> *const Self::Element {
-        // SAFETY: Forwarding call to inner buffer.
-        unsafe { self.inner.ptr(index) }
+        debug_assert!(index < self.capacity());
+        // SAFETY: `index` is a valid position, so it's inside the memory in
+        // use.
+        unsafe { self.start().add(index) }
     }
 
+    #[inline(always)]
     unsafe fn mut_ptr(&mut self, index: usize) -> *mut Self::Element {
-        // SAFETY: Forwarding call to inner buffer.
-        unsafe { self.inner.mut_ptr(index) }
+        debug_assert!(index < self.capacity());
+        // SAFETY: `index` is a valid position, so it's inside the memory in
+        // use.
+        unsafe { self.mut_start().add(index) }
+    }
+
+    unsafe fn segment_len(&self, index: usize) -> usize {
+        // All the positions are in a single allocation.
+        self.capacity() - index
     }
 }
 
-impl<const SMALL_SIZE: usize, B> RefBuffer for SvoBuffer<SMALL_SIZE, B>
+impl<const SMALL_SIZE: usize, B, T> RefBuffer for SvoBuffer<SMALL_SIZE, B, T>
 where
-    B: ContiguousMemoryBuffer + Default,
-    for<'a> B: RefBuffer<
-            ConstantReference<'a> = &'a <B as Buffer>::Element,
-            MutableReference<'a> = &'a mut <B as Buffer>::Element,
-        > + 'a,
+    B: ContiguousMemoryBuffer<Element = T>,
 {
-    type ConstantReference<'a> = B::ConstantReference<'a>;
-
-    type MutableReference<'a> = B::MutableReference<'a>;
-
-    unsafe fn index<'a: 'b, 'b>(&'a self, index: usize) -> Self::ConstantReference<'b> {
-        // Borrow checker can't check `self.inner.index(index)` lifetimes.
-        match self.inner {
-            EitherBuffer::First(ref b) => {
-                // SAFETY: Forwarding call to small buffer.
-                unsafe { RefBuffer::index(b, index) }
-            }
-            EitherBuffer::Second(ref b) => {
-                // SAFETY: Forwarding call to big buffer.
-                unsafe { RefBuffer::index(b, index) }
-            }
-        }
+    type ConstantReference<'a> = &'a B::Element
+    where
+        Self: 'a;
+    type MutableReference<'a> = &'a mut B::Element
+    where
+        Self: 'a;
+
+    #[inline(always)]
+    unsafe fn index<'a: 'b, 'b>(&'a self, index: usize) -> &'b B::Element {
+        // Both buffers have contiguous memory, so the pointer can be used
+        // directly regardless of which one is currently used.
+        // SAFETY: [`RefBuffer::index`] has at least the same requirements as
+        // [`PtrBuffer::ptr`].
+        let ptr = unsafe { self.ptr(index) };
+        // SAFETY: [`PtrBuffer::ptr`] requires that the pointer can be
+        // dereferenced.
+        unsafe { &*ptr }
     }
 
-    unsafe fn mut_index<'a: 'b, 'b>(&'a mut self, index: usize) -> Self::MutableReference<'b> {
-        // Borrow checker can't check `self.inner.mut_index(index)` lifetimes.
-        match self.inner {
-            EitherBuffer::First(ref mut b) => {
-                // SAFETY: Forwarding call to small buffer.
-                unsafe { RefBuffer::mut_index(b, index) }
-            }
-            EitherBuffer::Second(ref mut b) => {
-                // SAFETY: Forwarding call to big buffer.
-                unsafe { RefBuffer::mut_index(b, index) }
-            }
-        }
+    #[inline(always)]
+    unsafe fn mut_index<'a: 'b, 'b>(&'a mut self, index: usize) -> &'b mut B::Element {
+        // SAFETY: [`RefBuffer::mut_index`] has at least the same requirements
+        // as [`PtrBuffer::mut_ptr`].
+        let ptr = unsafe { self.mut_ptr(index) };
+        // SAFETY: [`PtrBuffer::mut_ptr`] requires that the pointer can be
+        // dereferenced.
+        unsafe { &mut *ptr }
     }
 }
 
-impl<const SMALL_SIZE: usize, B> ContiguousMemoryBuffer for SvoBuffer<SMALL_SIZE, B> where
-    B: ContiguousMemoryBuffer + Default
+impl<const SMALL_SIZE: usize, B, T> ContiguousMemoryBuffer for SvoBuffer<SMALL_SIZE, B, T> where
+    B: ContiguousMemoryBuffer<Element = T>
+{
+}
+
+/// The small buffer can't grow, but it moves into the big one when necessary.
+impl<const SMALL_SIZE: usize, B, T> GrowableBuffer for SvoBuffer<SMALL_SIZE, B, T> where
+    B: ContiguousMemoryBuffer<Element = T> + GrowableBuffer
+{
+}
+
+/// Shrinking enough moves the values back into the small buffer, giving back
+/// all the big buffer's memory.
+impl<const SMALL_SIZE: usize, B, T> ShrinkableBuffer for SvoBuffer<SMALL_SIZE, B, T> where
+    B: ContiguousMemoryBuffer<Element = T> + ShrinkableBuffer
 {
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::base_buffers::heap::HeapBuffer;
+    use crate::base_buffers::{heap::HeapBuffer, InlineBuffer};
 
     use super::*;
 
+    // Compile-time check: it must be covariant like its inner buffers.
+    #[allow(dead_code)]
+    fn is_covariant<'a>(
+        buffer: SvoBuffer<2, HeapBuffer<&'static str>>,
+    ) -> SvoBuffer<2, HeapBuffer<&'a str>> {
+        buffer
+    }
+
+    #[test]
+    fn allocated_bytes_include_the_small_buffer() {
+        let mut buffer = SvoBuffer::<4, HeapBuffer<u16>>::new();
+        assert_eq!(buffer.allocated_bytes(), 8);
+        // SAFETY: 10 is bigger than its capacity (4) and it's empty.
+        unsafe { buffer.try_grow(10) }.unwrap();
+        assert_eq!(buffer.allocated_bytes(), 8 + 20);
+    }
+
+    #[test]
+    fn caps_are_the_ones_of_the_big_buffer() {
+        let heap = SvoBuffer::<4, HeapBuffer<u16>>::new();
+        assert_eq!(heap.caps(), BufferCaps::all());
+        let inline = SvoBuffer::<2, InlineBuffer<u16, 8>>::new();
+        assert_eq!(inline.caps(), BufferCaps::CONTIGUOUS);
+    }
+
+    #[test]
+    fn boxed_inline_buffers_keep_the_small_buffer_optimization() {
+        let mut buffer: BoxedInline<2, String> = SvoBuffer::new_boxed_inline();
+        assert_eq!(buffer.capacity(), 2);
+        // SAFETY: 0 is a valid position, and it's empty.
+        unsafe { buffer.put(0, "a".to_string()) };
+        // SAFETY: 8 > 2
+        unsafe { buffer.try_grow(8) }.expect("Should be able to grow");
+        // SAFETY: 0 is still valid, and it was filled before growing.
+        assert_eq!(unsafe { buffer.take(0) }, "a");
+    }
+
     #[test]
     fn should_be_able_to_grow() {
         let mut buffer: SvoBuffer<1, HeapBuffer<u32>> = Default::default();
         assert_eq!(buffer.capacity(), 1);
+        // SAFETY: 32 > 1
         unsafe { buffer.try_grow(32) }.expect("Should be able to grow");
         assert!(buffer.capacity() >= 32)
     }
@@ -221,10 +485,45 @@ mod tests {
     #[test]
     fn should_move_elements_when_growing() {
         let mut buffer: SvoBuffer<1, HeapBuffer<u32>> = Default::default();
-        unsafe {
-            buffer.put(0, 123);
-            buffer.try_grow(32).expect("Should be able to grow");
-            assert_eq!(buffer.take(0), 123);
-        }
+        // SAFETY: 0 is a valid position, and it's empty.
+        unsafe { buffer.put(0, 123) };
+        // SAFETY: 32 > 1
+        unsafe { buffer.try_grow(32) }.expect("Should be able to grow");
+        // SAFETY: 0 is still valid, and it was filled before growing.
+        assert_eq!(unsafe { buffer.take(0) }, 123);
+    }
+
+    #[test]
+    fn should_move_elements_back_when_shrinking() {
+        let mut buffer: SvoBuffer<2, HeapBuffer<u32>> = Default::default();
+        // SAFETY: 32 > 2
+        unsafe { buffer.try_grow(32) }.expect("Should be able to grow");
+        // SAFETY: 0 and 1 are valid positions, and they're empty.
+        unsafe { buffer.put(0, 1) };
+        // SAFETY: Same as before.
+        unsafe { buffer.put(1, 2) };
+
+        // SAFETY: 2 < 32 and only 0 and 1 are filled.
+        unsafe { buffer.try_shrink(2) }.expect("Should be able to shrink");
+        assert_eq!(buffer.capacity(), 2);
+        assert_eq!(buffer.big.capacity(), 0);
+        // SAFETY: 0 is still valid and filled.
+        assert_eq!(unsafe { buffer.take(0) }, 1);
+        // SAFETY: Same for 1.
+        assert_eq!(unsafe { buffer.take(1) }, 2);
+    }
+
+    #[test]
+    fn should_shrink_the_big_buffer_while_it_fits() {
+        let mut buffer: SvoBuffer<2, HeapBuffer<u32>> = Default::default();
+        // SAFETY: 32 > 2
+        unsafe { buffer.try_grow(32) }.expect("Should be able to grow");
+        // SAFETY: 0 is a valid position, and it's empty.
+        unsafe { buffer.put(0, 123) };
+        // SAFETY: 4 < 32 and only 0 is filled.
+        unsafe { buffer.try_shrink(4) }.expect("Should be able to shrink");
+        assert_eq!(buffer.capacity(), 4);
+        // SAFETY: 0 is still valid and filled.
+        assert_eq!(unsafe { buffer.take(0) }, 123);
     }
 }
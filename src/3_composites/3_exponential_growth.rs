@@ -1,4 +1,9 @@
-use crate::interface::{indirect_buffer::IndirectBuffer, resize_error::ResizeError, Buffer};
+use std::ops::Range;
+
+use crate::interface::{
+    capacity_contract::CapacityContract, indirect_buffer::IndirectBuffer,
+    resize_error::ResizeError, sparse::SparseBuffer, transparent::TransparentBuffer, Buffer,
+};
 
 /// Composite buffer that modifies the underlying buffer so it grows
 /// exponentially (in powers of 2) instead of the actual target passed.
@@ -26,22 +31,54 @@ impl<B: Buffer> IndirectBuffer for ExponentialGrowthBuffer<B> {
     type InnerBufferRef<'a> = &'a Self::InnerBuffer where Self: 'a;
     type InnerBufferMutRef<'a> = &'a mut Self::InnerBuffer where Self: 'a;
 
+    const CAPACITY_CONTRACT: CapacityContract = B::CAPACITY_CONTRACT.rounded_up();
+
+    #[inline(always)]
     fn inner(&self) -> &B {
         &self.0
     }
 
+    #[inline(always)]
     fn inner_mut(&mut self) -> &mut B {
         &mut self.0
     }
 
     unsafe fn try_grow(&mut self, target: usize) -> Result<(), ResizeError> {
-        // SAFETY: target is always bigger than 0 because of the restriction on Buffer; it won't underflow.
-        let new_target = (target - 1).next_power_of_two();
+        let new_target = round_up(target);
         let inner = self.inner_mut();
 
         // SAFETY: `new_target` >= `target` > `self.capacity()`.
         unsafe { inner.try_grow(new_target) }
     }
+
+    fn preferred_grow_target(&self, _current: usize, required: usize) -> usize {
+        round_up(required)
+    }
+}
+
+// SAFETY: It's `#[repr(transparent)]` over `B`, it only changes how it grows
+// and it doesn't implement `Drop`.
+unsafe impl<B: Buffer> TransparentBuffer for ExponentialGrowthBuffer<B> {
+    type Inner = B;
+}
+
+// SAFETY: It only changes how it grows, so the positions are the ones of `B`.
+unsafe impl<B: SparseBuffer> SparseBuffer for ExponentialGrowthBuffer<B> {
+    #[inline(always)]
+    fn is_filled(&self, index: usize) -> bool {
+        self.0.is_filled(index)
+    }
+
+    #[inline(always)]
+    fn next_filled_run(&self, from: usize) -> Option<Range<usize>> {
+        self.0.next_filled_run(from)
+    }
+}
+
+/// Internal utility that rounds `target` up to a power of 2 (if there is one
+/// that big).
+fn round_up(target: usize) -> usize {
+    target.checked_next_power_of_two().unwrap_or(target)
 }
 
 #[cfg(test)]
@@ -59,8 +96,21 @@ mod tests {
         {
             let mut buffer = ExponentialGrowthBuffer::from(&mut mock_buffer);
             // This will fail, but it doesn't matter for this test.
+            // SAFETY: 10 > 1
             let _ = unsafe { buffer.try_grow(10) };
         }
         assert_eq!(mock_buffer.last_target(), 16);
     }
+
+    #[test]
+    fn powers_of_two_are_kept() {
+        let mock_buffer: GrowMockBuffer<InlineBuffer<u32, 1>> = Default::default();
+        let mut buffer = ExponentialGrowthBuffer::from(mock_buffer);
+        // SAFETY: 8 > 1
+        let _ = unsafe { buffer.try_grow(8) };
+        assert_eq!(buffer.0.last_target(), 8);
+        // SAFETY: 9 > 1
+        let _ = unsafe { buffer.try_grow(9) };
+        assert_eq!(buffer.0.last_target(), 16);
+    }
 }
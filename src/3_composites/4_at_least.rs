@@ -1,6 +1,9 @@
-use std::cmp::max;
+use std::{cmp::max, ops::Range};
 
-use crate::interface::{indirect_buffer::IndirectBuffer, resize_error::ResizeError, Buffer};
+use crate::interface::{
+    capacity_contract::CapacityContract, indirect_buffer::IndirectBuffer,
+    resize_error::ResizeError, sparse::SparseBuffer, transparent::TransparentBuffer, Buffer,
+};
 
 /// Composite that ensures that when trying to grow it has at least a value.
 /// The initial status may still be under this value and you may shrink lower
@@ -27,10 +30,14 @@ impl<const MIN_SIZE: usize, B: Buffer> IndirectBuffer for AtLeastBuffer<MIN_SIZE
     type InnerBufferRef<'a> = &'a Self::InnerBuffer where Self: 'a;
     type InnerBufferMutRef<'a> = &'a mut Self::InnerBuffer where Self: 'a;
 
+    const CAPACITY_CONTRACT: CapacityContract = B::CAPACITY_CONTRACT.rounded_up();
+
+    #[inline(always)]
     fn inner(&self) -> &B {
         &self.0
     }
 
+    #[inline(always)]
     fn inner_mut(&mut self) -> &mut B {
         &mut self.0
     }
@@ -42,6 +49,30 @@ impl<const MIN_SIZE: usize, B: Buffer> IndirectBuffer for AtLeastBuffer<MIN_SIZE
         // SAFETY: `new_target` >= `target` > `self.capacity()`.
         unsafe { inner.try_grow(new_target) }
     }
+
+    fn preferred_grow_target(&self, current: usize, required: usize) -> usize {
+        let inner = self.inner().preferred_grow_target(current, required);
+        max(inner, MIN_SIZE)
+    }
+}
+
+// SAFETY: It's `#[repr(transparent)]` over `B`, it only changes how it grows
+// and it doesn't implement `Drop`.
+unsafe impl<const MIN_SIZE: usize, B: Buffer> TransparentBuffer for AtLeastBuffer<MIN_SIZE, B> {
+    type Inner = B;
+}
+
+// SAFETY: It only changes how it grows, so the positions are the ones of `B`.
+unsafe impl<const MIN_SIZE: usize, B: SparseBuffer> SparseBuffer for AtLeastBuffer<MIN_SIZE, B> {
+    #[inline(always)]
+    fn is_filled(&self, index: usize) -> bool {
+        self.0.is_filled(index)
+    }
+
+    #[inline(always)]
+    fn next_filled_run(&self, from: usize) -> Option<Range<usize>> {
+        self.0.next_filled_run(from)
+    }
 }
 
 #[cfg(test)]
@@ -59,6 +90,7 @@ mod tests {
         {
             let mut buffer: AtLeastBuffer<14, _> = AtLeastBuffer::from(&mut mock_buffer);
             // This will fail, but it doesn't matter for this test.
+            // SAFETY: 3 > 1
             let _ = unsafe { buffer.try_grow(3) };
         }
         assert_eq!(mock_buffer.last_target(), 14);
@@ -1,6 +1,13 @@
 use std::{mem::MaybeUninit, ops::RangeBounds};
 
-use crate::interface::{copy_value::CopyValueBuffer, Buffer, ResizeError};
+use crate::{
+    composites::lanes::{try_grow_all, try_shrink_all, Lane},
+    interface::{
+        contiguous_memory::ContiguousMemoryBuffer, copy_value::CopyValueBuffer,
+        growable::GrowableBuffer, shrinkable::ShrinkableBuffer, Buffer, BufferCaps,
+        CapacityContract, ResizeError,
+    },
+};
 
 /// Buffer that given a fixed-size array, it makes a buffer the underlying
 /// layout of which is an array of buffers of the array's element type. This is
@@ -34,9 +41,60 @@ where
     B: Buffer,
 {
     /// Make a new [`ArrayBuffer<SIZE, B>`] given the underlying array of
-    /// buffers.
+    /// buffers. Convenience version of [`ArrayBuffer::try_from_buffers`] for
+    /// buffers that are known to have the same capacity (eg. new ones).
+    ///
+    /// # Panics
+    /// Panics if the buffers don't all have the same capacity.
     pub fn from(buffers: [B; SIZE]) -> Self {
-        Self { buffers }
+        match Self::try_from_buffers(buffers) {
+            Ok(result) => result,
+            Err(_) => panic!("All the buffers of an ArrayBuffer must have the same capacity"),
+        }
+    }
+
+    /// Make a new [`ArrayBuffer<SIZE, B>`] given the underlying array of
+    /// buffers. If they don't all have the same capacity, it gives them back.
+    pub fn try_from_buffers(buffers: [B; SIZE]) -> Result<Self, [B; SIZE]> {
+        let result = Self { buffers };
+        if result
+            .buffer_iter()
+            .all(|buffer| buffer.capacity() == result.capacity())
+        {
+            Ok(result)
+        } else {
+            Err(result.buffers)
+        }
+    }
+
+    /// Slices with the first `len` values of each inner buffer (the columns
+    /// of the first `len` rows).
+    ///
+    /// # Safety
+    /// The first `len` positions must be filled.
+    pub unsafe fn lanes(&self, len: usize) -> [&[B::Element]; SIZE]
+    where
+        B: ContiguousMemoryBuffer,
+    {
+        self.buffers.each_ref().map(|buffer| {
+            // SAFETY: If the first `len` positions of this buffer are filled,
+            // they are also filled for all the inner ones.
+            unsafe { buffer.slice(..len) }
+        })
+    }
+
+    /// Mutable version of [`ArrayBuffer::lanes`].
+    ///
+    /// # Safety
+    /// The first `len` positions must be filled.
+    pub unsafe fn lanes_mut(&mut self, len: usize) -> [&mut [B::Element]; SIZE]
+    where
+        B: ContiguousMemoryBuffer,
+    {
+        self.buffers.each_mut().map(|buffer| {
+            // SAFETY: Same as in `lanes`.
+            unsafe { buffer.mut_slice(..len) }
+        })
     }
 
     /// Helper function to iterate over all inner buffers
@@ -79,12 +137,27 @@ where
 {
     type Element = [B::Element; SIZE];
 
+    const CAPACITY_CONTRACT: CapacityContract = B::CAPACITY_CONTRACT;
+
     fn capacity(&self) -> usize {
         self.buffers.iter().map(B::capacity).min().unwrap_or(0)
     }
 
+    /// Sum of all the inner buffers, which may be bigger than the capacity.
+    fn allocated_bytes(&self) -> usize {
+        self.buffer_iter().map(B::allocated_bytes).sum()
+    }
+
+    /// What all the inner buffers can do. The lanes are separate, so it's
+    /// never contiguous as a whole.
+    fn caps(&self) -> BufferCaps {
+        self.buffer_iter()
+            .map(B::caps)
+            .fold(!BufferCaps::CONTIGUOUS, BufferCaps::intersection)
+    }
+
     unsafe fn take(&mut self, index: usize) -> Self::Element {
-        let mut result = MaybeUninit::<B::Element>::uninit_array::<SIZE>();
+        let mut result = [const { MaybeUninit::<B::Element>::uninit() }; SIZE];
         for (i, buffer) in self.buffer_iter_mut().enumerate() {
             let ptr = result[i].as_mut_ptr();
 
@@ -116,31 +189,56 @@ where
         }
     }
 
-    unsafe fn try_grow(&mut self, target: usize) -> Result<(), ResizeError> {
-        for buffer in self.buffer_iter_mut() {
-            if buffer.capacity() < target {
-                // SAFETY: Conditional guards precondition.
-                match unsafe { buffer.try_grow(target) } {
-                    Ok(_) => {}
-                    Err(ResizeError::UnsupportedOperation) => {}
-                    Err(e) => return Err(e),
-                }
-            }
+    /// Swap removes in each inner buffer, so each value is moved once (instead
+    /// of moving the whole array twice).
+    #[inline]
+    unsafe fn swap_remove_into(&mut self, index: usize, last_index: usize) -> Self::Element {
+        let mut result = [const { MaybeUninit::<B::Element>::uninit() }; SIZE];
+        for (i, buffer) in self.buffer_iter_mut().enumerate() {
+            let ptr = result[i].as_mut_ptr();
+
+            // SAFETY: if both positions are valid and filled in this buffer,
+            // they are also valid and filled in all the underlying ones.
+            let val = unsafe { buffer.swap_remove_into(index, last_index) };
+            // SAFETY: `ptr` is part of a local array, thus a valid location
+            // (and without a value).
+            unsafe { ptr.write(val) };
         }
-        Ok(())
+
+        // SAFETY: the loop filled the entire array, thus it's initialized.
+        unsafe { MaybeUninit::array_assume_init(result) }
     }
 
-    unsafe fn try_shrink(&mut self, target: usize) -> Result<(), crate::interface::ResizeError> {
-        for buffer in self.buffer_iter_mut() {
-            // SAFETY: `self.capacity()` <= `inner_buffer.capacity()`. Thus
-            // `target` < `inner_buffer.capacity()` for all inner buffers.
-            match unsafe { buffer.try_shrink(target) } {
-                Ok(_) => {}
-                Err(ResizeError::UnsupportedOperation) => {}
-                Err(e) => return Err(e),
-            }
-        }
-        Ok(())
+    /// Grows all the inner buffers. If any of them fails, the capacity stays
+    /// the same (see [`try_grow_all`]).
+    unsafe fn try_grow(&mut self, target: usize) -> Result<(), ResizeError> {
+        let lanes = self
+            .buffers
+            .each_mut()
+            .map(|buffer| buffer as &mut dyn Lane);
+        // SAFETY: Same requirements.
+        unsafe { try_grow_all(lanes, target) }
+    }
+
+    /// The biggest one preferred by the inner buffers, so none of them has to
+    /// grow again sooner than it wants.
+    fn preferred_grow_target(&self, current: usize, required: usize) -> usize {
+        self.buffer_iter()
+            .map(|buffer| buffer.preferred_grow_target(current, required))
+            .max()
+            .unwrap_or(required)
+    }
+
+    /// Shrinks all the inner buffers. If any of them fails, the ones that
+    /// already shrank grow back (see [`try_shrink_all`]).
+    unsafe fn try_shrink(&mut self, target: usize) -> Result<(), ResizeError> {
+        let lanes = self
+            .buffers
+            .each_mut()
+            .map(|buffer| buffer as &mut dyn Lane);
+        // SAFETY: `self.capacity()` <= `inner_buffer.capacity()`. Thus
+        // `target` < `inner_buffer.capacity()` for all inner buffers.
+        unsafe { try_shrink_all(lanes, target) }
     }
 
     unsafe fn manually_drop_range<R: RangeBounds<usize> + Clone>(&mut self, values_range: R) {
@@ -174,7 +272,7 @@ where
     B::Element: Copy,
 {
     unsafe fn copy(&self, index: usize) -> Self::Element {
-        let mut result = MaybeUninit::<B::Element>::uninit_array::<SIZE>();
+        let mut result = [const { MaybeUninit::<B::Element>::uninit() }; SIZE];
         for (i, buffer) in self.buffer_iter().enumerate() {
             let ptr = result[i].as_mut_ptr();
 
@@ -191,10 +289,14 @@ where
     }
 }
 
+impl<const SIZE: usize, B> GrowableBuffer for ArrayBuffer<SIZE, B> where B: GrowableBuffer {}
+
+impl<const SIZE: usize, B> ShrinkableBuffer for ArrayBuffer<SIZE, B> where B: ShrinkableBuffer {}
+
 /// Helper function. It cretes a default fixed-size array for any T which is
 /// [`Default`].
 fn default_array<T: Default, const N: usize>() -> [T; N] {
-    let mut result = MaybeUninit::<T>::uninit_array::<N>();
+    let mut result = [const { MaybeUninit::<T>::uninit() }; N];
     for position in result.iter_mut() {
         // SAFETY: All positions are empty before the loop. The loop visits them
         // only once. This the moving on each value is valid.
@@ -204,3 +306,155 @@ fn default_array<T: Default, const N: usize>() -> [T; N] {
     // SAFETY: All values have been set on the previous loop
     unsafe { MaybeUninit::array_assume_init(result) }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+
+    use crate::{
+        base_buffers::{HeapBuffer, InlineBuffer},
+        collections::Vector,
+        composites::either::EitherBuffer,
+        test_utils::{faulty::FaultyBuffer, panic::assert_panic, panicking::PanickingElement},
+    };
+
+    use super::*;
+
+    #[test]
+    fn inner_buffers_must_have_the_same_capacity() {
+        let mut bigger = HeapBuffer::<u32>::new();
+        // SAFETY: 2 is bigger than its capacity (0).
+        unsafe { bigger.try_grow(2) }.unwrap();
+        let buffers = ArrayBuffer::try_from_buffers([HeapBuffer::new(), bigger])
+            .err()
+            .unwrap();
+        assert_eq!(buffers[1].capacity(), 2);
+        assert_panic(|| ArrayBuffer::from(buffers));
+    }
+
+    #[test]
+    fn filling_drops_the_clones_when_one_panics() {
+        let counter = AtomicI64::new(0);
+        let clones_left = AtomicUsize::new(2);
+        let value = [PanickingElement::new(&counter, 1).panicking_on_clone_after(&clones_left)];
+        let mut buffer = ArrayBuffer::<1, HeapBuffer<_>>::default();
+        // SAFETY: 4 is bigger than its capacity (0).
+        unsafe { buffer.try_grow(4) }.unwrap();
+        // SAFETY: All the positions are valid and empty.
+        assert_panic(std::panic::AssertUnwindSafe(|| unsafe {
+            buffer.fill_range(.., &value)
+        }));
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn swap_remove_works_on_each_column() {
+        let mut vec = Vector::<[String; 2], ArrayBuffer<2, HeapBuffer<String>>>::new();
+        for row in ["ab", "cd", "ef"] {
+            let [first, second] = [&row[..1], &row[1..]].map(str::to_string);
+            vec.push([first, second]);
+        }
+        assert_eq!(vec.swap_remove(0), ["a", "b"]);
+        assert_eq!(vec.swap_remove(1), ["c", "d"]);
+        assert_eq!(vec.columns(), [["e"], ["f"]]);
+    }
+
+    #[test]
+    fn allocated_bytes_are_the_sum_of_the_lanes() {
+        let mut buffer = ArrayBuffer::<3, HeapBuffer<u32>>::default();
+        assert_eq!(buffer.element_layout().size(), 12);
+        // SAFETY: 5 is bigger than its capacity (0).
+        unsafe { buffer.try_grow(5) }.unwrap();
+        assert_eq!(buffer.allocated_bytes(), 3 * 5 * 4);
+    }
+
+    #[test]
+    fn caps_are_the_common_ones_of_the_lanes_without_contiguity() {
+        let heap = ArrayBuffer::<3, HeapBuffer<u32>>::default();
+        assert_eq!(heap.caps(), BufferCaps::GROW | BufferCaps::SHRINK);
+
+        // Both empty, so they have the same capacity.
+        let mixed: ArrayBuffer<2, EitherBuffer<HeapBuffer<u32>, InlineBuffer<u32, 0>>> =
+            ArrayBuffer::from([
+                EitherBuffer::First(HeapBuffer::new()),
+                EitherBuffer::Second(InlineBuffer::new()),
+            ]);
+        assert_eq!(mixed.caps(), BufferCaps::empty());
+    }
+
+    #[test]
+    fn failing_to_grow_rolls_back() {
+        let failing = FaultyBuffer::default().failing_grow(ResizeError::OutOfMemory);
+        let mut buffer: ArrayBuffer<3, FaultyBuffer<HeapBuffer<u32>>> =
+            ArrayBuffer::from([FaultyBuffer::default(), FaultyBuffer::default(), failing]);
+
+        // SAFETY: 4 is bigger than its capacity (0).
+        let result = unsafe { buffer.try_grow(4) };
+        assert!(matches!(result, Err(ResizeError::OutOfMemory)));
+        assert_eq!(buffer.capacity(), 0);
+        assert!(buffer.buffer_iter().all(|inner| inner.capacity() == 0));
+    }
+
+    #[test]
+    fn unsupported_growth_is_an_error() {
+        let mut buffer = ArrayBuffer::<2, InlineBuffer<u32, 2>>::default();
+        // SAFETY: 4 is bigger than its capacity (2).
+        let result = unsafe { buffer.try_grow(4) };
+        assert!(matches!(result, Err(ResizeError::UnsupportedOperation)));
+
+        let mut vec = Vector::from_buffer(buffer);
+        vec.push([1, 2]);
+        vec.push([3, 4]);
+        assert_panic(move || vec.push([5, 6]));
+    }
+
+    #[test]
+    fn stacked_composites_roll_back_every_level() {
+        type Inner = ArrayBuffer<2, FaultyBuffer<HeapBuffer<u32>>>;
+        let failing = FaultyBuffer::default().failing_grow(ResizeError::OutOfMemory);
+        let mut buffer = ArrayBuffer::<2, Inner>::from([
+            ArrayBuffer::default(),
+            ArrayBuffer::from([FaultyBuffer::default(), failing]),
+        ]);
+
+        // SAFETY: 4 is bigger than its capacity (0).
+        let result = unsafe { buffer.try_grow(4) };
+        assert!(matches!(result, Err(ResizeError::OutOfMemory)));
+        assert_eq!(buffer.capacity(), 0);
+        let lanes = buffer.buffer_iter().flat_map(ArrayBuffer::buffer_iter);
+        assert!(lanes.map(Buffer::capacity).all(|capacity| capacity == 0));
+    }
+
+    #[test]
+    fn vectors_keep_their_elements_when_a_lane_fails() {
+        let limited = FaultyBuffer::default().failing_grow_beyond(2, ResizeError::OutOfMemory);
+        let buffer = ArrayBuffer::<2, FaultyBuffer<HeapBuffer<u32>>>::from([
+            FaultyBuffer::default(),
+            limited,
+        ]);
+        let mut vec = Vector::from_buffer(buffer);
+        vec.push([1, 2]);
+        vec.push([3, 4]);
+
+        assert!(vec.try_reserve(1).is_err());
+        assert_eq!(vec.capacity(), 2);
+        assert_eq!(vec.len(), 2);
+        assert_eq!(vec.iter_copied().last(), Some([3, 4]));
+        assert_panic(move || vec.push([5, 6]));
+    }
+
+    #[test]
+    fn failing_to_shrink_rolls_back() {
+        let failing = FaultyBuffer::default().failing_shrink_with(ResizeError::OutOfMemory);
+        let mut buffer: ArrayBuffer<3, FaultyBuffer<HeapBuffer<u32>>> =
+            ArrayBuffer::from([FaultyBuffer::default(), FaultyBuffer::default(), failing]);
+        // SAFETY: 8 is bigger than its capacity (0).
+        unsafe { buffer.try_grow(8) }.unwrap();
+
+        // SAFETY: 2 is smaller than its capacity (8), and it's empty.
+        let result = unsafe { buffer.try_shrink(2) };
+        assert!(matches!(result, Err(ResizeError::OutOfMemory)));
+        assert_eq!(buffer.capacity(), 8);
+        assert!(buffer.buffer_iter().all(|inner| inner.capacity() == 8));
+    }
+}
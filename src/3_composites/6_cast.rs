@@ -0,0 +1,256 @@
+use std::{marker::PhantomData, mem};
+
+use crate::interface::{
+    contiguous_memory::ContiguousMemoryBuffer, copy_value::CopyValueBuffer,
+    out_of_line::OutOfLineBuffer, ptrs::PtrBuffer, refs::RefBuffer, resize_error::ResizeError,
+    Buffer, BufferCaps,
+};
+
+/// Marker for "plain old data": types that are just bytes.
+///
+/// # Safety
+/// Implementors must ensure that:
+///   * Any bit pattern is a valid value.
+///   * There is no padding (all the bytes are initialized).
+///
+/// Those are what makes reinterpreting bytes as values (and values as bytes)
+/// sound. Zero-sized types (like `[u32; 0]`) may implement it, but they are
+/// rejected when casting.
+pub unsafe trait Pod: Copy + 'static {}
+
+macro_rules! impl_pod {
+    ($($t:ty),*) => {
+        // SAFETY: Primitive numbers are plain bytes.
+        $(unsafe impl Pod for $t {})*
+    };
+}
+impl_pod!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+// SAFETY: Arrays have no padding between their elements.
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+
+/// Reasons why bytes cannot be reinterpreted as another type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CastError {
+    /// The bytes don't start at an address aligned for the type.
+    Alignment,
+
+    /// The number of bytes isn't a multiple of the type's size.
+    Size,
+
+    /// The type is zero-sized, so it cannot be made from bytes.
+    ZeroSized,
+}
+
+/// Composite that reinterprets a contiguous byte buffer as a buffer of `U`
+/// (without copying).
+///
+/// Its capacity is how many whole `U` fit in the bytes. It cannot be resized:
+/// growing could move the bytes to memory that isn't aligned for `U`. Use
+/// [`CastBuffer::into_inner`] to get the bytes back.
+///
+/// The alignment is only checked when it's made, so the bytes can't be stored
+/// inside the buffer (see [`OutOfLineBuffer`]): moving it would move them to
+/// an address that may not be aligned anymore.
+/// ```compile_fail
+/// # use buffers::{base_buffers::InlineBuffer, composites::CastBuffer};
+/// let _ = CastBuffer::<u32, _>::try_from_buffer(InlineBuffer::<u8, 8>::new());
+/// ```
+///
+/// Usually made by [`crate::collections::Vector::cast`].
+pub struct CastBuffer<U: Pod, B: Buffer<Element = u8> + ContiguousMemoryBuffer + OutOfLineBuffer> {
+    inner: B,
+    _m: PhantomData<U>,
+}
+
+impl<U: Pod, B: Buffer<Element = u8> + ContiguousMemoryBuffer + OutOfLineBuffer> CastBuffer<U, B> {
+    /// Reinterprets the bytes of `buffer` as `U`. Fails if `U` is zero-sized
+    /// or if the bytes aren't aligned for it, giving the buffer back.
+    pub fn try_from_buffer(buffer: B) -> Result<Self, (CastError, B)> {
+        let size = mem::size_of::<U>();
+        if size == 0 {
+            return Err((CastError::ZeroSized, buffer));
+        }
+        // Without space for a single value no pointer is ever made, so any
+        // address works.
+        if buffer.capacity() >= size {
+            // SAFETY: It has space for at least one byte.
+            let start = unsafe { buffer.ptr(0) };
+            if !start.cast::<U>().is_aligned() {
+                return Err((CastError::Alignment, buffer));
+            }
+        }
+        Ok(Self {
+            inner: buffer,
+            _m: PhantomData,
+        })
+    }
+
+    /// Gives back the byte buffer.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+impl<U: Pod, B: Buffer<Element = u8> + ContiguousMemoryBuffer + OutOfLineBuffer> Buffer
+    for CastBuffer<U, B>
+{
+    type Element = U;
+
+    fn capacity(&self) -> usize {
+        self.inner.capacity() / mem::size_of::<U>()
+    }
+
+    /// All the bytes of the inner buffer, including the ones left over at
+    /// the end (which don't fit a whole value).
+    fn allocated_bytes(&self) -> usize {
+        self.inner.allocated_bytes()
+    }
+
+    /// Resizing isn't supported.
+    fn caps(&self) -> BufferCaps {
+        BufferCaps::CONTIGUOUS
+    }
+
+    unsafe fn take(&mut self, index: usize) -> U {
+        // SAFETY: Same requirements.
+        unsafe { self.copy(index) }
+    }
+
+    unsafe fn put(&mut self, index: usize, value: U) {
+        // SAFETY: Same requirements.
+        let ptr = unsafe { self.mut_ptr(index) };
+        // SAFETY: The pointer is valid, aligned and not aliased.
+        unsafe { ptr.write(value) }
+    }
+
+    unsafe fn manually_drop(&mut self, _index: usize) {
+        // `Pod` values are `Copy`, so they have nothing to drop.
+    }
+
+    unsafe fn try_grow(&mut self, _target: usize) -> Result<(), ResizeError> {
+        Err(ResizeError::UnsupportedOperation)
+    }
+
+    unsafe fn try_shrink(&mut self, _target: usize) -> Result<(), ResizeError> {
+        Err(ResizeError::UnsupportedOperation)
+    }
+}
+
+impl<U: Pod, B: Buffer<Element = u8> + ContiguousMemoryBuffer + OutOfLineBuffer> CopyValueBuffer
+    for CastBuffer<U, B>
+{
+    unsafe fn copy(&self, index: usize) -> U {
+        // SAFETY: Same requirements.
+        let ptr = unsafe { self.ptr(index) };
+        // SAFETY: The position is filled, so it has been written (and any bytes
+        // are a valid `U`).
+        unsafe { ptr.read() }
+    }
+}
+
+impl<U: Pod, B: Buffer<Element = u8> + ContiguousMemoryBuffer + OutOfLineBuffer> PtrBuffer
+    for CastBuffer<U, B>
+{
+    type ConstantPointer = *const U;
+    type MutablePointer = *mut U;
+
+    unsafe fn ptr(&self, index: usize) -> *const U {
+        debug_assert!(index < self.capacity());
+        // SAFETY: There is at least a value, so the first byte is valid.
+        let start = unsafe { self.inner.ptr(0) };
+        // SAFETY: The bytes were checked to be aligned and `index` is a valid
+        // position, so it's inside the bytes.
+        unsafe { start.cast::<U>().add(index) }
+    }
+
+    unsafe fn mut_ptr(&mut self, index: usize) -> *mut U {
+        debug_assert!(index < self.capacity());
+        // SAFETY: There is at least a value, so the first byte is valid.
+        let start = unsafe { self.inner.mut_ptr(0) };
+        // SAFETY: The bytes were checked to be aligned and `index` is a valid
+        // position, so it's inside the bytes.
+        unsafe { start.cast::<U>().add(index) }
+    }
+
+    unsafe fn segment_len(&self, index: usize) -> usize {
+        // All the positions are in a single allocation.
+        self.capacity() - index
+    }
+}
+
+impl<U: Pod, B: Buffer<Element = u8> + ContiguousMemoryBuffer + OutOfLineBuffer> RefBuffer
+    for CastBuffer<U, B>
+{
+    type ConstantReference<'a> = &'a U
+    where
+        Self: 'a;
+    type MutableReference<'a> = &'a mut U
+    where
+        Self: 'a;
+
+    unsafe fn index<'x: 'y, 'y>(&'x self, index: usize) -> &'y U {
+        // SAFETY: Same requirements.
+        let ptr = unsafe { self.ptr(index) };
+        // SAFETY: The position is filled and the reference borrows the buffer.
+        unsafe { &*ptr }
+    }
+
+    unsafe fn mut_index<'x: 'y, 'y>(&'x mut self, index: usize) -> &'y mut U {
+        // SAFETY: Same requirements.
+        let ptr = unsafe { self.mut_ptr(index) };
+        // SAFETY: The position is filled and the reference borrows the buffer.
+        unsafe { &mut *ptr }
+    }
+}
+
+impl<U: Pod, B: Buffer<Element = u8> + ContiguousMemoryBuffer + OutOfLineBuffer>
+    ContiguousMemoryBuffer for CastBuffer<U, B>
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::MaybeUninit;
+
+    use crate::base_buffers::{HeapBuffer, SliceBuffer};
+
+    use super::*;
+
+    #[repr(align(8))]
+    struct AlignedBytes([MaybeUninit<u8>; 11]);
+
+    impl AlignedBytes {
+        fn new() -> Self {
+            Self([MaybeUninit::uninit(); 11])
+        }
+    }
+
+    #[test]
+    fn capacity_counts_whole_values() {
+        let mut bytes = AlignedBytes::new();
+        let buffer = CastBuffer::<u32, _>::try_from_buffer(SliceBuffer::from_slice(&mut bytes.0));
+        assert_eq!(buffer.ok().unwrap().capacity(), 2);
+    }
+
+    #[test]
+    fn checks_the_alignment() {
+        let mut bytes = AlignedBytes::new();
+        let misaligned = SliceBuffer::from_slice(&mut bytes.0[1..]);
+        let result = CastBuffer::<u32, _>::try_from_buffer(misaligned);
+        assert_eq!(
+            result.err().map(|(error, _)| error),
+            Some(CastError::Alignment)
+        );
+    }
+
+    #[test]
+    fn rejects_zero_sized_types() {
+        let result = CastBuffer::<[u32; 0], _>::try_from_buffer(HeapBuffer::<u8>::new());
+        assert_eq!(
+            result.err().map(|(error, _)| error),
+            Some(CastError::ZeroSized)
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use std::{
+    sync::OnceLock,
+    thread::{self, ThreadId},
+};
+
+use crate::interface::{indirect_buffer::IndirectBuffer, Buffer};
+
+/// Debugging composite that panics if the buffer is used from more than one
+/// thread.
+///
+/// It records the thread that uses it first and checks every access against
+/// it. This catches sharing a buffer across threads by accident (eg. behind an
+/// `unsafe impl Sync`) while developing, before it turns into a data race.
+///
+/// Note that moving it to another thread also counts (even if it's [`Send`]),
+/// so it's meant for buffers which should stay in a single thread. It adds a
+/// check to every access, so swap it for `B` once done debugging.
+pub struct DebugAffinityBuffer<B: Buffer> {
+    inner: B,
+    owner: OnceLock<ThreadId>,
+}
+
+impl<B: Buffer> DebugAffinityBuffer<B> {
+    /// Make a new [`DebugAffinityBuffer<B>`] given the underlying buffer `B`.
+    pub fn from(buffer: B) -> Self {
+        Self {
+            inner: buffer,
+            owner: OnceLock::new(),
+        }
+    }
+
+    /// Thread that used the buffer first (if it has been used).
+    pub fn owner(&self) -> Option<ThreadId> {
+        self.owner.get().copied()
+    }
+
+    /// Internal utility that records the current thread if it's the first
+    /// use, or panics if it's not the one that was recorded.
+    #[track_caller]
+    fn check_thread(&self) {
+        let current = thread::current().id();
+        let owner = *self.owner.get_or_init(|| current);
+        assert!(
+            owner == current,
+            "Buffer used from {current:?} but it belongs to {owner:?}"
+        );
+    }
+}
+
+impl<B: Buffer + Default> Default for DebugAffinityBuffer<B> {
+    fn default() -> Self {
+        Self::from(Default::default())
+    }
+}
+
+impl<B: Buffer> IndirectBuffer for DebugAffinityBuffer<B> {
+    type InnerBuffer = B;
+    type InnerBufferRef<'a> = &'a Self::InnerBuffer where Self: 'a;
+    type InnerBufferMutRef<'a> = &'a mut Self::InnerBuffer where Self: 'a;
+
+    fn inner(&self) -> &B {
+        self.check_thread();
+        &self.inner
+    }
+
+    fn inner_mut(&mut self) -> &mut B {
+        self.check_thread();
+        &mut self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use crate::{base_buffers::HeapBuffer, collections::Vector};
+
+    use super::*;
+
+    #[test]
+    fn can_be_used_from_its_thread() {
+        let mut vec = Vector::<u32, DebugAffinityBuffer<HeapBuffer<u32>>>::new();
+        vec.push(1);
+        vec.push(2);
+        assert_eq!(vec.pop(), Some(2));
+    }
+
+    #[test]
+    fn panics_when_used_from_another_thread() {
+        let mut vec = Vector::<u32, DebugAffinityBuffer<HeapBuffer<u32>>>::new();
+        vec.push(1);
+
+        let result = thread::scope(|scope| scope.spawn(|| vec.get_copy(0)).join());
+        assert!(result.is_err());
+        assert_eq!(vec.get_copy(0), Some(1));
+    }
+}
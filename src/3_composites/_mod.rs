@@ -8,7 +8,7 @@ pub use zsto::ZstoBuffer;
 
 #[path = "2_svo.rs"]
 pub mod svo;
-pub use svo::SvoBuffer;
+pub use svo::{BoxedInline, SvoBuffer};
 
 #[path = "3_exponential_growth.rs"]
 pub mod exponential_growth;
@@ -24,6 +24,14 @@ pub mod array;
 #[cfg(feature = "array")]
 pub use array::ArrayBuffer;
 
+#[path = "6_cast.rs"]
+pub mod cast;
+pub use cast::CastBuffer;
+
+#[path = "7_debug_affinity.rs"]
+pub mod debug_affinity;
+pub use debug_affinity::DebugAffinityBuffer;
+
 #[path = "a_conditional.rs"]
 pub mod conditional;
 
@@ -32,3 +40,32 @@ pub mod either;
 
 #[path = "c_grow_mock.rs"]
 pub mod grow_mock;
+
+#[path = "d_lanes.rs"]
+pub mod lanes;
+
+#[cfg(feature = "crypto")]
+#[path = "e_encrypted.rs"]
+pub mod encrypted;
+#[cfg(feature = "crypto")]
+pub use encrypted::EncryptedBuffer;
+
+#[path = "f_snapshot.rs"]
+pub mod snapshot;
+pub use snapshot::SnapshotBuffer;
+
+#[path = "g_observed.rs"]
+pub mod observed;
+pub use observed::{AllocationObserver, ObservedBuffer};
+
+#[path = "h_deferred_drop.rs"]
+pub mod deferred_drop;
+pub use deferred_drop::{DeferredDropBuffer, Reclaimer};
+
+#[path = "i_maybe_instrumented.rs"]
+pub mod maybe_instrumented;
+pub use maybe_instrumented::{MaybeInstrumented, UninstrumentedBuffer};
+
+#[path = "j_slab.rs"]
+pub mod slab;
+pub use slab::SlabBuffer;
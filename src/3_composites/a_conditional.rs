@@ -5,8 +5,9 @@
 use std::{marker::PhantomData, mem::MaybeUninit, ops::RangeBounds};
 
 use crate::interface::{
-    contiguous_memory::ContiguousMemoryBuffer, copy_value::CopyValueBuffer, ptrs::PtrBuffer,
-    refs::RefBuffer, resize_error::ResizeError, Buffer,
+    contiguous_memory::ContiguousMemoryBuffer, copy_value::CopyValueBuffer,
+    growable::GrowableBuffer, ptrs::PtrBuffer, refs::RefBuffer, resize_error::ResizeError,
+    shrinkable::ShrinkableBuffer, Buffer, BufferCaps, CapacityContract,
 };
 
 /// Trait used to choose between buffer A or buffer B.
@@ -22,6 +23,9 @@ pub trait Selector {
 ///
 /// This uses both buffers but only uses one. This may be able to change with
 /// generic const expressions.
+///
+/// Both buffers are kept as [`MaybeUninit`], which hides their niches: an
+/// `Option` of it will always need extra space.
 pub struct ConditionalBuffer<A, B, S>
 where
     A: Buffer,
@@ -30,7 +34,11 @@ where
 {
     a: MaybeUninit<A>,
     b: MaybeUninit<B>,
-    _m: PhantomData<S>,
+    // One of them is owned, but `MaybeUninit` hides it from dropck.
+    _owned: PhantomData<(A, B)>,
+    // The selector is never owned, so it shouldn't affect variance, auto traits
+    // nor dropck.
+    _m: PhantomData<fn() -> S>,
 }
 
 impl<A, B, S> ConditionalBuffer<A, B, S>
@@ -48,6 +56,7 @@ where
         Self {
             a: MaybeUninit::new(first),
             b: MaybeUninit::uninit(),
+            _owned: PhantomData,
             _m: PhantomData,
         }
     }
@@ -60,6 +69,7 @@ where
         Self {
             a: MaybeUninit::uninit(),
             b: MaybeUninit::new(second),
+            _owned: PhantomData,
             _m: PhantomData,
         }
     }
@@ -87,6 +97,13 @@ where
     S: Selector,
 {
     type Element = A::Element;
+
+    const CAPACITY_CONTRACT: CapacityContract = if S::SELECT_A {
+        A::CAPACITY_CONTRACT
+    } else {
+        B::CAPACITY_CONTRACT
+    };
+    #[inline]
     fn capacity(&self) -> usize {
         if S::SELECT_A {
             unsafe { self.a.assume_init_ref() }.capacity()
@@ -95,6 +112,25 @@ where
         }
     }
 
+    #[inline]
+    fn allocated_bytes(&self) -> usize {
+        if S::SELECT_A {
+            unsafe { self.a.assume_init_ref() }.allocated_bytes()
+        } else {
+            unsafe { self.b.assume_init_ref() }.allocated_bytes()
+        }
+    }
+
+    #[inline]
+    fn caps(&self) -> BufferCaps {
+        if S::SELECT_A {
+            unsafe { self.a.assume_init_ref() }.caps()
+        } else {
+            unsafe { self.b.assume_init_ref() }.caps()
+        }
+    }
+
+    #[inline]
     unsafe fn take(&mut self, index: usize) -> Self::Element {
         if S::SELECT_A {
             let reference = unsafe { self.a.assume_init_mut() };
@@ -105,6 +141,7 @@ where
         }
     }
 
+    #[inline]
     unsafe fn put(&mut self, index: usize, value: Self::Element) {
         if S::SELECT_A {
             let reference = unsafe { self.a.assume_init_mut() };
@@ -115,6 +152,7 @@ where
         }
     }
 
+    #[inline]
     unsafe fn manually_drop(&mut self, index: usize) {
         if S::SELECT_A {
             let reference = unsafe { self.a.assume_init_mut() };
@@ -125,6 +163,7 @@ where
         }
     }
 
+    #[inline]
     unsafe fn manually_drop_range<R: RangeBounds<usize> + Clone>(&mut self, values_range: R) {
         if S::SELECT_A {
             let reference = unsafe { self.a.assume_init_mut() };
@@ -134,6 +173,30 @@ where
             unsafe { reference.manually_drop_range(values_range) }
         }
     }
+    #[inline]
+    unsafe fn fill_range<R: RangeBounds<usize> + Clone>(&mut self, range: R, value: &Self::Element)
+    where
+        Self::Element: Clone,
+    {
+        if S::SELECT_A {
+            let reference = unsafe { self.a.assume_init_mut() };
+            unsafe { reference.fill_range(range, value) }
+        } else {
+            let reference = unsafe { self.b.assume_init_mut() };
+            unsafe { reference.fill_range(range, value) }
+        }
+    }
+    #[inline]
+    unsafe fn swap_remove_into(&mut self, index: usize, last_index: usize) -> Self::Element {
+        if S::SELECT_A {
+            let reference = unsafe { self.a.assume_init_mut() };
+            unsafe { reference.swap_remove_into(index, last_index) }
+        } else {
+            let reference = unsafe { self.b.assume_init_mut() };
+            unsafe { reference.swap_remove_into(index, last_index) }
+        }
+    }
+    #[inline]
     unsafe fn try_grow(&mut self, target: usize) -> Result<(), ResizeError> {
         if S::SELECT_A {
             let reference = unsafe { self.a.assume_init_mut() };
@@ -144,6 +207,33 @@ where
         }
     }
 
+    #[inline]
+    unsafe fn try_grow_at_least(
+        &mut self,
+        min: usize,
+        preferred: usize,
+    ) -> Result<usize, ResizeError> {
+        if S::SELECT_A {
+            let reference = unsafe { self.a.assume_init_mut() };
+            unsafe { reference.try_grow_at_least(min, preferred) }
+        } else {
+            let reference = unsafe { self.b.assume_init_mut() };
+            unsafe { reference.try_grow_at_least(min, preferred) }
+        }
+    }
+
+    #[inline]
+    fn preferred_grow_target(&self, current: usize, required: usize) -> usize {
+        if S::SELECT_A {
+            let reference = unsafe { self.a.assume_init_ref() };
+            reference.preferred_grow_target(current, required)
+        } else {
+            let reference = unsafe { self.b.assume_init_ref() };
+            reference.preferred_grow_target(current, required)
+        }
+    }
+
+    #[inline]
     unsafe fn try_shrink(&mut self, target: usize) -> Result<(), ResizeError> {
         if S::SELECT_A {
             let reference = unsafe { self.a.assume_init_mut() };
@@ -162,6 +252,7 @@ where
     B: Buffer<Element = A::Element> + CopyValueBuffer,
     S: Selector,
 {
+    #[inline]
     unsafe fn copy(&self, index: usize) -> Self::Element {
         if S::SELECT_A {
             let reference = unsafe { self.a.assume_init_ref() };
@@ -183,6 +274,7 @@ where
     type ConstantPointer = A::ConstantPointer;
     type MutablePointer = A::MutablePointer;
 
+    #[inline]
     unsafe fn ptr(&self, index: usize) -> Self::ConstantPointer {
         if S::SELECT_A {
             let reference = unsafe { self.a.assume_init_ref() };
@@ -193,6 +285,7 @@ where
         }
     }
 
+    #[inline]
     unsafe fn mut_ptr(&mut self, index: usize) -> Self::MutablePointer {
         if S::SELECT_A {
             let reference = unsafe { self.a.assume_init_mut() };
@@ -202,6 +295,17 @@ where
             unsafe { reference.mut_ptr(index) }
         }
     }
+
+    #[inline]
+    unsafe fn segment_len(&self, index: usize) -> usize {
+        if S::SELECT_A {
+            let reference = unsafe { self.a.assume_init_ref() };
+            unsafe { reference.segment_len(index) }
+        } else {
+            let reference = unsafe { self.b.assume_init_ref() };
+            unsafe { reference.segment_len(index) }
+        }
+    }
 }
 
 impl<A, B, S> RefBuffer for ConditionalBuffer<A, B, S>
@@ -223,6 +327,7 @@ where
     where
         Self: 'a;
 
+    #[inline]
     unsafe fn index<'a: 'b, 'b>(&'a self, index: usize) -> Self::ConstantReference<'b> {
         if S::SELECT_A {
             let reference = unsafe { self.a.assume_init_ref() };
@@ -233,6 +338,7 @@ where
         }
     }
 
+    #[inline]
     unsafe fn mut_index<'a: 'b, 'b>(&'a mut self, index: usize) -> Self::MutableReference<'b> {
         if S::SELECT_A {
             let reference = unsafe { self.a.assume_init_mut() };
@@ -252,7 +358,25 @@ where
 {
 }
 
-impl<A, B, S> Drop for ConditionalBuffer<A, B, S>
+impl<A, B, S> GrowableBuffer for ConditionalBuffer<A, B, S>
+where
+    A: GrowableBuffer,
+    B: Buffer<Element = A::Element> + GrowableBuffer,
+    S: Selector,
+{
+}
+
+impl<A, B, S> ShrinkableBuffer for ConditionalBuffer<A, B, S>
+where
+    A: ShrinkableBuffer,
+    B: Buffer<Element = A::Element> + ShrinkableBuffer,
+    S: Selector,
+{
+}
+
+// SAFETY: It only drops the selected buffer, which `PhantomData<(A, B)>` tells
+// dropck about.
+unsafe impl<#[may_dangle] A, #[may_dangle] B, S> Drop for ConditionalBuffer<A, B, S>
 where
     A: Buffer,
     B: Buffer<Element = A::Element>,
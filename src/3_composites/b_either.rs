@@ -4,8 +4,9 @@
 use std::ops::RangeBounds;
 
 use crate::interface::{
-    contiguous_memory::ContiguousMemoryBuffer, copy_value::CopyValueBuffer, ptrs::PtrBuffer,
-    refs::RefBuffer, resize_error::ResizeError, Buffer,
+    contiguous_memory::ContiguousMemoryBuffer, copy_value::CopyValueBuffer,
+    growable::GrowableBuffer, ptrs::PtrBuffer, refs::RefBuffer, resize_error::ResizeError,
+    shrinkable::ShrinkableBuffer, Buffer, BufferCaps, CapacityContract,
 };
 
 /// Utility buffer that may contain one of two buffers.
@@ -39,6 +40,9 @@ where
 {
     type Element = A::Element;
 
+    const CAPACITY_CONTRACT: CapacityContract = A::CAPACITY_CONTRACT.either(B::CAPACITY_CONTRACT);
+
+    #[inline]
     fn capacity(&self) -> usize {
         match self {
             EitherBuffer::First(buf) => buf.capacity(),
@@ -46,6 +50,24 @@ where
         }
     }
 
+    #[inline]
+    fn allocated_bytes(&self) -> usize {
+        match self {
+            EitherBuffer::First(buf) => buf.allocated_bytes(),
+            EitherBuffer::Second(buf) => buf.allocated_bytes(),
+        }
+    }
+
+    /// The capabilities of the buffer in use.
+    #[inline]
+    fn caps(&self) -> BufferCaps {
+        match self {
+            EitherBuffer::First(buf) => buf.caps(),
+            EitherBuffer::Second(buf) => buf.caps(),
+        }
+    }
+
+    #[inline]
     unsafe fn take(&mut self, index: usize) -> Self::Element {
         match self {
             EitherBuffer::First(buf) => unsafe { buf.take(index) },
@@ -53,6 +75,7 @@ where
         }
     }
 
+    #[inline]
     unsafe fn put(&mut self, index: usize, value: Self::Element) {
         match self {
             EitherBuffer::First(buf) => unsafe { buf.put(index, value) },
@@ -60,6 +83,7 @@ where
         }
     }
 
+    #[inline]
     unsafe fn manually_drop(&mut self, index: usize) {
         match self {
             EitherBuffer::First(buf) => unsafe { buf.manually_drop(index) },
@@ -67,6 +91,7 @@ where
         }
     }
 
+    #[inline]
     unsafe fn manually_drop_range<R: RangeBounds<usize> + Clone>(&mut self, values_range: R) {
         match self {
             EitherBuffer::First(buf) => unsafe { buf.manually_drop_range(values_range) },
@@ -74,6 +99,26 @@ where
         }
     }
 
+    #[inline]
+    unsafe fn fill_range<R: RangeBounds<usize> + Clone>(&mut self, range: R, value: &Self::Element)
+    where
+        Self::Element: Clone,
+    {
+        match self {
+            EitherBuffer::First(buf) => unsafe { buf.fill_range(range, value) },
+            EitherBuffer::Second(buf) => unsafe { buf.fill_range(range, value) },
+        }
+    }
+
+    #[inline]
+    unsafe fn swap_remove_into(&mut self, index: usize, last_index: usize) -> Self::Element {
+        match self {
+            EitherBuffer::First(buf) => unsafe { buf.swap_remove_into(index, last_index) },
+            EitherBuffer::Second(buf) => unsafe { buf.swap_remove_into(index, last_index) },
+        }
+    }
+
+    #[inline]
     unsafe fn try_grow(&mut self, target: usize) -> Result<(), ResizeError> {
         match self {
             EitherBuffer::First(buf) => unsafe { buf.try_grow(target) },
@@ -81,6 +126,27 @@ where
         }
     }
 
+    #[inline]
+    unsafe fn try_grow_at_least(
+        &mut self,
+        min: usize,
+        preferred: usize,
+    ) -> Result<usize, ResizeError> {
+        match self {
+            EitherBuffer::First(buf) => unsafe { buf.try_grow_at_least(min, preferred) },
+            EitherBuffer::Second(buf) => unsafe { buf.try_grow_at_least(min, preferred) },
+        }
+    }
+
+    #[inline]
+    fn preferred_grow_target(&self, current: usize, required: usize) -> usize {
+        match self {
+            EitherBuffer::First(buf) => buf.preferred_grow_target(current, required),
+            EitherBuffer::Second(buf) => buf.preferred_grow_target(current, required),
+        }
+    }
+
+    #[inline]
     unsafe fn try_shrink(&mut self, target: usize) -> Result<(), ResizeError> {
         match self {
             EitherBuffer::First(buf) => unsafe { buf.try_shrink(target) },
@@ -95,6 +161,7 @@ where
     A::Element: Copy,
     B: Buffer<Element = A::Element> + CopyValueBuffer,
 {
+    #[inline]
     unsafe fn copy(&self, index: usize) -> Self::Element {
         match self {
             EitherBuffer::First(buf) => unsafe { buf.copy(index) },
@@ -112,6 +179,7 @@ where
     type ConstantPointer = A::ConstantPointer;
     type MutablePointer = A::MutablePointer;
 
+    #[inline]
     unsafe fn ptr(&self, index: usize) -> Self::ConstantPointer {
         match self {
             EitherBuffer::First(buf) => unsafe { buf.ptr(index) },
@@ -119,12 +187,21 @@ where
         }
     }
 
+    #[inline]
     unsafe fn mut_ptr(&mut self, index: usize) -> Self::MutablePointer {
         match self {
             EitherBuffer::First(buf) => unsafe { buf.mut_ptr(index) },
             EitherBuffer::Second(buf) => unsafe { buf.mut_ptr(index) },
         }
     }
+
+    #[inline]
+    unsafe fn segment_len(&self, index: usize) -> usize {
+        match self {
+            EitherBuffer::First(buf) => unsafe { buf.segment_len(index) },
+            EitherBuffer::Second(buf) => unsafe { buf.segment_len(index) },
+        }
+    }
 }
 
 impl<A, B> RefBuffer for EitherBuffer<A, B>
@@ -145,6 +222,7 @@ where
     where
         Self: 'a;
 
+    #[inline]
     unsafe fn index<'a: 'b, 'b>(&'a self, index: usize) -> Self::ConstantReference<'b> {
         match self {
             EitherBuffer::First(buf) => unsafe { buf.index(index) },
@@ -152,6 +230,7 @@ where
         }
     }
 
+    #[inline]
     unsafe fn mut_index<'a: 'b, 'b>(&'a mut self, index: usize) -> Self::MutableReference<'b> {
         match self {
             EitherBuffer::First(buf) => unsafe { buf.mut_index(index) },
@@ -166,3 +245,17 @@ where
     B: Buffer<Element = A::Element> + ContiguousMemoryBuffer,
 {
 }
+
+impl<A, B> GrowableBuffer for EitherBuffer<A, B>
+where
+    A: GrowableBuffer,
+    B: Buffer<Element = A::Element> + GrowableBuffer,
+{
+}
+
+impl<A, B> ShrinkableBuffer for EitherBuffer<A, B>
+where
+    A: ShrinkableBuffer,
+    B: Buffer<Element = A::Element> + ShrinkableBuffer,
+{
+}
@@ -0,0 +1,172 @@
+//! Shared logic for composites made of several inner buffers used in
+//! parallel ("lanes"), like [`super::ArrayBuffer`]: each position of the
+//! composite is the same position in all of its lanes, so its capacity is the
+//! smallest capacity of the lanes.
+//!
+//! Growing them is transactional: either all the lanes grow or the composite
+//! keeps its capacity. Shrinking them is too, as far as the lanes that already
+//! shrank can grow back.
+
+use crate::interface::{resize_error::ResizeError, Buffer};
+
+/// The resizing part of a [`Buffer`], so lanes of different types can be
+/// handled together (as `&mut dyn Lane`).
+pub trait Lane {
+    /// See [`Buffer::capacity`].
+    fn lane_capacity(&self) -> usize;
+
+    /// See [`Buffer::try_grow`].
+    ///
+    /// # Safety
+    /// Same as [`Buffer::try_grow`].
+    unsafe fn try_grow_lane(&mut self, target: usize) -> Result<(), ResizeError>;
+
+    /// See [`Buffer::try_shrink`].
+    ///
+    /// # Safety
+    /// Same as [`Buffer::try_shrink`].
+    unsafe fn try_shrink_lane(&mut self, target: usize) -> Result<(), ResizeError>;
+}
+
+impl<B: Buffer> Lane for B {
+    fn lane_capacity(&self) -> usize {
+        self.capacity()
+    }
+
+    unsafe fn try_grow_lane(&mut self, target: usize) -> Result<(), ResizeError> {
+        // SAFETY: Same requirements.
+        unsafe { self.try_grow(target) }
+    }
+
+    unsafe fn try_shrink_lane(&mut self, target: usize) -> Result<(), ResizeError> {
+        // SAFETY: Same requirements.
+        unsafe { self.try_shrink(target) }
+    }
+}
+
+/// Grows all the `lanes` (that are smaller) to `target`, in order.
+///
+/// If one of them fails, the ones that already grew are shrunk back to their
+/// previous capacity and the error is returned. A lane that cannot shrink back
+/// stays bigger, which doesn't change the smallest capacity either.
+///
+/// # Safety
+/// `target` must be bigger than the smallest capacity of the lanes.
+pub unsafe fn try_grow_all<const N: usize>(
+    mut lanes: [&mut dyn Lane; N],
+    target: usize,
+) -> Result<(), ResizeError> {
+    let previous = lanes.each_ref().map(|lane| lane.lane_capacity());
+    for index in 0..N {
+        if previous[index] >= target {
+            continue;
+        }
+        // SAFETY: Conditional guards precondition.
+        if let Err(error) = unsafe { lanes[index].try_grow_lane(target) } {
+            for (lane, &capacity) in lanes[..index].iter_mut().zip(&previous) {
+                if lane.lane_capacity() > capacity {
+                    // SAFETY: It just grew from `capacity`, so it's bigger and
+                    // the new positions are empty.
+                    let _ = unsafe { lane.try_shrink_lane(capacity) };
+                }
+            }
+            return Err(error);
+        }
+    }
+    Ok(())
+}
+
+/// Shrinks all the `lanes` (that are bigger) to `target`, in order. Lanes that
+/// don't support shrinking are left as they are.
+///
+/// If one of them fails, the ones that already shrank are grown back to their
+/// previous capacity and the error is returned, so they don't end up with
+/// different capacities. A lane that cannot grow back stays smaller, which is
+/// still valid (its positions from `target` on were empty).
+///
+/// # Safety
+///   * `target` must be smaller than the smallest capacity of the lanes.
+///   * Positions from `target` on must be empty in all the lanes.
+pub unsafe fn try_shrink_all<const N: usize>(
+    mut lanes: [&mut dyn Lane; N],
+    target: usize,
+) -> Result<(), ResizeError> {
+    let previous = lanes.each_ref().map(|lane| lane.lane_capacity());
+    for index in 0..N {
+        // SAFETY: It's smaller than the capacity of every lane, and the
+        // positions after it are empty.
+        match unsafe { lanes[index].try_shrink_lane(target) } {
+            Ok(()) | Err(ResizeError::UnsupportedOperation) => {}
+            Err(error) => {
+                for (lane, &capacity) in lanes[..index].iter_mut().zip(&previous) {
+                    if lane.lane_capacity() < capacity {
+                        // SAFETY: It just shrank from `capacity`, so it's
+                        // smaller.
+                        let _ = unsafe { lane.try_grow_lane(capacity) };
+                    }
+                }
+                return Err(error);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{base_buffers::HeapBuffer, test_utils::faulty::FaultyBuffer};
+
+    use super::*;
+
+    #[test]
+    fn failing_lanes_roll_back_the_others() {
+        let mut first = HeapBuffer::<u32>::new();
+        let mut bigger = HeapBuffer::<u64>::new();
+        // SAFETY: 8 is bigger than its capacity (0).
+        unsafe { bigger.try_grow(8) }.unwrap();
+        let mut failing = FaultyBuffer::<HeapBuffer<u8>>::default()
+            .failing_grow(ResizeError::TheoreticalLimitSurpassed);
+
+        // SAFETY: 4 is bigger than the smallest capacity (0).
+        let result = unsafe { try_grow_all([&mut first, &mut bigger, &mut failing], 4) };
+        assert!(matches!(
+            result,
+            Err(ResizeError::TheoreticalLimitSurpassed)
+        ));
+        assert_eq!(first.capacity(), 0);
+        assert_eq!(bigger.capacity(), 8);
+        assert_eq!(failing.capacity(), 0);
+    }
+
+    #[test]
+    fn lanes_that_cannot_shrink_back_stay_bigger() {
+        let mut stuck = FaultyBuffer::<HeapBuffer<u32>>::default().failing_shrink();
+        let mut failing =
+            FaultyBuffer::<HeapBuffer<u32>>::default().failing_grow(ResizeError::OutOfMemory);
+
+        // SAFETY: 4 is bigger than the smallest capacity (0).
+        let result = unsafe { try_grow_all([&mut stuck, &mut failing], 4) };
+        assert!(result.is_err());
+        assert_eq!(stuck.capacity(), 4);
+        assert_eq!(failing.capacity(), 0);
+    }
+
+    #[test]
+    fn failing_lanes_undo_the_shrinking_of_the_others() {
+        let mut first = HeapBuffer::<u32>::new();
+        let mut unsupported = FaultyBuffer::<HeapBuffer<u32>>::default().failing_shrink();
+        let mut failing =
+            FaultyBuffer::<HeapBuffer<u32>>::default().failing_shrink_with(ResizeError::OutOfMemory);
+        for lane in [&mut first as &mut dyn Lane, &mut unsupported, &mut failing] {
+            // SAFETY: 8 is bigger than its capacity (0).
+            unsafe { lane.try_grow_lane(8) }.unwrap();
+        }
+
+        // SAFETY: 2 is smaller than all the capacities, and they are empty.
+        let result = unsafe { try_shrink_all([&mut first, &mut unsupported, &mut failing], 2) };
+        assert!(matches!(result, Err(ResizeError::OutOfMemory)));
+        assert_eq!(first.capacity(), 8);
+        assert_eq!(unsupported.capacity(), 8);
+        assert_eq!(failing.capacity(), 8);
+    }
+}
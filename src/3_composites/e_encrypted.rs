@@ -0,0 +1,253 @@
+use std::{mem, ops::RangeBounds, slice};
+
+use crate::{
+    composites::cast::Pod,
+    interface::{
+        copy_value::CopyValueBuffer, growable::GrowableBuffer, shrinkable::ShrinkableBuffer,
+        Buffer, BufferCaps, CapacityContract, ResizeError,
+    },
+};
+
+/// Symmetric cipher used by [`EncryptedBuffer`].
+///
+/// It must be length-preserving (like a stream cipher or a tweakable block
+/// cipher), and decrypting must undo encrypting for the same `position`. The
+/// position can be used as a tweak or a nonce so equal values don't look the
+/// same, but note that each position is written many times: a plain stream
+/// cipher would reuse its keystream.
+pub trait Cipher {
+    /// Encrypts (in place) the bytes of the value stored at `position`.
+    fn encrypt(&self, position: usize, bytes: &mut [u8]);
+
+    /// Decrypts (in place) the bytes of the value stored at `position`.
+    fn decrypt(&self, position: usize, bytes: &mut [u8]);
+}
+
+/// Composite that keeps the values of the inner buffer encrypted, for
+/// sensitive data that lives in memory for a long time (eg. a table of keys),
+/// so it doesn't show up in memory dumps or swap in the clear.
+///
+/// Values are encrypted when they are put and decrypted when they are taken or
+/// copied, so the buffer cannot give references nor pointers to them (only
+/// copies, with [`CopyValueBuffer`]). That's why the elements must be
+/// [`Pod`]: they are handled as plain bytes.
+///
+/// # Performance
+/// Every access runs the cipher over the whole value, and moving values (eg.
+/// inserting in the middle of a vector) decrypts and encrypts each of them
+/// again, as they are bound to their position. Growing the inner buffer
+/// copies the encrypted bytes as they are. The decrypted copies are normal
+/// values, so they can still end up on the stack or in registers.
+///
+/// It requires the `crypto` feature.
+///
+/// # Example
+/// ```
+/// # use buffers::{base_buffers::HeapBuffer, collections::Vector, composites::encrypted::{Cipher, EncryptedBuffer}};
+/// /// Toy cipher. Use a real one!
+/// struct Xor(u8);
+///
+/// impl Cipher for Xor {
+///     fn encrypt(&self, position: usize, bytes: &mut [u8]) {
+///         bytes.iter_mut().for_each(|byte| *byte ^= self.0 ^ position as u8);
+///     }
+///
+///     fn decrypt(&self, position: usize, bytes: &mut [u8]) {
+///         self.encrypt(position, bytes);
+///     }
+/// }
+///
+/// let buffer = EncryptedBuffer::new(HeapBuffer::<u64>::new(), Xor(0x5a));
+/// let mut vec = Vector::from_buffer(buffer);
+/// vec.push(1);
+/// vec.insert(0, 2);
+/// assert_eq!(vec.iter_copied().collect::<Vec<_>>(), [2, 1]);
+/// ```
+pub struct EncryptedBuffer<B: Buffer, C: Cipher>
+where
+    B::Element: Pod,
+{
+    inner: B,
+    cipher: C,
+}
+
+impl<B: Buffer, C: Cipher> EncryptedBuffer<B, C>
+where
+    B::Element: Pod,
+{
+    /// Makes a buffer that stores the values encrypted with `cipher` in
+    /// `inner`.
+    pub fn new(inner: B, cipher: C) -> Self {
+        Self { inner, cipher }
+    }
+
+    /// Inner buffer, which has the encrypted values.
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Cipher used to encrypt the values.
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+}
+
+impl<B: Buffer, C: Cipher> Buffer for EncryptedBuffer<B, C>
+where
+    B::Element: Pod,
+{
+    type Element = B::Element;
+
+    const CAPACITY_CONTRACT: CapacityContract = B::CAPACITY_CONTRACT;
+
+    fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    fn allocated_bytes(&self) -> usize {
+        self.inner.allocated_bytes()
+    }
+
+    /// The values can only be copied out (after decrypting them), so it's
+    /// never contiguous.
+    fn caps(&self) -> BufferCaps {
+        self.inner.caps() & (BufferCaps::GROW | BufferCaps::SHRINK)
+    }
+
+    unsafe fn take(&mut self, index: usize) -> Self::Element {
+        // SAFETY: Same requirements.
+        let mut value = unsafe { self.inner.take(index) };
+        self.cipher.decrypt(index, bytes_mut(&mut value));
+        value
+    }
+
+    unsafe fn put(&mut self, index: usize, mut value: Self::Element) {
+        self.cipher.encrypt(index, bytes_mut(&mut value));
+        // SAFETY: Same requirements.
+        unsafe { self.inner.put(index, value) };
+    }
+
+    unsafe fn manually_drop(&mut self, index: usize) {
+        // SAFETY: Same requirements.
+        unsafe { self.inner.manually_drop(index) };
+    }
+
+    unsafe fn manually_drop_range<R: RangeBounds<usize> + Clone>(&mut self, values_range: R) {
+        // SAFETY: Same requirements.
+        unsafe { self.inner.manually_drop_range(values_range) };
+    }
+
+    unsafe fn try_grow(&mut self, target: usize) -> Result<(), ResizeError> {
+        // SAFETY: Same requirements.
+        unsafe { self.inner.try_grow(target) }
+    }
+
+    fn preferred_grow_target(&self, current: usize, required: usize) -> usize {
+        self.inner.preferred_grow_target(current, required)
+    }
+
+    unsafe fn try_shrink(&mut self, target: usize) -> Result<(), ResizeError> {
+        // SAFETY: Same requirements.
+        unsafe { self.inner.try_shrink(target) }
+    }
+}
+
+impl<B: CopyValueBuffer, C: Cipher> CopyValueBuffer for EncryptedBuffer<B, C>
+where
+    B::Element: Pod,
+{
+    unsafe fn copy(&self, index: usize) -> Self::Element {
+        // SAFETY: Same requirements.
+        let mut value = unsafe { self.inner.copy(index) };
+        self.cipher.decrypt(index, bytes_mut(&mut value));
+        value
+    }
+}
+
+impl<B, C> GrowableBuffer for EncryptedBuffer<B, C>
+where
+    B: GrowableBuffer,
+    B::Element: Pod,
+    C: Cipher,
+{
+}
+
+impl<B, C> ShrinkableBuffer for EncryptedBuffer<B, C>
+where
+    B: ShrinkableBuffer,
+    B::Element: Pod,
+    C: Cipher,
+{
+}
+
+impl<B: Buffer + Default, C: Cipher + Default> Default for EncryptedBuffer<B, C>
+where
+    B::Element: Pod,
+{
+    fn default() -> Self {
+        Self::new(B::default(), C::default())
+    }
+}
+
+/// Internal utility that gives the bytes of a value.
+fn bytes_mut<T: Pod>(value: &mut T) -> &mut [u8] {
+    let ptr = (value as *mut T).cast::<u8>();
+    // SAFETY: `Pod` values have no padding (all their bytes are initialized)
+    // and any bit pattern is valid, so they can be changed as bytes.
+    unsafe { slice::from_raw_parts_mut(ptr, mem::size_of::<T>()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{base_buffers::HeapBuffer, collections::Vector};
+
+    use super::*;
+
+    /// Cipher that adds the position (and a key) to every byte.
+    #[derive(Default)]
+    struct Shift(u8);
+
+    impl Cipher for Shift {
+        fn encrypt(&self, position: usize, bytes: &mut [u8]) {
+            let key = self.0.wrapping_add(position as u8);
+            bytes
+                .iter_mut()
+                .for_each(|byte| *byte = byte.wrapping_add(key));
+        }
+
+        fn decrypt(&self, position: usize, bytes: &mut [u8]) {
+            let key = self.0.wrapping_add(position as u8);
+            bytes
+                .iter_mut()
+                .for_each(|byte| *byte = byte.wrapping_sub(key));
+        }
+    }
+
+    #[test]
+    fn stores_the_values_encrypted() {
+        let mut buffer = EncryptedBuffer::new(HeapBuffer::<[u8; 2]>::new(), Shift(10));
+        // SAFETY: 4 > 0 (the current capacity).
+        unsafe {
+            buffer.try_grow(4).unwrap();
+        }
+        // SAFETY: The position is valid and empty.
+        unsafe { buffer.put(3, [1, 2]) };
+
+        // SAFETY: The position is filled.
+        assert_eq!(unsafe { buffer.inner().copy(3) }, [14, 15]);
+        // SAFETY: The position is filled.
+        assert_eq!(unsafe { buffer.copy(3) }, [1, 2]);
+        // SAFETY: The position is filled.
+        assert_eq!(unsafe { buffer.take(3) }, [1, 2]);
+    }
+
+    #[test]
+    fn values_can_move_between_positions() {
+        let mut vec = Vector::<u32, EncryptedBuffer<HeapBuffer<u32>, Shift>>::new();
+        vec.extend_from_slice(&[1, 2, 3]);
+        vec.insert(0, 0);
+        assert_eq!(vec.remove(2), 2);
+        vec.shrink_to_fit();
+        assert_eq!(vec.iter_copied().collect::<Vec<_>>(), [0, 1, 3]);
+    }
+}
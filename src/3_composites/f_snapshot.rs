@@ -0,0 +1,232 @@
+use crate::interface::{
+    contiguous_memory::ContiguousMemoryBuffer, indirect_buffer::IndirectBuffer,
+    resize_error::ResizeError, Buffer,
+};
+
+/// Composite that can save a copy of the first values of the inner buffer (a
+/// checkpoint) and later restore them (a rollback), for speculative algorithms
+/// or backtracking parsers.
+///
+/// The copy is kept in another buffer (`S`, by default the same type as the
+/// inner one). Otherwise it works exactly as the inner buffer. Usually used
+/// through [`crate::collections::Vector::checkpoint`] and
+/// [`crate::collections::Vector::rollback`].
+///
+/// # Example
+/// ```
+/// # use buffers::{base_buffers::HeapBuffer, collections::Vector, composites::SnapshotBuffer};
+/// let mut tokens = Vector::<String, SnapshotBuffer<HeapBuffer<String>>>::new();
+/// tokens.push("let".to_string());
+/// tokens.checkpoint();
+///
+/// tokens.push("x".to_string());
+/// tokens.push("?".to_string()); // Oops, not what was expected.
+/// assert!(tokens.rollback());
+/// assert_eq!(tokens.as_slice(), ["let"]);
+/// ```
+pub struct SnapshotBuffer<B: Buffer, S: Buffer<Element = B::Element> = B> {
+    inner: B,
+    snapshot: S,
+    snapshot_len: Option<usize>,
+}
+
+impl<B: Buffer, S: Buffer<Element = B::Element>> SnapshotBuffer<B, S> {
+    /// Makes a buffer on top of `inner` that keeps its checkpoints in
+    /// `snapshot`.
+    pub fn new(inner: B, snapshot: S) -> Self {
+        Self {
+            inner,
+            snapshot,
+            snapshot_len: None,
+        }
+    }
+
+    /// Number of values saved in the checkpoint, if there is one.
+    pub fn checkpoint_len(&self) -> Option<usize> {
+        self.snapshot_len
+    }
+
+    /// Saves a copy of the first `len` values, replacing the previous
+    /// checkpoint (if any).
+    ///
+    /// If the snapshot buffer cannot hold them, it returns the error and
+    /// there is no checkpoint.
+    ///
+    /// # Safety
+    /// The first `len` positions of the inner buffer must be filled.
+    pub unsafe fn checkpoint(&mut self, len: usize) -> Result<(), ResizeError>
+    where
+        B: ContiguousMemoryBuffer,
+        B::Element: Clone,
+    {
+        self.discard_checkpoint();
+        if self.snapshot.capacity() < len {
+            // SAFETY: It's bigger than the current capacity.
+            unsafe { self.snapshot.try_grow(len) }?;
+        }
+
+        // SAFETY: The positions are filled.
+        let values = unsafe { self.inner.slice(..len) };
+        self.snapshot_len = Some(0);
+        for (index, value) in values.iter().enumerate() {
+            // SAFETY: The snapshot is empty after `index` (the checkpoint only
+            // counts the values cloned so far) and it has space for `len`.
+            unsafe { self.snapshot.put(index, value.clone()) };
+            self.snapshot_len = Some(index + 1);
+        }
+        Ok(())
+    }
+
+    /// Replaces the first `len` values with the ones saved in the checkpoint
+    /// (which is used up). Returns how many there are now, or `None` if there
+    /// was no checkpoint (and nothing changes).
+    ///
+    /// If the inner buffer has shrunk since and cannot grow back, it returns
+    /// the error and nothing changes.
+    ///
+    /// If dropping one of the current values panics, the rest are still
+    /// dropped (so all the positions end up empty) and the checkpoint is kept.
+    ///
+    /// # Safety
+    /// The first `len` positions of the inner buffer must be filled, and the
+    /// rest empty. The caller must not consider them filled while this runs,
+    /// as they may be dropped before it returns or unwinds.
+    pub unsafe fn rollback(&mut self, len: usize) -> Result<Option<usize>, ResizeError> {
+        let Some(saved) = self.snapshot_len else {
+            return Ok(None);
+        };
+        if self.inner.capacity() < saved {
+            // SAFETY: It's bigger than the current capacity.
+            unsafe { self.inner.try_grow(saved) }?;
+        }
+
+        // SAFETY: The positions are filled. If it panics, they are all dropped
+        // and the checkpoint still has its values.
+        unsafe { self.inner.manually_drop_range(..len) };
+        // The saved values are moved out right after, and that can't panic.
+        self.snapshot_len = None;
+        for index in 0..saved {
+            // SAFETY: The snapshot has `saved` values.
+            let value = unsafe { self.snapshot.take(index) };
+            // SAFETY: All the positions of the inner buffer are empty now, and
+            // it has space for `saved`.
+            unsafe { self.inner.put(index, value) };
+        }
+        Ok(Some(saved))
+    }
+
+    /// Drops the saved values (if any).
+    pub fn discard_checkpoint(&mut self) {
+        if let Some(len) = self.snapshot_len.take() {
+            // SAFETY: The checkpoint has exactly `len` values.
+            unsafe { self.snapshot.manually_drop_range(..len) };
+        }
+    }
+}
+
+impl<B, S> Default for SnapshotBuffer<B, S>
+where
+    B: Buffer + Default,
+    S: Buffer<Element = B::Element> + Default,
+{
+    fn default() -> Self {
+        Self::new(B::default(), S::default())
+    }
+}
+
+impl<B: Buffer, S: Buffer<Element = B::Element>> IndirectBuffer for SnapshotBuffer<B, S> {
+    type InnerBuffer = B;
+    type InnerBufferRef<'a> = &'a Self::InnerBuffer where Self: 'a;
+    type InnerBufferMutRef<'a> = &'a mut Self::InnerBuffer where Self: 'a;
+
+    fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    fn inner_mut(&mut self) -> &mut B {
+        &mut self.inner
+    }
+
+    /// Both the inner buffer and the one with the checkpoint.
+    fn allocated_bytes(&self) -> usize {
+        self.inner.allocated_bytes() + self.snapshot.allocated_bytes()
+    }
+}
+
+impl<B: Buffer, S: Buffer<Element = B::Element>> Drop for SnapshotBuffer<B, S> {
+    fn drop(&mut self) {
+        // The saved values belong to this buffer (unlike the inner ones).
+        self.discard_checkpoint();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicI64, Ordering};
+
+    use crate::{
+        base_buffers::{HeapBuffer, InlineBuffer},
+        collections::Vector,
+        test_utils::{panic::assert_panic, panicking::PanickingElement},
+    };
+
+    use super::*;
+
+    #[test]
+    fn checkpoints_are_dropped_with_the_buffer() {
+        let counter = AtomicI64::new(0);
+        let mut vec = Vector::<_, SnapshotBuffer<HeapBuffer<_>>>::new();
+        vec.push(PanickingElement::new(&counter, 1));
+        vec.checkpoint();
+        vec.clear();
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+
+        drop(vec);
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn rolling_back_drops_everything_once_when_one_panics() {
+        let counter = AtomicI64::new(0);
+        let mut vec = Vector::<_, SnapshotBuffer<HeapBuffer<_>>>::new();
+        vec.push(PanickingElement::new(&counter, 1));
+        vec.checkpoint();
+        vec.push(PanickingElement::new(&counter, 2).panicking_on_drop());
+        vec.push(PanickingElement::new(&counter, 3));
+        assert_eq!(counter.load(Ordering::SeqCst), 4);
+
+        assert_panic(std::panic::AssertUnwindSafe(|| vec.rollback()));
+        assert!(vec.is_empty());
+        assert!(vec.has_checkpoint());
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+
+        assert!(vec.rollback());
+        assert_eq!(vec.index(0).value(), 1);
+        drop(vec);
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn allocated_bytes_include_the_checkpoint() {
+        let buffer =
+            SnapshotBuffer::new(InlineBuffer::<u8, 4>::new(), InlineBuffer::<u8, 2>::new());
+        let vec = Vector::from_buffer(buffer);
+        assert_eq!(vec.allocated_bytes(), 6);
+    }
+
+    #[test]
+    fn fixed_snapshots_fail_when_full() {
+        let buffer =
+            SnapshotBuffer::new(InlineBuffer::<u8, 4>::new(), InlineBuffer::<u8, 2>::new());
+        let mut vec = Vector::from_buffer(buffer);
+        vec.extend_from_slice(&[1, 2, 3]);
+        assert!(vec.try_checkpoint().is_err());
+        assert!(!vec.has_checkpoint());
+
+        vec.pop();
+        vec.checkpoint();
+        vec.push(4);
+        assert!(vec.rollback());
+        assert_eq!(vec.as_slice(), [1, 2]);
+    }
+}
@@ -0,0 +1,214 @@
+use std::{rc::Rc, sync::Arc};
+
+use crate::interface::{indirect_buffer::IndirectBuffer, resize_error::ResizeError, Buffer};
+
+/// Receives the changes of memory usage of an [`ObservedBuffer`], to integrate
+/// buffers with memory profilers (or any other accounting) without replacing
+/// the global allocator.
+///
+/// The sizes are changes in [`Buffer::allocated_bytes`], so they include inline
+/// memory (eg. the small buffer of [`super::SvoBuffer`]) too.
+pub trait AllocationObserver {
+    /// The buffer now uses `bytes` more bytes.
+    fn allocated(&self, bytes: usize);
+
+    /// The buffer now uses `bytes` less bytes.
+    fn deallocated(&self, bytes: usize);
+}
+
+impl<O: AllocationObserver + ?Sized> AllocationObserver for &O {
+    fn allocated(&self, bytes: usize) {
+        (**self).allocated(bytes);
+    }
+
+    fn deallocated(&self, bytes: usize) {
+        (**self).deallocated(bytes);
+    }
+}
+
+impl<O: AllocationObserver + ?Sized> AllocationObserver for Rc<O> {
+    fn allocated(&self, bytes: usize) {
+        (**self).allocated(bytes);
+    }
+
+    fn deallocated(&self, bytes: usize) {
+        (**self).deallocated(bytes);
+    }
+}
+
+impl<O: AllocationObserver + ?Sized> AllocationObserver for Arc<O> {
+    fn allocated(&self, bytes: usize) {
+        (**self).allocated(bytes);
+    }
+
+    fn deallocated(&self, bytes: usize) {
+        (**self).deallocated(bytes);
+    }
+}
+
+/// Composite that reports to an [`AllocationObserver`] every time the memory
+/// used by the inner buffer changes: when it's made, when it grows or shrinks
+/// and when it's dropped. Otherwise it works exactly as the inner buffer.
+///
+/// # Example
+/// ```
+/// # use std::sync::atomic::{AtomicUsize, Ordering};
+/// # use buffers::{base_buffers::HeapBuffer, collections::Vector, composites::{AllocationObserver, ObservedBuffer}};
+/// #[derive(Default)]
+/// struct Profiler(AtomicUsize);
+///
+/// impl AllocationObserver for Profiler {
+///     fn allocated(&self, bytes: usize) {
+///         self.0.fetch_add(bytes, Ordering::Relaxed);
+///     }
+///
+///     fn deallocated(&self, bytes: usize) {
+///         self.0.fetch_sub(bytes, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let profiler = Profiler::default();
+/// let mut vec = Vector::from_buffer(ObservedBuffer::new(HeapBuffer::<u32>::new(), &profiler));
+/// vec.reserve_exact(4);
+/// assert_eq!(profiler.0.load(Ordering::Relaxed), 16);
+///
+/// drop(vec);
+/// assert_eq!(profiler.0.load(Ordering::Relaxed), 0);
+/// ```
+pub struct ObservedBuffer<B: Buffer, O: AllocationObserver> {
+    inner: B,
+    observer: O,
+}
+
+impl<B: Buffer, O: AllocationObserver> ObservedBuffer<B, O> {
+    /// Makes a buffer on top of `inner` that reports to `observer`. The memory
+    /// that `inner` already uses is reported as allocated.
+    pub fn new(inner: B, observer: O) -> Self {
+        let this = Self { inner, observer };
+        this.report(0);
+        this
+    }
+
+    /// Observer that receives the changes.
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    /// Internal utility that reports the change from `before` bytes to the
+    /// ones used now (if any).
+    fn report(&self, before: usize) {
+        let after = self.inner.allocated_bytes();
+        if after > before {
+            self.observer.allocated(after - before);
+        } else if after < before {
+            self.observer.deallocated(before - after);
+        }
+    }
+}
+
+impl<B, O> Default for ObservedBuffer<B, O>
+where
+    B: Buffer + Default,
+    O: AllocationObserver + Default,
+{
+    fn default() -> Self {
+        Self::new(B::default(), O::default())
+    }
+}
+
+impl<B: Buffer, O: AllocationObserver> IndirectBuffer for ObservedBuffer<B, O> {
+    type InnerBuffer = B;
+    type InnerBufferRef<'a> = &'a Self::InnerBuffer where Self: 'a;
+    type InnerBufferMutRef<'a> = &'a mut Self::InnerBuffer where Self: 'a;
+
+    fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    fn inner_mut(&mut self) -> &mut B {
+        &mut self.inner
+    }
+
+    unsafe fn try_grow(&mut self, target: usize) -> Result<(), ResizeError> {
+        let before = self.inner.allocated_bytes();
+        // SAFETY: Same requirements.
+        let result = unsafe { self.inner.try_grow(target) };
+        self.report(before);
+        result
+    }
+
+    unsafe fn try_shrink(&mut self, target: usize) -> Result<(), ResizeError> {
+        let before = self.inner.allocated_bytes();
+        // SAFETY: Same requirements.
+        let result = unsafe { self.inner.try_shrink(target) };
+        self.report(before);
+        result
+    }
+}
+
+impl<B: Buffer, O: AllocationObserver> Drop for ObservedBuffer<B, O> {
+    fn drop(&mut self) {
+        let bytes = self.inner.allocated_bytes();
+        if bytes > 0 {
+            self.observer.deallocated(bytes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use crate::{
+        base_buffers::HeapBuffer,
+        collections::Vector,
+        composites::{ExponentialGrowthBuffer, SvoBuffer},
+        test_utils::faulty::FaultyBuffer,
+    };
+
+    use super::*;
+
+    /// Observer that keeps every change (positive when allocating).
+    #[derive(Default)]
+    struct Changes(RefCell<Vec<isize>>);
+
+    impl AllocationObserver for Changes {
+        fn allocated(&self, bytes: usize) {
+            self.0.borrow_mut().push(bytes as isize);
+        }
+
+        fn deallocated(&self, bytes: usize) {
+            self.0.borrow_mut().push(-(bytes as isize));
+        }
+    }
+
+    #[test]
+    fn reports_every_change() {
+        let changes = Changes::default();
+        let buffer = ObservedBuffer::new(
+            ExponentialGrowthBuffer::<SvoBuffer<2, HeapBuffer<u8>>>::default(),
+            &changes,
+        );
+        let mut vec = Vector::from_buffer(buffer);
+        vec.extend_from_slice(&[1, 2, 3]);
+        vec.push(4);
+        vec.push(5);
+        vec.truncate(1);
+        vec.shrink_to_fit();
+        drop(vec);
+
+        assert_eq!(*changes.0.borrow(), [2, 4, 4, -8, -2]);
+    }
+
+    #[test]
+    fn failing_to_grow_reports_nothing() {
+        let changes = Changes::default();
+        let inner =
+            FaultyBuffer::<HeapBuffer<u32>>::default().failing_grow(ResizeError::OutOfMemory);
+        let mut vec = Vector::from_buffer(ObservedBuffer::new(inner, &changes));
+        assert!(vec.try_push(1).is_err());
+        drop(vec);
+
+        assert!(changes.0.borrow().is_empty());
+    }
+}
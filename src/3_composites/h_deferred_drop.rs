@@ -0,0 +1,222 @@
+use std::{
+    mem::{self, ManuallyDrop},
+    ptr,
+    rc::Rc,
+    sync::Arc,
+};
+
+use crate::interface::{
+    contiguous_memory::ContiguousMemoryBuffer, indirect_buffer::IndirectBuffer,
+    resize_error::ResizeError, Buffer,
+};
+
+/// Receives the buffers that a [`DeferredDropBuffer`] doesn't want to free
+/// itself. Dropping them frees their memory, so it may be done later or in
+/// another thread (eg. sending them through a channel).
+///
+/// Any `Fn(B)` is a reclaimer.
+pub trait Reclaimer<B> {
+    /// Takes ownership of `buffer`, which is empty (all of its positions
+    /// are).
+    fn reclaim(&self, buffer: B);
+}
+
+impl<B, F: Fn(B)> Reclaimer<B> for F {
+    fn reclaim(&self, buffer: B) {
+        self(buffer);
+    }
+}
+
+/// Reclaimer shared with reference counting.
+impl<B, R: Reclaimer<B> + ?Sized> Reclaimer<B> for Rc<R> {
+    fn reclaim(&self, buffer: B) {
+        (**self).reclaim(buffer);
+    }
+}
+
+/// Reclaimer shared between threads.
+impl<B, R: Reclaimer<B> + ?Sized> Reclaimer<B> for Arc<R> {
+    fn reclaim(&self, buffer: B) {
+        (**self).reclaim(buffer);
+    }
+}
+
+/// Composite that never frees the memory of its inner buffer itself: when
+/// it's dropped or shrunk, the old buffer is handed to a [`Reclaimer`]
+/// instead. Useful in latency-critical threads which drop big vectors, to
+/// free them somewhere else.
+///
+/// To shrink, it makes a new buffer (with [`Default`]) with the requested
+/// capacity and moves the values there, so the big allocation is never
+/// reallocated in place. If the new one doesn't end up smaller (eg. the inner
+/// buffer rounds its capacity up), it's the one reclaimed and the values stay
+/// where they are. Growing works as in the inner buffer.
+///
+/// # Example
+/// ```
+/// # use std::{sync::mpsc, thread};
+/// # use buffers::{base_buffers::HeapBuffer, collections::Vector, composites::DeferredDropBuffer};
+/// let (sender, receiver) = mpsc::channel::<HeapBuffer<u64>>();
+/// let freeing = thread::spawn(move || receiver.into_iter().count());
+///
+/// let reclaimer = move |buffer| sender.send(buffer).unwrap();
+/// let mut vec = Vector::from_buffer(DeferredDropBuffer::new(HeapBuffer::new(), reclaimer));
+/// vec.extend(0..1000);
+/// vec.truncate(10);
+/// vec.shrink_to_fit();
+/// assert_eq!(vec.capacity(), 10);
+/// drop(vec);
+///
+/// assert_eq!(freeing.join().unwrap(), 2);
+/// ```
+pub struct DeferredDropBuffer<B: Buffer, R: Reclaimer<B>> {
+    inner: ManuallyDrop<B>,
+    reclaimer: R,
+}
+
+impl<B: Buffer, R: Reclaimer<B>> DeferredDropBuffer<B, R> {
+    /// Makes a buffer on top of `inner` that gives the buffers to free to
+    /// `reclaimer`.
+    pub fn new(inner: B, reclaimer: R) -> Self {
+        Self {
+            inner: ManuallyDrop::new(inner),
+            reclaimer,
+        }
+    }
+
+    /// Reclaimer that receives the buffers.
+    pub fn reclaimer(&self) -> &R {
+        &self.reclaimer
+    }
+}
+
+impl<B, R> Default for DeferredDropBuffer<B, R>
+where
+    B: Buffer + Default,
+    R: Reclaimer<B> + Default,
+{
+    fn default() -> Self {
+        Self::new(B::default(), R::default())
+    }
+}
+
+impl<B, R> IndirectBuffer for DeferredDropBuffer<B, R>
+where
+    B: ContiguousMemoryBuffer + Default,
+    R: Reclaimer<B>,
+{
+    type InnerBuffer = B;
+    type InnerBufferRef<'a> = &'a Self::InnerBuffer where Self: 'a;
+    type InnerBufferMutRef<'a> = &'a mut Self::InnerBuffer where Self: 'a;
+
+    fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    fn inner_mut(&mut self) -> &mut B {
+        &mut self.inner
+    }
+
+    /// Moves the first `target` positions into a new buffer and reclaims
+    /// the old one. If the new one cannot grow, nothing changes. If it isn't
+    /// smaller than the old one, it's reclaimed instead (without moving).
+    unsafe fn try_shrink(&mut self, target: usize) -> Result<(), ResizeError> {
+        let mut smaller = B::default();
+        if smaller.capacity() < target {
+            // SAFETY: It's bigger than the capacity.
+            unsafe { smaller.try_grow(target) }?;
+        }
+        if smaller.capacity() >= B::capacity(&self.inner) {
+            // Moving wouldn't give anything back. It's empty.
+            self.reclaimer.reclaim(smaller);
+            return Ok(());
+        }
+        if target > 0 {
+            // SAFETY: `target` is smaller than the capacity, so 0 is valid.
+            let src = unsafe { self.inner.ptr(0) };
+            // SAFETY: It has at least `target` positions, so 0 is valid.
+            let dst = unsafe { smaller.mut_ptr(0) };
+            // SAFETY: Both have (contiguous) space for `target` values and
+            // they don't overlap. Empty positions are copied too, but they
+            // stay empty.
+            unsafe { ptr::copy_nonoverlapping(src, dst, target) };
+        }
+        // The values were moved, so all the positions of the old one are
+        // empty now.
+        let old = mem::replace(&mut *self.inner, smaller);
+        self.reclaimer.reclaim(old);
+        Ok(())
+    }
+}
+
+impl<B: Buffer, R: Reclaimer<B>> Drop for DeferredDropBuffer<B, R> {
+    fn drop(&mut self) {
+        // SAFETY: It's never used again.
+        let inner = unsafe { ManuallyDrop::take(&mut self.inner) };
+        self.reclaimer.reclaim(inner);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::RefCell,
+        sync::atomic::{AtomicI64, Ordering},
+    };
+
+    use crate::{
+        base_buffers::HeapBuffer, collections::Vector, composites::ExponentialGrowthBuffer,
+        test_utils::life_counter::LifeCounter,
+    };
+
+    use super::*;
+
+    #[test]
+    fn shrinking_and_dropping_reclaim_the_old_buffers() {
+        let counter = AtomicI64::new(0);
+        let reclaimed: RefCell<Vec<HeapBuffer<LifeCounter>>> = RefCell::default();
+        let reclaimer = |buffer| reclaimed.borrow_mut().push(buffer);
+        let mut vec = Vector::from_buffer(DeferredDropBuffer::new(HeapBuffer::new(), &reclaimer));
+        vec.reserve_exact(8);
+        for _ in 0..5 {
+            vec.push(LifeCounter::new(&counter));
+        }
+
+        vec.truncate(3);
+        vec.shrink_to_fit();
+        assert_eq!(vec.capacity(), 3);
+        assert_eq!(counter.load(Ordering::SeqCst), 3);
+        assert_eq!(reclaimed.borrow().len(), 1);
+        assert_eq!(reclaimed.borrow()[0].capacity(), 8);
+
+        drop(vec);
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+        let capacities: Vec<_> = reclaimed.borrow().iter().map(|b| b.capacity()).collect();
+        assert_eq!(capacities, [8, 3]);
+    }
+
+    #[test]
+    fn buffers_that_do_not_end_up_smaller_are_not_used() {
+        type Rounding = ExponentialGrowthBuffer<HeapBuffer<u32>>;
+        let reclaimed: RefCell<Vec<Rounding>> = RefCell::default();
+        let reclaimer = |buffer| reclaimed.borrow_mut().push(buffer);
+        let mut vec = Vector::from_buffer(DeferredDropBuffer::new(Rounding::default(), &reclaimer));
+        vec.extend(0..5);
+        assert_eq!(vec.capacity(), 8);
+        let data = vec.as_ptr();
+
+        // The new buffer rounds 6 up to 8, so the old one is kept.
+        vec.truncate(3);
+        vec.shrink_to(6);
+        assert_eq!(vec.capacity(), 8);
+        assert_eq!(vec.as_ptr(), data);
+        assert_eq!(vec.as_slice(), [0, 1, 2]);
+        assert_eq!(reclaimed.borrow().len(), 1);
+        assert_eq!(Buffer::capacity(&reclaimed.borrow()[0]), 8);
+
+        vec.shrink_to_fit();
+        assert_eq!(vec.capacity(), 4);
+        assert_eq!(vec.as_slice(), [0, 1, 2]);
+        assert_eq!(reclaimed.borrow().len(), 2);
+    }
+}
@@ -0,0 +1,163 @@
+//! [`MaybeInstrumented`] is an [`super::ObservedBuffer`] in debug builds (or
+//! with the `instrumentation` feature) and an [`UninstrumentedBuffer`]
+//! otherwise, so type aliases can keep the instrumentation without paying for
+//! it in release builds. [`INSTRUMENTED`] tells which one it is.
+//!
+//! Both are made with `new(inner, observer)` (and [`Default`]), but only the
+//! observed one has [`super::ObservedBuffer::observer`].
+//!
+//! # Example
+//! ```
+//! # use std::cell::Cell;
+//! # use buffers::{base_buffers::HeapBuffer, collections::Vector, composites::{AllocationObserver, MaybeInstrumented}};
+//! # use buffers::composites::maybe_instrumented::INSTRUMENTED;
+//! #[derive(Default)]
+//! struct Profiler(Cell<usize>);
+//!
+//! impl AllocationObserver for Profiler {
+//!     fn allocated(&self, bytes: usize) {
+//!         self.0.set(self.0.get() + bytes);
+//!     }
+//!
+//!     fn deallocated(&self, bytes: usize) {
+//!         self.0.set(self.0.get() - bytes);
+//!     }
+//! }
+//!
+//! type Buffer<'a> = MaybeInstrumented<HeapBuffer<u32>, &'a Profiler>;
+//!
+//! let profiler = Profiler::default();
+//! let mut vec = Vector::from_buffer(Buffer::new(HeapBuffer::new(), &profiler));
+//! vec.reserve_exact(4);
+//! assert_eq!(profiler.0.get(), if INSTRUMENTED { 16 } else { 0 });
+//! ```
+
+use std::marker::PhantomData;
+
+use crate::interface::{indirect_buffer::IndirectBuffer, transparent::TransparentBuffer, Buffer};
+
+use super::observed::AllocationObserver;
+#[cfg(any(debug_assertions, feature = "instrumentation"))]
+use super::observed::ObservedBuffer;
+
+/// [`ObservedBuffer`] in debug builds (or with the `instrumentation`
+/// feature). See the [module documentation](self).
+#[cfg(any(debug_assertions, feature = "instrumentation"))]
+pub type MaybeInstrumented<B, O> = ObservedBuffer<B, O>;
+
+/// [`UninstrumentedBuffer`] in release builds (without the `instrumentation`
+/// feature). See the [module documentation](self).
+#[cfg(not(any(debug_assertions, feature = "instrumentation")))]
+pub type MaybeInstrumented<B, O> = UninstrumentedBuffer<B, O>;
+
+/// Whether [`MaybeInstrumented`] is an [`super::ObservedBuffer`] in this build.
+pub const INSTRUMENTED: bool = cfg!(any(debug_assertions, feature = "instrumentation"));
+
+/// Stand-in for [`super::ObservedBuffer`] that works exactly as its inner
+/// buffer: the observer is dropped as soon as it's made and never reports
+/// anything. It's `#[repr(transparent)]` over `B`.
+///
+/// See [`MaybeInstrumented`].
+#[repr(transparent)]
+pub struct UninstrumentedBuffer<B: Buffer, O: AllocationObserver> {
+    inner: B,
+    _observer: PhantomData<fn() -> O>,
+}
+
+impl<B: Buffer, O: AllocationObserver> UninstrumentedBuffer<B, O> {
+    /// Makes a buffer on top of `inner`. `observer` is ignored.
+    pub fn new(inner: B, _observer: O) -> Self {
+        Self {
+            inner,
+            _observer: PhantomData,
+        }
+    }
+}
+
+impl<B: Buffer + Default, O: AllocationObserver> Default for UninstrumentedBuffer<B, O> {
+    fn default() -> Self {
+        Self {
+            inner: B::default(),
+            _observer: PhantomData,
+        }
+    }
+}
+
+impl<B: Buffer, O: AllocationObserver> IndirectBuffer for UninstrumentedBuffer<B, O> {
+    type InnerBuffer = B;
+    type InnerBufferRef<'a> = &'a Self::InnerBuffer where Self: 'a;
+    type InnerBufferMutRef<'a> = &'a mut Self::InnerBuffer where Self: 'a;
+
+    #[inline(always)]
+    fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    #[inline(always)]
+    fn inner_mut(&mut self) -> &mut B {
+        &mut self.inner
+    }
+}
+
+// SAFETY: It's `#[repr(transparent)]` over `B` (the other field is a ZST), it
+// doesn't change anything and it doesn't implement `Drop`.
+unsafe impl<B: Buffer, O: AllocationObserver> TransparentBuffer for UninstrumentedBuffer<B, O> {
+    type Inner = B;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use crate::{base_buffers::HeapBuffer, collections::Vector};
+
+    use super::*;
+
+    /// Observer that counts how many times it's called.
+    #[derive(Default)]
+    struct Calls(Cell<usize>);
+
+    impl AllocationObserver for &Calls {
+        fn allocated(&self, _bytes: usize) {
+            self.0.set(self.0.get() + 1);
+        }
+
+        fn deallocated(&self, _bytes: usize) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn uninstrumented_buffers_never_report() {
+        let calls = Calls::default();
+        let buffer = UninstrumentedBuffer::new(HeapBuffer::<u32>::new(), &calls);
+        let mut vec = Vector::from_buffer(buffer);
+        vec.extend([1, 2, 3]);
+        vec.shrink_to_fit();
+        assert_eq!(vec.as_slice(), [1, 2, 3]);
+        drop(vec);
+        assert_eq!(calls.0.get(), 0);
+    }
+
+    #[test]
+    fn maybe_instrumented_only_reports_when_instrumented() {
+        let calls = Calls::default();
+        let mut vec = Vector::from_buffer(MaybeInstrumented::new(HeapBuffer::<u32>::new(), &calls));
+        vec.reserve_exact(4);
+        let expected = if cfg!(any(debug_assertions, feature = "instrumentation")) {
+            1
+        } else {
+            0
+        };
+        assert_eq!(calls.0.get(), expected);
+        assert_eq!(INSTRUMENTED, expected == 1);
+    }
+
+    #[test]
+    fn uninstrumented_buffers_are_as_big_as_their_inner_buffer() {
+        assert_eq!(
+            std::mem::size_of::<UninstrumentedBuffer<HeapBuffer<u32>, &Calls>>(),
+            std::mem::size_of::<HeapBuffer<u32>>()
+        );
+    }
+}
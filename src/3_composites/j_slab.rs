@@ -0,0 +1,361 @@
+use std::ops::Range;
+
+use crate::{
+    interface::{
+        contiguous_memory::ContiguousMemoryBuffer, growable::GrowableBuffer, ptrs::PtrBuffer,
+        resize_error::ResizeError, shrinkable::ShrinkableBuffer, sparse::SparseBuffer, Buffer,
+    },
+    DefaultBuffer,
+};
+
+/// Bits in each word of the bitmap.
+const BITS: usize = usize::BITS as usize;
+
+/// Composite that keeps track of which positions of `B` are filled, so they
+/// can be filled and emptied in any order (like in a slab). It's the
+/// [`SparseBuffer`] that collections with holes build on.
+///
+/// It keeps a bit per position in a bitmap, stored in `W`. With inline buffers
+/// for both it never allocates:
+/// ```
+/// # use buffers::{base_buffers::InlineBuffer, composites::SlabBuffer, interface::{sparse::SparseBuffer, Buffer}};
+/// let mut buffer = SlabBuffer::<InlineBuffer<u32, 8>, InlineBuffer<usize, 1>>::new();
+/// // SAFETY: 8 > 0.
+/// unsafe { buffer.try_grow(8) }.unwrap();
+/// // SAFETY: They are valid and empty.
+/// unsafe {
+///     buffer.put(1, 10);
+///     buffer.put(2, 20);
+///     buffer.put(5, 50);
+/// }
+/// assert_eq!(buffer.filled_runs().collect::<Vec<_>>(), [1..3, 5..6]);
+/// # unsafe { buffer.manually_drop_range(1..3) };
+/// # unsafe { buffer.manually_drop(5) };
+/// ```
+///
+/// Its capacity is the one of `B`, up to the positions the bitmap covers. So
+/// it starts with none, even if `B` already has some, until it's grown.
+///
+/// Positions must only be filled and emptied through [`Buffer`] (not by
+/// writing through pointers), or it loses track of them.
+pub struct SlabBuffer<B: Buffer, W = DefaultBuffer<usize>>
+where
+    W: ContiguousMemoryBuffer<Element = usize>,
+{
+    buffer: B,
+    bitmap: W,
+    /// Number of filled positions of `bitmap` (the words in use).
+    words: usize,
+}
+
+impl<B: Buffer, W> SlabBuffer<B, W>
+where
+    W: ContiguousMemoryBuffer<Element = usize>,
+{
+    /// Makes a new [`SlabBuffer`] given the (empty) buffers of the values and
+    /// of the bitmap.
+    pub fn from_parts(buffer: B, bitmap: W) -> Self {
+        Self {
+            buffer,
+            bitmap,
+            words: 0,
+        }
+    }
+
+    /// Makes a new [`SlabBuffer`] with default buffers.
+    pub fn new() -> Self
+    where
+        B: Default,
+        W: Default,
+    {
+        Self::from_parts(B::default(), W::default())
+    }
+
+    /// Internal utility that gives the words of the bitmap.
+    fn bitmap(&self) -> &[usize] {
+        // SAFETY: The first `words` positions are valid and filled.
+        unsafe { self.bitmap.slice(..self.words) }
+    }
+
+    /// Internal utility that gives the words of the bitmap to change them.
+    fn bitmap_mut(&mut self) -> &mut [usize] {
+        // SAFETY: The first `words` positions are valid and filled.
+        unsafe { self.bitmap.mut_slice(..self.words) }
+    }
+
+    /// Internal utility that marks `index` as filled or empty.
+    fn mark(&mut self, index: usize, filled: bool) {
+        let word = &mut self.bitmap_mut()[index / BITS];
+        let bit = 1 << (index % BITS);
+        if filled {
+            *word |= bit;
+        } else {
+            *word &= !bit;
+        }
+    }
+
+    /// Internal utility that finds the first position at `from` or after it
+    /// which is filled (or empty, when `filled` is `false`), a word at a time.
+    fn find(&self, from: usize, filled: bool) -> Option<usize> {
+        let capacity = self.capacity();
+        if from >= capacity {
+            return None;
+        }
+        // Flipping the bits looks for empty positions instead.
+        let flip = if filled { 0 } else { !0 };
+        let bitmap = self.bitmap();
+        let mut word = from / BITS;
+        let mut bits = (bitmap[word] ^ flip) & (!0 << (from % BITS));
+        while bits == 0 {
+            word += 1;
+            bits = bitmap.get(word)? ^ flip;
+        }
+        let index = word * BITS + bits.trailing_zeros() as usize;
+        (index < capacity).then_some(index)
+    }
+}
+
+impl<B: Buffer + Default, W> Default for SlabBuffer<B, W>
+where
+    W: ContiguousMemoryBuffer<Element = usize> + Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B: Buffer, W> Buffer for SlabBuffer<B, W>
+where
+    W: ContiguousMemoryBuffer<Element = usize>,
+{
+    type Element = B::Element;
+
+    fn capacity(&self) -> usize {
+        self.buffer.capacity().min(self.words.saturating_mul(BITS))
+    }
+
+    fn allocated_bytes(&self) -> usize {
+        self.buffer.allocated_bytes() + self.bitmap.allocated_bytes()
+    }
+
+    unsafe fn take(&mut self, index: usize) -> Self::Element {
+        self.mark(index, false);
+        // SAFETY: Same requirements.
+        unsafe { self.buffer.take(index) }
+    }
+
+    unsafe fn put(&mut self, index: usize, value: Self::Element) {
+        self.mark(index, true);
+        // SAFETY: Same requirements.
+        unsafe { self.buffer.put(index, value) }
+    }
+
+    unsafe fn manually_drop(&mut self, index: usize) {
+        self.mark(index, false);
+        // SAFETY: Same requirements.
+        unsafe { self.buffer.manually_drop(index) }
+    }
+
+    /// Grows the bitmap first, so if `B` cannot grow it goes back to the
+    /// words it had and the capacity stays the same.
+    unsafe fn try_grow(&mut self, target: usize) -> Result<(), ResizeError> {
+        let old_words = self.words;
+        let words = target.div_ceil(BITS);
+        if words > old_words {
+            if words > self.bitmap.capacity() {
+                // SAFETY: It's bigger than the capacity.
+                unsafe { self.bitmap.try_grow(words) }?;
+            }
+            // SAFETY: The positions after the words in use are empty, and now
+            // valid.
+            unsafe { self.bitmap.fill_range(old_words..words, &0) };
+            self.words = words;
+        }
+        if target > self.buffer.capacity() {
+            // SAFETY: It's bigger than the capacity.
+            if let Err(error) = unsafe { self.buffer.try_grow(target) } {
+                // SAFETY: They were just filled.
+                unsafe { self.bitmap.manually_drop_range(old_words..self.words) };
+                self.words = old_words;
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+
+    fn preferred_grow_target(&self, current: usize, required: usize) -> usize {
+        self.buffer.preferred_grow_target(current, required)
+    }
+
+    /// The bitmap is shrunk only if it can be. Otherwise it keeps the space of
+    /// the words it no longer needs.
+    unsafe fn try_shrink(&mut self, target: usize) -> Result<(), ResizeError> {
+        if target < self.buffer.capacity() {
+            // SAFETY: It's smaller than the capacity, and the positions after
+            // it are empty.
+            unsafe { self.buffer.try_shrink(target) }?;
+        }
+        let words = target.div_ceil(BITS);
+        if words < self.words {
+            // SAFETY: They are in use, and all their positions are empty.
+            unsafe { self.bitmap.manually_drop_range(words..self.words) };
+            self.words = words;
+            // SAFETY: `words` is less than the words in use (so less than the
+            // capacity), and the positions after it were just emptied.
+            let _ = unsafe { self.bitmap.try_shrink(words) };
+        }
+        Ok(())
+    }
+}
+
+// SAFETY: Every position is marked when it's filled (`put`) and unmarked when
+// it's emptied (`take` or `manually_drop`), and positions out of the capacity
+// are never filled. `next_filled_run` finds the same runs as the default
+// implementation, a word at a time.
+unsafe impl<B: Buffer, W> SparseBuffer for SlabBuffer<B, W>
+where
+    W: ContiguousMemoryBuffer<Element = usize>,
+{
+    fn is_filled(&self, index: usize) -> bool {
+        index < self.capacity() && self.bitmap()[index / BITS] & (1 << (index % BITS)) != 0
+    }
+
+    fn next_filled_run(&self, from: usize) -> Option<Range<usize>> {
+        let start = self.find(from, true)?;
+        let end = self.find(start, false).unwrap_or(self.capacity());
+        Some(start..end)
+    }
+}
+
+impl<B: PtrBuffer, W> PtrBuffer for SlabBuffer<B, W>
+where
+    W: ContiguousMemoryBuffer<Element = usize>,
+{
+    type ConstantPointer = B::ConstantPointer;
+    type MutablePointer = B::MutablePointer;
+
+    unsafe fn ptr(&self, index: usize) -> Self::ConstantPointer {
+        // SAFETY: Same requirements.
+        unsafe { self.buffer.ptr(index) }
+    }
+
+    unsafe fn mut_ptr(&mut self, index: usize) -> Self::MutablePointer {
+        // SAFETY: Same requirements.
+        unsafe { self.buffer.mut_ptr(index) }
+    }
+
+    unsafe fn segment_len(&self, index: usize) -> usize {
+        // SAFETY: Same requirements.
+        let len = unsafe { self.buffer.segment_len(index) };
+        // The capacity may be smaller than the one of `B`.
+        len.min(self.capacity() - index)
+    }
+}
+
+impl<B: ContiguousMemoryBuffer, W> ContiguousMemoryBuffer for SlabBuffer<B, W> where
+    W: ContiguousMemoryBuffer<Element = usize>
+{
+}
+
+impl<B: GrowableBuffer, W> GrowableBuffer for SlabBuffer<B, W> where
+    W: GrowableBuffer + ContiguousMemoryBuffer<Element = usize>
+{
+}
+
+impl<B: ShrinkableBuffer, W> ShrinkableBuffer for SlabBuffer<B, W> where
+    W: ContiguousMemoryBuffer<Element = usize>
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        base_buffers::{HeapBuffer, InlineBuffer},
+        test_utils::faulty::FaultyBuffer,
+    };
+
+    use super::*;
+
+    fn filled<B: Buffer, W>(buffer: &SlabBuffer<B, W>) -> Vec<usize>
+    where
+        W: ContiguousMemoryBuffer<Element = usize>,
+    {
+        buffer.filled_positions().collect()
+    }
+
+    #[test]
+    fn keeps_track_of_the_filled_positions() {
+        let mut buffer = SlabBuffer::<HeapBuffer<u32>>::new();
+        // SAFETY: 200 > 0.
+        unsafe { buffer.try_grow(200) }.unwrap();
+        for index in [0, 1, 2, 63, 64, 130, 199] {
+            // SAFETY: They are valid and empty.
+            unsafe { buffer.put(index, index as u32) };
+        }
+        assert_eq!(
+            buffer.filled_runs().collect::<Vec<_>>(),
+            [0..3, 63..65, 130..131, 199..200]
+        );
+        assert_eq!(buffer.next_filled_run(1), Some(1..3));
+        assert_eq!(buffer.next_filled_run(65), Some(130..131));
+
+        // SAFETY: They are filled.
+        assert_eq!(unsafe { buffer.take(64) }, 64);
+        // SAFETY: They are filled.
+        unsafe { buffer.manually_drop(1) };
+        assert_eq!(filled(&buffer), [0, 2, 63, 130, 199]);
+        assert!(!buffer.is_filled(200));
+
+        for index in filled(&buffer) {
+            // SAFETY: They are filled.
+            unsafe { buffer.manually_drop(index) };
+        }
+        assert_eq!(buffer.next_filled_run(0), None);
+    }
+
+    #[test]
+    fn the_bitmap_limits_the_capacity() {
+        let mut buffer = SlabBuffer::<InlineBuffer<u32, 8>, InlineBuffer<usize, 1>>::new();
+        assert_eq!(buffer.capacity(), 0);
+        // SAFETY: 3 > 0.
+        unsafe { buffer.try_grow(3) }.unwrap();
+        assert_eq!(buffer.capacity(), 8);
+
+        let mut buffer = SlabBuffer::<HeapBuffer<u32>, InlineBuffer<usize, 1>>::new();
+        // SAFETY: It's bigger than the capacity.
+        let result = unsafe { buffer.try_grow(BITS + 1) };
+        assert!(matches!(result, Err(ResizeError::UnsupportedOperation)));
+        assert_eq!(buffer.capacity(), 0);
+    }
+
+    #[test]
+    fn the_capacity_stays_the_same_when_growing_fails() {
+        let faulty = FaultyBuffer::from(HeapBuffer::<u32>::new())
+            .failing_grow_beyond(BITS, ResizeError::OutOfMemory);
+        let mut buffer = SlabBuffer::<_, HeapBuffer<usize>>::from_parts(faulty, HeapBuffer::new());
+        // SAFETY: It's bigger than the capacity.
+        unsafe { buffer.try_grow(BITS) }.unwrap();
+
+        // SAFETY: It's bigger than the capacity.
+        assert!(unsafe { buffer.try_grow(BITS * 2) }.is_err());
+        assert_eq!(buffer.capacity(), BITS);
+        assert_eq!(buffer.bitmap().len(), 1);
+    }
+
+    #[test]
+    fn shrinking_drops_the_words_it_no_longer_needs() {
+        let mut buffer = SlabBuffer::<HeapBuffer<u32>, HeapBuffer<usize>>::new();
+        // SAFETY: 200 > 0.
+        unsafe { buffer.try_grow(200) }.unwrap();
+        // SAFETY: It's valid and empty.
+        unsafe { buffer.put(10, 10) };
+
+        // SAFETY: 20 < 200 and the positions after it are empty.
+        unsafe { buffer.try_shrink(20) }.unwrap();
+        assert_eq!(buffer.capacity(), 20);
+        assert_eq!(buffer.bitmap.capacity(), 1);
+        assert_eq!(filled(&buffer), [10]);
+        // SAFETY: It's filled.
+        unsafe { buffer.manually_drop(10) };
+    }
+}
@@ -1,22 +1,117 @@
-use std::marker::PhantomData;
+use std::{
+    marker::PhantomData,
+    mem::{self, ManuallyDrop, MaybeUninit},
+    ops::{Index, IndexMut, Range, RangeBounds},
+    ptr::NonNull,
+};
 
 use crate::{
+    base_buffers::{
+        external::ExternFree, ExternBuffer, HeapBuffer, InlineBuffer, SharedSliceBuffer,
+        SliceBuffer,
+    },
+    composites::{
+        cast::{CastError, Pod},
+        CastBuffer, SnapshotBuffer, SvoBuffer, ZstoBuffer,
+    },
     interface::{
-        contiguous_memory::ContiguousMemoryBuffer, ptrs::PtrBuffer, refs::RefBuffer,
-        resize_error::ResizeError, Buffer,
+        buffer::normalize_range, contiguous_memory::ContiguousMemoryBuffer,
+        copy_value::CopyValueBuffer, growable::GrowableBuffer, out_of_line::OutOfLineBuffer,
+        ptrs::PtrBuffer, refs::RefBuffer, resize_error::ResizeError, shrinkable::ShrinkableBuffer,
+        transparent::TransparentBuffer, Buffer, BufferCaps, CapacityContract,
     },
     DefaultBuffer,
 };
 
+#[cfg(feature = "array")]
+use crate::composites::ArrayBuffer;
+
+use super::raw_vec::RawVector;
+
 /// Implementation of a vector but using a [`Buffer`].
 ///
 /// This structure mimics the [`Vec`] interface.
+///
+/// It only adds its length to the buffer, so the layout depends on it. For
+/// example `Vector<T, HeapBuffer<T>>` is exactly as big as `Vec<T>` and, like
+/// it, `Option<Vector<T, HeapBuffer<T>>>` doesn't take any extra space.
+///
+/// As long as its buffer is, it's covariant in `T` and it's [`Send`] and
+/// [`Sync`] only when `T` is (like [`Vec`]):
+/// ```compile_fail
+/// # use buffers::collections::Vector;
+/// fn assert_send<T: Send>(_: T) {}
+/// assert_send(Vector::<std::rc::Rc<u32>>::new());
+/// ```
+/// ```compile_fail
+/// # use buffers::collections::Vector;
+/// fn assert_sync<T: Sync>(_: T) {}
+/// assert_sync(Vector::<std::cell::Cell<u32>>::new());
+/// ```
+///
+/// There are no `unsafe impl`s for them: they are derived from both `T` and
+/// the buffer, so a buffer can't make the vector [`Send`] when its elements
+/// aren't (even if the buffer itself is):
+/// ```compile_fail
+/// # use std::{marker::PhantomData, rc::Rc};
+/// # use buffers::{collections::Vector, interface::{Buffer, ResizeError}};
+/// struct EmptyBuffer<T>(PhantomData<fn() -> T>);
+///
+/// impl<T> Buffer for EmptyBuffer<T> {
+///     type Element = T;
+///
+///     fn capacity(&self) -> usize {
+///         0
+///     }
+///     unsafe fn take(&mut self, _index: usize) -> T {
+///         unreachable!()
+///     }
+///     unsafe fn put(&mut self, _index: usize, _value: T) {
+///         unreachable!()
+///     }
+///     unsafe fn try_grow(&mut self, _target: usize) -> Result<(), ResizeError> {
+///         Err(ResizeError::UnsupportedOperation)
+///     }
+///     unsafe fn try_shrink(&mut self, _target: usize) -> Result<(), ResizeError> {
+///         Err(ResizeError::UnsupportedOperation)
+///     }
+/// }
+///
+/// fn assert_send<T: Send>(_: T) {}
+/// assert_send(EmptyBuffer::<Rc<u32>>(PhantomData));
+/// assert_send(Vector::from_buffer(EmptyBuffer::<Rc<u32>>(PhantomData)));
+/// ```
+///
+/// Dropping the vector drops its elements through the buffer, so the memory
+/// the buffer borrows must outlive it:
+/// ```compile_fail
+/// # use std::mem::MaybeUninit;
+/// # use buffers::{base_buffers::SliceBuffer, collections::Vector};
+/// let mut vec;
+/// let mut storage = [const { MaybeUninit::<String>::uninit() }; 2];
+/// vec = Vector::from_buffer(SliceBuffer::from_slice(&mut storage));
+/// vec.push(String::from("dropped after its storage"));
+/// ```
+///
+/// # Limitations
+/// It doesn't have the same drop check as [`Vec`]: values borrowed by the
+/// elements must outlive the vector too, even if the elements don't use them
+/// when dropped (which `Vec` allows). Matching it would mean letting the
+/// buffer dangle while it drops the elements, which isn't sound as long as
+/// [`Buffer`] is a safe trait (any implementation could use what it borrows).
 pub struct Vector<T, B: Buffer<Element = T> = DefaultBuffer<T>> {
-    len: usize,
-    buffer: B,
+    raw: RawVector<B>,
     _m: PhantomData<T>,
 }
 
+/// Vector with space for `N` elements inline. It never allocates, so it cannot
+/// grow past `N`.
+pub type FixedVector<T, const N: usize> = Vector<T, InlineBuffer<T, N>>;
+
+/// Vector which keeps up to `N` elements inline, and moves them to the heap
+/// when it needs more space (small vector optimization).
+pub type SmallVector<T, const N: usize> = Vector<T, ZstoBuffer<SvoBuffer<N, HeapBuffer<T>>>>;
+
 impl<T, B: Buffer<Element = T>> Vector<T, B> {
     /// Create a new vector using the given buffer.
     ///
@@ -27,8 +122,7 @@ impl<T, B: Buffer<Element = T>> Vector<T, B> {
     /// ```
     pub fn from_buffer(buffer: B) -> Vector<T, B> {
         Vector {
-            len: 0,
-            buffer,
+            raw: RawVector::from_buffer(buffer),
             _m: PhantomData,
         }
     }
@@ -42,8 +136,8 @@ impl<T, B: Buffer<Element = T>> Vector<T, B> {
     /// let vec = Vector::<_, ExampleBuffer>::new();
     /// assert_eq!(vec.len(), 0);
     /// ```
-    pub fn len(&self) -> usize {
-        self.len
+    pub const fn len(&self) -> usize {
+        self.raw.len()
     }
 
     /// Returns of the element is empty (doesn't have any elements).
@@ -55,8 +149,8 @@ impl<T, B: Buffer<Element = T>> Vector<T, B> {
     /// let vec = Vector::<_, ExampleBuffer>::new();
     /// assert_eq!(vec.is_empty(), true);
     /// ```
-    pub fn is_empty(&self) -> bool {
-        self.len == 0
+    pub const fn is_empty(&self) -> bool {
+        self.raw.is_empty()
     }
 
     /// Queries the buffer for its capacity
@@ -68,52 +162,87 @@ impl<T, B: Buffer<Element = T>> Vector<T, B> {
     /// assert_eq!(vec.capacity(), 150);
     /// ```
     pub fn capacity(&self) -> usize {
-        self.buffer.capacity()
+        self.raw.capacity()
     }
 
-    /// Reserves capacity for at least `additional` more elements to be inserted.
-    /// It can request more memory in some cases, as this is meant to be optimized for
-    /// conscutive inserts.
-    ///
-    /// Note that some buffers (like `InlineBuffer`) can't really grow.
-    ///
-    /// # Panics
-    /// Panics if it cannot grow
+    /// Returns how many more elements fit without growing the buffer.
     ///
     /// # Example
     /// ```
-    /// # use buffers::collections::Vector;
-    /// let mut vec = Vector::<u32>::new();
-    /// vec.reserve(150);
-    /// assert!(vec.capacity() >= 150);
+    /// # use buffers::{base_buffers::inline::InlineBuffer, collections::Vector};
+    /// let mut vec = Vector::<_, InlineBuffer::<u32, 4>>::new();
+    /// vec.push(1);
+    /// assert_eq!(vec.remaining_capacity(), 3);
     /// ```
-    pub fn reserve(&mut self, additional: usize) {
-        self.try_reserve(additional)
-            .expect("Couldn't reserve the necessary space")
+    pub fn remaining_capacity(&self) -> usize {
+        self.capacity() - self.len()
     }
 
-    /// Reserves capacity for at least `additional` more elements to be inserted.
+    /// Returns how many bytes the buffer uses to hold the elements (see
+    /// [`Buffer::allocated_bytes`]).
     ///
-    /// Note that unlike `reserve`, this will request exactly the additional size to the buffer.
+    /// # Example
+    /// ```
+    /// # use buffers::{base_buffers::HeapBuffer, collections::Vector};
+    /// let mut vec = Vector::<u32, HeapBuffer<_>>::new();
+    /// vec.reserve_exact(10);
+    /// assert_eq!(vec.allocated_bytes(), 40);
+    /// ```
+    pub fn allocated_bytes(&self) -> usize {
+        self.raw.buffer().allocated_bytes()
+    }
+
+    /// Returns the capabilities of the buffer right now (see
+    /// [`Buffer::caps`]).
     ///
-    /// # Panics
-    /// Panics if it cannot grow
+    /// # Example
+    /// ```
+    /// # use buffers::{base_buffers::{HeapBuffer, InlineBuffer}, collections::Vector};
+    /// # use buffers::interface::BufferCaps;
+    /// let heap = Vector::<u32, HeapBuffer<_>>::new();
+    /// assert!(heap.caps().contains(BufferCaps::GROW | BufferCaps::CONTIGUOUS));
+    /// let inline = Vector::<u32, InlineBuffer<_, 4>>::new();
+    /// assert_eq!(inline.caps(), BufferCaps::CONTIGUOUS);
+    /// ```
+    pub fn caps(&self) -> BufferCaps {
+        self.raw.buffer().caps()
+    }
+
+    /// What the capacity is after growing (see [`Buffer::CAPACITY_CONTRACT`]).
+    /// With [`CapacityContract::Exact`], [`Vector::try_reserve_exact`] never
+    /// leaves any surplus.
     ///
     /// # Example
     /// ```
-    /// # use buffers::collections::Vector;
-    /// let mut vec = Vector::<u32>::new();
-    /// vec.reserve_exact(150);
-    /// assert!(vec.capacity() >= 150);
+    /// # use buffers::{base_buffers::HeapBuffer, collections::Vector, interface::CapacityContract};
+    /// let mut vec = Vector::<u32, HeapBuffer<_>>::new();
+    /// assert_eq!(vec.capacity_contract(), CapacityContract::Exact);
+    /// vec.reserve_exact(3);
+    /// assert_eq!(vec.capacity(), 3);
     /// ```
-    pub fn reserve_exact(&mut self, additional: usize) {
-        self.try_reserve_exact(additional)
-            .expect("Couldn't reserve the necessary space")
+    pub const fn capacity_contract(&self) -> CapacityContract {
+        B::CAPACITY_CONTRACT
+    }
+
+    /// Changes the length of the vector, without dropping nor initializing
+    /// anything. Usually used after writing into
+    /// [`Vector::spare_capacity_mut`].
+    ///
+    /// # Safety
+    ///   * `new_len` must be less or equal to the capacity.
+    ///   * The positions `0..new_len` must be filled (and the ones after it
+    ///     must not have anything that needs to be dropped).
+    pub unsafe fn set_len(&mut self, new_len: usize) {
+        // SAFETY: Same requirements.
+        unsafe { self.raw.set_len(new_len) }
     }
 
     /// Tries reserves capacity for at least `additional` more elements to be inserted.
     ///
-    /// Note that unlike `try_reserve`, this will request exactly the additional size to the buffer.
+    /// It asks the buffer for the capacity it prefers (see
+    /// [`Buffer::preferred_grow_target`]), which may be more than needed. For
+    /// example, an [`crate::composites::ExponentialGrowthBuffer`] rounds it up
+    /// to a power of 2.
     ///
     /// # Examples
     /// Ok case:
@@ -133,13 +262,15 @@ impl<T, B: Buffer<Element = T>> Vector<T, B> {
     /// assert_eq!(result.is_err(), true);
     /// ```
     pub fn try_reserve(&mut self, additional: usize) -> Result<(), ResizeError> {
-        // TODO Grow exponentially
-        self.try_reserve_exact(additional)
+        self.raw.try_reserve(additional)
     }
 
     /// Tries reserves capacity for at least `additional` more elements to be inserted.
     ///
     /// Note that unlike `try_reserve`, this will request exactly the additional size to the buffer.
+    /// If it grows, the capacity follows [`Vector::capacity_contract`]: it's
+    /// exactly `len + additional` for [`CapacityContract::Exact`] buffers, but
+    /// composites may round it up.
     ///
     /// # Examples
     /// Ok case:
@@ -159,72 +290,44 @@ impl<T, B: Buffer<Element = T>> Vector<T, B> {
     /// assert_eq!(result.is_err(), true);
     /// ```
     pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), ResizeError> {
-        let target = self.len() + additional;
-        if target > self.capacity() {
-            // SAFETY: It's bigger than the current size
-            unsafe { self.buffer.try_grow(target) }
-        } else {
-            Ok(())
-        }
+        self.raw.try_reserve_exact(additional)
     }
 
-    /// Shrinks the capacity of the vector as much as possible.
+    /// Shortens the vector, keeping the first len elements and dropping the rest.
     ///
-    /// # Example
-    /// ```
-    /// # use buffers::base_buffers::heap::HeapBuffer;
-    /// # use buffers::collections::Vector;
-    /// let mut vec = Vector::<u32, HeapBuffer<_>>::new();
-    /// vec.reserve(10);
-    /// assert!(vec.capacity() >= 10);
+    /// If len is greater than the vector’s current length, this has no effect.
     ///
-    /// vec.shrink_to_fit();
-    /// assert_eq!(vec.capacity(), 0);
-    /// ```
-    pub fn shrink_to_fit(&mut self) {
-        self.shrink_to(self.len())
+    /// Note that this method has no effect on the allocated capacity of the vector.
+    pub fn truncate(&mut self, keep_n_first: usize) {
+        self.raw.truncate(keep_n_first)
     }
 
-    /// Hints the vector that it may shrink up to a lower bound.
+    /// Drops all the elements of the vector.
     ///
-    /// The capacity will remain at least as large as both the length and the supplied value.
+    /// All of them are dropped at once (using
+    /// [`Buffer::manually_drop_range`]). If dropping one of them panics, the
+    /// rest are still dropped and the vector ends up empty anyway.
     ///
-    /// If the current capacity is less than the lower limit, this is a no-op.
+    /// Note that this method has no effect on the capacity of the vector, so it
+    /// can be filled again without growing.
     ///
     /// # Example
     /// ```
-    /// # use buffers::base_buffers::heap::HeapBuffer;
     /// # use buffers::collections::Vector;
-    /// let mut vec = Vector::<u32, HeapBuffer<_>>::new();
-    /// vec.reserve(10);
-    /// assert!(vec.capacity() >= 10);
+    /// let mut vec = Vector::<u32>::new();
+    /// vec.push(1);
+    /// vec.push(2);
+    /// let capacity = vec.capacity();
     ///
-    /// vec.shrink_to(0);
-    /// assert_eq!(vec.capacity(), 0);
+    /// vec.clear();
+    ///
+    /// assert!(vec.is_empty());
+    /// assert_eq!(vec.capacity(), capacity);
     /// ```
-    pub fn shrink_to(&mut self, min_capacity: usize) {
-        let target = std::cmp::max(min_capacity, self.len());
-        if target < self.capacity() {
-            // SAFETY: it should get OOM but the buffer may not be able to shrink (eg. InlineBuffer)
-            // this still is considered successful in that case
-            let _ = unsafe { self.buffer.try_shrink(min_capacity) };
-        }
+    pub fn clear(&mut self) {
+        self.raw.truncate(0)
     }
 
-    /// Shortens the vector, keeping the first len elements and dropping the rest.
-    ///
-    /// If len is greater than the vector’s current length, this has no effect.
-    ///
-    /// Note that this method has no effect on the allocated capacity of the vector.
-    pub fn truncate(&mut self, keep_n_first: usize) {
-        if keep_n_first < self.len {
-            // SAFETY: the values from keep to len exist
-            unsafe {
-                self.buffer.manually_drop_range(keep_n_first..self.len);
-            }
-            self.len = keep_n_first
-        }
-    }
     /// Removes an element from the vector and returns it.
     ///
     /// The removed element is replaced by the last element of the vector.
@@ -253,24 +356,11 @@ impl<T, B: Buffer<Element = T>> Vector<T, B> {
     /// assert_eq!(*vec.index(2), 2);
     /// ```
     pub fn swap_remove(&mut self, index: usize) -> T {
-        if index >= self.len {
+        if index >= self.len() {
             panic!("Index out of bounds")
         }
-        self.len -= 1;
-
         // SAFETY: index is in bounds
-        let current = unsafe { self.buffer.take(index) };
-
-        // Move only when necessary
-        if self.len != index {
-            // SAFETY: `self.len` has been decreased but the position hasn't
-            // been emptied, yet.
-            let value = unsafe { self.buffer.take(self.len) };
-            // SAFETY: `index` was empties when reading to return the value.
-            unsafe { self.buffer.put(index, value) };
-        }
-
-        current
+        unsafe { self.raw.swap_remove_unchecked(index) }
     }
 
     /// Inserts an element at position `index` within the vector, shifting all elements after it to the right.
@@ -297,24 +387,14 @@ impl<T, B: Buffer<Element = T>> Vector<T, B> {
     /// assert_eq!(*vec.index(3), 2);
     /// ```
     pub fn insert(&mut self, index: usize, element: T) {
-        if index > self.len {
+        if index > self.len() {
             panic!("Index out of bounds")
         }
-
-        if self.len >= self.buffer.capacity() {
-            let new_target = self.len + 1;
-            // SAFETY: `new_target` > `self.len` >= `self.buffer.capacity()`
-            let resize_result = unsafe { self.buffer.try_grow(new_target) };
-            resize_result.expect("Cannot grow the buffer when trying to insert a new value")
-        }
-
-        // SAFETY: The conditional before ensured that there is an empty
-        // position at `self.len`.
-        unsafe { self.buffer.shift_right(index..self.len, 1) };
-        // SAFETY: After shifting index, that position is empty.
-        unsafe { self.buffer.put(index, element) };
-
-        self.len += 1;
+        self.raw
+            .try_reserve_exact(1)
+            .expect("Cannot grow the buffer when trying to insert a new value");
+        // SAFETY: `index` is in bounds and there is space for one more.
+        unsafe { self.raw.insert_unchecked(index, element) }
     }
 
     /// Removes and returns the element at position `index` within the vector,
@@ -342,20 +422,114 @@ impl<T, B: Buffer<Element = T>> Vector<T, B> {
     /// assert_eq!(*vec.index(2), 3);
     /// ```
     pub fn remove(&mut self, index: usize) -> T {
-        if index >= self.len {
+        if index >= self.len() {
+            panic!("Index out of bounds")
+        }
+        // SAFETY: index is in bounds
+        unsafe { self.raw.remove_unchecked(index) }
+    }
+
+    /// Same as [`Vector::swap_remove`], but it returns `None` instead of
+    /// panicking when `index` is out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32>::new();
+    /// vec.push(0);
+    /// vec.push(1);
+    ///
+    /// assert_eq!(vec.swap_remove_if_present(0), Some(0));
+    /// assert_eq!(vec.swap_remove_if_present(1), None);
+    /// ```
+    pub fn swap_remove_if_present(&mut self, index: usize) -> Option<T> {
+        if index < self.len() {
+            // SAFETY: index is in bounds
+            Some(unsafe { self.raw.swap_remove_unchecked(index) })
+        } else {
+            None
+        }
+    }
+
+    /// Same as [`Vector::remove`], but it returns `None` instead of panicking
+    /// when `index` is out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32>::new();
+    /// vec.push(0);
+    /// vec.push(1);
+    ///
+    /// assert_eq!(vec.remove_if_present(0), Some(0));
+    /// assert_eq!(vec.remove_if_present(1), None);
+    /// ```
+    pub fn remove_if_present(&mut self, index: usize) -> Option<T> {
+        if index < self.len() {
+            // SAFETY: index is in bounds
+            Some(unsafe { self.raw.remove_unchecked(index) })
+        } else {
+            None
+        }
+    }
+
+    /// Drops the element at `index` in place and replaces it with the last
+    /// element of the vector.
+    ///
+    /// Like [`Vector::swap_remove`] it's O(1) and doesn't preserve ordering,
+    /// but the element is never moved out of the buffer (which avoids copying
+    /// big elements that are only meant to be deleted).
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32>::new();
+    /// for i in 0..4 {
+    ///     vec.push(i);
+    /// }
+    ///
+    /// vec.swap_drop(1);
+    ///
+    /// assert_eq!(vec.iter_copied().collect::<Vec<_>>(), [0, 3, 2]);
+    /// ```
+    pub fn swap_drop(&mut self, index: usize) {
+        if index >= self.len() {
             panic!("Index out of bounds")
         }
+        // SAFETY: index is in bounds
+        unsafe { self.raw.swap_drop_unchecked(index) }
+    }
 
-        // SAFETY: `0..self.len` is valid. 0 < `index` < `self.len`, so it's
-        // valid.
-        let result = unsafe { self.buffer.take(index) };
-        // SAFETY: We remove a single element (`index`). `(index + 1)..self.len`
-        // are valid and can be shifted by 1 (position `index` is now empty).
-        unsafe {
-            self.buffer.shift_left((index + 1)..self.len, 1);
+    /// Drops the element at `index` in place and shifts all elements after it
+    /// to the left.
+    ///
+    /// Like [`Vector::remove`] it preserves the order, but the element is
+    /// never moved out of the buffer.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32>::new();
+    /// for i in 0..4 {
+    ///     vec.push(i);
+    /// }
+    ///
+    /// vec.drop_at(1);
+    ///
+    /// assert_eq!(vec.iter_copied().collect::<Vec<_>>(), [0, 2, 3]);
+    /// ```
+    pub fn drop_at(&mut self, index: usize) {
+        if index >= self.len() {
+            panic!("Index out of bounds")
         }
-        self.len -= 1;
-        result
+        // SAFETY: index is in bounds
+        unsafe { self.raw.drop_unchecked(index) }
     }
 
     /// Tries to add a value at the end of the vector. This may fail if there is not enough
@@ -369,18 +543,9 @@ impl<T, B: Buffer<Element = T>> Vector<T, B> {
     /// let length = vec.len(); // Length is 1
     /// # assert_eq!(length, 1);
     /// ```
+    #[inline]
     pub fn try_push(&mut self, value: T) -> Result<usize, ResizeError> {
-        let index = self.len;
-        if index >= self.buffer.capacity() {
-            // SAFETY: conditional checks precondition.
-            unsafe {
-                self.buffer.try_grow(self.len + 1)?;
-            }
-        }
-        // SAFETY: we know this value is unused because of `self.len`
-        unsafe { self.buffer.put(index, value) };
-        self.len += 1;
-        Ok(index)
+        self.raw.try_push(value)
     }
 
     /// Adds a value at the end of the vector. Panics if it cannot.
@@ -393,6 +558,7 @@ impl<T, B: Buffer<Element = T>> Vector<T, B> {
     /// let length = vec.len(); // Length is 1
     /// # assert_eq!(length, 1);
     /// ```
+    #[inline]
     pub fn push(&mut self, value: T) -> usize {
         self.try_push(value)
             .expect("Should push while having space")
@@ -409,129 +575,3312 @@ impl<T, B: Buffer<Element = T>> Vector<T, B> {
     /// # assert_eq!(value, 123);
     /// ```
     pub fn pop(&mut self) -> Option<T> {
-        if self.len > 0 {
-            self.len -= 1;
-            // SAFETY: self.len-1 is the last element, which we are poping
-            let value = unsafe { self.buffer.take(self.len) };
-            Some(value)
-        } else {
-            None
-        }
+        self.raw.pop()
     }
-}
 
-impl<T, B> Vector<T, B>
-where
-    B: Buffer<Element = T> + Default,
-{
-    /// Creates a new vector by default-constructing the underlying buffer.
+    /// Removes the elements in `range` from the vector, returning them in an
+    /// iterator. Elements after the range are moved back when the iterator is
+    /// dropped, even if not all elements were consumed (the rest get dropped).
     ///
-    /// # Example
+    /// # Panics
+    /// Panics if the range is out of bounds or decreasing.
     ///
+    /// # Example
     /// ```
     /// # use buffers::collections::Vector;
-    /// let _vec = Vector::<u32>::new();
+    /// let mut vec = Vector::<u32>::new();
+    /// for i in 0..5 {
+    ///     vec.push(i);
+    /// }
+    ///
+    /// let drained: Vec<u32> = vec.drain(1..3).collect();
+    ///
+    /// assert_eq!(drained, [1, 2]);
+    /// assert_eq!(vec.iter_copied().collect::<Vec<_>>(), [0, 3, 4]);
     /// ```
-    pub fn new() -> Vector<T, B> {
-        Self::from_buffer(Default::default())
+    pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<'_, T, B> {
+        let Range { start, end } = normalize_range(range, self.len());
+        if start > end || end > self.len() {
+            panic!("Drain range out of bounds")
+        }
+
+        let tail_len = self.len() - end;
+        // Until the drain is dropped, only the elements before the range are
+        // considered part of the vector. Forgetting the drain just leaks.
+        // SAFETY: `0..start` stays filled, and the drain owns the rest.
+        unsafe { self.raw.set_len(start) };
+        Drain {
+            vec: self,
+            front: start,
+            back: end,
+            tail_start: end,
+            tail_len,
+        }
     }
-}
 
-impl<T, B> Vector<T, B>
-where
-    B: Buffer<Element = T> + PtrBuffer,
-{
-    /// Returns an unsafe pointer to the start of the vector's buffer
-    pub fn as_ptr(&self) -> B::ConstantPointer {
-        // SAFETY: even if empty, the (unsafe) pointer is corrent
-        unsafe { self.buffer.ptr(0) }
+    /// Keeps only the elements for which `f` returns `true`, keeping their
+    /// order.
+    ///
+    /// If `f` panics, no more elements are removed and the vector is left in a
+    /// consistent state.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32>::new();
+    /// for i in 0..6 {
+    ///     vec.push(i);
+    /// }
+    ///
+    /// vec.retain(|x| x % 2 == 0);
+    ///
+    /// assert_eq!(vec.iter_copied().collect::<Vec<_>>(), [0, 2, 4]);
+    /// ```
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        let mut compactor = Compactor::new(self);
+        while let Some(current) = compactor.next() {
+            if f(current) {
+                compactor.keep_current();
+            } else {
+                compactor.drop_current();
+            }
+        }
     }
 
-    /// Returns an unsafe mutable pointer to the start of the vector's buffer
-    pub fn as_mut_ptr(&mut self) -> B::MutablePointer {
-        // SAFETY: even if empty, the (unsafe) pointer is corrent
-        unsafe { self.buffer.mut_ptr(0) }
+    /// Removes the elements for which `pred` returns `true`, returning them in
+    /// a lazy iterator. The rest keep their order.
+    ///
+    /// Elements are only checked (and removed) as the iterator advances. If
+    /// it's dropped early, the elements that weren't checked stay in the
+    /// vector. If `pred` panics, the element being checked stays too.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32>::new();
+    /// for i in 0..6 {
+    ///     vec.push(i);
+    /// }
+    ///
+    /// let evens: Vec<u32> = vec.extract_if(|x| *x % 2 == 0).collect();
+    ///
+    /// assert_eq!(evens, [0, 2, 4]);
+    /// assert_eq!(vec.iter_copied().collect::<Vec<_>>(), [1, 3, 5]);
+    /// ```
+    pub fn extract_if<F: FnMut(&mut T) -> bool>(&mut self, pred: F) -> ExtractIf<'_, T, B, F> {
+        ExtractIf {
+            compactor: Compactor::new(self),
+            pred,
+        }
     }
-}
 
-impl<T, B> Vector<T, B>
-where
-    B: Buffer<Element = T> + RefBuffer,
-{
-    /// Get a reference to the element in index
+    /// Removes consecutive elements for which `same_bucket` returns `true`.
+    /// It's called with the element being checked and the previous element
+    /// that was kept (in that order).
     ///
-    /// # Safety
-    /// index < self.len()
-    pub fn index(&self, index: usize) -> B::ConstantReference<'_> {
-        debug_assert!(index < self.len());
-        // SAFETY: values up to len exist
-        unsafe { self.buffer.index(index) }
+    /// If `same_bucket` panics, no more elements are removed and the vector is
+    /// left in a consistent state.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32>::new();
+    /// for i in [1, 2, 4, 7, 8] {
+    ///     vec.push(i);
+    /// }
+    ///
+    /// vec.dedup_by(|a, b| *a - *b == 1);
+    ///
+    /// assert_eq!(vec.iter_copied().collect::<Vec<_>>(), [1, 4, 7]);
+    /// ```
+    pub fn dedup_by<F: FnMut(&mut T, &mut T) -> bool>(&mut self, mut same_bucket: F) {
+        let mut compactor = Compactor::new(self);
+        while let Some((current, previous)) = compactor.next_with_previous() {
+            if same_bucket(current, previous) {
+                compactor.drop_current();
+            } else {
+                compactor.keep_current();
+            }
+        }
     }
 
-    /// Get a mutable reference to the element in index
+    /// Removes consecutive elements that resolve to the same key.
     ///
-    /// # Safety
-    /// index < self.len()
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32>::new();
+    /// for i in [10, 11, 20, 21, 22, 10] {
+    ///     vec.push(i);
+    /// }
+    ///
+    /// vec.dedup_by_key(|x| *x / 10);
+    ///
+    /// assert_eq!(vec.iter_copied().collect::<Vec<_>>(), [10, 20, 10]);
+    /// ```
+    pub fn dedup_by_key<K: PartialEq, F: FnMut(&mut T) -> K>(&mut self, mut key: F) {
+        self.dedup_by(|a, b| key(a) == key(b))
+    }
+
+    /// Removes consecutive repeated elements.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32>::new();
+    /// for i in [1, 1, 2, 3, 3, 3, 1] {
+    ///     vec.push(i);
+    /// }
+    ///
+    /// vec.dedup();
+    ///
+    /// assert_eq!(vec.iter_copied().collect::<Vec<_>>(), [1, 2, 3, 1]);
+    /// ```
+    pub fn dedup(&mut self)
+    where
+        T: PartialEq,
+    {
+        self.dedup_by(|a, b| a == b)
+    }
+
+    /// Clones and appends all the elements of `other` at the end of the vector.
+    ///
+    /// If cloning an element panics, the elements cloned so far stay in the
+    /// vector.
+    ///
+    /// # Panics
+    /// Panics if it cannot grow.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32>::new();
+    /// vec.push(1);
+    ///
+    /// vec.extend_from_slice(&[2, 3]);
+    ///
+    /// assert_eq!(vec.iter_copied().collect::<Vec<_>>(), [1, 2, 3]);
+    /// ```
+    pub fn extend_from_slice(&mut self, other: &[T])
+    where
+        T: Clone,
+    {
+        self.try_reserve(other.len())
+            .expect("Couldn't reserve the necessary space");
+        for value in other {
+            // The length is updated on each push, so it's always consistent.
+            self.push(value.clone());
+        }
+    }
+
+    /// Tries to append all the values of `iter`.
+    ///
+    /// When it runs out of space, it asks for room for what the iterator
+    /// says it has left (its [`Iterator::size_hint`]) but settles for less,
+    /// even a single value (see [`Buffer::try_grow_at_least`]). So iterators
+    /// of unknown length still fit as long as there is memory for them.
+    ///
+    /// If it cannot grow, it returns the error. The values appended so far
+    /// stay in the vector and the rest (including the one that didn't fit)
+    /// are dropped.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::FixedVector;
+    /// let mut vec = FixedVector::<u32, 4>::new();
+    /// vec.try_extend([1, 2, 3]).ok().unwrap();
+    /// assert!(vec.try_extend([4, 5]).is_err());
+    /// assert_eq!(vec.as_slice(), [1, 2, 3, 4]);
+    /// ```
+    pub fn try_extend<I: IntoIterator<Item = T>>(&mut self, iter: I) -> Result<(), ResizeError> {
+        let mut iter = iter.into_iter();
+        while let Some(value) = iter.next() {
+            if self.len() == self.capacity() {
+                let (remaining, _) = iter.size_hint();
+                self.raw
+                    .try_reserve_at_least(1, remaining.saturating_add(1))?;
+            }
+            // SAFETY: There is space for one more.
+            unsafe { self.raw.push_unchecked(value) };
+        }
+        Ok(())
+    }
+
+    /// Appends all the elements of `iter`, reserving space for them only
+    /// once (with its exact length) and writing them in place.
+    ///
+    /// It's all or nothing: if it cannot grow, it returns the error and
+    /// nothing is appended; if iterating panics, the elements written so far
+    /// are dropped before unwinding. An iterator which reports the wrong
+    /// length isn't unsound: at most that many elements are taken, and
+    /// fewer are appended if it ends early.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::FixedVector;
+    /// let mut vec = FixedVector::<u32, 4>::new();
+    /// vec.try_fill_from_exact([1, 2, 3]).ok().unwrap();
+    /// assert!(vec.try_fill_from_exact([4, 5]).is_err());
+    /// assert_eq!(vec.as_slice(), [1, 2, 3]);
+    /// ```
+    pub fn try_fill_from_exact<I>(&mut self, iter: I) -> Result<(), ResizeError>
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        /// Drops the elements appended after `start` unless it's forgotten.
+        struct Rollback<'r, B: Buffer> {
+            raw: &'r mut RawVector<B>,
+            start: usize,
+        }
+
+        impl<B: Buffer> Drop for Rollback<'_, B> {
+            fn drop(&mut self) {
+                self.raw.truncate(self.start);
+            }
+        }
+
+        let iter = iter.into_iter();
+        let additional = iter.len();
+        self.raw.try_reserve_exact(additional)?;
+        let start = self.len();
+        let guard = Rollback {
+            raw: &mut self.raw,
+            start,
+        };
+        for value in iter.take(additional) {
+            // SAFETY: It reserved space for `additional` more elements and
+            // at most that many are taken.
+            unsafe { guard.raw.push_unchecked(value) };
+        }
+        std::mem::forget(guard);
+        Ok(())
+    }
+
+    /// Changes the length to `new_len`, either dropping the last elements or
+    /// appending clones of `value` (filled all at once with
+    /// [`Buffer::fill_range`]).
+    ///
+    /// If it cannot grow, it returns the error and nothing changes. If cloning
+    /// panics, nothing is appended.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::FixedVector;
+    /// let mut vec = FixedVector::<u8, 4>::new();
+    /// vec.push(1);
+    ///
+    /// vec.try_resize(3, 0).ok().unwrap();
+    /// assert_eq!(vec.as_slice(), [1, 0, 0]);
+    ///
+    /// assert!(vec.try_resize(5, 0).is_err());
+    /// assert_eq!(vec.len(), 3);
+    /// ```
+    pub fn try_resize(&mut self, new_len: usize, value: T) -> Result<(), ResizeError>
+    where
+        T: Clone,
+    {
+        let len = self.len();
+        if new_len <= len {
+            self.truncate(new_len);
+            return Ok(());
+        }
+        self.try_reserve(new_len - len)?;
+        // SAFETY: It reserved space for `new_len` elements.
+        unsafe { self.raw.fill_unchecked(new_len - 1, &value) };
+        // The last one doesn't need a clone.
+        // SAFETY: Same as before.
+        unsafe { self.raw.push_unchecked(value) };
+        Ok(())
+    }
+
+    /// Same as [`Vector::try_resize`] but it panics if it cannot grow.
+    ///
+    /// # Panics
+    /// Panics if it cannot grow.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<String>::new();
+    /// vec.resize(2, "a".to_string());
+    /// assert_eq!(vec.as_slice(), ["a", "a"]);
+    ///
+    /// vec.resize(1, "b".to_string());
+    /// assert_eq!(vec.as_slice(), ["a"]);
+    /// ```
+    pub fn resize(&mut self, new_len: usize, value: T)
+    where
+        T: Clone,
+    {
+        self.try_resize(new_len, value)
+            .expect("Couldn't reserve the necessary space")
+    }
+
+    /// Iterates over all the overlapping views of `size` consecutive
+    /// elements, in order. There are none if the vector is shorter than
+    /// `size`.
+    ///
+    /// Unlike slices, it works on any buffer. See [`View`].
+    ///
+    /// # Panics
+    /// Panics if `size` is 0.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32>::new();
+    /// for i in [1, 2, 4, 8] {
+    ///     vec.push(i);
+    /// }
+    ///
+    /// let sums: Vec<u32> = vec.windows(2).map(|w| w.iter().sum()).collect();
+    ///
+    /// assert_eq!(sums, [3, 6, 12]);
+    /// ```
+    pub fn windows(&self, size: usize) -> Windows<'_, T, B> {
+        assert!(size != 0, "Window size must be non-zero");
+        Windows {
+            vec: self,
+            start: 0,
+            size,
+        }
+    }
+
+    /// Iterates over views of `size` consecutive elements that don't overlap,
+    /// in order. The last one is shorter if the length isn't divisible by
+    /// `size`.
+    ///
+    /// Unlike slices, it works on any buffer. See [`View`].
+    ///
+    /// # Panics
+    /// Panics if `size` is 0.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32>::new();
+    /// for i in 0..5 {
+    ///     vec.push(i);
+    /// }
+    ///
+    /// let chunks: Vec<_> = vec.chunks(2).map(|c| c.range()).collect();
+    ///
+    /// assert_eq!(chunks, [0..2, 2..4, 4..5]);
+    /// ```
+    pub fn chunks(&self, size: usize) -> Chunks<'_, T, B> {
+        assert!(size != 0, "Chunk size must be non-zero");
+        Chunks {
+            vec: self,
+            start: 0,
+            size,
+        }
+    }
+
+    /// Moves the elements into a new vector that uses another kind of buffer
+    /// (eg. to promote an inline vector to the heap). The old buffer is
+    /// released.
+    ///
+    /// Elements are moved one by one. For contiguous buffers
+    /// [`Vector::rebuffer_contiguous`] copies them all at once.
+    ///
+    /// # Panics
+    /// Panics if the new buffer cannot hold all the elements (the elements are
+    /// dropped with the vector).
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::{base_buffers::{HeapBuffer, InlineBuffer}, collections::Vector};
+    /// let mut vec = Vector::<String, InlineBuffer<String, 2>>::new();
+    /// vec.push("a".to_string());
+    /// vec.push("b".to_string());
+    ///
+    /// let mut vec: Vector<String, HeapBuffer<String>> = vec.rebuffer();
+    /// vec.push("c".to_string());
+    /// assert_eq!(vec.len(), 3);
+    /// ```
+    pub fn rebuffer<B2>(self) -> Vector<T, B2>
+    where
+        B2: Buffer<Element = T> + Default,
+    {
+        self.rebuffer_with(|source, target: &mut B2, len| {
+            for index in 0..len {
+                // SAFETY: The first `len` positions of the source are filled
+                // and the ones of the target are empty. Each one is moved once.
+                let value = unsafe { source.take(index) };
+                // SAFETY: Same as before.
+                unsafe { target.put(index, value) };
+            }
+        })
+    }
+
+    /// Moves the elements into an array if there are exactly `N` of them.
+    /// Otherwise the vector is given back unchanged.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<String>::new();
+    /// vec.push("x".to_string());
+    /// vec.push("y".to_string());
+    ///
+    /// let vec = vec.try_into_array::<3>().err().unwrap();
+    /// let [x, y] = vec.try_into_array().ok().unwrap();
+    /// assert_eq!((x.as_str(), y.as_str()), ("x", "y"));
+    /// ```
+    pub fn try_into_array<const N: usize>(mut self) -> Result<[T; N], Self> {
+        if self.len() != N {
+            return Err(self);
+        }
+
+        // The elements are moved out, so they must not be dropped with this
+        // vector (the buffer is still freed when it's dropped).
+        // SAFETY: 0 is always a valid length.
+        unsafe { self.set_len(0) };
+        // SAFETY: Moving values doesn't change which positions are filled for
+        // the vector: it's already empty.
+        let buffer = unsafe { self.raw.buffer_mut() };
+        Ok(std::array::from_fn(|index| {
+            // SAFETY: The first `N` positions are filled and each one is
+            // taken once.
+            unsafe { buffer.take(index) }
+        }))
+    }
+
+    /// Wraps the buffer in the layer `W` (eg. to change how it grows)
+    /// without moving the elements nor reallocating.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::{base_buffers::HeapBuffer, collections::Vector, composites::ExponentialGrowthBuffer};
+    /// let mut vec = Vector::<u32, HeapBuffer<u32>>::new();
+    /// vec.push(1);
+    /// let ptr = vec.as_ptr();
+    ///
+    /// let mut vec: Vector<u32, ExponentialGrowthBuffer<_>> = vec.wrap_buffer();
+    /// assert_eq!(vec.as_ptr(), ptr);
+    /// vec.reserve_exact(2);
+    /// assert_eq!(vec.capacity(), 4);
+    ///
+    /// let vec = vec.unwrap_buffer();
+    /// assert_eq!(vec.as_slice(), [1]);
+    /// ```
+    pub fn wrap_buffer<W>(self) -> Vector<T, W>
+    where
+        W: TransparentBuffer<Element = T, Inner = B>,
+    {
+        let len = self.len();
+        let buffer = W::from_inner(self.into_raw().into_buffer());
+        // SAFETY: The wrapper has the same filled positions as the buffer.
+        unsafe { Vector::from_parts(buffer, len) }
+    }
+
+    /// Internal utility of the rebuffering methods: makes a new vector with
+    /// space for all the elements and uses `move_all` to move them.
+    fn rebuffer_with<B2, F>(mut self, move_all: F) -> Vector<T, B2>
+    where
+        B2: Buffer<Element = T> + Default,
+        F: FnOnce(&mut B, &mut B2, usize),
+    {
+        let len = self.len();
+        let mut other = Vector::<T, B2>::new();
+        other
+            .try_reserve_exact(len)
+            .expect("The new buffer cannot hold all the elements");
+
+        // The elements are moved out, so they must not be dropped with this
+        // vector (even if moving them panics, which leaks them instead).
+        // SAFETY: 0 is always a valid length.
+        unsafe { self.set_len(0) };
+        // SAFETY: Moving values doesn't change which positions are filled for
+        // the vectors: they are updated below.
+        let source = unsafe { self.raw.buffer_mut() };
+        // SAFETY: Same as before.
+        let target = unsafe { other.raw.buffer_mut() };
+        move_all(source, target, len);
+        // SAFETY: The first `len` positions were just filled.
+        unsafe { other.set_len(len) };
+        other
+    }
+
+    /// Internal utility that panics (like slices do) when `index` is out of
+    /// bounds.
+    fn assert_in_bounds(&self, index: usize) {
+        let len = self.len();
+        assert!(
+            index < len,
+            "index out of bounds: the len is {len} but the index is {index}"
+        );
+    }
+
+    /// Internal utility to make a vector from a buffer which already holds
+    /// `len` elements.
+    ///
+    /// # Safety
+    /// Positions `0..len` of `buffer` must be filled and the rest empty.
+    const unsafe fn from_parts(buffer: B, len: usize) -> Self {
+        Vector {
+            // SAFETY: Same requirements.
+            raw: unsafe { RawVector::from_parts(buffer, len) },
+            _m: PhantomData,
+        }
+    }
+
+    /// Internal utility to move the raw vector out without dropping the
+    /// elements.
+    fn into_raw(self) -> RawVector<B> {
+        let vec = ManuallyDrop::new(self);
+        // SAFETY: `vec` is never used nor dropped again, so the raw vector is
+        // moved out of it.
+        unsafe { std::ptr::read(&vec.raw) }
+    }
+}
+
+impl<T, B> Vector<T, B>
+where
+    B: TransparentBuffer<Element = T>,
+{
+    /// Removes the layer added by [`Vector::wrap_buffer`], without moving the
+    /// elements nor reallocating.
+    pub fn unwrap_buffer(self) -> Vector<T, B::Inner> {
+        let len = self.len();
+        let buffer = self.into_raw().into_buffer().into_inner();
+        // SAFETY: The inner buffer has the same filled positions as the
+        // wrapper.
+        unsafe { Vector::from_parts(buffer, len) }
+    }
+}
+
+impl<T, B> Vector<T, B>
+where
+    B: Buffer<Element = T> + GrowableBuffer,
+{
+    /// Reserves capacity for at least `additional` more elements to be inserted.
+    /// It can request more memory in some cases, as this is meant to be optimized for
+    /// conscutive inserts.
+    ///
+    /// It's only available for buffers that can grow (see [`GrowableBuffer`]).
+    /// Use [`Vector::try_reserve`] for the rest.
+    ///
+    /// # Panics
+    /// Panics if it cannot grow (eg. it runs out of memory)
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32>::new();
+    /// vec.reserve(150);
+    /// assert!(vec.capacity() >= 150);
+    /// ```
+    ///
+    /// An inline buffer cannot grow, so it doesn't compile:
+    /// ```compile_fail
+    /// # use buffers::{base_buffers::InlineBuffer, collections::Vector};
+    /// let mut vec = Vector::<u32, InlineBuffer<u32, 10>>::new();
+    /// vec.reserve(150);
+    /// ```
+    pub fn reserve(&mut self, additional: usize) {
+        self.try_reserve(additional)
+            .expect("Couldn't reserve the necessary space")
+    }
+
+    /// Reserves capacity for at least `additional` more elements to be inserted.
+    ///
+    /// Note that unlike `reserve`, this will request exactly the additional size to the buffer.
+    ///
+    /// # Panics
+    /// Panics if it cannot grow (eg. it runs out of memory)
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32>::new();
+    /// vec.reserve_exact(150);
+    /// assert!(vec.capacity() >= 150);
+    /// ```
+    pub fn reserve_exact(&mut self, additional: usize) {
+        self.try_reserve_exact(additional)
+            .expect("Couldn't reserve the necessary space")
+    }
+}
+
+impl<T, B> Vector<T, B>
+where
+    B: Buffer<Element = T> + ShrinkableBuffer,
+{
+    /// Shrinks the capacity of the vector as much as possible.
+    ///
+    /// It's only available for buffers that can shrink (see
+    /// [`ShrinkableBuffer`]). Vectors using a fixed-size buffer have a no-op
+    /// version instead.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::base_buffers::heap::HeapBuffer;
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32, HeapBuffer<_>>::new();
+    /// vec.reserve(10);
+    /// assert!(vec.capacity() >= 10);
+    ///
+    /// vec.shrink_to_fit();
+    /// assert_eq!(vec.capacity(), 0);
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        self.shrink_to(self.len())
+    }
+
+    /// Hints the vector that it may shrink up to a lower bound.
+    ///
+    /// The capacity will remain at least as large as both the length and the supplied value.
+    ///
+    /// If the current capacity is less than the lower limit, this is a no-op.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::base_buffers::heap::HeapBuffer;
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32, HeapBuffer<_>>::new();
+    /// vec.reserve(10);
+    /// vec.push(1);
+    /// vec.push(2);
+    ///
+    /// vec.shrink_to(4);
+    /// assert_eq!(vec.capacity(), 4);
+    /// vec.shrink_to(0);
+    /// assert_eq!(vec.capacity(), 2);
+    /// ```
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        // Failing (eg. running out of memory while reallocating) keeps the old
+        // capacity, which is still valid.
+        let _ = self.raw.try_shrink_to(min_capacity);
+    }
+
+    /// Shrinks the capacity towards the length, giving back at most
+    /// `max_bytes_per_call` bytes (but at least one position). Returns
+    /// whether it should be called again.
+    ///
+    /// It's meant for huge vectors in latency-sensitive code: giving back a
+    /// lot of memory at once (eg. a big `munmap`) may take long, so it can be
+    /// spread over several calls instead. Each call may still reallocate,
+    /// depending on the buffer.
+    ///
+    /// If shrinking fails, the capacity stays the same and it returns
+    /// `false` (so a loop calling it always ends).
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::base_buffers::heap::HeapBuffer;
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32, HeapBuffer<_>>::new();
+    /// vec.reserve_exact(100);
+    /// vec.push(1);
+    ///
+    /// assert!(vec.shrink_to_fit_incremental(200));
+    /// assert_eq!(vec.capacity(), 50);
+    /// while vec.shrink_to_fit_incremental(200) {}
+    /// assert_eq!(vec.capacity(), 1);
+    /// ```
+    pub fn shrink_to_fit_incremental(&mut self, max_bytes_per_call: usize) -> bool {
+        let len = self.len();
+        let capacity = self.capacity();
+        if capacity <= len {
+            return false;
+        }
+        let size = self.raw.buffer().element_layout().size();
+        let step = max_bytes_per_call
+            .checked_div(size)
+            .unwrap_or(usize::MAX)
+            .max(1);
+        self.shrink_to(std::cmp::max(len, capacity.saturating_sub(step)));
+        let new_capacity = self.capacity();
+        new_capacity < capacity && new_capacity > len
+    }
+
+    /// Drops all the elements of the vector and gives back all of its
+    /// capacity. It's the same as [`Vector::clear`] followed by
+    /// [`Vector::shrink_to_fit`].
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::base_buffers::heap::HeapBuffer;
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32, HeapBuffer<_>>::new();
+    /// vec.push(1);
+    ///
+    /// vec.clear_and_shrink();
+    ///
+    /// assert!(vec.is_empty());
+    /// assert_eq!(vec.capacity(), 0);
+    /// ```
+    pub fn clear_and_shrink(&mut self) {
+        self.clear();
+        self.shrink_to_fit();
+    }
+}
+
+impl<T, const SIZE: usize> Vector<T, InlineBuffer<T, SIZE>> {
+    /// Makes a full vector with the values of `array` (moved inline, nothing
+    /// is copied one by one). It can be used in constants.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::{FixedVector, Vector};
+    /// const PRIMES: FixedVector<u32, 4> = Vector::from_array([2, 3, 5, 7]);
+    /// assert_eq!(PRIMES.as_slice(), [2, 3, 5, 7]);
+    /// ```
+    pub const fn from_array(array: [T; SIZE]) -> Self {
+        // SAFETY: All the positions of the buffer are filled.
+        unsafe { Self::from_parts(InlineBuffer::from_array(array), SIZE) }
+    }
+
+    /// Does nothing: an inline buffer always has the same capacity.
+    ///
+    /// It exists so code written for growable vectors also works with
+    /// fixed-size ones.
+    pub fn shrink_to_fit(&mut self) {}
+
+    /// Does nothing: an inline buffer always has the same capacity.
+    ///
+    /// It exists so code written for growable vectors also works with
+    /// fixed-size ones.
+    pub fn shrink_to(&mut self, _min_capacity: usize) {}
+
+    /// Does nothing (and returns `false`): an inline buffer always has the same capacity.
+    ///
+    /// It exists so code written for growable vectors also works with
+    /// fixed-size ones.
+    pub fn shrink_to_fit_incremental(&mut self, _max_bytes_per_call: usize) -> bool {
+        false
+    }
+
+    /// Appends all the values of `array`, in order.
+    ///
+    /// Arrays bigger than the whole vector are rejected at compile time.
+    /// Otherwise it only depends on the length, so it's checked when called.
+    ///
+    /// # Panics
+    /// Panics if there isn't space for all of them (none are added).
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::FixedVector;
+    /// let mut vec = FixedVector::<u32, 4>::new();
+    /// vec.push_array([1, 2]);
+    /// vec.push_array([3, 4]);
+    /// assert_eq!(vec.as_slice(), [1, 2, 3, 4]);
+    /// ```
+    ///
+    /// An array that could never fit doesn't compile:
+    /// ```compile_fail
+    /// # use buffers::collections::FixedVector;
+    /// let mut vec = FixedVector::<u32, 2>::new();
+    /// vec.push_array([1, 2, 3]);
+    /// ```
+    pub fn push_array<const M: usize>(&mut self, array: [T; M]) {
+        if self.try_push_array(array).is_err() {
+            panic!("There isn't space for {M} more elements");
+        }
+    }
+
+    /// Same as [`Vector::push_array`], but gives the array back if there isn't
+    /// space for all its values (instead of panicking).
+    pub fn try_push_array<const M: usize>(&mut self, array: [T; M]) -> Result<(), [T; M]> {
+        const {
+            assert!(M <= SIZE, "The array is bigger than the vector's capacity");
+        }
+        if M > SIZE - self.len() {
+            return Err(array);
+        }
+        for value in array {
+            // SAFETY: There is space for all the values.
+            unsafe { self.raw.push_unchecked(value) };
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T> Vector<T, SliceBuffer<'a, T>> {
+    /// Makes a vector on top of an initialized slice (eg. an existing array),
+    /// to edit it through the vector's interface. Its first `len` values are
+    /// the elements and the rest of the slice is its spare capacity.
+    ///
+    /// Whatever the vector does, the slice keeps holding the values that were
+    /// last written into each position.
+    ///
+    /// # Safety
+    /// Same as [`SliceBuffer::from_initialized_slice`]: uninitialized values
+    /// must never be written into the vector (eg. through
+    /// [`Vector::spare_capacity_mut`]).
+    ///
+    /// # Panics
+    /// Panics if `len` is greater than the length of the slice.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut array = [1, 2, 3, 0, 0];
+    /// // SAFETY: Nothing uninitialized is written.
+    /// let mut vec = unsafe { Vector::from_slice_storage(&mut array, 3) };
+    /// vec.insert(0, 9);
+    /// vec.remove(2);
+    /// assert_eq!(vec.len(), 3);
+    /// drop(vec);
+    /// assert_eq!(array[..3], [9, 1, 3]);
+    /// ```
+    pub unsafe fn from_slice_storage(slice: &'a mut [T], len: usize) -> Self
+    where
+        T: Copy,
+    {
+        assert!(
+            len <= slice.len(),
+            "The length ({len}) is greater than the slice ({})",
+            slice.len()
+        );
+        // SAFETY: The caller ensures that nothing uninitialized is written.
+        let buffer = unsafe { SliceBuffer::from_initialized_slice(slice) };
+        let mut vec = Self::from_buffer(buffer);
+        // SAFETY: All the positions are filled and `len` fits.
+        unsafe { vec.set_len(len) };
+        vec
+    }
+
+    /// Does nothing: a slice buffer always uses the whole slice it was given.
+    ///
+    /// It exists so code written for growable vectors also works with
+    /// fixed-size ones.
+    pub fn shrink_to_fit(&mut self) {}
+
+    /// Does nothing: a slice buffer always uses the whole slice it was given.
+    ///
+    /// It exists so code written for growable vectors also works with
+    /// fixed-size ones.
+    pub fn shrink_to(&mut self, _min_capacity: usize) {}
+
+    /// Does nothing (and returns `false`): a slice buffer always uses the whole slice it was given.
+    ///
+    /// It exists so code written for growable vectors also works with
+    /// fixed-size ones.
+    pub fn shrink_to_fit_incremental(&mut self, _max_bytes_per_call: usize) -> bool {
+        false
+    }
+}
+
+impl<'a, T> Vector<T, SharedSliceBuffer<'a, T>> {
+    /// Makes a read-only vector with all the values of a shared slice, to
+    /// use the vector's interface over borrowed data (eg. while parsing).
+    ///
+    /// Anything that reads works, and the vector can forget elements (eg.
+    /// with [`Vector::truncate`]), but anything that moves or writes them
+    /// panics (see [`SharedSliceBuffer`]).
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let input = [3u8, b'a', b'b', b'c', 0xff];
+    /// let mut vec = Vector::from_shared_slice(&input);
+    /// vec.truncate(4);
+    /// assert_eq!(vec.get_copy(0), Some(3));
+    /// assert_eq!(vec.as_slice(), b"\x03abc");
+    /// ```
+    pub fn from_shared_slice(slice: &'a [T]) -> Self {
+        let mut vec = Self::from_buffer(SharedSliceBuffer::new(slice));
+        // SAFETY: All the positions of a shared slice buffer are filled.
+        unsafe { vec.set_len(slice.len()) };
+        vec
+    }
+
+    /// Extracts a slice containing the entire vector.
+    pub fn as_slice(&self) -> &'a [T] {
+        &self.raw.buffer().as_slice()[..self.len()]
+    }
+}
+
+impl<T> Vector<T, ExternBuffer<T>> {
+    /// Makes a vector which owns memory allocated outside of Rust (eg. data
+    /// received from a C library), without copying it. Its first `len`
+    /// values are the elements and the memory is freed with `free` when the
+    /// vector is dropped (see [`ExternBuffer`]).
+    ///
+    /// # Safety
+    /// Same as [`ExternBuffer::from_raw_parts`]. Also, the first `len`
+    /// values must be initialized (and the vector owns them).
+    ///
+    /// # Panics
+    /// Panics if `len` is greater than `capacity`.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::{ffi::c_void, ptr::NonNull};
+    /// # use buffers::collections::Vector;
+    /// // Stands for the `free` function of a C library.
+    /// unsafe extern "C" fn free_bytes(ptr: *mut c_void) {
+    ///     // SAFETY: It was leaked from a box.
+    ///     drop(unsafe { Box::from_raw(ptr.cast::<[u8; 4]>()) });
+    /// }
+    ///
+    /// let data = NonNull::from(Box::leak(Box::new([1u8, 2, 3, 0])));
+    /// // SAFETY: It owns the 4 bytes (3 of them are elements) and
+    /// // `free_bytes` frees them.
+    /// let mut vec = unsafe { Vector::from_extern(data.cast::<u8>(), 3, 4, free_bytes) };
+    /// vec.push(4);
+    /// assert_eq!(vec.as_slice(), [1, 2, 3, 4]);
+    /// ```
+    pub unsafe fn from_extern(
+        ptr: NonNull<T>,
+        len: usize,
+        capacity: usize,
+        free: ExternFree,
+    ) -> Self {
+        assert!(len <= capacity, "The length is bigger than the capacity");
+        // SAFETY: The caller ensures the same requirements.
+        let buffer = unsafe { ExternBuffer::from_raw_parts(ptr, capacity, free) };
+        // SAFETY: The caller ensures that the first `len` positions are
+        // filled, and the rest are empty.
+        unsafe { Self::from_parts(buffer, len) }
+    }
+}
+
+impl<T, B, S> Vector<T, SnapshotBuffer<B, S>>
+where
+    B: Buffer<Element = T>,
+    S: Buffer<Element = T>,
+{
+    /// Saves a copy of the elements, to go back to them later with
+    /// [`Vector::rollback`]. It replaces the previous checkpoint (if any).
+    ///
+    /// # Panics
+    /// Panics if the snapshot buffer cannot hold all the elements.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::{base_buffers::HeapBuffer, collections::Vector, composites::SnapshotBuffer};
+    /// let mut vec = Vector::<u32, SnapshotBuffer<HeapBuffer<u32>>>::new();
+    /// vec.extend_from_slice(&[1, 2, 3]);
+    /// vec.checkpoint();
+    ///
+    /// vec.retain(|value| value % 2 == 0);
+    /// vec.push(4);
+    /// assert_eq!(vec.as_slice(), [2, 4]);
+    ///
+    /// assert!(vec.rollback());
+    /// assert_eq!(vec.as_slice(), [1, 2, 3]);
+    /// assert!(!vec.rollback()); // The checkpoint is used up.
+    /// ```
+    pub fn checkpoint(&mut self)
+    where
+        T: Clone,
+        B: ContiguousMemoryBuffer,
+    {
+        self.try_checkpoint()
+            .expect("Couldn't reserve the space for the checkpoint")
+    }
+
+    /// Same as [`Vector::checkpoint`], but returns the error instead of
+    /// panicking (there is no checkpoint then).
+    pub fn try_checkpoint(&mut self) -> Result<(), ResizeError>
+    where
+        T: Clone,
+        B: ContiguousMemoryBuffer,
+    {
+        let len = self.len();
+        // SAFETY: Saving a copy doesn't change which positions are filled.
+        let buffer = unsafe { self.raw.buffer_mut() };
+        // SAFETY: The first `len` positions are filled.
+        unsafe { buffer.checkpoint(len) }
+    }
+
+    /// Goes back to the elements of the last checkpoint, dropping the current
+    /// ones. Returns `false` (and does nothing) if there is no checkpoint.
+    ///
+    /// # Panics
+    /// Panics if the buffer has shrunk since the checkpoint and it cannot grow
+    /// back.
+    pub fn rollback(&mut self) -> bool {
+        self.try_rollback()
+            .expect("Couldn't grow the buffer back to roll back")
+    }
+
+    /// Same as [`Vector::rollback`], but returns the error instead of
+    /// panicking (nothing changes then).
+    pub fn try_rollback(&mut self) -> Result<bool, ResizeError> {
+        let len = self.len();
+        // Like truncating, the elements are forgotten before being dropped so
+        // they aren't dropped twice if one of them panics.
+        // SAFETY: Forgetting elements is always safe.
+        unsafe { self.set_len(0) };
+        // SAFETY: The length is updated right after.
+        let buffer = unsafe { self.raw.buffer_mut() };
+        // SAFETY: Only the first `len` positions are filled, and the vector
+        // doesn't consider them filled anymore.
+        let result = unsafe { buffer.rollback(len) };
+        let new_len = match result {
+            Ok(Some(new_len)) => new_len,
+            // Nothing changed.
+            Ok(None) | Err(_) => len,
+        };
+        // SAFETY: Either the rollback filled the first `new_len` positions
+        // with the saved values, or nothing changed.
+        unsafe { self.set_len(new_len) };
+        result.map(|saved| saved.is_some())
+    }
+
+    /// Whether there is a checkpoint to roll back to.
+    pub fn has_checkpoint(&self) -> bool {
+        self.raw.buffer().checkpoint_len().is_some()
+    }
+
+    /// Drops the values saved by the last checkpoint (if any).
+    pub fn discard_checkpoint(&mut self) {
+        // SAFETY: It doesn't change which positions are filled.
+        let buffer = unsafe { self.raw.buffer_mut() };
+        buffer.discard_checkpoint();
+    }
+}
+
+#[cfg(feature = "array")]
+impl<T, const SIZE: usize, B> Vector<[T; SIZE], ArrayBuffer<SIZE, B>>
+where
+    B: Buffer<Element = T> + ContiguousMemoryBuffer,
+{
+    /// Slices with each column (one per inner buffer of the
+    /// [`ArrayBuffer`]), with all the rows.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::{base_buffers::HeapBuffer, collections::Vector, composites::ArrayBuffer};
+    /// let mut points = Vector::<[f32; 2], ArrayBuffer<2, HeapBuffer<f32>>>::new();
+    /// points.push([1.0, 2.0]);
+    /// points.push([3.0, 4.0]);
+    ///
+    /// let [xs, ys] = points.columns();
+    /// assert_eq!(xs, [1.0, 3.0]);
+    /// assert_eq!(ys, [2.0, 4.0]);
+    /// ```
+    pub fn columns(&self) -> [&[T]; SIZE] {
+        // SAFETY: The first `len` positions are filled.
+        unsafe { self.raw.buffer().lanes(self.len()) }
+    }
+
+    /// Mutable version of [`Vector::columns`].
+    pub fn columns_mut(&mut self) -> [&mut [T]; SIZE] {
+        let len = self.len();
+        // SAFETY: The values are changed in place, so the positions stay
+        // filled.
+        let buffer = unsafe { self.raw.buffer_mut() };
+        // SAFETY: The first `len` positions are filled.
+        unsafe { buffer.lanes_mut(len) }
+    }
+
+    /// Iterates over the rows, with a reference to the value of each column
+    /// (zipping all the columns).
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::{base_buffers::HeapBuffer, collections::Vector, composites::ArrayBuffer};
+    /// let mut points = Vector::<[f32; 2], ArrayBuffer<2, HeapBuffer<f32>>>::new();
+    /// points.push([1.0, 2.0]);
+    /// points.push([3.0, 4.0]);
+    ///
+    /// let sums: Vec<f32> = points.rows().map(|[x, y]| x + y).collect();
+    /// assert_eq!(sums, [3.0, 7.0]);
+    /// ```
+    pub fn rows(&self) -> Rows<'_, T, SIZE> {
+        Rows {
+            columns: self.columns().map(|column| column.iter()),
+            remaining: self.len(),
+        }
+    }
+
+    /// Mutable version of [`Vector::rows`]. Each row borrows a different
+    /// value of every column, so all of them can be changed at once.
+    pub fn rows_mut(&mut self) -> RowsMut<'_, T, SIZE> {
+        let remaining = self.len();
+        RowsMut {
+            columns: self.columns_mut().map(|column| column.iter_mut()),
+            remaining,
+        }
+    }
+
+    /// Calls `f` with every row, as mutable references to the value of each
+    /// column. Same as `rows_mut().for_each(f)`.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::{base_buffers::HeapBuffer, collections::Vector, composites::ArrayBuffer};
+    /// let mut particles = Vector::<[f32; 2], ArrayBuffer<2, HeapBuffer<f32>>>::new();
+    /// particles.push([0.0, 1.5]);
+    /// particles.push([10.0, -2.0]);
+    ///
+    /// // Position += speed.
+    /// particles.for_each_row(|[position, speed]| *position += *speed);
+    ///
+    /// assert_eq!(particles.columns()[0], [1.5, 8.0]);
+    /// ```
+    pub fn for_each_row<F: FnMut([&mut T; SIZE])>(&mut self, f: F) {
+        self.rows_mut().for_each(f)
+    }
+
+    /// View with only some of the columns (in the given order), to work with
+    /// them without touching the rest.
+    ///
+    /// # Panics
+    /// Panics if a column doesn't exist.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::{base_buffers::HeapBuffer, collections::Vector, composites::ArrayBuffer};
+    /// let mut points = Vector::<[f32; 3], ArrayBuffer<3, HeapBuffer<f32>>>::new();
+    /// points.push([1.0, 2.0, 3.0]);
+    /// points.push([4.0, 5.0, 6.0]);
+    ///
+    /// let zx = points.project([2, 0]);
+    /// let rows: Vec<[&f32; 2]> = zx.rows().collect();
+    /// assert_eq!(rows, [[&3.0, &1.0], [&6.0, &4.0]]);
+    /// ```
+    pub fn project<const K: usize>(&self, columns: [usize; K]) -> Projection<'_, T, K> {
+        let all = self.columns();
+        Projection {
+            columns: columns.map(|column| {
+                assert!(column < SIZE, "There is no column {column}");
+                all[column]
+            }),
+            len: self.len(),
+        }
+    }
+
+    /// Mutable version of [`Vector::project`].
+    ///
+    /// # Panics
+    /// Panics if a column doesn't exist or if it's projected twice.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::{base_buffers::HeapBuffer, collections::Vector, composites::ArrayBuffer};
+    /// let mut points = Vector::<[f32; 3], ArrayBuffer<3, HeapBuffer<f32>>>::new();
+    /// points.push([1.0, 2.0, 3.0]);
+    ///
+    /// points.project_mut([2, 0]).for_each_row(|[z, x]| *x += *z);
+    /// assert_eq!(points.columns()[0], [4.0]);
+    /// ```
+    pub fn project_mut<const K: usize>(&mut self, columns: [usize; K]) -> ProjectionMut<'_, T, K> {
+        let len = self.len();
+        let mut all = self.columns_mut().map(Some);
+        ProjectionMut {
+            columns: columns.map(|column| {
+                assert!(column < SIZE, "There is no column {column}");
+                all[column]
+                    .take()
+                    .unwrap_or_else(|| panic!("Column {column} is projected twice"))
+            }),
+            len,
+        }
+    }
+
+    /// Moves the row at `index` to the end of `dst`, and returns where it is
+    /// now. Like [`Vector::swap_remove`], the last row takes its place.
+    ///
+    /// It's the core operation of archetype-based storage (eg. in an ECS):
+    /// moving an entity between two tables with the same columns.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds or if `dst` cannot grow. Either way,
+    /// nothing is moved.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::{base_buffers::HeapBuffer, collections::Vector, composites::ArrayBuffer};
+    /// type Table = Vector<[u32; 2], ArrayBuffer<2, HeapBuffer<u32>>>;
+    /// let mut awake = Table::new();
+    /// let mut asleep = Table::new();
+    /// awake.push([1, 10]);
+    /// awake.push([2, 20]);
+    ///
+    /// assert_eq!(awake.move_row(0, &mut asleep), 0);
+    /// assert_eq!(awake.columns(), [[2], [20]]);
+    /// assert_eq!(asleep.columns(), [[1], [10]]);
+    /// ```
+    pub fn move_row<D>(&mut self, index: usize, dst: &mut Vector<[T; SIZE], D>) -> usize
+    where
+        D: Buffer<Element = [T; SIZE]>,
+    {
+        self.transfer_matching_columns(index, dst, std::array::from_fn(Some), |_| {
+            unreachable!("All the columns are moved")
+        })
+    }
+
+    /// Moves the row at `index` to the end of `dst`, which may have other
+    /// columns, and returns where it is now. Like [`Vector::swap_remove`], the
+    /// last row takes its place.
+    ///
+    /// Column `j` of `dst` gets the value of column `columns[j]` of this
+    /// vector, or `missing(j)` when it's `None`. The values of the columns
+    /// that aren't moved are dropped. It's how an entity changes archetype
+    /// (eg. in an ECS) when a component is added or removed.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds, if a column doesn't exist, if it's
+    /// moved twice or if `dst` cannot grow. In those cases nothing is moved.
+    /// If `missing` panics, the row is dropped.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::{base_buffers::HeapBuffer, collections::Vector, composites::ArrayBuffer};
+    /// let mut positions = Vector::<[f32; 2], ArrayBuffer<2, HeapBuffer<f32>>>::new();
+    /// let mut moving = Vector::<[f32; 3], ArrayBuffer<3, HeapBuffer<f32>>>::new();
+    /// positions.push([1.0, 2.0]);
+    ///
+    /// // Adds a speed (column 2) to the position (columns 0 and 1).
+    /// positions.transfer_matching_columns(0, &mut moving, [Some(0), Some(1), None], |_| 0.5);
+    /// assert!(positions.is_empty());
+    /// assert_eq!(moving.columns(), [[1.0], [2.0], [0.5]]);
+    /// ```
+    pub fn transfer_matching_columns<const M: usize, D, F>(
+        &mut self,
+        index: usize,
+        dst: &mut Vector<[T; M], D>,
+        columns: [Option<usize>; M],
+        mut missing: F,
+    ) -> usize
+    where
+        D: Buffer<Element = [T; M]>,
+        F: FnMut(usize) -> T,
+    {
+        let mut moved = [false; SIZE];
+        for column in columns.into_iter().flatten() {
+            assert!(column < SIZE, "There is no column {column}");
+            assert!(!moved[column], "Column {column} is moved twice");
+            moved[column] = true;
+        }
+        self.assert_in_bounds(index);
+        dst.try_reserve(1)
+            .expect("Couldn't reserve the necessary space");
+
+        let mut row = self.swap_remove(index).map(Some);
+        let new_row = std::array::from_fn(|column| match columns[column] {
+            Some(source) => row[source].take().expect("Each column is moved once"),
+            None => missing(column),
+        });
+        dst.push(new_row)
+    }
+}
+
+#[cfg(feature = "allocator")]
+impl<T, A: std::alloc::Allocator> Vector<T, crate::DefaultBufferIn<T, A>> {
+    /// Makes an empty vector with the default buffer composition (see
+    /// [`crate::DefaultBufferIn`]) which allocates with `alloc`.
+    ///
+    /// # Example
+    /// ```
+    /// #![feature(allocator_api)]
+    /// # use buffers::collections::Vector;
+    /// # use std::alloc::System;
+    /// let mut vec = Vector::new_in(System);
+    /// vec.push(1);
+    /// assert_eq!(vec.as_slice(), [1]);
+    /// ```
+    pub fn new_in(alloc: A) -> Self {
+        let big = crate::base_buffers::AllocatorBuffer::with_allocator(alloc);
+        Self::from_buffer(ZstoBuffer::from(SvoBuffer::with_big(big)))
+    }
+
+    /// Same as [`Vector::new_in`], but with space for at least `capacity`
+    /// elements.
+    ///
+    /// # Panics
+    /// Panics if it cannot grow (eg. the allocator fails).
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        let mut vec = Self::new_in(alloc);
+        vec.try_reserve_exact(capacity)
+            .expect("Couldn't reserve the necessary space");
+        vec
+    }
+}
+
+impl<T, B> Vector<T, B>
+where
+    B: Buffer<Element = T> + Default,
+{
+    /// Creates a new vector by default-constructing the underlying buffer.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let _vec = Vector::<u32>::new();
+    /// ```
+    pub fn new() -> Vector<T, B> {
+        Self::from_buffer(Default::default())
+    }
+
+    /// Moves the elements (and the buffer) out into a new vector, leaving
+    /// this one empty. Same as `std::mem::take(&mut vec)`.
+    ///
+    /// Making the empty buffer never allocates (the base buffers only allocate
+    /// when growing), so for heap-based buffers it's as cheap as moving a
+    /// pointer. Buffers with inline storage (like
+    /// [`crate::base_buffers::InlineBuffer`] or the small part of
+    /// [`crate::composites::SvoBuffer`]) copy all of it, used or not.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32>::new();
+    /// vec.push(1);
+    ///
+    /// let taken = vec.take();
+    ///
+    /// assert!(vec.is_empty());
+    /// assert_eq!(taken.len(), 1);
+    /// ```
+    pub fn take(&mut self) -> Vector<T, B> {
+        std::mem::take(self)
+    }
+
+    /// Makes a new vector with `n` clones of `value` (like `vec![value; n]`).
+    /// It reserves all the space at once and fills it with
+    /// [`Buffer::fill_range`].
+    ///
+    /// # Panics
+    /// Panics if it cannot grow.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let vec = Vector::<u32>::from_elem(7, 3);
+    /// assert_eq!(vec.iter_copied().collect::<Vec<_>>(), [7, 7, 7]);
+    /// ```
+    pub fn from_elem(value: T, n: usize) -> Vector<T, B>
+    where
+        T: Clone,
+    {
+        let mut vec = Self::new();
+        vec.try_reserve_exact(n)
+            .expect("Couldn't reserve the necessary space");
+        if n > 0 {
+            // SAFETY: It reserved space for `n` elements.
+            unsafe { vec.raw.fill_unchecked(n - 1, &value) };
+            // The last one doesn't need a clone.
+            // SAFETY: Same as before.
+            unsafe { vec.raw.push_unchecked(value) };
+        }
+        vec
+    }
+
+    /// Makes a new vector with clones of the values of `slice` (like
+    /// `slice.to_vec()`). It reserves all the space at once, and fails if the
+    /// buffer cannot hold them (eg. a fixed-size one that's too small).
+    ///
+    /// If cloning a value panics, the ones cloned so far are dropped.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::FixedVector;
+    /// let vec = FixedVector::<u32, 4>::try_from_slice(&[1, 2, 3]).ok().unwrap();
+    /// assert_eq!(vec.as_slice(), [1, 2, 3]);
+    ///
+    /// assert!(FixedVector::<u32, 2>::try_from_slice(&[1, 2, 3]).is_err());
+    /// ```
+    pub fn try_from_slice(slice: &[T]) -> Result<Vector<T, B>, ResizeError>
+    where
+        T: Clone,
+    {
+        let mut vec = Self::new();
+        vec.try_reserve_exact(slice.len())?;
+        for value in slice {
+            // SAFETY: It reserved space for all the values of `slice`.
+            unsafe { vec.raw.push_unchecked(value.clone()) };
+        }
+        Ok(vec)
+    }
+
+    /// Makes a new vector with clones of the values of `slice` (like
+    /// `slice.to_vec()`). It reserves all the space at once.
+    ///
+    /// It's only available for buffers that can grow (see [`GrowableBuffer`]).
+    /// Use [`Vector::try_from_slice`] for the rest.
+    ///
+    /// # Panics
+    /// Panics if it cannot grow (eg. it runs out of memory).
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let vec = Vector::<String>::from_slice(&["a".to_string(), "b".to_string()]);
+    /// assert_eq!(vec.as_slice(), ["a", "b"]);
+    /// ```
+    pub fn from_slice(slice: &[T]) -> Vector<T, B>
+    where
+        T: Clone,
+        B: GrowableBuffer,
+    {
+        Self::try_from_slice(slice).expect("Couldn't reserve the necessary space")
+    }
+}
+
+impl<T, B> Vector<T, B>
+where
+    B: Buffer<Element = T> + PtrBuffer,
+{
+    /// Returns an unsafe pointer to the start of the vector's buffer
+    pub fn as_ptr(&self) -> B::ConstantPointer {
+        // SAFETY: even if empty, the (unsafe) pointer is corrent
+        unsafe { self.raw.buffer().ptr(0) }
+    }
+
+    /// Returns an unsafe mutable pointer to the start of the vector's buffer
+    pub fn as_mut_ptr(&mut self) -> B::MutablePointer {
+        // SAFETY: Getting a pointer doesn't change which positions are filled.
+        let buffer = unsafe { self.raw.buffer_mut() };
+        // SAFETY: even if empty, the (unsafe) pointer is corrent
+        unsafe { buffer.mut_ptr(0) }
+    }
+
+    /// Iterates over the memory of the elements as `(pointer, length)` pairs,
+    /// one for each segment of the buffer (see [`PtrBuffer::segment_len`]).
+    /// It's meant for C APIs that take scatter lists (like `iovec`):
+    /// contiguous buffers give a single chunk, and buffers that keep the
+    /// elements in segments give one per segment, without copying them.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u8>::new();
+    /// vec.extend_from_slice(b"hello");
+    /// let chunks: Vec<_> = vec.ptr_chunks().collect();
+    /// assert_eq!(chunks, [(vec.as_slice().as_ptr(), 5)]);
+    /// ```
+    pub fn ptr_chunks(&self) -> PtrChunks<'_, T, B>
+    where
+        B: PtrBuffer<ConstantPointer = *const T>,
+    {
+        PtrChunks {
+            vec: self,
+            start: 0,
+        }
+    }
+}
+
+impl<T, B> Vector<T, B>
+where
+    B: Buffer<Element = T> + RefBuffer,
+{
+    /// Get a reference to the element in index
+    ///
+    /// # Panics
+    /// If `index` is out of bounds.
+    pub fn index(&self, index: usize) -> B::ConstantReference<'_> {
+        self.assert_in_bounds(index);
+        // SAFETY: values up to len exist
+        unsafe { self.get_unchecked(index) }
+    }
+
+    /// Get a mutable reference to the element in index
+    ///
+    /// # Panics
+    /// If `index` is out of bounds.
     pub fn mut_index(&mut self, index: usize) -> B::MutableReference<'_> {
+        self.assert_in_bounds(index);
+        // SAFETY: values up to len exist
+        unsafe { self.get_unchecked_mut(index) }
+    }
+
+    /// Iterates over the runs of adjacent elements for which `eq` returns
+    /// `true`, giving a [`View`] of each one. It's called with pairs of
+    /// adjacent elements (in order).
+    ///
+    /// It works on any buffer that gives references. On contiguous ones
+    /// [`View::as_slice`] gives the slice of each run.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32>::new();
+    /// for i in [1, 1, 2, 3, 3, 3] {
+    ///     vec.push(i);
+    /// }
+    ///
+    /// let runs: Vec<_> = vec.chunk_by(|a, b| a == b).map(|run| run.as_slice()).collect();
+    ///
+    /// assert_eq!(runs, [&[1, 1][..], &[2], &[3, 3, 3]]);
+    /// ```
+    pub fn chunk_by<'a, F>(&'a self, eq: F) -> ChunkBy<'a, T, B, F>
+    where
+        F: FnMut(B::ConstantReference<'a>, B::ConstantReference<'a>) -> bool,
+    {
+        ChunkBy {
+            vec: self,
+            start: 0,
+            eq,
+        }
+    }
+
+    /// Get a reference to the element in index without checking the bounds.
+    ///
+    /// # Safety
+    /// index < self.len()
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32>::new();
+    /// vec.push(1);
+    /// vec.push(2);
+    /// // SAFETY: 1 < 2
+    /// assert_eq!(*unsafe { vec.get_unchecked(1) }, 2);
+    /// ```
+    pub unsafe fn get_unchecked(&self, index: usize) -> B::ConstantReference<'_> {
+        debug_assert!(index < self.len());
+        // SAFETY: The caller ensures that the position is filled.
+        unsafe { self.raw.buffer().index(index) }
+    }
+
+    /// Get a mutable reference to the element in index without checking the
+    /// bounds.
+    ///
+    /// # Safety
+    /// index < self.len()
+    pub unsafe fn get_unchecked_mut(&mut self, index: usize) -> B::MutableReference<'_> {
         debug_assert!(index < self.len());
+        // SAFETY: Getting a reference doesn't change which positions are
+        // filled.
+        let buffer = unsafe { self.raw.buffer_mut() };
+        // SAFETY: The caller ensures that the position is filled.
+        unsafe { buffer.mut_index(index) }
+    }
+}
+
+impl<T, B> Vector<T, B>
+where
+    B: Buffer<Element = T> + ContiguousMemoryBuffer,
+{
+    /// Extracts a slice containing the entire vector
+    pub fn as_slice(&self) -> &[T] {
+        // SAFETY: values up to len exist
+        unsafe { self.raw.buffer().slice(0..self.len()) }
+    }
+
+    /// Returns the two raw pointers spanning the elements (see
+    /// [`slice::as_ptr_range`]), for C APIs that take a start and an end.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32>::new();
+    /// vec.extend_from_slice(&[1, 2, 3]);
+    /// let range = vec.as_ptr_range();
+    /// assert_eq!(range.start, vec.as_slice().as_ptr());
+    /// assert_eq!(range.end, range.start.wrapping_add(3));
+    /// ```
+    pub fn as_ptr_range(&self) -> Range<*const T> {
+        self.as_slice().as_ptr_range()
+    }
+
+    /// Extracts a mutable slice containing the entire vector
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        let len = self.len();
+        // SAFETY: Getting a slice doesn't change which positions are filled.
+        let buffer = unsafe { self.raw.buffer_mut() };
+        // SAFETY: values up to len exist
+        unsafe { buffer.mut_slice(0..len) }
+    }
+
+    /// Same as [`Vector::rebuffer`], but copies all the elements at once
+    /// (both buffers are contiguous).
+    ///
+    /// # Panics
+    /// Panics if the new buffer cannot hold all the elements (the elements are
+    /// dropped with the vector).
+    ///
+    /// # Example
+    /// Freezing a vector into a fixed buffer on the heap:
+    /// ```
+    /// # use buffers::{base_buffers::{HeapBuffer, InlineBuffer}, collections::Vector};
+    /// let mut vec = Vector::<u32, HeapBuffer<u32>>::new();
+    /// vec.extend_from_slice(&[1, 2, 3]);
+    ///
+    /// let frozen: Vector<u32, Box<InlineBuffer<u32, 3>>> = vec.rebuffer_contiguous();
+    /// assert_eq!(frozen.as_slice(), [1, 2, 3]);
+    /// ```
+    pub fn rebuffer_contiguous<B2>(self) -> Vector<T, B2>
+    where
+        B2: Buffer<Element = T> + ContiguousMemoryBuffer + Default,
+    {
+        self.rebuffer_with(|source, target: &mut B2, len| {
+            if len == 0 {
+                return;
+            }
+            // SAFETY: There is at least one element, so 0 is valid.
+            let from = unsafe { source.ptr(0) };
+            // SAFETY: The target has space for them, so 0 is valid.
+            let to = unsafe { target.mut_ptr(0) };
+            // SAFETY: Both buffers are contiguous and have at least `len`
+            // positions, and they are different buffers. The values are moved
+            // (the source ones are considered empty afterwards).
+            unsafe { std::ptr::copy_nonoverlapping(from, to, len) };
+        })
+    }
+
+    /// Gives the positions after the elements (up to the capacity) as a slice
+    /// of maybe-uninit values. After filling some of them, use
+    /// [`Vector::set_len`] to make them part of the vector.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u8>::new();
+    /// vec.reserve(3);
+    /// for value in vec.spare_capacity_mut().iter_mut().take(2) {
+    ///     value.write(7);
+    /// }
+    /// // SAFETY: The first 2 positions were just filled.
+    /// unsafe { vec.set_len(2) };
+    /// assert_eq!(vec.iter_copied().collect::<Vec<_>>(), [7, 7]);
+    /// ```
+    pub fn spare_capacity_mut(&mut self) -> &mut [MaybeUninit<T>] {
+        let len = self.len();
+        let spare = self.capacity() - len;
+        if spare == 0 {
+            return &mut [];
+        }
+        // SAFETY: Getting a pointer doesn't change which positions are filled.
+        let buffer = unsafe { self.raw.buffer_mut() };
+        // SAFETY: There is spare capacity, so `len` is a valid position.
+        let start = unsafe { buffer.mut_ptr(len) };
+        // SAFETY: The memory is contiguous up to the capacity. The slice is of
+        // maybe-uninit values, so empty positions are fine, and it borrows the
+        // vector mutably.
+        unsafe { std::slice::from_raw_parts_mut(start.cast::<MaybeUninit<T>>(), spare) }
+    }
+
+    /// Makes a new vector with the elements of all the `vectors`, one after
+    /// the other. It reserves all the space at once.
+    ///
+    /// # Panics
+    /// Panics if it cannot grow.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut a = Vector::<u32>::new();
+    /// a.push(1);
+    /// let mut b = Vector::<u32>::new();
+    /// b.push(2);
+    /// b.push(3);
+    ///
+    /// let vec = Vector::concat(&[a, b]);
+    ///
+    /// assert_eq!(vec.iter_copied().collect::<Vec<_>>(), [1, 2, 3]);
+    /// ```
+    pub fn concat(vectors: &[Self]) -> Self
+    where
+        T: Clone,
+        B: Default,
+    {
+        let total = vectors
+            .iter()
+            .try_fold(0usize, |total, vec| total.checked_add(vec.len()))
+            .expect("Capacity overflow");
+        let mut result = Self::new();
+        result
+            .try_reserve_exact(total)
+            .expect("Couldn't reserve the necessary space");
+        for vec in vectors {
+            result.extend_from_slice(vec.slice_of(0..vec.len()));
+        }
+        result
+    }
+
+    /// Makes a new vector repeating the elements of this one `n` times. It
+    /// reserves all the space at once and copies the elements in bulk.
+    ///
+    /// # Panics
+    /// Panics if it cannot grow.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32>::new();
+    /// vec.push(1);
+    /// vec.push(2);
+    ///
+    /// let repeated = vec.repeat(3);
+    ///
+    /// assert_eq!(repeated.iter_copied().collect::<Vec<_>>(), [1, 2, 1, 2, 1, 2]);
+    /// ```
+    pub fn repeat(&self, n: usize) -> Self
+    where
+        T: Copy,
+        B: Default,
+    {
+        let len = self.len();
+        let total = len.checked_mul(n).expect("Capacity overflow");
+        let mut result = Self::new();
+        result
+            .try_reserve_exact(total)
+            .expect("Couldn't reserve the necessary space");
+        if total > 0 {
+            let source = self.slice_of(0..len).as_ptr();
+            // SAFETY: Getting a pointer doesn't change which positions are
+            // filled.
+            let buffer = unsafe { result.raw.buffer_mut() };
+            // SAFETY: `total` > 0 and it has at least `total` capacity.
+            let destination = unsafe { buffer.mut_ptr(0) };
+            for i in 0..n {
+                // SAFETY: The memory is contiguous, and `i * len` < `total`.
+                let copy = unsafe { destination.add(i * len) };
+                // SAFETY: `i * len + len` <= `total`. Both vectors are
+                // different, so they don't overlap. `T` is `Copy`, so copying
+                // the bytes is enough.
+                unsafe { std::ptr::copy_nonoverlapping(source, copy, len) };
+            }
+        }
+        // SAFETY: All `total` positions were just filled.
+        unsafe { result.raw.set_len(total) };
+        result
+    }
+
+    /// Internal utility to get the slice of filled positions in `range`.
+    fn slice_of(&self, range: Range<usize>) -> &[T] {
+        debug_assert!(range.end <= self.len());
+        // SAFETY: The positions in the range are filled.
+        unsafe { self.raw.buffer().slice(range) }
+    }
+}
+
+impl<B> Vector<u8, B>
+where
+    B: Buffer<Element = u8> + ContiguousMemoryBuffer + OutOfLineBuffer,
+{
+    /// Reinterprets the bytes as values of `U`, without copying them.
+    ///
+    /// Fails if the bytes aren't aligned for `U`, if the length isn't a
+    /// multiple of its size, or if it's zero-sized (see [`CastError`]). The
+    /// vector is given back (unchanged) in that case.
+    ///
+    /// Only buffers that don't store the bytes inside themselves can be cast
+    /// (see [`OutOfLineBuffer`]), so moving the vector keeps them aligned.
+    ///
+    /// The resulting vector cannot grow (see [`CastBuffer`]). Use
+    /// [`Vector::into_bytes`] to get the bytes back.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::mem::MaybeUninit;
+    /// # use buffers::{base_buffers::SliceBuffer, collections::Vector};
+    /// #[repr(align(4))]
+    /// struct Payload([MaybeUninit<u8>; 8]);
+    /// let mut payload = Payload([MaybeUninit::uninit(); 8]);
+    ///
+    /// let mut bytes = Vector::from_buffer(SliceBuffer::from_slice(&mut payload.0));
+    /// bytes.extend_from_slice(&[1, 0, 0, 0, 2, 0, 0, 0]);
+    ///
+    /// let records = bytes.cast::<u32>().ok().unwrap();
+    /// assert_eq!(records.len(), 2);
+    /// assert_eq!(records[1], u32::from_ne_bytes([2, 0, 0, 0]));
+    /// ```
+    pub fn cast<U: Pod>(self) -> Result<Vector<U, CastBuffer<U, B>>, (CastError, Self)> {
+        let size = mem::size_of::<U>();
+        if size != 0 && !self.len().is_multiple_of(size) {
+            return Err((CastError::Size, self));
+        }
+        let raw = self.into_raw();
+        let len = raw.len();
+        let buffer = match CastBuffer::try_from_buffer(raw.into_buffer()) {
+            Ok(buffer) => buffer,
+            // SAFETY: It's the same buffer, with the same `len` bytes filled.
+            Err((error, buffer)) => return Err((error, unsafe { Self::from_parts(buffer, len) })),
+        };
+        let mut vec = Vector::from_buffer(buffer);
+        // SAFETY: The bytes of the first `len / size` values were filled.
+        unsafe { vec.raw.set_len(len / size) };
+        Ok(vec)
+    }
+}
+
+impl<U, B> Vector<U, CastBuffer<U, B>>
+where
+    U: Pod,
+    B: Buffer<Element = u8> + ContiguousMemoryBuffer + OutOfLineBuffer,
+{
+    /// Gives back the bytes of a vector made with [`Vector::cast`] (including
+    /// changes done to the values).
+    pub fn into_bytes(self) -> Vector<u8, B> {
+        let raw = self.into_raw();
+        let len = raw.len() * mem::size_of::<U>();
+        let mut vec = Vector::from_buffer(raw.into_buffer().into_inner());
+        // SAFETY: All the bytes of the first `len` values are filled (`Pod` has
+        // no padding).
+        unsafe { vec.raw.set_len(len) };
+        vec
+    }
+}
+
+impl<T, B> Vector<T, B>
+where
+    T: Copy,
+    B: Buffer<Element = T> + CopyValueBuffer,
+{
+    /// Get a copy of the element in `index`, or `None` if it's out of bounds.
+    ///
+    /// Unlike [`Vector::index`], it works on buffers which cannot make
+    /// references (eg. `ArrayBuffer`, with the `array` feature).
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::{base_buffers::HeapBuffer, collections::Vector};
+    /// let mut vec = Vector::<u32, HeapBuffer<u32>>::new();
+    /// vec.push(1);
+    /// assert_eq!(vec.get_copy(0), Some(1));
+    /// assert_eq!(vec.get_copy(1), None);
+    /// ```
+    pub fn get_copy(&self, index: usize) -> Option<T> {
+        if index < self.len() {
+            // SAFETY: values up to len exist
+            Some(unsafe { self.raw.buffer().copy(index) })
+        } else {
+            None
+        }
+    }
+
+    /// Iterates over copies of all the elements of the vector.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32>::new();
+    /// vec.push(1);
+    /// vec.push(2);
+    /// assert_eq!(vec.iter_copied().sum::<u32>(), 3);
+    /// ```
+    pub fn iter_copied(&self) -> impl Iterator<Item = T> + '_ {
         // SAFETY: values up to len exist
-        unsafe { self.buffer.mut_index(index) }
+        (0..self.len()).map(|index| unsafe { self.raw.buffer().copy(index) })
+    }
+
+    /// Returns `true` if the vector contains an element equal to `value`.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32>::new();
+    /// vec.push(1);
+    /// assert!(vec.contains(&1));
+    /// assert!(!vec.contains(&2));
+    /// ```
+    pub fn contains(&self, value: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.iter_copied().any(|element| element == *value)
+    }
+
+    /// Binary searches this vector for a given element. The vector must be
+    /// sorted.
+    ///
+    /// If the value is found then `Ok` is returned with the index of the
+    /// matching element (if there are multiple matches, any of them may be
+    /// returned). If it's not found, then `Err` is returned with the index
+    /// where it could be inserted while maintaining the order.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32>::new();
+    /// vec.push(1);
+    /// vec.push(3);
+    /// vec.push(5);
+    /// assert_eq!(vec.binary_search(&3), Ok(1));
+    /// assert_eq!(vec.binary_search(&4), Err(2));
+    /// ```
+    pub fn binary_search(&self, value: &T) -> Result<usize, usize>
+    where
+        T: Ord,
+    {
+        self.binary_search_by(|element| element.cmp(value))
+    }
+
+    /// Binary searches this vector with a comparator function. The vector must
+    /// be sorted according to it.
+    ///
+    /// See [`Vector::binary_search`] for details on the returned value.
+    pub fn binary_search_by<F>(&self, mut f: F) -> Result<usize, usize>
+    where
+        F: FnMut(&T) -> std::cmp::Ordering,
+    {
+        let mut low = 0;
+        let mut high = self.len();
+        while low < high {
+            let middle = low + (high - low) / 2;
+            // SAFETY: `low` <= `middle` < `high` <= `self.len()`, so it exists.
+            let element = unsafe { self.raw.buffer().copy(middle) };
+            match f(&element) {
+                std::cmp::Ordering::Less => low = middle + 1,
+                std::cmp::Ordering::Greater => high = middle,
+                std::cmp::Ordering::Equal => return Ok(middle),
+            }
+        }
+        Err(low)
+    }
+}
+
+impl<T, B> Default for Vector<T, B>
+where
+    B: Buffer<Element = T> + Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Logs the elements like a slice with the `defmt` feature.
+#[cfg(feature = "defmt")]
+impl<T, B> defmt::Format for Vector<T, B>
+where
+    T: defmt::Format,
+    B: Buffer<Element = T> + ContiguousMemoryBuffer,
+{
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(f, "{=[?]}", self.as_slice());
+    }
+}
+
+/// A [`FixedVector`] fits in a [`SmallVector`] of any size (which spills to the
+/// heap if needed).
+///
+/// # Example
+/// ```
+/// # use buffers::collections::{FixedVector, SmallVector};
+/// let mut fixed = FixedVector::<u32, 4>::new();
+/// fixed.extend_from_slice(&[1, 2, 3]);
+///
+/// let small = SmallVector::<u32, 2>::from(fixed);
+/// assert_eq!(small.as_slice(), [1, 2, 3]);
+/// ```
+impl<T, const N: usize, const M: usize> From<FixedVector<T, N>> for SmallVector<T, M> {
+    fn from(vec: FixedVector<T, N>) -> Self {
+        vec.rebuffer_contiguous()
+    }
+}
+
+impl<T, const N: usize> From<FixedVector<T, N>> for Vector<T, HeapBuffer<T>> {
+    fn from(vec: FixedVector<T, N>) -> Self {
+        vec.rebuffer_contiguous()
+    }
+}
+
+/// If the elements are already on the heap, its buffer is reused (nothing is
+/// copied).
+impl<T, const N: usize> From<SmallVector<T, N>> for Vector<T, HeapBuffer<T>> {
+    fn from(vec: SmallVector<T, N>) -> Self {
+        let len = vec.len();
+        let buffer = vec.into_raw().into_buffer();
+        match buffer.into_inner().into_big() {
+            // SAFETY: The big buffer holds the first `len` elements.
+            Ok(big) => unsafe { Vector::from_parts(big, len) },
+            Err(svo) => {
+                // SAFETY: The buffer is given back unchanged.
+                let vec = unsafe { Vector::from_parts(ZstoBuffer::from(svo), len) };
+                vec.rebuffer_contiguous()
+            }
+        }
+    }
+}
+
+/// Same as [`Vector::from_array`].
+///
+/// # Example
+/// ```
+/// # use buffers::collections::FixedVector;
+/// let vec = FixedVector::from([String::from("a"), String::from("b")]);
+/// assert_eq!(vec.len(), 2);
+/// assert_eq!(vec.capacity(), 2);
+/// ```
+impl<T, const N: usize> From<[T; N]> for FixedVector<T, N> {
+    fn from(array: [T; N]) -> Self {
+        Self::from_array(array)
+    }
+}
+
+/// If the heap buffer is bigger than the inline one, it's reused (nothing is
+/// copied).
+impl<T, const N: usize> From<Vector<T, HeapBuffer<T>>> for SmallVector<T, N> {
+    fn from(vec: Vector<T, HeapBuffer<T>>) -> Self {
+        let len = vec.len();
+        let buffer = vec.into_raw().into_buffer();
+        match SvoBuffer::from_big(buffer) {
+            // SAFETY: The big buffer (in use) holds the first `len` elements.
+            Ok(svo) => unsafe { Vector::from_parts(ZstoBuffer::from(svo), len) },
+            // SAFETY: The buffer is given back unchanged.
+            Err(heap) => unsafe { Vector::from_parts(heap, len) }.rebuffer_contiguous(),
+        }
+    }
+}
+
+/// Takes the allocation of the `Vec` (nothing is copied). Only fails for
+/// zero-sized elements, which `HeapBuffer` doesn't support.
+///
+/// # Example
+/// ```
+/// # use buffers::{base_buffers::HeapBuffer, collections::Vector};
+/// let vec = Vector::<u32, HeapBuffer<u32>>::try_from(vec![1, 2, 3]).ok().unwrap();
+/// assert_eq!(vec.as_slice(), [1, 2, 3]);
+/// ```
+impl<T> TryFrom<Vec<T>> for Vector<T, HeapBuffer<T>> {
+    type Error = Vec<T>;
+
+    fn try_from(vec: Vec<T>) -> Result<Self, Self::Error> {
+        let (buffer, len) = HeapBuffer::from_vec(vec)?;
+        // SAFETY: The first `len` positions of the buffer are filled.
+        Ok(unsafe { Vector::from_parts(buffer, len) })
+    }
+}
+
+/// Same as [`Vector::try_from_slice`], for any buffer that can be made with
+/// [`Default`].
+///
+/// # Example
+/// ```
+/// # use buffers::collections::{FixedVector, SmallVector};
+/// let small = SmallVector::<u32, 2>::try_from(&[1, 2, 3][..]).ok().unwrap();
+/// assert_eq!(small.as_slice(), [1, 2, 3]);
+///
+/// assert!(FixedVector::<u32, 2>::try_from(&[1, 2, 3][..]).is_err());
+/// ```
+impl<T, B> TryFrom<&[T]> for Vector<T, B>
+where
+    T: Clone,
+    B: Buffer<Element = T> + Default,
+{
+    type Error = ResizeError;
+
+    fn try_from(slice: &[T]) -> Result<Self, Self::Error> {
+        Self::try_from_slice(slice)
+    }
+}
+
+/// Same as [`Vector::try_extend`], for buffers that can grow. It panics if
+/// it cannot.
+///
+/// # Example
+/// ```
+/// # use buffers::collections::Vector;
+/// let mut vec = Vector::<u32>::new();
+/// vec.extend((1..4).filter(|i| i % 2 == 1));
+/// assert_eq!(vec.as_slice(), [1, 3]);
+/// ```
+impl<T, B> Extend<T> for Vector<T, B>
+where
+    B: Buffer<Element = T> + GrowableBuffer,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.try_extend(iter)
+            .expect("Couldn't reserve the necessary space")
+    }
+}
+
+/// Only works if the elements fit. Otherwise the vector is given back
+/// unchanged.
+///
+/// # Example
+/// ```
+/// # use buffers::collections::{FixedVector, SmallVector};
+/// let mut small = SmallVector::<u32, 2>::new();
+/// small.extend_from_slice(&[1, 2, 3]);
+///
+/// let small = FixedVector::<u32, 2>::try_from(small).err().unwrap();
+/// let fixed = FixedVector::<u32, 3>::try_from(small).ok().unwrap();
+/// assert_eq!(fixed.as_slice(), [1, 2, 3]);
+/// ```
+impl<T, const N: usize, const M: usize> TryFrom<SmallVector<T, N>> for FixedVector<T, M> {
+    type Error = SmallVector<T, N>;
+
+    fn try_from(vec: SmallVector<T, N>) -> Result<Self, Self::Error> {
+        if vec.len() <= M {
+            Ok(vec.rebuffer_contiguous())
+        } else {
+            Err(vec)
+        }
+    }
+}
+
+/// Only works if the elements fit. Otherwise the vector is given back
+/// unchanged.
+impl<T, const M: usize> TryFrom<Vector<T, HeapBuffer<T>>> for FixedVector<T, M> {
+    type Error = Vector<T, HeapBuffer<T>>;
+
+    fn try_from(vec: Vector<T, HeapBuffer<T>>) -> Result<Self, Self::Error> {
+        if vec.len() <= M {
+            Ok(vec.rebuffer_contiguous())
+        } else {
+            Err(vec)
+        }
+    }
+}
+
+/// Indexing with `vec[index]`. It panics when `index` is out of bounds.
+///
+/// It needs contiguous memory because it must give plain references (which
+/// other buffers may not have). Use [`Vector::index`] for them.
+///
+/// # Example
+/// ```
+/// # use buffers::collections::Vector;
+/// let mut vec = Vector::<u32>::new();
+/// vec.push(1);
+/// vec.push(2);
+/// vec[1] = 3;
+/// assert_eq!(vec[0] + vec[1], 4);
+/// ```
+impl<T, B> Index<usize> for Vector<T, B>
+where
+    B: Buffer<Element = T> + ContiguousMemoryBuffer,
+{
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        self.assert_in_bounds(index);
+        // SAFETY: `index` is filled, so it's a valid position.
+        let ptr = unsafe { self.raw.buffer().ptr(index) };
+        // SAFETY: The position is filled and the reference borrows the vector.
+        unsafe { &*ptr }
+    }
+}
+
+impl<T, B> IndexMut<usize> for Vector<T, B>
+where
+    B: Buffer<Element = T> + ContiguousMemoryBuffer,
+{
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        self.assert_in_bounds(index);
+        // SAFETY: Getting a pointer doesn't change which positions are filled.
+        let buffer = unsafe { self.raw.buffer_mut() };
+        // SAFETY: `index` is filled, so it's a valid position.
+        let ptr = unsafe { buffer.mut_ptr(index) };
+        // SAFETY: The position is filled and the reference borrows the vector
+        // mutably.
+        unsafe { &mut *ptr }
+    }
+}
+
+/// Iterator which removes a range of elements from a [`Vector`]. See
+/// [`Vector::drain`].
+pub struct Drain<'a, T, B: Buffer<Element = T>> {
+    vec: &'a mut Vector<T, B>,
+    // Positions `front..back` still have to be yielded.
+    front: usize,
+    back: usize,
+    // Elements after the drained range, which get moved back at the end.
+    tail_start: usize,
+    tail_len: usize,
+}
+
+impl<'a, T, B: Buffer<Element = T>> Iterator for Drain<'a, T, B> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.front < self.back {
+            self.front += 1;
+            // SAFETY: The drain owns the positions after the vector's length.
+            let buffer = unsafe { self.vec.raw.buffer_mut() };
+            // SAFETY: Positions in `front..back` are filled.
+            Some(unsafe { buffer.take(self.front - 1) })
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl<'a, T, B: Buffer<Element = T>> DoubleEndedIterator for Drain<'a, T, B> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.front < self.back {
+            self.back -= 1;
+            // SAFETY: The drain owns the positions after the vector's length.
+            let buffer = unsafe { self.vec.raw.buffer_mut() };
+            // SAFETY: Positions in `front..back` are filled.
+            Some(unsafe { buffer.take(self.back) })
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, T, B: Buffer<Element = T>> ExactSizeIterator for Drain<'a, T, B> {}
+
+impl<'a, T, B: Buffer<Element = T>> Drop for Drain<'a, T, B> {
+    fn drop(&mut self) {
+        /// Moves the tail back even if dropping the remaining elements panics.
+        struct MoveTailBack<'r, 'a, T, B: Buffer<Element = T>>(&'r mut Drain<'a, T, B>);
+
+        impl<T, B: Buffer<Element = T>> Drop for MoveTailBack<'_, '_, T, B> {
+            fn drop(&mut self) {
+                let drain = &mut *self.0;
+                let start = drain.vec.len();
+                let tail_end = drain.tail_start + drain.tail_len;
+                if drain.tail_start != start {
+                    // SAFETY: The tail is moved right after the vector's end.
+                    let buffer = unsafe { drain.vec.raw.buffer_mut() };
+                    // SAFETY: The tail is filled and all positions between the
+                    // vector's end and the tail are empty.
+                    unsafe {
+                        buffer.shift_left(drain.tail_start..tail_end, drain.tail_start - start)
+                    };
+                }
+                // SAFETY: The tail is now right after the vector's end.
+                unsafe { drain.vec.raw.set_len(start + drain.tail_len) };
+            }
+        }
+
+        let guard = MoveTailBack(self);
+        let remaining = guard.0.front..guard.0.back;
+        guard.0.front = guard.0.back;
+        // SAFETY: The drain owns the positions after the vector's length.
+        let buffer = unsafe { guard.0.vec.raw.buffer_mut() };
+        // SAFETY: The positions that weren't yielded are still filled.
+        unsafe { buffer.manually_drop_range(remaining) };
+    }
+}
+
+/// Iterator which removes the elements of a [`Vector`] that match a predicate.
+/// See [`Vector::extract_if`].
+pub struct ExtractIf<'a, T, B: Buffer<Element = T>, F: FnMut(&mut T) -> bool> {
+    compactor: Compactor<'a, T, B>,
+    pred: F,
+}
+
+impl<'a, T, B, F> Iterator for ExtractIf<'a, T, B, F>
+where
+    B: Buffer<Element = T>,
+    F: FnMut(&mut T) -> bool,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        while let Some(current) = self.compactor.next() {
+            if (self.pred)(current) {
+                return Some(self.compactor.extract_current());
+            }
+            self.compactor.keep_current();
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.compactor.remaining()))
+    }
+}
+
+/// View of a range of consecutive elements of a [`Vector`]. It's what slices
+/// are for contiguous memory, but it works on any buffer: the elements are
+/// accessed one by one (with [`View::get`] or [`View::iter`]).
+///
+/// When the memory is contiguous, [`View::as_slice`] gives the actual slice.
+pub struct View<'a, T, B: Buffer<Element = T>> {
+    vec: &'a Vector<T, B>,
+    range: Range<usize>,
+}
+
+impl<'a, T, B: Buffer<Element = T>> View<'a, T, B> {
+    /// Positions of the elements in the vector.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Number of elements in the view.
+    pub fn len(&self) -> usize {
+        self.range.len()
+    }
+
+    /// Whether the view has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.range.is_empty()
+    }
+}
+
+impl<'a, T, B: Buffer<Element = T> + RefBuffer> View<'a, T, B> {
+    /// Reference to the element at `index` (relative to the view), or `None`
+    /// if it's out of bounds.
+    pub fn get(&self, index: usize) -> Option<B::ConstantReference<'a>> {
+        if index < self.len() {
+            Some(self.vec.index(self.range.start + index))
+        } else {
+            None
+        }
+    }
+
+    /// Iterates over references to the elements of the view.
+    pub fn iter(&self) -> impl Iterator<Item = B::ConstantReference<'a>> + 'a {
+        let vec = self.vec;
+        self.range.clone().map(move |index| vec.index(index))
+    }
+}
+
+impl<'a, T, B: Buffer<Element = T> + ContiguousMemoryBuffer> View<'a, T, B> {
+    /// The elements of the view as a slice.
+    pub fn as_slice(&self) -> &'a [T] {
+        self.vec.slice_of(self.range.clone())
+    }
+}
+
+/// Iterator over overlapping views of a [`Vector`]. See [`Vector::windows`].
+pub struct Windows<'a, T, B: Buffer<Element = T>> {
+    vec: &'a Vector<T, B>,
+    // Where the next window starts.
+    start: usize,
+    size: usize,
+}
+
+impl<'a, T, B: Buffer<Element = T>> Iterator for Windows<'a, T, B> {
+    type Item = View<'a, T, B>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let end = self.start + self.size;
+        if end > self.vec.len() {
+            return None;
+        }
+        let range = self.start..end;
+        self.start += 1;
+        Some(View {
+            vec: self.vec,
+            range,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.vec.len() + 1).saturating_sub(self.start + self.size);
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, T, B: Buffer<Element = T>> ExactSizeIterator for Windows<'a, T, B> {}
+
+/// Iterator over non-overlapping views of a [`Vector`]. See
+/// [`Vector::chunks`].
+pub struct Chunks<'a, T, B: Buffer<Element = T>> {
+    vec: &'a Vector<T, B>,
+    // Where the next chunk starts.
+    start: usize,
+    size: usize,
+}
+
+impl<'a, T, B: Buffer<Element = T>> Iterator for Chunks<'a, T, B> {
+    type Item = View<'a, T, B>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = self.vec.len();
+        if self.start >= len {
+            return None;
+        }
+        let end = std::cmp::min(self.start + self.size, len);
+        let range = self.start..end;
+        self.start = end;
+        Some(View {
+            vec: self.vec,
+            range,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.vec.len() - self.start).div_ceil(self.size);
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, T, B: Buffer<Element = T>> ExactSizeIterator for Chunks<'a, T, B> {}
+
+/// Iterator over the rows of a [`Vector`] using an [`ArrayBuffer`], with a
+/// reference to the value of each column. See [`Vector::rows`].
+#[cfg(feature = "array")]
+pub struct Rows<'a, T, const SIZE: usize> {
+    columns: [std::slice::Iter<'a, T>; SIZE],
+    // Needed when there are no columns.
+    remaining: usize,
+}
+
+#[cfg(feature = "array")]
+impl<'a, T, const SIZE: usize> Iterator for Rows<'a, T, SIZE> {
+    type Item = [&'a T; SIZE];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.remaining = self.remaining.checked_sub(1)?;
+        Some(self.columns.each_mut().map(|column| {
+            column
+                .next()
+                .expect("All the columns have a value in each row")
+        }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+#[cfg(feature = "array")]
+impl<'a, T, const SIZE: usize> ExactSizeIterator for Rows<'a, T, SIZE> {}
+
+/// Mutable version of [`Rows`]. See [`Vector::rows_mut`].
+#[cfg(feature = "array")]
+pub struct RowsMut<'a, T, const SIZE: usize> {
+    columns: [std::slice::IterMut<'a, T>; SIZE],
+    // Needed when there are no columns.
+    remaining: usize,
+}
+
+#[cfg(feature = "array")]
+impl<'a, T, const SIZE: usize> Iterator for RowsMut<'a, T, SIZE> {
+    type Item = [&'a mut T; SIZE];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.remaining = self.remaining.checked_sub(1)?;
+        Some(self.columns.each_mut().map(|column| {
+            column
+                .next()
+                .expect("All the columns have a value in each row")
+        }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+#[cfg(feature = "array")]
+impl<'a, T, const SIZE: usize> ExactSizeIterator for RowsMut<'a, T, SIZE> {}
+
+/// Some of the columns of a [`Vector`] using an [`ArrayBuffer`]. See
+/// [`Vector::project`].
+#[cfg(feature = "array")]
+pub struct Projection<'a, T, const K: usize> {
+    columns: [&'a [T]; K],
+    // Needed when there are no columns.
+    len: usize,
+}
+
+#[cfg(feature = "array")]
+impl<'a, T, const K: usize> Projection<'a, T, K> {
+    /// Number of rows.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// If there are no rows.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Slices with the projected columns.
+    pub fn columns(&self) -> [&'a [T]; K] {
+        self.columns
+    }
+
+    /// Iterates over the rows, with a reference to the value of each
+    /// projected column.
+    pub fn rows(&self) -> Rows<'a, T, K> {
+        Rows {
+            columns: self.columns.map(|column| column.iter()),
+            remaining: self.len,
+        }
+    }
+}
+
+/// Mutable version of [`Projection`]. See [`Vector::project_mut`].
+#[cfg(feature = "array")]
+pub struct ProjectionMut<'a, T, const K: usize> {
+    columns: [&'a mut [T]; K],
+    // Needed when there are no columns.
+    len: usize,
+}
+
+#[cfg(feature = "array")]
+impl<'a, T, const K: usize> ProjectionMut<'a, T, K> {
+    /// Number of rows.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// If there are no rows.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Mutable slices with the projected columns.
+    pub fn columns_mut(&mut self) -> [&mut [T]; K] {
+        self.columns.each_mut().map(|column| &mut **column)
+    }
+
+    /// Iterates over the rows, with a mutable reference to the value of each
+    /// projected column.
+    pub fn rows_mut(&mut self) -> RowsMut<'_, T, K> {
+        let remaining = self.len;
+        RowsMut {
+            columns: self.columns_mut().map(|column| column.iter_mut()),
+            remaining,
+        }
+    }
+
+    /// Same as [`Vector::for_each_row`] with the projected columns.
+    pub fn for_each_row<F: FnMut([&mut T; K])>(&mut self, f: F) {
+        self.rows_mut().for_each(f)
+    }
+}
+
+/// Iterator over the segments of the elements of a [`Vector`], as
+/// `(pointer, length)` pairs. See [`Vector::ptr_chunks`].
+pub struct PtrChunks<'a, T, B: Buffer<Element = T>> {
+    vec: &'a Vector<T, B>,
+    // Where the next chunk starts.
+    start: usize,
+}
+
+impl<'a, T, B> Iterator for PtrChunks<'a, T, B>
+where
+    B: Buffer<Element = T> + PtrBuffer<ConstantPointer = *const T>,
+{
+    type Item = (*const T, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = self.vec.len();
+        if self.start >= len {
+            return None;
+        }
+        let buffer = self.vec.raw.buffer();
+        // SAFETY: It's less than the length, so it's a valid position.
+        let first = unsafe { buffer.ptr(self.start) };
+        // SAFETY: It's less than the length, so it's a valid position.
+        let segment = unsafe { buffer.segment_len(self.start) };
+        let chunk_len = segment.clamp(1, len - self.start);
+        self.start += chunk_len;
+        Some((first, chunk_len))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.vec.len() - self.start;
+        (usize::from(remaining > 0), Some(remaining))
+    }
+}
+
+/// Iterator over views of the runs of adjacent elements of a [`Vector`] that
+/// compare equal. See [`Vector::chunk_by`].
+pub struct ChunkBy<'a, T, B: Buffer<Element = T>, F> {
+    vec: &'a Vector<T, B>,
+    // Where the next run starts.
+    start: usize,
+    eq: F,
+}
+
+impl<'a, T, B, F> Iterator for ChunkBy<'a, T, B, F>
+where
+    B: Buffer<Element = T> + RefBuffer,
+    F: FnMut(B::ConstantReference<'a>, B::ConstantReference<'a>) -> bool,
+{
+    type Item = View<'a, T, B>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = self.vec.len();
+        if self.start >= len {
+            return None;
+        }
+        let start = self.start;
+        let mut end = start + 1;
+        while end < len && (self.eq)(self.vec.index(end - 1), self.vec.index(end)) {
+            end += 1;
+        }
+        self.start = end;
+        Some(View {
+            vec: self.vec,
+            range: start..end,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.vec.len() - self.start;
+        (usize::from(remaining > 0), Some(remaining))
+    }
+}
+
+/// Internal utility to remove elements from a vector while keeping the order of
+/// the rest.
+///
+/// Positions `0..kept` are filled with the elements that are kept, then there
+/// is a gap of empty positions up to `processed`, and `processed..original_len`
+/// are still filled. The element being checked (and the previous one, if
+/// requested) are held outside of the buffer. When dropped (even while
+/// panicking) the held elements are kept and the gap is closed.
+struct Compactor<'a, T, B: Buffer<Element = T>> {
+    vec: &'a mut Vector<T, B>,
+    kept: usize,
+    processed: usize,
+    original_len: usize,
+    current: Option<T>,
+    previous: Option<T>,
+}
+
+impl<'a, T, B: Buffer<Element = T>> Compactor<'a, T, B> {
+    fn new(vec: &'a mut Vector<T, B>) -> Self {
+        let original_len = vec.len();
+        // While compacting, the guard owns the elements. If it's forgotten, it
+        // leaks instead of double dropping.
+        // SAFETY: No positions are filled up to 0.
+        unsafe { vec.raw.set_len(0) };
+        Self {
+            vec,
+            kept: 0,
+            processed: 0,
+            original_len,
+            current: None,
+            previous: None,
+        }
+    }
+
+    /// Takes the next element out to check it.
+    fn next(&mut self) -> Option<&mut T> {
+        debug_assert!(self.current.is_none());
+        if self.processed == self.original_len {
+            return None;
+        }
+        // SAFETY: The compactor owns all the positions.
+        let buffer = unsafe { self.vec.raw.buffer_mut() };
+        // SAFETY: `processed..original_len` is filled.
+        let value = unsafe { buffer.take(self.processed) };
+        self.processed += 1;
+        Some(self.current.insert(value))
+    }
+
+    /// Same as `next`, but it also takes out the last kept element. The first
+    /// element is always kept, since it has no previous element.
+    fn next_with_previous(&mut self) -> Option<(&mut T, &mut T)> {
+        if self.kept == 0 {
+            self.next()?;
+            self.keep_current();
+        }
+        if self.processed == self.original_len {
+            return None;
+        }
+        self.kept -= 1;
+        // SAFETY: The compactor owns all the positions.
+        let buffer = unsafe { self.vec.raw.buffer_mut() };
+        // SAFETY: `0..kept` was filled (before decreasing it).
+        let previous = unsafe { buffer.take(self.kept) };
+        self.previous = Some(previous);
+        self.next()?;
+        match (&mut self.current, &mut self.previous) {
+            (Some(current), Some(previous)) => Some((current, previous)),
+            _ => unreachable!("Both were just set"),
+        }
+    }
+
+    /// Puts the held elements back after the kept ones. Previous goes first.
+    fn put_back_held(&mut self) {
+        for value in [self.previous.take(), self.current.take()]
+            .into_iter()
+            .flatten()
+        {
+            // SAFETY: The compactor owns all the positions.
+            let buffer = unsafe { self.vec.raw.buffer_mut() };
+            // SAFETY: The held elements come from the gap, so it has space for
+            // them.
+            unsafe { buffer.put(self.kept, value) };
+            self.kept += 1;
+        }
+    }
+
+    fn keep_current(&mut self) {
+        self.put_back_held();
+    }
+
+    fn drop_current(&mut self) {
+        let current = self.current.take();
+        self.put_back_held();
+        drop(current);
+    }
+
+    /// Removes the current element, giving it back.
+    fn extract_current(&mut self) -> T {
+        let current = self.current.take().expect("There is a current element");
+        self.put_back_held();
+        current
+    }
+
+    /// How many elements haven't been checked yet.
+    fn remaining(&self) -> usize {
+        self.original_len - self.processed
+    }
+}
+
+impl<'a, T, B: Buffer<Element = T>> Drop for Compactor<'a, T, B> {
+    fn drop(&mut self) {
+        self.put_back_held();
+        let gap = self.processed - self.kept;
+        if gap > 0 && self.processed < self.original_len {
+            // SAFETY: The compactor owns all the positions.
+            let buffer = unsafe { self.vec.raw.buffer_mut() };
+            // SAFETY: `processed..original_len` is filled and the gap is empty.
+            unsafe { buffer.shift_left(self.processed..self.original_len, gap) };
+        }
+        // SAFETY: After closing the gap, the kept elements are at the start.
+        unsafe { self.vec.raw.set_len(self.original_len - gap) };
+    }
+}
+
+// SAFETY: Like `Vec`, dropping only drops the elements (which `PhantomData<T>`
+// tells dropck about). The buffer isn't `may_dangle`: it's a safe trait, so
+// its methods may use anything it borrows (see "Limitations" in the docs).
+unsafe impl<#[may_dangle] T, B: Buffer<Element = T>> Drop for Vector<T, B> {
+    fn drop(&mut self) {
+        self.raw.truncate(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+
+    use crate::{
+        base_buffers::{HeapBuffer, InlineBuffer, ZstBuffer},
+        composites::AtLeastBuffer,
+        test_utils::{
+            faulty::FaultyBuffer, life_counter::LifeCounter, panic::assert_panic,
+            panicking::PanickingElement,
+        },
+    };
+
+    use super::*;
+
+    type InlineVector = Vector<u32, InlineBuffer<u32, 4>>;
+
+    /// Static assertion that `V` has the same size as `Vec` and that
+    /// `Option<V>` doesn't need extra space (niche optimization).
+    const fn is_vec_like<V>() -> bool {
+        use std::mem::size_of;
+        size_of::<V>() == size_of::<Vec<u32>>() && size_of::<Option<V>>() == size_of::<V>()
+    }
+
+    // Tracking the positions makes `HeapBuffer` bigger.
+    #[cfg(not(all(debug_assertions, feature = "contract-checks")))]
+    const _: () = assert!(is_vec_like::<Vector<u32, HeapBuffer<u32>>>());
+    #[cfg(not(all(debug_assertions, feature = "contract-checks")))]
+    const _: () = assert!(is_vec_like::<
+        Vector<u32, crate::composites::ZstoBuffer<HeapBuffer<u32>>>,
+    >());
+    #[cfg(not(all(debug_assertions, feature = "contract-checks")))]
+    const _: () = assert!(is_vec_like::<
+        Vector<u32, crate::composites::ExponentialGrowthBuffer<HeapBuffer<u32>>>,
+    >());
+    #[cfg(feature = "allocator")]
+    const _: () = assert!(is_vec_like::<
+        Vector<u32, crate::base_buffers::AllocatorBuffer<u32>>,
+    >());
+
+    // The default buffer has inline space, so it cannot be as small as `Vec`,
+    // but it still has a niche.
+    const _: () =
+        assert!(std::mem::size_of::<Option<Vector<u32>>>() == std::mem::size_of::<Vector<u32>>());
+
+    // Compile-time checks: it must be covariant like `Vec`, including with the
+    // default buffer.
+    #[allow(dead_code)]
+    fn is_covariant<'a>(vec: Vector<&'static str>) -> Vector<&'a str> {
+        vec
+    }
+    #[allow(dead_code)]
+    fn is_covariant_with_heap<'a>(
+        vec: Vector<&'static str, HeapBuffer<&'static str>>,
+    ) -> Vector<&'a str, HeapBuffer<&'a str>> {
+        vec
+    }
+
+    #[test]
+    fn is_send_and_sync_when_its_elements_are() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Vector<u32>>();
+        assert_send_sync::<Vector<String, HeapBuffer<String>>>();
+        assert_send_sync::<InlineVector>();
+    }
+
+    #[test]
+    fn pushed_values_should_increase_len() {
+        let mut vec = InlineVector::new();
+        assert_eq!(vec.len(), 0);
+
+        vec.push(0);
+        assert_eq!(vec.len(), 1);
+
+        vec.push(1);
+        assert_eq!(vec.len(), 2);
+    }
+
+    #[test]
+    fn indexing_checks_the_bounds() {
+        let mut vec = InlineVector::new();
+        vec.push(1);
+        assert_eq!(*vec.index(0), 1);
+        *vec.mut_index(0) = 2;
+        // SAFETY: 0 < len
+        assert_eq!(*unsafe { vec.get_unchecked(0) }, 2);
+
+        // The buffer has space for it, but it's not filled.
+        assert_panic(|| *vec.index(1));
+    }
+
+    #[test]
+    fn chunk_by_groups_adjacent_elements() {
+        let mut vec = InlineVector::new();
+        assert!(vec.chunk_by(|a, b| a == b).next().is_none());
+
+        for i in [1, 3, 4, 6] {
+            vec.push(i);
+        }
+        let runs: Vec<_> = vec.chunk_by(|a, b| a % 2 == b % 2).collect();
+        let ranges: Vec<_> = runs.iter().map(|run| run.range()).collect();
+        assert_eq!(ranges, [0..2, 2..4]);
+        assert_eq!(runs[1].as_slice(), [4, 6]);
+        assert_eq!(runs[0].iter().copied().collect::<Vec<_>>(), [1, 3]);
+    }
+
+    #[test]
+    fn windows_and_chunks_give_views() {
+        let mut vec = InlineVector::new();
+        for i in 1..=4 {
+            vec.push(i);
+        }
+
+        let windows: Vec<_> = vec.windows(3).map(|w| w.range()).collect();
+        assert_eq!(windows, [0..3, 1..4]);
+        assert_eq!(vec.windows(5).len(), 0);
+
+        let mut chunks = vec.chunks(3);
+        assert_eq!(chunks.len(), 2);
+        let first = chunks.next().unwrap();
+        assert_eq!(first.get(2), Some(&3));
+        assert_eq!(first.get(3), None);
+        let last = chunks.next().unwrap();
+        assert_eq!(last.as_slice(), [4]);
+        assert!(chunks.next().is_none());
+
+        assert_panic(|| vec.chunks(0));
+    }
+
+    #[test]
+    fn building_from_repetitions() {
+        let vec = InlineVector::from_elem(5, 2);
+        assert_eq!(vec.iter_copied().collect::<Vec<_>>(), [5, 5]);
+        assert!(InlineVector::from_elem(5, 0).is_empty());
+
+        assert_eq!(vec.repeat(2).iter_copied().collect::<Vec<_>>(), [5; 4]);
+        assert!(vec.repeat(0).is_empty());
+        assert_panic(|| vec.repeat(3));
+
+        let vec = InlineVector::concat(&[vec.repeat(1), InlineVector::new(), vec.repeat(1)]);
+        assert_eq!(vec.iter_copied().collect::<Vec<_>>(), [5; 4]);
+        assert!(InlineVector::concat(&[]).is_empty());
+    }
+
+    #[test]
+    fn macros_make_vectors_from_literals() {
+        let vec: Vector<u32> = crate::vector![];
+        assert!(vec.is_empty());
+
+        let vec: InlineVector = crate::vector![in InlineBuffer<_, 4>; 1, 2,];
+        assert_eq!(vec.iter_copied().collect::<Vec<_>>(), [1, 2]);
+
+        let vec: FixedVector<u32, 4> = crate::fixed_vector![7; 3];
+        assert_eq!(vec.iter_copied().collect::<Vec<_>>(), [7, 7, 7]);
+        assert_panic(|| -> FixedVector<u32, 1> { crate::fixed_vector![1, 2] });
+
+        let vec: SmallVector<u32, 1> = crate::small_vector![1, 2];
+        assert_eq!(vec.iter_copied().collect::<Vec<_>>(), [1, 2]);
+    }
+
+    #[test]
+    fn casting_reinterprets_the_bytes() {
+        let mut bytes = Vector::<u8, HeapBuffer<u8>>::new();
+        bytes.extend_from_slice(&[1, 2, 3]);
+        let (error, bytes) = bytes.cast::<u16>().err().unwrap();
+        assert_eq!(error, CastError::Size);
+        assert_eq!(bytes.as_slice(), [1, 2, 3]);
+        let (error, bytes) = bytes.cast::<[u16; 0]>().err().unwrap();
+        assert_eq!(error, CastError::ZeroSized);
+        assert_eq!(bytes.as_slice(), [1, 2, 3]);
+
+        #[repr(align(2))]
+        struct Storage([MaybeUninit<u8>; 6]);
+        let mut storage = Storage([MaybeUninit::uninit(); 6]);
+        let mut bytes = Vector::from_buffer(SliceBuffer::from_slice(&mut storage.0));
+        bytes.extend_from_slice(&[1, 0, 2, 0]);
+
+        let mut values = bytes.cast::<u16>().ok().unwrap();
+        assert_eq!(values.len(), 2);
+        assert_eq!(values.capacity(), 3);
+        values.push(u16::from_ne_bytes([3, 0]));
+        values[0] = u16::from_ne_bytes([4, 0]);
+        assert!(values.try_push(0).is_err());
+
+        let bytes = values.into_bytes();
+        assert_eq!(bytes.iter_copied().collect::<Vec<_>>(), [4, 0, 2, 0, 3, 0]);
+    }
+
+    #[test]
+    fn slices_have_all_the_elements() {
+        let mut vec = Vector::<u32, HeapBuffer<u32>>::new();
+        assert_eq!(vec.as_slice(), []);
+        vec.extend_from_slice(&[1, 2, 3]);
+        vec.as_mut_slice()[1] = 5;
+        assert_eq!(vec.as_slice(), [1, 5, 3]);
+    }
+
+    #[test]
+    fn slice_storage_edits_the_slice_in_place() {
+        let mut array = [1u32, 2, 3, 4];
+        {
+            // SAFETY: Nothing uninitialized is written.
+            let mut vec = unsafe { Vector::from_slice_storage(&mut array, 2) };
+            assert_eq!(vec.as_slice(), [1, 2]);
+            assert_eq!(vec.capacity(), 4);
+            vec.push(5);
+            vec.swap_remove(0);
+            vec.truncate(1);
+            vec.push(6);
+        }
+        assert_eq!(array, [5, 6, 5, 4]);
+
+        assert_panic(|| {
+            // SAFETY: Nothing uninitialized is written.
+            let _ = unsafe { Vector::from_slice_storage(&mut [0u8; 1], 2) };
+        });
     }
-}
 
-impl<T, B> Vector<T, B>
-where
-    B: Buffer<Element = T> + ContiguousMemoryBuffer,
-{
-    /// Extracts a slice containing the entire vector
-    pub fn as_slice(&self) -> &[T] {
-        // SAFETY: values up to len exist
-        unsafe { self.buffer.slice(0..self.len) }
+    #[test]
+    fn rebuffering_moves_every_element() {
+        let counter = AtomicI64::new(0);
+        let mut vec = Vector::<_, InlineBuffer<_, 3>>::new();
+        for _ in 0..3 {
+            vec.push(LifeCounter::new(&counter));
+        }
+
+        let vec: Vector<_, HeapBuffer<_>> = vec.rebuffer();
+        assert_eq!(vec.len(), 3);
+        assert_eq!(counter.load(Ordering::SeqCst), 3);
+        drop(vec);
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
     }
 
-    /// Extracts a mutable slice containing the entire vector
-    pub fn as_mut_slice(&mut self) -> &mut [T] {
-        // SAFETY: values up to len exist
-        unsafe { self.buffer.mut_slice(0..self.len) }
+    #[test]
+    fn rebuffering_into_a_small_buffer_panics_without_leaking() {
+        let counter = AtomicI64::new(0);
+        let mut vec = Vector::<_, HeapBuffer<_>>::new();
+        for _ in 0..3 {
+            vec.push(LifeCounter::new(&counter));
+        }
+
+        assert_panic(|| {
+            let _: Vector<_, InlineBuffer<_, 2>> = vec.rebuffer_contiguous();
+        });
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
     }
-}
 
-impl<T, B> Default for Vector<T, B>
-where
-    B: Buffer<Element = T> + Default,
-{
-    fn default() -> Self {
-        Self::new()
+    #[test]
+    fn converting_into_an_array_moves_the_elements_only_if_they_fit() {
+        let counter = AtomicI64::new(0);
+        let mut vec = Vector::<_, HeapBuffer<_>>::new();
+        for _ in 0..3 {
+            vec.push(LifeCounter::new(&counter));
+        }
+
+        let vec = vec.try_into_array::<2>().err().unwrap();
+        assert_eq!(vec.len(), 3);
+        let array: [LifeCounter; 3] = vec.try_into_array().ok().unwrap();
+        assert_eq!(counter.load(Ordering::SeqCst), 3);
+        drop(array);
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+
+        let empty = Vector::<u32, InlineBuffer<u32, 2>>::new();
+        assert_eq!(empty.try_into_array::<0>().ok().unwrap(), []);
     }
-}
 
-impl<T, B: Buffer<Element = T>> Drop for Vector<T, B> {
-    fn drop(&mut self) {
-        // Safety: All the allocated elements are in 0 <= index < self.len.
-        unsafe {
-            self.buffer.manually_drop_range(0..self.len);
+    #[test]
+    fn filling_from_an_exact_iterator_rolls_back_when_it_panics() {
+        let counter = AtomicI64::new(0);
+        let mut vec = Vector::<_, HeapBuffer<_>>::new();
+        vec.push(LifeCounter::new(&counter));
+
+        let values = (0..4).map(|i| {
+            if i == 2 {
+                panic!("Failing on purpose");
+            }
+            LifeCounter::new(&counter)
+        });
+        assert_panic(std::panic::AssertUnwindSafe(|| {
+            vec.try_fill_from_exact(values)
+        }));
+        assert_eq!(vec.len(), 1);
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+
+        vec.try_fill_from_exact((0..3).map(|_| LifeCounter::new(&counter)))
+            .ok()
+            .unwrap();
+        assert_eq!(vec.len(), 4);
+        assert_eq!(counter.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn wrapping_the_buffer_keeps_the_elements_in_place() {
+        let counter = AtomicI64::new(0);
+        let mut vec = Vector::<_, HeapBuffer<_>>::new();
+        vec.push(LifeCounter::new(&counter));
+
+        let mut vec: Vector<_, AtLeastBuffer<8, _>> = vec.wrap_buffer();
+        vec.push(LifeCounter::new(&counter));
+        assert_eq!(vec.capacity(), 8);
+        let vec = vec.unwrap_buffer();
+        assert_eq!(vec.capacity(), 8);
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+        drop(vec);
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn rebuffering_contiguous_buffers_copies_the_elements() {
+        let mut vec = Vector::<u32, HeapBuffer<u32>>::new();
+        vec.extend_from_slice(&[1, 2, 3]);
+        let vec: Vector<u32, Box<InlineBuffer<u32, 4>>> = vec.rebuffer_contiguous();
+        assert_eq!(vec.as_slice(), [1, 2, 3]);
+
+        let empty = Vector::<u32, HeapBuffer<u32>>::new();
+        let empty: Vector<u32, InlineBuffer<u32, 1>> = empty.rebuffer_contiguous();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn spilled_small_vectors_give_their_heap_buffer() {
+        let mut small = SmallVector::<String, 2>::new();
+        for value in ["a", "b", "c"] {
+            small.push(value.to_string());
         }
+        let ptr = small.as_ptr();
+
+        let heap = Vector::<String, HeapBuffer<String>>::from(small);
+        assert_eq!(heap.as_ptr(), ptr);
+        assert_eq!(heap.as_slice(), ["a", "b", "c"]);
+
+        let small = SmallVector::<String, 2>::from(heap);
+        assert_eq!(small.as_ptr(), ptr);
+        assert_eq!(small.as_slice(), ["a", "b", "c"]);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::base_buffers::{HeapBuffer, InlineBuffer};
+    #[test]
+    fn inline_elements_are_moved_between_vector_kinds() {
+        let mut small = SmallVector::<String, 4>::new();
+        small.push("a".to_string());
+        let heap = Vector::<String, HeapBuffer<String>>::from(small);
+        assert_eq!(heap.as_slice(), ["a"]);
 
-    use super::*;
+        // Its buffer is not bigger than the inline one, so it isn't used.
+        let small = SmallVector::<String, 4>::from(heap);
+        assert_eq!(small.as_slice(), ["a"]);
 
-    type InlineVector = Vector<u32, InlineBuffer<u32, 4>>;
+        let fixed = FixedVector::<String, 1>::try_from(small).ok().unwrap();
+        let heap = Vector::<String, HeapBuffer<String>>::from(fixed);
+        assert_eq!(heap.as_slice(), ["a"]);
+    }
 
     #[test]
-    fn pushed_values_should_increase_len() {
-        let mut vec = InlineVector::new();
-        assert_eq!(vec.len(), 0);
+    fn converting_into_fixed_vectors_fails_if_they_dont_fit() {
+        let mut heap = Vector::<String, HeapBuffer<String>>::new();
+        heap.push("a".to_string());
+        heap.push("b".to_string());
 
-        vec.push(0);
+        let heap = FixedVector::<String, 1>::try_from(heap).err().unwrap();
+        assert_eq!(heap.as_slice(), ["a", "b"]);
+        let fixed = FixedVector::<String, 2>::try_from(heap).ok().unwrap();
+        assert_eq!(fixed.as_slice(), ["a", "b"]);
+    }
+
+    #[test]
+    fn pushing_arrays_needs_space_for_all_of_them() {
+        let mut vec = FixedVector::<String, 3>::new();
+        vec.push_array(["a".to_string(), "b".to_string()]);
+
+        let rejected = vec.try_push_array(["c".to_string(), "d".to_string()]);
+        assert_eq!(rejected.unwrap_err(), ["c", "d"]);
+        assert_eq!(vec.as_slice(), ["a", "b"]);
+
+        vec.push_array([]);
+        vec.push_array(["c".to_string()]);
+        assert_eq!(vec.as_slice(), ["a", "b", "c"]);
+        assert_panic(move || vec.push_array(["d".to_string()]));
+    }
+
+    #[test]
+    fn vectors_from_arrays_own_the_values() {
+        let counter = AtomicI64::new(0);
+        let mut vec = FixedVector::from([
+            LifeCounter::new(&counter),
+            LifeCounter::new(&counter),
+            LifeCounter::new(&counter),
+        ]);
+        assert_eq!(counter.load(Ordering::SeqCst), 3);
+
+        drop(vec.pop());
+        vec.push(LifeCounter::new(&counter));
+        assert_eq!(counter.load(Ordering::SeqCst), 3);
+        drop(vec);
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn extending_settles_for_partial_growth() {
+        let counter = AtomicI64::new(0);
+        let buffer = FaultyBuffer::<HeapBuffer<_>>::default()
+            .failing_grow_beyond(6, ResizeError::OutOfMemory);
+        let mut vec = Vector::from_buffer(buffer);
+
+        // It asks for all 10 at first, which doesn't fit.
+        let values = (0..10).map(|_| LifeCounter::new(&counter));
+        assert!(matches!(
+            vec.try_extend(values),
+            Err(ResizeError::OutOfMemory)
+        ));
+        assert_eq!(vec.len(), 6);
+        assert_eq!(counter.load(Ordering::SeqCst), 6);
+        drop(vec);
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn shrinking_incrementally_stops_when_it_cannot_shrink() {
+        let mut vec = Vector::from_buffer(FaultyBuffer::<HeapBuffer<u64>>::default());
+        vec.reserve_exact(10);
+        vec.push(1);
+        assert!(vec.shrink_to_fit_incremental(1));
+        assert_eq!(vec.capacity(), 9);
+
+        let mut vec =
+            Vector::from_buffer(FaultyBuffer::<HeapBuffer<u64>>::default().failing_shrink());
+        vec.reserve_exact(10);
+        assert!(!vec.shrink_to_fit_incremental(16));
+        assert_eq!(vec.capacity(), 10);
+    }
+
+    #[test]
+    fn cloning_from_slices_drops_the_clones_when_one_panics() {
+        let counter = AtomicI64::new(0);
+        let values = [
+            PanickingElement::new(&counter, 1),
+            PanickingElement::new(&counter, 2).panicking_on_clone(),
+        ];
+        assert_panic(|| Vector::<_, HeapBuffer<_>>::from_slice(&values));
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+
+        let vec = SmallVector::<_, 1>::from_slice(&values[..1]);
+        assert_eq!(vec.len(), 1);
+        assert_eq!(counter.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn resizing_drops_the_clones_when_one_panics() {
+        let counter = AtomicI64::new(0);
+        let clones_left = AtomicUsize::new(2);
+        let mut vec = Vector::<_, HeapBuffer<_>>::new();
+        vec.push(PanickingElement::new(&counter, 1));
+        let value = PanickingElement::new(&counter, 2).panicking_on_clone_after(&clones_left);
+        assert_panic(std::panic::AssertUnwindSafe(|| vec.resize(5, value)));
         assert_eq!(vec.len(), 1);
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+
+        clones_left.store(2, Ordering::SeqCst);
+        let value = PanickingElement::new(&counter, 2).panicking_on_clone_after(&clones_left);
+        vec.resize(3, value);
+        let values: Vec<_> = vec.as_slice().iter().map(|e| e.value()).collect();
+        assert_eq!(values, [1, 2, 2]);
+        assert_eq!(counter.load(Ordering::SeqCst), 3);
+    }
+
+    #[cfg(feature = "array")]
+    #[test]
+    fn rows_zip_the_columns() {
+        use crate::composites::ArrayBuffer;
+
+        let mut vec = Vector::<[String; 2], ArrayBuffer<2, HeapBuffer<String>>>::new();
+        vec.push(["a".to_string(), "b".to_string()]);
+        vec.push(["c".to_string(), "d".to_string()]);
+        for [first, second] in vec.rows_mut() {
+            std::mem::swap(first, second);
+        }
+        let rows: Vec<[&String; 2]> = vec.rows().collect();
+        assert_eq!(rows, [[&"b", &"a"], [&"d", &"c"]]);
+
+        let mut no_columns = Vector::<[u8; 0], ArrayBuffer<0, HeapBuffer<u8>>>::new();
+        assert_eq!(no_columns.rows().len(), 0);
+        assert_eq!(no_columns.rows_mut().count(), 0);
+    }
+
+    #[cfg(feature = "array")]
+    #[test]
+    fn columns_can_only_be_projected_once() {
+        use crate::composites::ArrayBuffer;
+
+        let mut vec = Vector::<[u8; 3], ArrayBuffer<3, HeapBuffer<u8>>>::new();
+        vec.push([1, 2, 3]);
+        assert_eq!(vec.project_mut([2, 1]).columns_mut(), [[3], [2]]);
+        assert_panic(std::panic::AssertUnwindSafe(|| {
+            vec.project_mut([0, 0]).len()
+        }));
+        assert_panic(|| vec.project([3]).len());
+        assert!(vec.project([]).rows().next().is_some());
+    }
+
+    #[cfg(feature = "array")]
+    #[test]
+    fn transferring_rows_drops_the_columns_left_behind() {
+        use crate::composites::ArrayBuffer;
+
+        let counter = AtomicI64::new(0);
+        let mut src = Vector::<[_; 2], ArrayBuffer<2, HeapBuffer<_>>>::new();
+        src.push([LifeCounter::new(&counter), LifeCounter::new(&counter)]);
+        src.push([LifeCounter::new(&counter), LifeCounter::new(&counter)]);
+        let mut dst = Vector::<[_; 1], ArrayBuffer<1, InlineBuffer<_, 1>>>::new();
+
+        let moved_to = src.transfer_matching_columns(1, &mut dst, [Some(1)], |_| unreachable!());
+        assert_eq!(moved_to, 0);
+        assert_eq!(counter.load(Ordering::SeqCst), 3);
+        assert_eq!(src.len(), 1);
+
+        // `dst` is full, so nothing is moved.
+        assert_panic(std::panic::AssertUnwindSafe(|| {
+            src.transfer_matching_columns(0, &mut dst, [Some(0)], |_| unreachable!())
+        }));
+        assert_eq!(src.len(), 1);
+        let mut wider = Vector::<[_; 2], ArrayBuffer<2, HeapBuffer<_>>>::new();
+        assert_panic(std::panic::AssertUnwindSafe(|| {
+            src.transfer_matching_columns(0, &mut wider, [Some(0), Some(0)], |_| unreachable!())
+        }));
+        assert_eq!(counter.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn taking_moves_the_buffer() {
+        let mut vec = Vector::<u32, HeapBuffer<u32>>::new();
+        vec.push(1);
+        let ptr = vec.as_ptr();
+
+        let taken = vec.take();
+        assert_eq!(taken.as_ptr(), ptr);
+        assert_eq!(taken.len(), 1);
+        // The empty one didn't allocate.
+        assert_eq!(vec.capacity(), 0);
+    }
 
+    #[test]
+    fn index_operator_checks_the_bounds() {
+        let mut vec = InlineVector::new();
         vec.push(1);
-        assert_eq!(vec.len(), 2);
+        vec[0] += 1;
+        assert_eq!(vec[0], 2);
+        assert_panic(|| vec[1]);
     }
 
     #[test]
@@ -568,6 +3917,143 @@ mod tests {
         assert!(vec.capacity() >= vec.len()); // This can probably be testes with a proptest
     }
 
+    #[test]
+    fn shrinking_keeps_the_values() {
+        let mut vec: Vector<u32, HeapBuffer<u32>> = Vector::new();
+        vec.reserve_exact(8);
+        vec.push(1);
+        vec.push(2);
+        vec.push(3);
+
+        vec.shrink_to(0);
+        assert_eq!(vec.capacity(), 3);
+        assert_eq!(vec.iter_copied().collect::<Vec<_>>(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn clearing_keeps_the_capacity() {
+        let counter = AtomicI64::new(0);
+        let mut vec: Vector<_, HeapBuffer<_>> = Vector::new();
+        vec.reserve_exact(8);
+        for _ in 0..3 {
+            vec.push(LifeCounter::new(&counter));
+        }
+
+        vec.clear();
+
+        assert_eq!(vec.len(), 0);
+        assert_eq!(vec.capacity(), 8);
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn ranges_past_usize_max_panic() {
+        use std::ops::Bound::{Excluded, Included, Unbounded};
+
+        let mut vec = InlineVector::from_elem(1, 3);
+        assert_panic(std::panic::AssertUnwindSafe(|| {
+            vec.drain((Excluded(usize::MAX), Unbounded));
+        }));
+        assert_panic(std::panic::AssertUnwindSafe(|| {
+            vec.drain((Included(0), Included(usize::MAX)));
+        }));
+        assert_eq!(vec.len(), 3);
+    }
+
+    /// Buffer for 4 elements split into two segments: two different buffers,
+    /// which are usually right after each other in memory.
+    #[derive(Default)]
+    struct Segmented([InlineBuffer<u32, 2>; 2]);
+
+    impl Buffer for Segmented {
+        type Element = u32;
+
+        fn capacity(&self) -> usize {
+            4
+        }
+
+        unsafe fn take(&mut self, index: usize) -> u32 {
+            // SAFETY: Same requirements.
+            unsafe { self.0[index / 2].take(index % 2) }
+        }
+
+        unsafe fn put(&mut self, index: usize, value: u32) {
+            // SAFETY: Same requirements.
+            unsafe { self.0[index / 2].put(index % 2, value) }
+        }
+
+        unsafe fn try_grow(&mut self, _target: usize) -> Result<(), ResizeError> {
+            Err(ResizeError::UnsupportedOperation)
+        }
+
+        unsafe fn try_shrink(&mut self, _target: usize) -> Result<(), ResizeError> {
+            Err(ResizeError::UnsupportedOperation)
+        }
+    }
+
+    impl PtrBuffer for Segmented {
+        type ConstantPointer = *const u32;
+        type MutablePointer = *mut u32;
+
+        unsafe fn ptr(&self, index: usize) -> *const u32 {
+            // SAFETY: Same requirements.
+            unsafe { self.0[index / 2].ptr(index % 2) }
+        }
+
+        unsafe fn mut_ptr(&mut self, index: usize) -> *mut u32 {
+            // SAFETY: Same requirements.
+            unsafe { self.0[index / 2].mut_ptr(index % 2) }
+        }
+
+        unsafe fn segment_len(&self, index: usize) -> usize {
+            2 - index % 2
+        }
+    }
+
+    #[test]
+    fn ptr_chunks_give_one_chunk_per_segment() {
+        let mut vec = Vector::<u32, Segmented>::new();
+        assert_eq!(vec.ptr_chunks().count(), 0);
+
+        vec.extend_from_slice(&[1, 2, 3]);
+        let chunks: Vec<_> = vec.ptr_chunks().collect();
+        let lens: Vec<_> = chunks.iter().map(|&(_, len)| len).collect();
+        assert_eq!(lens, [2, 1]);
+        let values = chunks.iter().flat_map(|&(ptr, len)| {
+            // SAFETY: Each chunk points to `len` elements.
+            unsafe { std::slice::from_raw_parts(ptr, len) }
+        });
+        assert_eq!(values.copied().collect::<Vec<_>>(), [1, 2, 3]);
+
+        let mut zsts = Vector::<(), ZstBuffer<()>>::new();
+        zsts.extend_from_slice(&[(), (), ()]);
+        let lens: Vec<_> = zsts.ptr_chunks().map(|(_, len)| len).collect();
+        assert_eq!(lens, [3]);
+    }
+
+    #[test]
+    fn remaining_capacity_follows_the_length() {
+        let mut vec: Vector<u8, HeapBuffer<_>> = Vector::new();
+        assert_eq!(vec.remaining_capacity(), 0);
+        vec.reserve_exact(4);
+        vec.extend_from_slice(&[1, 2, 3]);
+        assert_eq!(vec.remaining_capacity(), 1);
+        vec.push(4);
+        assert_eq!(vec.remaining_capacity(), 0);
+        vec.clear();
+        assert_eq!(vec.remaining_capacity(), 4);
+    }
+
+    #[test]
+    fn shrinking_fixed_size_vectors_does_nothing() {
+        let mut vec = InlineVector::new();
+        vec.push(1);
+        vec.shrink_to_fit();
+        vec.shrink_to(0);
+        assert_eq!(vec.capacity(), 4);
+        assert_eq!(vec.len(), 1);
+    }
+
     #[test]
     #[should_panic]
     fn should_panic_if_growing_is_not_allowed() {
@@ -605,4 +4091,277 @@ mod tests {
         *vec.mut_index(3) = 4;
         assert_eq!(*vec.index(3), 4);
     }
+
+    #[test]
+    fn copying_reads_do_not_empty_positions() {
+        let mut vec = InlineVector::new();
+        vec.push(1);
+        vec.push(2);
+
+        assert_eq!(vec.get_copy(1), Some(2));
+        assert_eq!(vec.get_copy(1), Some(2));
+        assert_eq!(vec.len(), 2);
+    }
+
+    #[test]
+    fn binary_search_finds_insertion_points() {
+        let mut vec = InlineVector::new();
+        vec.push(10);
+        vec.push(20);
+        vec.push(30);
+
+        assert_eq!(vec.binary_search(&5), Err(0));
+        assert_eq!(vec.binary_search(&20), Ok(1));
+        assert_eq!(vec.binary_search(&35), Err(3));
+    }
+
+    #[cfg(feature = "allocator")]
+    #[test]
+    fn allocator_vectors_keep_their_allocator() {
+        use crate::base_buffers::allocators::TrackingAllocator;
+
+        let alloc = TrackingAllocator::new(std::alloc::Global);
+        let mut vec = Vector::with_capacity_in(300, &alloc);
+        assert!(vec.capacity() >= 300);
+        assert_eq!(alloc.used(), vec.capacity() * 8);
+
+        vec.extend_from_slice(&[1u64; 300]);
+        vec.truncate(1);
+        vec.shrink_to_fit();
+        assert_eq!(alloc.used(), 0);
+        vec.extend_from_slice(&[2; 400]);
+        assert_eq!(alloc.used(), vec.capacity() * 8);
+    }
+
+    #[test]
+    fn reserving_past_usize_max_is_a_capacity_overflow() {
+        let mut vec = Vector::<u64, HeapBuffer<u64>>::new();
+        vec.push(1);
+        assert!(matches!(
+            vec.try_reserve(usize::MAX),
+            Err(ResizeError::CapacityOverflow)
+        ));
+        assert!(matches!(
+            vec.try_reserve_exact(usize::MAX),
+            Err(ResizeError::CapacityOverflow)
+        ));
+        assert!(matches!(
+            vec.try_reserve_exact(usize::MAX - 1),
+            Err(ResizeError::LayoutOverflow { .. })
+        ));
+        assert_eq!(vec.capacity(), 1);
+    }
+
+    #[test]
+    fn pushing_past_usize_max_is_a_capacity_overflow() {
+        let mut vec = Vector::<(), ZstoBuffer<HeapBuffer<()>>>::new();
+        vec.try_reserve_exact(usize::MAX).unwrap();
+        // SAFETY: Zero-sized values need no initialization, and the capacity
+        // is `usize::MAX`.
+        unsafe { vec.set_len(usize::MAX) };
+        assert!(matches!(
+            vec.try_push(()),
+            Err(ResizeError::CapacityOverflow)
+        ));
+        assert_panic(std::panic::AssertUnwindSafe(|| vec.insert(0, ())));
+        assert_eq!(vec.len(), usize::MAX);
+        // SAFETY: Zero-sized values have nothing to drop.
+        unsafe { vec.set_len(0) };
+    }
+
+    #[test]
+    fn rollback_grows_the_buffer_back() {
+        let mut vec = Vector::<String, SnapshotBuffer<HeapBuffer<String>>>::new();
+        vec.extend_from_slice(&["a".to_string(), "b".to_string()]);
+        vec.checkpoint();
+        assert!(vec.has_checkpoint());
+
+        vec.clear_and_shrink();
+        assert_eq!(vec.capacity(), 0);
+        assert!(vec.rollback());
+        assert_eq!(vec.as_slice(), ["a", "b"]);
+        assert!(!vec.has_checkpoint());
+    }
+
+    mod panic_safety {
+        use std::sync::atomic::{AtomicI64, Ordering};
+
+        use crate::test_utils::{panic::assert_panic, panicking::PanickingElement};
+
+        use super::*;
+
+        type PanickingVector<'a> = Vector<PanickingElement<'a>, HeapBuffer<PanickingElement<'a>>>;
+
+        /// Makes a vector with elements `0..len`, where `panicking` panics
+        /// when dropped.
+        fn make(counter: &AtomicI64, len: u32, panicking: u32) -> PanickingVector<'_> {
+            let mut vec = Vector::new();
+            for i in 0..len {
+                let element = PanickingElement::new(counter, i);
+                vec.push(if i == panicking {
+                    element.panicking_on_drop()
+                } else {
+                    element
+                });
+            }
+            vec
+        }
+
+        fn values(vec: &PanickingVector<'_>) -> Vec<u32> {
+            (0..vec.len()).map(|i| vec.index(i).value()).collect()
+        }
+
+        #[test]
+        fn truncate_drops_the_rest_when_one_panics() {
+            let counter = AtomicI64::new(0);
+            let mut vec = make(&counter, 5, 2);
+            assert_panic(std::panic::AssertUnwindSafe(|| vec.truncate(1)));
+            assert_eq!(vec.len(), 1);
+            assert_eq!(counter.load(Ordering::SeqCst), 1);
+        }
+
+        #[test]
+        fn dropping_in_place_keeps_the_rest_when_it_panics() {
+            let counter = AtomicI64::new(0);
+            let mut vec = make(&counter, 5, 1);
+            assert_panic(std::panic::AssertUnwindSafe(|| vec.swap_drop(1)));
+            assert_eq!(values(&vec), [0, 4, 2, 3]);
+            assert_eq!(counter.load(Ordering::SeqCst), 4);
+
+            let mut vec = make(&counter, 5, 1);
+            assert_panic(std::panic::AssertUnwindSafe(|| vec.drop_at(1)));
+            assert_eq!(values(&vec), [0, 2, 3, 4]);
+            assert_eq!(counter.load(Ordering::SeqCst), 8);
+        }
+
+        #[test]
+        fn dropping_drops_the_rest_when_one_panics() {
+            let counter = AtomicI64::new(0);
+            let vec = make(&counter, 5, 2);
+            assert_panic(move || drop(vec));
+            assert_eq!(counter.load(Ordering::SeqCst), 0);
+        }
+
+        #[test]
+        fn drain_moves_the_tail_back_when_one_panics() {
+            let counter = AtomicI64::new(0);
+            let mut vec = make(&counter, 6, 2);
+            assert_panic(std::panic::AssertUnwindSafe(|| {
+                let mut drain = vec.drain(1..4);
+                drain.next();
+            }));
+            assert_eq!(values(&vec), [0, 4, 5]);
+            assert_eq!(counter.load(Ordering::SeqCst), 3);
+        }
+
+        #[test]
+        fn drain_can_be_consumed_from_both_ends() {
+            let counter = AtomicI64::new(0);
+            let mut vec = make(&counter, 6, u32::MAX);
+            let mut drain = vec.drain(1..5);
+            assert_eq!(drain.len(), 4);
+            assert_eq!(drain.next_back().map(|e| e.value()), Some(4));
+            assert_eq!(drain.next().map(|e| e.value()), Some(1));
+            drop(drain);
+            assert_eq!(values(&vec), [0, 5]);
+            assert_eq!(counter.load(Ordering::SeqCst), 2);
+        }
+
+        #[test]
+        fn retain_keeps_the_rest_when_the_predicate_panics() {
+            let counter = AtomicI64::new(0);
+            let mut vec = make(&counter, 6, u32::MAX);
+            assert_panic(std::panic::AssertUnwindSafe(|| {
+                vec.retain(|e| {
+                    assert!(e.value() < 3);
+                    e.value() != 1
+                })
+            }));
+            assert_eq!(values(&vec), [0, 2, 3, 4, 5]);
+            assert_eq!(counter.load(Ordering::SeqCst), 5);
+        }
+
+        #[test]
+        fn retain_stays_consistent_when_dropping_panics() {
+            let counter = AtomicI64::new(0);
+            let mut vec = make(&counter, 5, 1);
+            assert_panic(std::panic::AssertUnwindSafe(|| {
+                vec.retain(|e| e.value() % 2 == 0)
+            }));
+            assert_eq!(values(&vec), [0, 2, 3, 4]);
+            assert_eq!(counter.load(Ordering::SeqCst), 4);
+        }
+
+        #[test]
+        fn extract_if_keeps_the_unchecked_elements_when_dropped_early() {
+            let counter = AtomicI64::new(0);
+            let mut vec = make(&counter, 6, u32::MAX);
+            let mut extracted = vec.extract_if(|e| e.value() % 2 == 1);
+            assert_eq!(extracted.next().map(|e| e.value()), Some(1));
+            drop(extracted);
+            assert_eq!(values(&vec), [0, 2, 3, 4, 5]);
+            assert_eq!(counter.load(Ordering::SeqCst), 5);
+        }
+
+        #[test]
+        fn extract_if_keeps_the_rest_when_the_predicate_panics() {
+            let counter = AtomicI64::new(0);
+            let mut vec = make(&counter, 6, u32::MAX);
+            assert_panic(std::panic::AssertUnwindSafe(|| {
+                vec.extract_if(|e| {
+                    assert!(e.value() < 3);
+                    e.value() == 1
+                })
+                .count()
+            }));
+            assert_eq!(values(&vec), [0, 2, 3, 4, 5]);
+            assert_eq!(counter.load(Ordering::SeqCst), 5);
+        }
+
+        #[test]
+        fn dedup_keeps_the_rest_when_the_comparison_panics() {
+            let counter = AtomicI64::new(0);
+            let mut vec = make(&counter, 5, u32::MAX);
+            assert_panic(std::panic::AssertUnwindSafe(|| {
+                vec.dedup_by(|a, b| {
+                    assert!(a.value() < 3);
+                    a.value() - b.value() == 1
+                })
+            }));
+            assert_eq!(values(&vec), [0, 2, 3, 4]);
+            assert_eq!(counter.load(Ordering::SeqCst), 4);
+        }
+
+        #[test]
+        fn extend_from_slice_keeps_clones_when_cloning_panics() {
+            let counter = AtomicI64::new(0);
+            let source = [
+                PanickingElement::new(&counter, 1),
+                PanickingElement::new(&counter, 2).panicking_on_clone(),
+            ];
+            let mut vec = make(&counter, 1, u32::MAX);
+            assert_panic(std::panic::AssertUnwindSafe(|| {
+                vec.extend_from_slice(&source)
+            }));
+            assert_eq!(values(&vec), [0, 1]);
+            assert_eq!(counter.load(Ordering::SeqCst), 4);
+        }
+
+        #[test]
+        fn checkpoint_keeps_the_clones_when_cloning_panics() {
+            let counter = AtomicI64::new(0);
+            let mut vec = Vector::<_, SnapshotBuffer<HeapBuffer<_>>>::new();
+            vec.push(PanickingElement::new(&counter, 0));
+            vec.push(PanickingElement::new(&counter, 1).panicking_on_clone());
+            assert_panic(std::panic::AssertUnwindSafe(|| vec.checkpoint()));
+            assert_eq!(counter.load(Ordering::SeqCst), 3);
+
+            vec.push(PanickingElement::new(&counter, 2));
+            assert!(vec.rollback());
+            assert_eq!(vec.len(), 1);
+            assert_eq!(vec.index(0).value(), 0);
+            drop(vec);
+            assert_eq!(counter.load(Ordering::SeqCst), 0);
+        }
+    }
 }
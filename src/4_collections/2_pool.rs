@@ -0,0 +1,198 @@
+use std::ops::{Deref, DerefMut};
+
+use crate::{base_buffers::heap::HeapBuffer, interface::Buffer, DefaultBuffer};
+
+use super::vec::Vector;
+
+#[cfg(not(feature = "sync"))]
+type IdleStorage<T> = std::cell::RefCell<T>;
+#[cfg(feature = "sync")]
+type IdleStorage<T> = crate::sync::Mutex<T>;
+
+/// Internal type. Vectors waiting to be lent.
+type IdleVectors<T, B> = Vector<Vector<T, B>, HeapBuffer<Vector<T, B>>>;
+
+/// Pool of reusable vectors.
+///
+/// It lends [`PooledVector`]s which, when dropped, get emptied (all their
+/// elements are dropped) and returned to the pool keeping their capacity. This
+/// is useful when similarly-sized vectors are rebuilt over and over (eg. once
+/// per request in a server), since after warming up the pool no more
+/// allocations are needed.
+///
+/// By default the pool can only be used from a single thread. Enabling the
+/// `sync` feature makes it use a mutex internally, so it can be shared between
+/// threads (as long as the vectors themselves can be sent).
+///
+/// # Example
+/// ```
+/// # use buffers::collections::pool::VectorPool;
+/// let pool = VectorPool::<u32>::new();
+/// {
+///     let mut vec = pool.get();
+///     vec.push(1);
+///     vec.push(2);
+/// } // `vec` is cleared and goes back into the pool here.
+///
+/// assert_eq!(pool.idle(), 1);
+/// assert_eq!(pool.get().len(), 0);
+/// ```
+pub struct VectorPool<T, B: Buffer<Element = T> = DefaultBuffer<T>> {
+    idle: IdleStorage<IdleVectors<T, B>>,
+}
+
+impl<T, B: Buffer<Element = T>> VectorPool<T, B> {
+    /// Creates a new empty pool.
+    pub fn new() -> Self {
+        Self {
+            idle: IdleStorage::new(Vector::new()),
+        }
+    }
+
+    /// Number of vectors currently waiting in the pool to be lent.
+    pub fn idle(&self) -> usize {
+        self.with_idle(|idle| idle.len())
+    }
+
+    /// Gives back a vector into the pool so it can be reused later. It will be
+    /// emptied before being stored.
+    ///
+    /// Note that [`PooledVector`] already does this automatically when dropped.
+    pub fn put_back(&self, mut vec: Vector<T, B>) {
+        vec.clear();
+        self.with_idle(|idle| {
+            idle.push(vec);
+        })
+    }
+
+    /// Internal utility that gives access to the idle vectors, independently of
+    /// the synchronization mechanism used.
+    #[cfg(not(feature = "sync"))]
+    fn with_idle<R>(&self, f: impl FnOnce(&mut IdleVectors<T, B>) -> R) -> R {
+        f(&mut self.idle.borrow_mut())
+    }
+
+    /// Internal utility that gives access to the idle vectors, independently of
+    /// the synchronization mechanism used.
+    #[cfg(feature = "sync")]
+    fn with_idle<R>(&self, f: impl FnOnce(&mut IdleVectors<T, B>) -> R) -> R {
+        // A poisoned pool only means that a thread panicked while pushing or
+        // popping; the idle vectors are still valid.
+        let mut idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut idle)
+    }
+}
+
+impl<T, B: Buffer<Element = T> + Default> VectorPool<T, B> {
+    /// Lends a vector from the pool. If there are no idle vectors, a new one
+    /// gets default-constructed.
+    ///
+    /// The returned vector is always empty, but it may have some capacity from
+    /// its previous uses.
+    pub fn get(&self) -> PooledVector<'_, T, B> {
+        let vec = self.with_idle(|idle| idle.pop()).unwrap_or_default();
+        PooledVector {
+            vec: Some(vec),
+            pool: self,
+        }
+    }
+}
+
+impl<T, B: Buffer<Element = T>> Default for VectorPool<T, B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Vector lent by a [`VectorPool`]. It works like a regular [`Vector`] (it
+/// dereferences into one) but it goes back into the pool when dropped.
+pub struct PooledVector<'a, T, B: Buffer<Element = T> = DefaultBuffer<T>> {
+    // It's only `None` after detaching or while dropping.
+    vec: Option<Vector<T, B>>,
+    pool: &'a VectorPool<T, B>,
+}
+
+impl<'a, T, B: Buffer<Element = T>> PooledVector<'a, T, B> {
+    /// Takes the vector out of the pool's control. It won't be returned into
+    /// the pool when dropped.
+    pub fn detach(mut self) -> Vector<T, B> {
+        self.vec
+            .take()
+            .expect("A pooled vector always has a value until dropped")
+    }
+}
+
+impl<'a, T, B: Buffer<Element = T>> Deref for PooledVector<'a, T, B> {
+    type Target = Vector<T, B>;
+
+    fn deref(&self) -> &Self::Target {
+        self.vec
+            .as_ref()
+            .expect("A pooled vector always has a value until dropped")
+    }
+}
+
+impl<'a, T, B: Buffer<Element = T>> DerefMut for PooledVector<'a, T, B> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.vec
+            .as_mut()
+            .expect("A pooled vector always has a value until dropped")
+    }
+}
+
+impl<'a, T, B: Buffer<Element = T>> Drop for PooledVector<'a, T, B> {
+    fn drop(&mut self) {
+        if let Some(vec) = self.vec.take() {
+            self.pool.put_back(vec);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicI64, Ordering};
+
+    use crate::{base_buffers::HeapBuffer, test_utils::life_counter::LifeCounter};
+
+    use super::*;
+
+    #[test]
+    fn returned_vectors_keep_their_capacity() {
+        let pool = VectorPool::<u32, HeapBuffer<u32>>::new();
+        {
+            let mut vec = pool.get();
+            vec.reserve(32);
+            vec.push(1);
+        }
+
+        let vec = pool.get();
+        assert_eq!(vec.len(), 0);
+        assert!(vec.capacity() >= 32);
+    }
+
+    #[test]
+    fn returned_vectors_drop_their_elements() {
+        let counter = AtomicI64::new(0);
+        let pool = VectorPool::<LifeCounter<'_>, HeapBuffer<_>>::new();
+        {
+            let mut vec = pool.get();
+            vec.reserve(2);
+            vec.push(LifeCounter::new(&counter));
+            vec.push(LifeCounter::new(&counter));
+            assert_eq!(counter.load(Ordering::SeqCst), 2);
+        }
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+        assert_eq!(pool.idle(), 1);
+    }
+
+    #[test]
+    fn detached_vectors_do_not_go_back_into_the_pool() {
+        let pool = VectorPool::<u32, HeapBuffer<u32>>::new();
+        let mut vec = pool.get().detach();
+        vec.reserve(1);
+        vec.push(1);
+
+        assert_eq!(pool.idle(), 0);
+        assert_eq!(vec.len(), 1);
+    }
+}
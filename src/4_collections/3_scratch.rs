@@ -0,0 +1,70 @@
+use std::mem::MaybeUninit;
+
+use crate::base_buffers::slice::SliceBuffer;
+
+use super::vec::Vector;
+
+/// Runs `f` with a temporary empty vector which uses `N` positions of scratch
+/// space in the caller's stack. This allows making temporary collections
+/// without any allocation.
+///
+/// The vector cannot grow beyond `N` elements. Whatever values are left in it
+/// are dropped when `f` returns (or panics).
+///
+/// # Example
+/// ```
+/// # use buffers::collections::with_scratch;
+/// let sum = with_scratch::<u32, 16, _>(|vec| {
+///     for i in 0..10 {
+///         vec.push(i);
+///     }
+///     (0..vec.len()).map(|i| *vec.index(i)).sum::<u32>()
+/// });
+/// assert_eq!(sum, 45);
+/// ```
+pub fn with_scratch<T, const N: usize, R>(
+    f: impl FnOnce(&mut Vector<T, SliceBuffer<'_, T>>) -> R,
+) -> R {
+    let mut scratch = [const { MaybeUninit::<T>::uninit() }; N];
+    let mut vec = Vector::from_buffer(SliceBuffer::from_slice(&mut scratch));
+    f(&mut vec)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicI64, Ordering};
+
+    use crate::test_utils::{life_counter::LifeCounter, panic::catch_panic_unwind_silent};
+
+    use super::*;
+
+    #[test]
+    fn scratch_vector_has_the_requested_capacity() {
+        let capacity = with_scratch::<u32, 10, _>(|vec| vec.capacity());
+        assert_eq!(capacity, 10);
+    }
+
+    #[test]
+    fn scratch_vector_drops_its_values_on_return() {
+        let counter = AtomicI64::new(0);
+        with_scratch::<_, 3, _>(|vec| {
+            vec.push(LifeCounter::new(&counter));
+            vec.push(LifeCounter::new(&counter));
+            assert_eq!(counter.load(Ordering::SeqCst), 2);
+        });
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn scratch_vector_drops_its_values_on_panic() {
+        let counter = AtomicI64::new(0);
+        let result = catch_panic_unwind_silent(|| {
+            with_scratch::<_, 3, ()>(|vec| {
+                vec.push(LifeCounter::new(&counter));
+                panic!("Panic while using the scratch vector");
+            })
+        });
+        assert!(result.is_err());
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+    }
+}
@@ -0,0 +1,499 @@
+use std::cmp::max;
+
+use crate::interface::{resize_error::ResizeError, Buffer};
+
+/// Unsafe core of a vector-like collection: a buffer plus how many of its
+/// positions are filled.
+///
+/// It keeps track that positions `0..len` are filled and the rest are empty,
+/// and handles growing the buffer, so collections built on top of it (like
+/// [`super::Vector`]) don't need to repeat that logic. It's meant for
+/// collection authors, similarly to `RawVec` in the standard library.
+///
+/// Unlike a collection it doesn't check bounds (the unchecked methods are
+/// `unsafe`) and it never drops its elements: dropping it only drops the
+/// buffer, leaking whatever is filled. Owners are expected to call
+/// [`RawVector::truncate`] when dropped.
+///
+/// # Example
+/// ```
+/// # use buffers::{base_buffers::HeapBuffer, collections::raw_vec::RawVector};
+/// let mut raw = RawVector::from_buffer(HeapBuffer::<u32>::new());
+/// raw.try_push(1).unwrap();
+/// raw.try_push(2).unwrap();
+/// // SAFETY: There are 2 elements.
+/// assert_eq!(unsafe { raw.remove_unchecked(0) }, 1);
+/// assert_eq!(raw.len(), 1);
+/// raw.truncate(0);
+/// ```
+pub struct RawVector<B: Buffer> {
+    len: usize,
+    buffer: B,
+}
+
+impl<B: Buffer> RawVector<B> {
+    /// Makes a new empty raw vector using the given (empty) buffer.
+    pub const fn from_buffer(buffer: B) -> Self {
+        Self { len: 0, buffer }
+    }
+
+    /// Makes a raw vector with the first `len` positions of `buffer` filled.
+    ///
+    /// # Safety
+    ///   * `len` must be less or equal to the capacity.
+    ///   * Positions `0..len` must be filled and the rest must be empty.
+    pub const unsafe fn from_parts(buffer: B, len: usize) -> Self {
+        Self { len, buffer }
+    }
+
+    /// Number of filled positions.
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no positions are filled.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Capacity of the underlying buffer.
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    /// Changes how many positions are considered filled.
+    ///
+    /// # Safety
+    ///   * `len` must be less or equal to the capacity.
+    ///   * Positions `0..len` must be filled and the rest must be empty.
+    pub unsafe fn set_len(&mut self, len: usize) {
+        debug_assert!(len <= self.capacity());
+        self.len = len;
+    }
+
+    /// Reference to the underlying buffer.
+    pub fn buffer(&self) -> &B {
+        &self.buffer
+    }
+
+    /// Mutable reference to the underlying buffer.
+    ///
+    /// # Safety
+    /// Whatever is done with the buffer, positions `0..len` must be filled and
+    /// the rest must be empty when the raw vector is used again. It may be
+    /// broken temporarily (eg. to move elements around).
+    pub unsafe fn buffer_mut(&mut self) -> &mut B {
+        &mut self.buffer
+    }
+
+    /// Gives back the underlying buffer. Filled positions are leaked.
+    pub fn into_buffer(self) -> B {
+        self.buffer
+    }
+
+    /// Tries to make space for at least `additional` more elements, growing
+    /// as the buffer prefers (see [`Buffer::preferred_grow_target`]). It never
+    /// asks for less than needed, whatever the buffer prefers.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), ResizeError> {
+        let required = self
+            .len
+            .checked_add(additional)
+            .ok_or(ResizeError::CapacityOverflow)?;
+        let capacity = self.capacity();
+        if required > capacity {
+            let preferred = self.buffer.preferred_grow_target(capacity, required);
+            // It's a safe method, so it can't be trusted to be big enough.
+            let target = max(preferred, required);
+            // SAFETY: It's at least `required`, which is bigger than the
+            // current capacity.
+            unsafe { self.buffer.try_grow(target) }?;
+            self.check_capacity_contract(target);
+            Ok(())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Tries to make space for `preferred` more elements (growing as the
+    /// buffer prefers), settling for `additional` if it cannot (see
+    /// [`Buffer::try_grow_at_least`]). Returns the capacity.
+    pub fn try_reserve_at_least(
+        &mut self,
+        additional: usize,
+        preferred: usize,
+    ) -> Result<usize, ResizeError> {
+        let min = self
+            .len
+            .checked_add(additional)
+            .ok_or(ResizeError::CapacityOverflow)?;
+        let capacity = self.capacity();
+        if min <= capacity {
+            return Ok(capacity);
+        }
+        let wanted = max(self.len.saturating_add(preferred), min);
+        let preferred = self.buffer.preferred_grow_target(capacity, wanted);
+        // It's a safe method, so it can't be trusted to be big enough.
+        let target = max(preferred, wanted);
+        // SAFETY: `min` is bigger than the current capacity and `target` is
+        // at least `wanted`, which is at least `min`.
+        unsafe { self.buffer.try_grow_at_least(min, target) }
+    }
+
+    /// Tries to make space for exactly `additional` more elements (if it
+    /// doesn't have it already).
+    pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), ResizeError> {
+        let target = self
+            .len
+            .checked_add(additional)
+            .ok_or(ResizeError::CapacityOverflow)?;
+        if target > self.capacity() {
+            // SAFETY: It's bigger than the current capacity.
+            unsafe { self.buffer.try_grow(target) }?;
+            self.check_capacity_contract(target);
+            Ok(())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Internal utility that checks (in debug builds) that growing to
+    /// `target` followed [`Buffer::CAPACITY_CONTRACT`].
+    #[inline]
+    fn check_capacity_contract(&self, target: usize) {
+        debug_assert!(
+            B::CAPACITY_CONTRACT.allows(target, self.capacity()),
+            "Growing to {target} gave a capacity of {}, which breaks {:?}",
+            self.capacity(),
+            B::CAPACITY_CONTRACT
+        );
+    }
+
+    /// Tries to shrink the buffer, keeping space for at least `min_capacity`
+    /// elements (and always the filled ones).
+    pub fn try_shrink_to(&mut self, min_capacity: usize) -> Result<(), ResizeError> {
+        let target = max(min_capacity, self.len);
+        if target < self.capacity() {
+            // SAFETY: `target` is smaller than the capacity and positions from
+            // `len` onwards are empty.
+            unsafe { self.buffer.try_shrink(target) }
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Adds a value at the end, growing if necessary. Returns its index.
+    #[inline]
+    pub fn try_push(&mut self, value: B::Element) -> Result<usize, ResizeError> {
+        if self.len >= self.capacity() {
+            self.grow_for_push()?;
+        }
+        let index = self.len;
+        // SAFETY: There is space for one more.
+        unsafe { self.push_unchecked(value) };
+        Ok(index)
+    }
+
+    /// Slow path of [`RawVector::try_push`], kept out of line so pushing with
+    /// space left stays small.
+    #[cold]
+    #[inline(never)]
+    fn grow_for_push(&mut self) -> Result<(), ResizeError> {
+        self.try_reserve(1)
+    }
+
+    /// Adds a value at the end without checking for space.
+    ///
+    /// # Safety
+    /// `len` must be less than the capacity.
+    #[inline]
+    pub unsafe fn push_unchecked(&mut self, value: B::Element) {
+        debug_assert!(self.len < self.capacity());
+        // SAFETY: Position `len` is empty and the caller ensures it's valid.
+        unsafe { self.buffer.put(self.len, value) };
+        self.len += 1;
+    }
+
+    /// Appends clones of `value` until there are `new_len` values, filling
+    /// all the positions at once (see [`Buffer::fill_range`]). If cloning
+    /// panics, nothing is appended.
+    ///
+    /// # Safety
+    /// `new_len` must be at least the length and at most the capacity.
+    pub unsafe fn fill_unchecked(&mut self, new_len: usize, value: &B::Element)
+    where
+        B::Element: Clone,
+    {
+        debug_assert!(self.len <= new_len && new_len <= self.capacity());
+        // SAFETY: The positions after `len` are empty and the caller ensures
+        // that they are valid.
+        unsafe { self.buffer.fill_range(self.len..new_len, value) };
+        self.len = new_len;
+    }
+
+    /// Removes the last value, if any.
+    pub fn pop(&mut self) -> Option<B::Element> {
+        if self.len > 0 {
+            self.len -= 1;
+            // SAFETY: It was the last filled position.
+            Some(unsafe { self.buffer.take(self.len) })
+        } else {
+            None
+        }
+    }
+
+    /// Inserts a value at `index`, moving the ones after it one position to
+    /// the right.
+    ///
+    /// # Safety
+    ///   * `index` must be less or equal to `len`.
+    ///   * `len` must be less than the capacity.
+    pub unsafe fn insert_unchecked(&mut self, index: usize, value: B::Element) {
+        debug_assert!(index <= self.len && self.len < self.capacity());
+        // SAFETY: `index..len` is filled and the caller ensures that position
+        // `len` is valid (and it's empty).
+        unsafe { self.buffer.shift_right(index..self.len, 1) };
+        // SAFETY: After shifting, `index` is empty.
+        unsafe { self.buffer.put(index, value) };
+        self.len += 1;
+    }
+
+    /// Removes the value at `index`, moving the ones after it one position to
+    /// the left.
+    ///
+    /// # Safety
+    /// `index` must be less than `len`.
+    pub unsafe fn remove_unchecked(&mut self, index: usize) -> B::Element {
+        debug_assert!(index < self.len);
+        // SAFETY: The caller ensures that it's filled.
+        let value = unsafe { self.buffer.take(index) };
+        // SAFETY: `(index + 1)..len` is filled and `index` was just emptied.
+        unsafe { self.buffer.shift_left((index + 1)..self.len, 1) };
+        self.len -= 1;
+        value
+    }
+
+    /// Removes the value at `index`, replacing it with the last one (see
+    /// [`Buffer::swap_remove_into`]).
+    ///
+    /// # Safety
+    /// `index` must be less than `len`.
+    pub unsafe fn swap_remove_unchecked(&mut self, index: usize) -> B::Element {
+        debug_assert!(index < self.len);
+        self.len -= 1;
+        // SAFETY: The caller ensures that `index` is filled, and `len` was the
+        // last filled position.
+        unsafe { self.buffer.swap_remove_into(index, self.len) }
+    }
+
+    /// Drops the value at `index` in place (without moving it out), then moves
+    /// the last value into its position.
+    ///
+    /// If dropping the value panics, the last value is still moved.
+    ///
+    /// # Safety
+    /// `index` must be less than `len`.
+    pub unsafe fn swap_drop_unchecked(&mut self, index: usize) {
+        debug_assert!(index < self.len);
+
+        /// Moves the last value into the hole, even while unwinding.
+        struct FillHole<'a, B: Buffer> {
+            raw: &'a mut RawVector<B>,
+            hole: usize,
+        }
+        impl<B: Buffer> Drop for FillHole<'_, B> {
+            fn drop(&mut self) {
+                if self.hole != self.raw.len {
+                    // SAFETY: It was the last filled position.
+                    let last = unsafe { self.raw.buffer.take(self.raw.len) };
+                    // SAFETY: The hole was emptied by dropping its value.
+                    unsafe { self.raw.buffer.put(self.hole, last) };
+                }
+            }
+        }
+
+        self.len -= 1;
+        let guard = FillHole {
+            raw: self,
+            hole: index,
+        };
+        // SAFETY: The caller ensures that it's filled.
+        unsafe { guard.raw.buffer.manually_drop(index) };
+    }
+
+    /// Drops the value at `index` in place (without moving it out), then moves
+    /// the ones after it one position to the left.
+    ///
+    /// If dropping the value panics, the rest are still moved.
+    ///
+    /// # Safety
+    /// `index` must be less than `len`.
+    pub unsafe fn drop_unchecked(&mut self, index: usize) {
+        debug_assert!(index < self.len);
+
+        /// Closes the gap, even while unwinding.
+        struct CloseGap<'a, B: Buffer> {
+            raw: &'a mut RawVector<B>,
+            gap: usize,
+            old_len: usize,
+        }
+        impl<B: Buffer> Drop for CloseGap<'_, B> {
+            fn drop(&mut self) {
+                // SAFETY: `gap + 1..old_len` is filled and the gap is empty.
+                unsafe { self.raw.buffer.shift_left(self.gap + 1..self.old_len, 1) };
+                self.raw.len = self.old_len - 1;
+            }
+        }
+
+        let old_len = self.len;
+        // If the guard is forgotten, the values after `index` leak instead of
+        // being dropped twice.
+        self.len = index;
+        let guard = CloseGap {
+            raw: self,
+            gap: index,
+            old_len,
+        };
+        // SAFETY: The caller ensures that it's filled.
+        unsafe { guard.raw.buffer.manually_drop(index) };
+    }
+
+    /// Drops the values from `len` onwards. Does nothing if there are fewer
+    /// values.
+    ///
+    /// If dropping a value panics, the rest are still dropped.
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len {
+            let old_len = self.len;
+            // The length is updated first so nothing gets dropped twice if
+            // dropping a value panics.
+            self.len = len;
+            // SAFETY: `len..old_len` is filled.
+            unsafe { self.buffer.manually_drop_range(len..old_len) };
+        }
+    }
+}
+
+impl<B: Buffer + Default> Default for RawVector<B> {
+    fn default() -> Self {
+        Self::from_buffer(Default::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicI64, Ordering};
+
+    use crate::{
+        base_buffers::{HeapBuffer, InlineBuffer},
+        composites::{grow_mock::GrowMockBuffer, AtLeastBuffer, ExponentialGrowthBuffer},
+        interface::indirect_buffer::IndirectBuffer,
+        test_utils::{faulty::FaultyBuffer, life_counter::LifeCounter},
+    };
+
+    use super::*;
+
+    #[test]
+    fn keeps_track_of_the_filled_positions() {
+        let mut raw = RawVector::from_buffer(InlineBuffer::<u32, 4>::new());
+        raw.try_push(1).unwrap();
+        raw.try_push(3).unwrap();
+        // SAFETY: 1 <= len < capacity.
+        unsafe { raw.insert_unchecked(1, 2) };
+        assert_eq!(raw.len(), 3);
+
+        // SAFETY: 0 < len.
+        assert_eq!(unsafe { raw.swap_remove_unchecked(0) }, 1);
+        assert_eq!(raw.pop(), Some(2));
+        assert_eq!(raw.pop(), Some(3));
+        assert_eq!(raw.pop(), None);
+    }
+
+    #[test]
+    fn grows_when_pushing() {
+        let mut raw = RawVector::<HeapBuffer<u32>>::default();
+        for i in 0..10 {
+            assert_eq!(raw.try_push(i).unwrap(), i as usize);
+        }
+        assert!(raw.capacity() >= 10);
+        assert!(matches!(
+            raw.try_reserve_exact(usize::MAX),
+            Err(ResizeError::CapacityOverflow)
+        ));
+    }
+
+    #[test]
+    fn reserving_at_least_settles_for_the_minimum() {
+        let buffer = FaultyBuffer::<HeapBuffer<u32>>::default()
+            .failing_grow_beyond(6, ResizeError::OutOfMemory);
+        let mut raw = RawVector::from_buffer(buffer);
+
+        assert_eq!(raw.try_reserve_at_least(2, 4).unwrap(), 4);
+        assert_eq!(raw.try_reserve_at_least(5, 10).unwrap(), 5);
+        assert_eq!(raw.try_reserve_at_least(1, 1).unwrap(), 5);
+        assert!(matches!(
+            raw.try_reserve_at_least(7, 7),
+            Err(ResizeError::OutOfMemory)
+        ));
+        assert_eq!(raw.capacity(), 5);
+    }
+
+    #[test]
+    fn only_truncating_drops_values() {
+        let counter = AtomicI64::new(0);
+        let mut raw = RawVector::<HeapBuffer<_>>::default();
+        raw.try_push(LifeCounter::new(&counter)).unwrap();
+        raw.try_push(LifeCounter::new(&counter)).unwrap();
+
+        raw.truncate(1);
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+        raw.truncate(0);
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn reserving_follows_the_growth_policy_of_the_buffer() {
+        type Policy = AtLeastBuffer<4, ExponentialGrowthBuffer<HeapBuffer<u32>>>;
+        let mut raw = RawVector::from_buffer(GrowMockBuffer::<Policy>::default());
+
+        raw.try_reserve(1).unwrap();
+        assert_eq!(raw.buffer().last_target(), 4);
+        raw.try_reserve(5).unwrap();
+        assert_eq!(raw.buffer().last_target(), 8);
+        raw.try_reserve_exact(9).unwrap();
+        assert_eq!(raw.buffer().last_target(), 9);
+    }
+
+    #[test]
+    fn reserving_never_asks_for_less_than_needed() {
+        /// Buffer whose growth policy is broken: it prefers not to grow.
+        struct StingyBuffer(GrowMockBuffer<HeapBuffer<u32>>);
+
+        impl IndirectBuffer for StingyBuffer {
+            type InnerBuffer = GrowMockBuffer<HeapBuffer<u32>>;
+            type InnerBufferRef<'a> = &'a Self::InnerBuffer where Self: 'a;
+            type InnerBufferMutRef<'a> = &'a mut Self::InnerBuffer where Self: 'a;
+
+            fn inner(&self) -> &Self::InnerBuffer {
+                &self.0
+            }
+
+            fn inner_mut(&mut self) -> &mut Self::InnerBuffer {
+                &mut self.0
+            }
+
+            fn preferred_grow_target(&self, current: usize, _required: usize) -> usize {
+                current.max(1)
+            }
+        }
+
+        let mut raw = RawVector::from_buffer(StingyBuffer(Default::default()));
+        for i in 0..4 {
+            raw.try_push(i).unwrap();
+        }
+        assert_eq!(raw.buffer().0.last_target(), 4);
+        raw.try_reserve(3).unwrap();
+        assert_eq!(raw.buffer().0.last_target(), 7);
+        assert_eq!(raw.try_reserve_at_least(4, 6).unwrap(), 10);
+        assert_eq!(raw.buffer().0.last_target(), 10);
+    }
+}
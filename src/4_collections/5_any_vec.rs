@@ -0,0 +1,377 @@
+use std::any::{type_name, TypeId};
+
+use crate::{
+    base_buffers::erased::{ErasedBuffer, ErasedLayout},
+    interface::resize_error::ResizeError,
+};
+
+/// Vector the element type of which is only known at runtime.
+///
+/// It stores its elements in an [`ErasedBuffer`] and remembers their
+/// [`TypeId`], so typed accesses are checked at runtime. Moving elements
+/// between vectors of the same type doesn't need to know it, which makes it
+/// useful for dynamic component systems (eg. moving an entity's components
+/// from one table to another).
+///
+/// # Example
+/// ```
+/// # use buffers::collections::any_vec::AnyVec;
+/// let mut vec = AnyVec::new::<String>();
+/// vec.push(String::from("a"));
+/// vec.push(String::from("b"));
+///
+/// assert_eq!(vec.get::<String>(1).map(String::as_str), Some("b"));
+/// assert_eq!(vec.get::<u32>(1), None);
+/// assert_eq!(vec.downcast_slice::<String>().unwrap().len(), 2);
+/// ```
+pub struct AnyVec {
+    len: usize,
+    buffer: ErasedBuffer,
+    type_id: TypeId,
+    type_name: &'static str,
+}
+
+impl AnyVec {
+    /// Makes a new empty vector for elements of type `T`.
+    pub fn new<T: 'static>() -> Self {
+        Self {
+            len: 0,
+            buffer: ErasedBuffer::new(ErasedLayout::of::<T>()),
+            type_id: TypeId::of::<T>(),
+            type_name: type_name::<T>(),
+        }
+    }
+
+    /// [`TypeId`] of the elements.
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    /// Whether the elements are of type `T`.
+    pub fn is<T: 'static>(&self) -> bool {
+        self.type_id == TypeId::of::<T>()
+    }
+
+    /// Returns the number of elements.
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether there are no elements.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Queries the buffer for its capacity.
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    /// Returns how many more elements fit without growing.
+    pub fn remaining_capacity(&self) -> usize {
+        self.capacity() - self.len
+    }
+
+    /// Tries to reserve capacity for at least `additional` more elements. It
+    /// may reserve more, so consecutive pushes don't need to grow every time.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), ResizeError> {
+        let target = self
+            .len
+            .checked_add(additional)
+            .ok_or(ResizeError::CapacityOverflow)?;
+        if target > self.capacity() {
+            let target = std::cmp::max(target, self.capacity().saturating_mul(2));
+            // SAFETY: It's bigger than the current capacity.
+            unsafe { self.buffer.try_grow(target) }
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Reserves capacity for at least `additional` more elements.
+    ///
+    /// # Panics
+    /// Panics if it cannot grow (eg. it runs out of memory).
+    pub fn reserve(&mut self, additional: usize) {
+        self.try_reserve(additional)
+            .expect("Couldn't reserve the necessary space")
+    }
+
+    /// Adds a value at the end.
+    ///
+    /// # Panics
+    /// Panics if `T` isn't the element type or if it cannot grow.
+    pub fn push<T: 'static>(&mut self, value: T) {
+        self.assert_type::<T>();
+        self.reserve(1);
+        // SAFETY: The type matches and `len` is valid and empty.
+        unsafe { self.buffer.put_as(self.len, value) };
+        self.len += 1;
+    }
+
+    /// Removes the last value and returns it, or `None` if it's empty.
+    ///
+    /// # Panics
+    /// Panics if `T` isn't the element type.
+    pub fn pop<T: 'static>(&mut self) -> Option<T> {
+        self.assert_type::<T>();
+        if self.len > 0 {
+            self.len -= 1;
+            // SAFETY: The type matches and it was the last filled position.
+            Some(unsafe { self.buffer.take_as(self.len) })
+        } else {
+            None
+        }
+    }
+
+    /// Reference to the element at `index`. Returns `None` if it's out of
+    /// bounds or `T` isn't the element type.
+    pub fn get<T: 'static>(&self, index: usize) -> Option<&T> {
+        self.downcast_slice().and_then(|slice| slice.get(index))
+    }
+
+    /// Mutable reference to the element at `index`. Returns `None` if it's out
+    /// of bounds or `T` isn't the element type.
+    pub fn get_mut<T: 'static>(&mut self, index: usize) -> Option<&mut T> {
+        self.downcast_mut_slice()
+            .and_then(|slice| slice.get_mut(index))
+    }
+
+    /// All the elements as a slice, or `None` if `T` isn't the element type.
+    pub fn downcast_slice<T: 'static>(&self) -> Option<&[T]> {
+        if !self.is::<T>() {
+            return None;
+        }
+        if self.len == 0 {
+            return Some(&[]);
+        }
+        // SAFETY: It's not empty, so 0 is valid.
+        let ptr = unsafe { self.buffer.ptr(0) };
+        // SAFETY: The type matches and `0..len` are filled and contiguous.
+        Some(unsafe { std::slice::from_raw_parts(ptr.cast::<T>(), self.len) })
+    }
+
+    /// All the elements as a mutable slice, or `None` if `T` isn't the element
+    /// type.
+    pub fn downcast_mut_slice<T: 'static>(&mut self) -> Option<&mut [T]> {
+        if !self.is::<T>() {
+            return None;
+        }
+        if self.len == 0 {
+            return Some(&mut []);
+        }
+        // SAFETY: It's not empty, so 0 is valid.
+        let ptr = unsafe { self.buffer.mut_ptr(0) };
+        // SAFETY: The type matches and `0..len` are filled and contiguous.
+        Some(unsafe { std::slice::from_raw_parts_mut(ptr.cast::<T>(), self.len) })
+    }
+
+    /// Moves all the elements of `other` at the end of this vector (in a
+    /// single copy), leaving `other` empty.
+    ///
+    /// # Panics
+    /// Panics if the element types are different or if it cannot grow.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::any_vec::AnyVec;
+    /// let mut a = AnyVec::new::<u32>();
+    /// a.push(1u32);
+    /// let mut b = AnyVec::new::<u32>();
+    /// b.push(2u32);
+    /// b.push(3u32);
+    ///
+    /// a.append(&mut b);
+    ///
+    /// assert_eq!(a.downcast_slice::<u32>(), Some(&[1, 2, 3][..]));
+    /// assert!(b.is_empty());
+    /// ```
+    pub fn append(&mut self, other: &mut AnyVec) {
+        self.assert_same_type(other);
+        if other.is_empty() {
+            return;
+        }
+        self.reserve(other.len);
+        let count = other.len;
+        // The elements are owned by `self` from now on.
+        other.len = 0;
+        // SAFETY: `other` had elements, so 0 is valid.
+        let values = unsafe { other.buffer.ptr(0) };
+        // SAFETY: There is space for `count` values after `len`, and `values`
+        // points to `count` values of the same type (which are moved).
+        unsafe { self.buffer.put_many(self.len, values, count) };
+        self.len += count;
+    }
+
+    /// Moves the element at `index` to the end of `other`, replacing it with
+    /// the last one. It doesn't need to know the element type.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds, if the element types are different
+    /// or if `other` cannot grow.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::any_vec::AnyVec;
+    /// let mut a = AnyVec::new::<u32>();
+    /// a.push(1u32);
+    /// a.push(2u32);
+    /// a.push(3u32);
+    /// let mut b = AnyVec::new::<u32>();
+    ///
+    /// a.swap_remove_into(0, &mut b);
+    ///
+    /// assert_eq!(a.downcast_slice::<u32>(), Some(&[3, 2][..]));
+    /// assert_eq!(b.downcast_slice::<u32>(), Some(&[1][..]));
+    /// ```
+    pub fn swap_remove_into(&mut self, index: usize, other: &mut AnyVec) {
+        if index >= self.len {
+            panic!("Index out of bounds")
+        }
+        self.assert_same_type(other);
+        other.reserve(1);
+
+        self.len -= 1;
+        // SAFETY: `index` is filled.
+        let value = unsafe { self.buffer.ptr(index) };
+        // SAFETY: `other.len` is valid and empty, and the value is moved.
+        unsafe { other.buffer.put(other.len, value) };
+        other.len += 1;
+
+        if index != self.len {
+            // SAFETY: It was the last filled position.
+            let last = unsafe { self.buffer.ptr(self.len) };
+            // SAFETY: `index` was just moved out, and the last value is moved.
+            unsafe { self.buffer.put(index, last) };
+        }
+    }
+
+    /// Drops the element at `index`, replacing it with the last one. It
+    /// doesn't need to know the element type.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn swap_remove_drop(&mut self, index: usize) {
+        if index >= self.len {
+            panic!("Index out of bounds")
+        }
+        self.len -= 1;
+        if index != self.len {
+            // The removed value is swapped to the end first so the vector is
+            // consistent even if dropping it panics.
+            // SAFETY: It was the last filled position.
+            let last = unsafe { self.buffer.ptr(self.len) };
+            // SAFETY: `index` is valid and different from the last position.
+            let removed = unsafe { self.buffer.mut_ptr(index) };
+            // SAFETY: Both are filled and don't overlap.
+            unsafe {
+                std::ptr::swap_nonoverlapping(
+                    removed,
+                    last.cast_mut(),
+                    self.buffer.layout().layout().size(),
+                )
+            };
+        }
+        // SAFETY: It's filled (with the removed value).
+        unsafe { self.buffer.manually_drop(self.len) };
+    }
+
+    /// Keeps the first `len` elements and drops the rest.
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len {
+            let old_len = self.len;
+            // The length is updated first so nothing gets dropped twice if
+            // dropping a value panics.
+            self.len = len;
+            // SAFETY: `len..old_len` is filled.
+            unsafe { self.buffer.manually_drop_range(len..old_len) };
+        }
+    }
+
+    /// Drops all the elements, keeping the capacity.
+    pub fn clear(&mut self) {
+        self.truncate(0)
+    }
+
+    /// Internal utility that panics if `T` isn't the element type.
+    fn assert_type<T: 'static>(&self) {
+        if !self.is::<T>() {
+            panic!(
+                "Type mismatch: the vector stores `{}` but `{}` was used",
+                self.type_name,
+                type_name::<T>(),
+            )
+        }
+    }
+
+    /// Internal utility that panics if `other` stores a different type.
+    fn assert_same_type(&self, other: &AnyVec) {
+        if self.type_id != other.type_id {
+            panic!(
+                "Type mismatch: the vector stores `{}` but the other one stores `{}`",
+                self.type_name, other.type_name,
+            )
+        }
+    }
+}
+
+impl Drop for AnyVec {
+    fn drop(&mut self) {
+        self.truncate(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicI64, Ordering};
+
+    use crate::test_utils::{life_counter::LifeCounter, panic::assert_panic};
+
+    use super::*;
+
+    #[test]
+    fn checks_the_type_at_runtime() {
+        let mut vec = AnyVec::new::<u32>();
+        vec.push(1u32);
+        assert!(vec.is::<u32>());
+        assert_eq!(vec.get::<u64>(0), None);
+        assert_eq!(vec.downcast_slice::<i32>(), None);
+
+        let mut vec = AnyVec::new::<u32>();
+        assert_panic(move || vec.push(1u64));
+    }
+
+    #[test]
+    fn moves_elements_between_vectors() {
+        // `TypeId` only works with `'static` types.
+        static COUNTER: AtomicI64 = AtomicI64::new(0);
+        let counter = &COUNTER;
+        let mut a = AnyVec::new::<LifeCounter<'static>>();
+        let mut b = AnyVec::new::<LifeCounter<'static>>();
+        for _ in 0..3 {
+            a.push(LifeCounter::new(counter));
+            b.push(LifeCounter::new(counter));
+        }
+
+        a.swap_remove_into(0, &mut b);
+        assert_eq!((a.len(), b.len()), (2, 4));
+        a.append(&mut b);
+        assert_eq!((a.len(), b.len()), (6, 0));
+        assert_eq!(counter.load(Ordering::SeqCst), 6);
+
+        a.swap_remove_drop(1);
+        assert_eq!(counter.load(Ordering::SeqCst), 5);
+        drop(a);
+        drop(b);
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn zero_sized_elements_are_supported() {
+        let mut vec = AnyVec::new::<()>();
+        vec.push(());
+        vec.push(());
+        assert_eq!(vec.downcast_slice::<()>().map(<[()]>::len), Some(2));
+        assert_eq!(vec.pop::<()>(), Some(()));
+    }
+}
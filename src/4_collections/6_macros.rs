@@ -0,0 +1,85 @@
+//! Macros to make vectors from literals, like [`vec!`].
+
+/// Makes a [`crate::collections::Vector`] with the given elements, like
+/// [`vec!`]. It reserves exactly the space needed up front.
+///
+/// By default it uses [`crate::DefaultBuffer`]. Another buffer can be given
+/// with `in Buffer;` before the elements.
+///
+/// # Panics
+/// Panics if the buffer cannot hold the elements.
+///
+/// # Example
+/// ```
+/// # use buffers::{base_buffers::HeapBuffer, vector};
+/// let vec = vector![1, 2, 3];
+/// assert_eq!(vec.iter_copied().collect::<Vec<u32>>(), [1, 2, 3]);
+///
+/// let vec = vector![0; 4];
+/// assert_eq!(vec.len(), 4);
+///
+/// let vec = vector![in HeapBuffer<_>; 1, 2];
+/// assert_eq!(vec.capacity(), 2);
+/// ```
+#[macro_export]
+macro_rules! vector {
+    (in $buffer:ty $(;)?) => {
+        $crate::collections::Vector::<_, $buffer>::new()
+    };
+    (in $buffer:ty; $value:expr; $n:expr) => {
+        $crate::collections::Vector::<_, $buffer>::from_elem($value, $n)
+    };
+    (in $buffer:ty; $($value:expr),+ $(,)?) => {{
+        let values = [$($value),+];
+        let mut vec = $crate::collections::Vector::<_, $buffer>::new();
+        vec.try_reserve_exact(values.len())
+            .expect("Couldn't reserve the necessary space");
+        for value in values {
+            vec.push(value);
+        }
+        vec
+    }};
+    ($($elements:tt)*) => {
+        $crate::vector![in $crate::DefaultBuffer<_>; $($elements)*]
+    };
+}
+
+/// Same as [`vector!`] but makes a [`crate::collections::SmallVector`]. Its
+/// inline capacity is inferred from the type.
+///
+/// # Example
+/// ```
+/// # use buffers::{collections::SmallVector, small_vector};
+/// let vec: SmallVector<u32, 2> = small_vector![1, 2, 3];
+/// assert_eq!(vec.len(), 3);
+/// ```
+#[macro_export]
+macro_rules! small_vector {
+    ($($elements:tt)*) => {
+        $crate::vector![
+            in $crate::composites::ZstoBuffer<
+                $crate::composites::SvoBuffer<_, $crate::base_buffers::HeapBuffer<_>>,
+            >;
+            $($elements)*
+        ]
+    };
+}
+
+/// Same as [`vector!`] but makes a [`crate::collections::FixedVector`]. Its
+/// capacity is inferred from the type.
+///
+/// # Panics
+/// Panics if there are more elements than its capacity.
+///
+/// # Example
+/// ```
+/// # use buffers::{collections::FixedVector, fixed_vector};
+/// let vec: FixedVector<u32, 4> = fixed_vector![1, 2, 3];
+/// assert_eq!(vec.capacity(), 4);
+/// ```
+#[macro_export]
+macro_rules! fixed_vector {
+    ($($elements:tt)*) => {
+        $crate::vector![in $crate::base_buffers::InlineBuffer<_, _>; $($elements)*]
+    };
+}
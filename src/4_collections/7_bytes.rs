@@ -0,0 +1,568 @@
+//! Helpers to use a `Vector<u8, B>` as raw bytes (eg. for codecs).
+
+use std::{
+    fmt,
+    io::{self, IoSlice},
+    ops::Range,
+};
+
+use crate::interface::{contiguous_memory::ContiguousMemoryBuffer, ptrs::PtrBuffer, Buffer};
+
+use super::Vector;
+
+/// Order of the bytes of a number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ByteOrder {
+    /// Least significant byte first.
+    Little,
+    /// Most significant byte first (network order).
+    Big,
+}
+
+/// Numbers that can be read from and written to bytes in a [`ByteOrder`].
+pub trait FromToBytes: Copy {
+    /// Bytes of the number (an array of its size).
+    type Bytes: AsRef<[u8]> + AsMut<[u8]> + Default;
+
+    /// Makes the number from its bytes.
+    fn from_bytes(bytes: Self::Bytes, order: ByteOrder) -> Self;
+
+    /// Gives the bytes of the number.
+    fn to_bytes(self, order: ByteOrder) -> Self::Bytes;
+}
+
+macro_rules! impl_from_to_bytes {
+    ($($t:ty),*) => {
+        $(
+            impl FromToBytes for $t {
+                type Bytes = [u8; std::mem::size_of::<$t>()];
+
+                fn from_bytes(bytes: Self::Bytes, order: ByteOrder) -> Self {
+                    match order {
+                        ByteOrder::Little => <$t>::from_le_bytes(bytes),
+                        ByteOrder::Big => <$t>::from_be_bytes(bytes),
+                    }
+                }
+
+                fn to_bytes(self, order: ByteOrder) -> Self::Bytes {
+                    match order {
+                        ByteOrder::Little => self.to_le_bytes(),
+                        ByteOrder::Big => self.to_be_bytes(),
+                    }
+                }
+            }
+        )*
+    };
+}
+impl_from_to_bytes!(u16, u32, u64, u128, i16, i32, i64, i128, f32, f64);
+
+/// Error when accessing bytes which are not in the vector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OutOfBounds {
+    /// Offset of the first byte that was accessed.
+    pub offset: usize,
+    /// How many bytes were accessed.
+    pub size: usize,
+    /// Length of the vector.
+    pub len: usize,
+}
+
+impl<B> Vector<u8, B>
+where
+    B: Buffer<Element = u8> + ContiguousMemoryBuffer,
+{
+    /// Reads a number from the bytes starting at `offset` (which may be
+    /// unaligned). Returns `None` if they aren't all in the vector.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::{bytes::ByteOrder, Vector};
+    /// let mut vec = Vector::<u8>::new();
+    /// vec.extend_from_slice(&[0xff, 0x12, 0x34]);
+    /// assert_eq!(vec.read_at::<u16>(1, ByteOrder::Big), Some(0x1234));
+    /// assert_eq!(vec.read_at::<u16>(1, ByteOrder::Little), Some(0x3412));
+    /// assert_eq!(vec.read_at::<u16>(2, ByteOrder::Big), None);
+    /// ```
+    pub fn read_at<N: FromToBytes>(&self, offset: usize, order: ByteOrder) -> Option<N> {
+        let mut bytes = N::Bytes::default();
+        let range = self.bytes_range(offset, bytes.as_ref().len()).ok()?;
+        for (byte, index) in bytes.as_mut().iter_mut().zip(range) {
+            *byte = self[index];
+        }
+        Some(N::from_bytes(bytes, order))
+    }
+
+    /// Overwrites the bytes starting at `offset` (which may be unaligned) with
+    /// the ones of `value`. Fails if they aren't all in the vector (it doesn't
+    /// grow).
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::{bytes::ByteOrder, Vector};
+    /// let mut vec = Vector::<u8>::from_elem(0, 4);
+    /// vec.write_at(1, 0x1234u16, ByteOrder::Little).unwrap();
+    /// assert_eq!(vec.iter_copied().collect::<Vec<_>>(), [0, 0x34, 0x12, 0]);
+    /// assert!(vec.write_at(3, 0u16, ByteOrder::Little).is_err());
+    /// ```
+    pub fn write_at<N: FromToBytes>(
+        &mut self,
+        offset: usize,
+        value: N,
+        order: ByteOrder,
+    ) -> Result<(), OutOfBounds> {
+        let bytes = value.to_bytes(order);
+        let range = self.bytes_range(offset, bytes.as_ref().len())?;
+        for (&byte, index) in bytes.as_ref().iter().zip(range) {
+            self[index] = byte;
+        }
+        Ok(())
+    }
+
+    /// Internal utility that checks that `size` bytes from `offset` are in
+    /// the vector.
+    fn bytes_range(&self, offset: usize, size: usize) -> Result<Range<usize>, OutOfBounds> {
+        let len = self.len();
+        match offset.checked_add(size) {
+            Some(end) if end <= len => Ok(offset..end),
+            _ => Err(OutOfBounds { offset, size, len }),
+        }
+    }
+}
+
+impl<B: Buffer<Element = u8>> Vector<u8, B> {
+    /// Appends `value` as a varint (unsigned LEB128): 7 bits per byte, least
+    /// significant first, with the highest bit set on all but the last byte.
+    /// It takes between 1 and 10 bytes. Returns how many.
+    ///
+    /// # Panics
+    /// Panics if it cannot grow.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u8>::new();
+    /// assert_eq!(vec.put_varint(300), 2);
+    /// assert_eq!(vec.iter_copied().collect::<Vec<_>>(), [0b1010_1100, 0b10]);
+    /// ```
+    pub fn put_varint(&mut self, mut value: u64) -> usize {
+        let mut written = 1;
+        while value >= 0x80 {
+            self.push((value as u8) | 0x80);
+            value >>= 7;
+            written += 1;
+        }
+        self.push(value as u8);
+        written
+    }
+
+    /// Appends `bytes` preceded by their length as a varint (see
+    /// [`Vector::put_varint`]), making a frame that
+    /// [`Vector::frames`] can split.
+    ///
+    /// # Panics
+    /// Panics if it cannot grow.
+    pub fn put_length_prefixed(&mut self, bytes: &[u8]) {
+        self.try_reserve(bytes.len().saturating_add(1))
+            .expect("Couldn't reserve the necessary space");
+        self.put_varint(bytes.len() as u64);
+        self.extend_from_slice(bytes);
+    }
+
+    /// Gives a [`io::Write`] adapter which appends to the vector (eg. to
+    /// stream the output of a compressor directly into it).
+    ///
+    /// # Example
+    /// ```
+    /// # use std::io::Write;
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u8>::new();
+    /// let mut writer = vec.writer();
+    /// writer.reserve_hint(5);
+    /// write!(writer, "{}-{}", 12, 34).unwrap();
+    /// assert_eq!(vec.iter_copied().collect::<Vec<_>>(), b"12-34");
+    /// ```
+    pub fn writer(&mut self) -> VectorWriter<'_, B> {
+        VectorWriter { vec: self }
+    }
+}
+
+impl<B> Vector<u8, B>
+where
+    B: Buffer<Element = u8> + ContiguousMemoryBuffer,
+{
+    /// Reads the varint (see [`Vector::put_varint`]) starting at `offset`.
+    /// Returns its value and how many bytes it takes, or `None` if it's
+    /// truncated or doesn't fit in a `u64`.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u8>::new();
+    /// vec.push(7);
+    /// vec.put_varint(300);
+    /// vec.push(0x80); // Truncated
+    /// assert_eq!(vec.get_varint(1), Some((300, 2)));
+    /// assert_eq!(vec.get_varint(3), None);
+    /// ```
+    pub fn get_varint(&self, offset: usize) -> Option<(u64, usize)> {
+        let mut value = 0u64;
+        for (position, index) in (offset..self.len()).enumerate() {
+            let byte = self[index];
+            let shift = 7 * position as u32;
+            let bits = u64::from(byte & 0x7f);
+            // The 10th byte may only hold the highest bit.
+            if shift >= 64 || (bits << shift) >> shift != bits {
+                return None;
+            }
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Some((value, position + 1));
+            }
+        }
+        None
+    }
+
+    /// Iterates over the frames written with [`Vector::put_length_prefixed`],
+    /// giving the range of the bytes of each (without the length).
+    ///
+    /// It stops at the first incomplete frame, which is useful when the bytes
+    /// come in parts: [`Frames::consumed`] tells where it starts.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u8>::new();
+    /// vec.put_length_prefixed(b"ab");
+    /// vec.put_length_prefixed(b"");
+    /// vec.push(5); // Incomplete
+    ///
+    /// let mut frames = vec.frames();
+    /// assert_eq!(frames.next(), Some(1..3));
+    /// assert_eq!(frames.next(), Some(4..4));
+    /// assert_eq!(frames.next(), None);
+    /// assert_eq!(frames.consumed(), 4);
+    /// ```
+    pub fn frames(&self) -> Frames<'_, B> {
+        Frames {
+            vec: self,
+            consumed: 0,
+        }
+    }
+}
+
+impl<B> Vector<u8, B>
+where
+    B: Buffer<Element = u8> + PtrBuffer<ConstantPointer = *const u8>,
+{
+    /// Gives the bytes as [`IoSlice`]s, one for each segment of the buffer
+    /// (see [`Vector::ptr_chunks`]), so they can all be written with a single
+    /// [`io::Write::write_vectored`] (eg. one `writev` syscall) even if the
+    /// buffer isn't contiguous. Contiguous buffers give a single slice.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::io::Write;
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u8>::new();
+    /// vec.extend_from_slice(b"hello");
+    ///
+    /// let mut out = Vec::new();
+    /// assert_eq!(out.write_vectored(&vec.as_io_slices()).unwrap(), 5);
+    /// assert_eq!(out, b"hello");
+    /// ```
+    pub fn as_io_slices(&self) -> Vec<IoSlice<'_>> {
+        self.ptr_chunks()
+            .map(|(ptr, len)| {
+                // SAFETY: The chunk is inside a single segment of the buffer
+                // (so `ptr` can reach all of it) and it has `len` filled
+                // positions, which stay borrowed as long as the slice.
+                let bytes = unsafe { std::slice::from_raw_parts(ptr, len) };
+                IoSlice::new(bytes)
+            })
+            .collect()
+    }
+}
+
+/// Iterator over length-prefixed frames. See [`Vector::frames`].
+pub struct Frames<'a, B: Buffer<Element = u8>> {
+    vec: &'a Vector<u8, B>,
+    consumed: usize,
+}
+
+impl<B: Buffer<Element = u8>> Frames<'_, B> {
+    /// How many bytes the complete frames so far take. The rest are an
+    /// incomplete frame (if any).
+    pub fn consumed(&self) -> usize {
+        self.consumed
+    }
+}
+
+impl<B> Iterator for Frames<'_, B>
+where
+    B: Buffer<Element = u8> + ContiguousMemoryBuffer,
+{
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (len, prefix) = self.vec.get_varint(self.consumed)?;
+        let start = self.consumed + prefix;
+        let len = usize::try_from(len).ok()?;
+        let range = self.vec.bytes_range(start, len).ok()?;
+        self.consumed = range.end;
+        Some(range)
+    }
+}
+
+/// [`io::Write`] adapter that appends to a byte vector. See
+/// [`Vector::writer`].
+///
+/// Writes grow the vector as needed. If it cannot grow (eg. it's full and
+/// uses an inline buffer), they write as much as fits, like writing into a
+/// slice.
+pub struct VectorWriter<'a, B: Buffer<Element = u8>> {
+    vec: &'a mut Vector<u8, B>,
+}
+
+impl<B: Buffer<Element = u8>> VectorWriter<'_, B> {
+    /// Lets the writer know that about `additional` bytes are coming, so it
+    /// can reserve the space at once. It's only a hint: if it cannot reserve,
+    /// nothing happens.
+    pub fn reserve_hint(&mut self, additional: usize) {
+        let _ = self.vec.try_reserve(additional);
+    }
+
+    /// Appends as much of `bytes` as fits (after trying to make space for all
+    /// of them). Returns how many were written.
+    fn append(&mut self, bytes: &[u8]) -> usize {
+        let _ = self.vec.try_reserve(bytes.len());
+        let spare = self.vec.capacity() - self.vec.len();
+        let written = std::cmp::min(spare, bytes.len());
+        self.vec.extend_from_slice(&bytes[..written]);
+        written
+    }
+}
+
+impl<B: Buffer<Element = u8>> io::Write for VectorWriter<'_, B> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(self.append(buf))
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        let total = bufs.iter().map(|buf| buf.len()).sum();
+        self.reserve_hint(total);
+        let mut written = 0;
+        for buf in bufs {
+            let appended = self.append(buf);
+            written += appended;
+            if appended < buf.len() {
+                break;
+            }
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Formatting text into a byte vector (as UTF-8) with [`write!`]. It works on
+/// any buffer, which makes it useful to format into inline storage without
+/// allocating.
+///
+/// Each piece of text is either written completely or not at all: if there is
+/// no space for it (and the vector cannot grow), it fails with [`fmt::Error`]
+/// and keeps what was written before.
+///
+/// # Example
+/// ```
+/// # use std::fmt::Write;
+/// # use buffers::collections::FixedVector;
+/// let mut line = FixedVector::<u8, 8>::new();
+/// write!(line, "{}+{}", 12, 34).unwrap();
+/// assert_eq!(line.as_slice(), b"12+34");
+///
+/// assert!(write!(line, "{}", 5678).is_err());
+/// assert_eq!(line.as_slice(), b"12+34");
+/// ```
+impl<B: Buffer<Element = u8>> fmt::Write for Vector<u8, B> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.try_reserve(s.len()).map_err(|_| fmt::Error)?;
+        self.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        base_buffers::{HeapBuffer, InlineBuffer},
+        interface::ResizeError,
+    };
+
+    use super::*;
+
+    #[test]
+    fn reads_what_was_written() {
+        let mut vec = Vector::<u8, HeapBuffer<u8>>::from_elem(0, 11);
+        vec.write_at(1, 1.5f64, ByteOrder::Big).unwrap();
+        vec.write_at(9, -2i16, ByteOrder::Little).unwrap();
+
+        assert_eq!(vec.read_at::<f64>(1, ByteOrder::Big), Some(1.5));
+        assert_eq!(vec.read_at::<i16>(9, ByteOrder::Little), Some(-2));
+        assert_eq!(vec.read_at::<u16>(9, ByteOrder::Big), Some(0xfeff));
+    }
+
+    #[test]
+    fn checks_the_bounds() {
+        let mut vec = Vector::<u8, HeapBuffer<u8>>::from_elem(0, 4);
+        assert_eq!(vec.read_at::<u32>(1, ByteOrder::Little), None);
+        assert_eq!(vec.read_at::<u16>(usize::MAX, ByteOrder::Little), None);
+        assert_eq!(
+            vec.write_at(2, 0u32, ByteOrder::Little),
+            Err(OutOfBounds {
+                offset: 2,
+                size: 4,
+                len: 4
+            })
+        );
+        assert_eq!(vec.iter_copied().collect::<Vec<_>>(), [0; 4]);
+    }
+
+    #[test]
+    fn io_slices_cover_all_the_bytes() {
+        let mut vec = Vector::<u8, HeapBuffer<u8>>::new();
+        assert!(vec.as_io_slices().is_empty());
+
+        vec.extend_from_slice(&[1, 2, 3]);
+        let slices = vec.as_io_slices();
+        assert_eq!(slices.len(), 1);
+        assert_eq!(&*slices[0], [1, 2, 3]);
+    }
+
+    /// Buffer made of two separate buffers, without segments of its own.
+    #[derive(Default)]
+    struct Split([InlineBuffer<u8, 2>; 2]);
+
+    impl Buffer for Split {
+        type Element = u8;
+
+        fn capacity(&self) -> usize {
+            4
+        }
+
+        unsafe fn take(&mut self, index: usize) -> u8 {
+            // SAFETY: Same requirements.
+            unsafe { self.0[index / 2].take(index % 2) }
+        }
+
+        unsafe fn put(&mut self, index: usize, value: u8) {
+            // SAFETY: Same requirements.
+            unsafe { self.0[index / 2].put(index % 2, value) }
+        }
+
+        unsafe fn try_grow(&mut self, _target: usize) -> Result<(), ResizeError> {
+            Err(ResizeError::UnsupportedOperation)
+        }
+
+        unsafe fn try_shrink(&mut self, _target: usize) -> Result<(), ResizeError> {
+            Err(ResizeError::UnsupportedOperation)
+        }
+    }
+
+    impl PtrBuffer for Split {
+        type ConstantPointer = *const u8;
+        type MutablePointer = *mut u8;
+
+        unsafe fn ptr(&self, index: usize) -> *const u8 {
+            // SAFETY: Same requirements.
+            unsafe { self.0[index / 2].ptr(index % 2) }
+        }
+
+        unsafe fn mut_ptr(&mut self, index: usize) -> *mut u8 {
+            // SAFETY: Same requirements.
+            unsafe { self.0[index / 2].mut_ptr(index % 2) }
+        }
+    }
+
+    #[test]
+    fn io_slices_never_span_separate_buffers() {
+        let mut vec = Vector::<u8, Split>::new();
+        vec.extend_from_slice(&[1, 2, 3]);
+        let slices = vec.as_io_slices();
+        let slices: Vec<_> = slices.iter().map(|slice| &**slice).collect();
+        assert_eq!(slices, [[1], [2], [3]]);
+    }
+
+    #[test]
+    fn writing_appends_and_reserves_once() {
+        let mut vec = Vector::<u8, HeapBuffer<u8>>::new();
+        vec.push(0);
+        let mut writer = vec.writer();
+        let bufs = [IoSlice::new(&[1, 2]), IoSlice::new(&[]), IoSlice::new(&[3])];
+        assert_eq!(io::Write::write_vectored(&mut writer, &bufs).unwrap(), 3);
+        assert_eq!(vec.capacity(), 4);
+        assert_eq!(vec.iter_copied().collect::<Vec<_>>(), [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn writing_into_a_full_vector_writes_what_fits() {
+        let mut vec = Vector::<u8, InlineBuffer<u8, 4>>::new();
+        let mut writer = vec.writer();
+        let bufs = [IoSlice::new(&[1, 2, 3]), IoSlice::new(&[4, 5])];
+        assert_eq!(io::Write::write_vectored(&mut writer, &bufs).unwrap(), 4);
+        let result = io::Write::write_all(&mut writer, &[6]);
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::WriteZero);
+        assert_eq!(vec.iter_copied().collect::<Vec<_>>(), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn formatting_appends_the_text() {
+        use std::fmt::Write;
+
+        let mut vec = Vector::<u8, HeapBuffer<u8>>::new();
+        write!(vec, "{:>4}|{:.1}", "ab", 1.25).unwrap();
+        writeln!(vec).unwrap();
+        assert_eq!(vec.iter_copied().collect::<Vec<_>>(), b"  ab|1.2\n");
+    }
+
+    #[test]
+    fn varints_round_trip() {
+        let mut vec = Vector::<u8, HeapBuffer<u8>>::new();
+        let values = [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX];
+        for value in values {
+            vec.put_varint(value);
+        }
+
+        let mut offset = 0;
+        for value in values {
+            let (read, size) = vec.get_varint(offset).unwrap();
+            assert_eq!(read, value);
+            offset += size;
+        }
+        assert_eq!(offset, vec.len());
+    }
+
+    #[test]
+    fn rejects_varints_that_overflow() {
+        let mut vec = Vector::<u8, HeapBuffer<u8>>::from_elem(0xff, 9);
+        vec.push(0x02);
+        assert_eq!(vec.get_varint(0), None);
+    }
+
+    #[test]
+    fn frames_can_come_in_parts() {
+        let mut vec = Vector::<u8, HeapBuffer<u8>>::new();
+        vec.put_length_prefixed(&[1; 200]);
+        let full = vec.len();
+        vec.put_length_prefixed(&[2, 3]);
+        vec.truncate(full + 2);
+
+        let mut frames = vec.frames();
+        assert_eq!(frames.next(), Some(2..202));
+        assert_eq!(frames.next(), None);
+        assert_eq!(frames.consumed(), full);
+    }
+}
@@ -0,0 +1,290 @@
+//! Hex and base64 encoding of byte vectors, writing into a caller-provided
+//! vector. Requires the `encoding` feature.
+//!
+//! Each operation reserves all the space it needs in the destination at once
+//! and appends to it, so the same destination can be reused (eg. cleared
+//! between messages) without allocating again.
+
+use crate::interface::{contiguous_memory::ContiguousMemoryBuffer, Buffer};
+
+use super::Vector;
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+const BASE64_DIGITS: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64_PADDING: u8 = b'=';
+
+/// Errors when decoding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DecodeError {
+    /// The byte at this offset isn't valid in the encoding (or in that
+    /// position).
+    InvalidByte(usize),
+
+    /// The number of bytes can't be the result of encoding.
+    InvalidLength,
+}
+
+impl<B> Vector<u8, B>
+where
+    B: Buffer<Element = u8> + ContiguousMemoryBuffer,
+{
+    /// Appends the bytes encoded as lowercase hexadecimal to `out`.
+    ///
+    /// # Panics
+    /// Panics if `out` cannot grow.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut bytes = Vector::<u8>::new();
+    /// bytes.extend_from_slice(&[0x01, 0xab]);
+    /// let mut out = Vector::<u8>::new();
+    /// bytes.encode_hex_into(&mut out);
+    /// assert_eq!(out.iter_copied().collect::<Vec<_>>(), b"01ab");
+    /// ```
+    pub fn encode_hex_into<O: Buffer<Element = u8>>(&self, out: &mut Vector<u8, O>) {
+        reserve(out, self.len().checked_mul(2));
+        for index in 0..self.len() {
+            let byte = self[index];
+            out.push(HEX_DIGITS[usize::from(byte >> 4)]);
+            out.push(HEX_DIGITS[usize::from(byte & 0xf)]);
+        }
+    }
+
+    /// Decodes the bytes as hexadecimal (either case) and appends the result
+    /// to `out`. If they aren't valid, `out` is left as it was.
+    ///
+    /// # Panics
+    /// Panics if `out` cannot grow.
+    pub fn decode_hex_into<O: Buffer<Element = u8>>(
+        &self,
+        out: &mut Vector<u8, O>,
+    ) -> Result<(), DecodeError> {
+        if !self.len().is_multiple_of(2) {
+            return Err(DecodeError::InvalidLength);
+        }
+        reserve(out, Some(self.len() / 2));
+        let old_len = out.len();
+        for index in (0..self.len()).step_by(2) {
+            let high = self.hex_value(index);
+            let low = self.hex_value(index + 1);
+            match (high, low) {
+                (Ok(high), Ok(low)) => {
+                    out.push(high << 4 | low);
+                }
+                (Err(error), _) | (_, Err(error)) => {
+                    out.truncate(old_len);
+                    return Err(error);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends the bytes encoded as base64 (standard alphabet, with padding)
+    /// to `out`.
+    ///
+    /// # Panics
+    /// Panics if `out` cannot grow.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut bytes = Vector::<u8>::new();
+    /// bytes.extend_from_slice(b"hi!?");
+    /// let mut out = Vector::<u8>::new();
+    /// bytes.encode_base64_into(&mut out);
+    /// assert_eq!(out.iter_copied().collect::<Vec<_>>(), b"aGkhPw==");
+    /// ```
+    pub fn encode_base64_into<O: Buffer<Element = u8>>(&self, out: &mut Vector<u8, O>) {
+        reserve(out, self.len().div_ceil(3).checked_mul(4));
+        for start in (0..self.len()).step_by(3) {
+            let available = std::cmp::min(3, self.len() - start);
+            let mut group = [0u8; 3];
+            for (offset, byte) in group.iter_mut().enumerate().take(available) {
+                *byte = self[start + offset];
+            }
+            let bits = u32::from(group[0]) << 16 | u32::from(group[1]) << 8 | u32::from(group[2]);
+            // Each byte gives at least one digit, and the rest is padding.
+            for digit in 0..4 {
+                if digit <= available {
+                    let value = (bits >> (18 - 6 * digit)) & 0x3f;
+                    out.push(BASE64_DIGITS[value as usize]);
+                } else {
+                    out.push(BASE64_PADDING);
+                }
+            }
+        }
+    }
+
+    /// Decodes the bytes as base64 (standard alphabet, with padding) and
+    /// appends the result to `out`. If they aren't valid, `out` is left as it
+    /// was.
+    ///
+    /// # Panics
+    /// Panics if `out` cannot grow.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut encoded = Vector::<u8>::new();
+    /// encoded.extend_from_slice(b"aGkhPw==");
+    /// let mut out = Vector::<u8>::new();
+    /// encoded.decode_base64_into(&mut out).unwrap();
+    /// assert_eq!(out.iter_copied().collect::<Vec<_>>(), b"hi!?");
+    /// ```
+    pub fn decode_base64_into<O: Buffer<Element = u8>>(
+        &self,
+        out: &mut Vector<u8, O>,
+    ) -> Result<(), DecodeError> {
+        if !self.len().is_multiple_of(4) {
+            return Err(DecodeError::InvalidLength);
+        }
+        let padding = (self.len().saturating_sub(2)..self.len())
+            .filter(|&index| self[index] == BASE64_PADDING)
+            .count();
+        reserve(out, Some(self.len() / 4 * 3 - padding));
+        let old_len = out.len();
+        let result = self.decode_base64_groups(out, padding);
+        if result.is_err() {
+            out.truncate(old_len);
+        }
+        result
+    }
+
+    /// Internal utility that decodes all the groups of 4 digits. Padding is
+    /// only valid as the last `padding` bytes.
+    fn decode_base64_groups<O: Buffer<Element = u8>>(
+        &self,
+        out: &mut Vector<u8, O>,
+        padding: usize,
+    ) -> Result<(), DecodeError> {
+        let digits_end = self.len() - padding;
+        for start in (0..self.len()).step_by(4) {
+            let mut bits = 0u32;
+            for index in start..start + 4 {
+                let value = if index < digits_end {
+                    base64_value(self[index]).ok_or(DecodeError::InvalidByte(index))?
+                } else {
+                    0
+                };
+                bits = bits << 6 | u32::from(value);
+            }
+            let decoded = std::cmp::min(3, (digits_end - start) * 6 / 8);
+            for byte in &bits.to_be_bytes()[1..=decoded] {
+                out.push(*byte);
+            }
+        }
+        Ok(())
+    }
+
+    /// Internal utility that decodes the hexadecimal digit at `index`.
+    fn hex_value(&self, index: usize) -> Result<u8, DecodeError> {
+        let digit = self[index];
+        match digit {
+            b'0'..=b'9' => Ok(digit - b'0'),
+            b'a'..=b'f' => Ok(digit - b'a' + 10),
+            b'A'..=b'F' => Ok(digit - b'A' + 10),
+            _ => Err(DecodeError::InvalidByte(index)),
+        }
+    }
+}
+
+/// Value of a base64 digit, if it is one.
+fn base64_value(digit: u8) -> Option<u8> {
+    match digit {
+        b'A'..=b'Z' => Some(digit - b'A'),
+        b'a'..=b'z' => Some(digit - b'a' + 26),
+        b'0'..=b'9' => Some(digit - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+/// Reserves space for `additional` bytes at once (`None` if it overflowed).
+fn reserve<O: Buffer<Element = u8>>(out: &mut Vector<u8, O>, additional: Option<usize>) {
+    additional
+        .and_then(|additional| out.try_reserve(additional).ok())
+        .expect("Couldn't reserve the necessary space");
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        base_buffers::{HeapBuffer, InlineBuffer},
+        interface::copy_value::CopyValueBuffer,
+        test_utils::panic::assert_panic,
+    };
+
+    use super::*;
+
+    fn bytes(values: &[u8]) -> Vector<u8, HeapBuffer<u8>> {
+        let mut vec = Vector::new();
+        vec.extend_from_slice(values);
+        vec
+    }
+
+    fn contents<B: Buffer<Element = u8> + CopyValueBuffer>(vec: &Vector<u8, B>) -> Vec<u8> {
+        vec.iter_copied().collect()
+    }
+
+    #[test]
+    fn hex_round_trips() {
+        let mut encoded = Vector::<u8, HeapBuffer<u8>>::new();
+        bytes(&[0x00, 0x7f, 0xff]).encode_hex_into(&mut encoded);
+        assert_eq!(contents(&encoded), b"007fff");
+
+        let mut decoded = bytes(&[1]);
+        bytes(b"007FfF").decode_hex_into(&mut decoded).unwrap();
+        assert_eq!(contents(&decoded), [1, 0x00, 0x7f, 0xff]);
+    }
+
+    #[test]
+    fn base64_round_trips_every_padding() {
+        let cases: [(&[u8], &[u8]); 4] = [
+            (b"", b""),
+            (b"f", b"Zg=="),
+            (b"fo", b"Zm8="),
+            (b"foobar", b"Zm9vYmFy"),
+        ];
+        for (plain, encoded) in cases {
+            let mut out = Vector::<u8, HeapBuffer<u8>>::new();
+            bytes(plain).encode_base64_into(&mut out);
+            assert_eq!(contents(&out), encoded);
+
+            let mut out = Vector::<u8, HeapBuffer<u8>>::new();
+            bytes(encoded).decode_base64_into(&mut out).unwrap();
+            assert_eq!(contents(&out), plain);
+        }
+    }
+
+    #[test]
+    fn invalid_input_leaves_the_output_untouched() {
+        let mut out = bytes(&[9]);
+        let result = bytes(b"0g").decode_hex_into(&mut out);
+        assert_eq!(result, Err(DecodeError::InvalidByte(1)));
+        let result = bytes(b"abc").decode_hex_into(&mut out);
+        assert_eq!(result, Err(DecodeError::InvalidLength));
+
+        let result = bytes(b"Zm9v=mFy").decode_base64_into(&mut out);
+        assert_eq!(result, Err(DecodeError::InvalidByte(4)));
+        let result = bytes(b"Zm9").decode_base64_into(&mut out);
+        assert_eq!(result, Err(DecodeError::InvalidLength));
+        assert_eq!(contents(&out), [9]);
+    }
+
+    #[test]
+    fn reserves_everything_at_once() {
+        let mut out = Vector::<u8, InlineBuffer<u8, 8>>::new();
+        bytes(b"foobar").encode_base64_into(&mut out);
+        assert_eq!(contents(&out), b"Zm9vYmFy");
+
+        assert_panic(|| {
+            let mut out = Vector::<u8, InlineBuffer<u8, 4>>::new();
+            bytes(b"foobar").encode_hex_into(&mut out);
+        });
+    }
+}
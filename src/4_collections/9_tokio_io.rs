@@ -0,0 +1,191 @@
+//! Integration with tokio's async IO traits. Requires the `tokio` feature.
+//!
+//! Reads go straight into the spare capacity of a byte vector and writes come
+//! straight from its elements, so there are no intermediate copies.
+
+use std::{
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::interface::{contiguous_memory::ContiguousMemoryBuffer, Buffer};
+
+use super::{bytes::VectorWriter, Vector};
+
+/// How much to reserve when reading into a full vector.
+const MIN_READ_SPACE: usize = 64;
+
+/// Reads from `reader` into the spare capacity of `vec`, appending what was
+/// read. Returns how many bytes were read (0 means the end of the stream).
+///
+/// If `vec` is full, it first tries to reserve some space. Reserve beforehand
+/// to control how much is read at once.
+pub fn poll_read_buf<R, B>(
+    reader: Pin<&mut R>,
+    cx: &mut Context<'_>,
+    vec: &mut Vector<u8, B>,
+) -> Poll<io::Result<usize>>
+where
+    R: AsyncRead + ?Sized,
+    B: Buffer<Element = u8> + ContiguousMemoryBuffer,
+{
+    if vec.len() == vec.capacity() {
+        vec.try_reserve(MIN_READ_SPACE)
+            .map_err(|error| io::Error::new(io::ErrorKind::OutOfMemory, format!("{error:?}")))?;
+    }
+    let spare = vec.spare_capacity_mut();
+    let (start, spare_len) = (spare.as_ptr().cast::<u8>(), spare.len());
+    let mut read_buf = ReadBuf::uninit(spare);
+    ready!(reader.poll_read(cx, &mut read_buf))?;
+    // The reader could have replaced the `ReadBuf` with its own one.
+    assert_eq!(
+        read_buf.filled().as_ptr(),
+        start,
+        "the reader replaced the `ReadBuf`"
+    );
+    let read = read_buf.filled().len().min(spare_len);
+    let len = vec.len();
+    // SAFETY: It's the same `ReadBuf`, which ensures that the first `read`
+    // spare positions were filled (and there are at least `read` of them).
+    unsafe { vec.set_len(len + read) };
+    Poll::Ready(Ok(read))
+}
+
+/// Writes the elements of `vec` into `writer` (directly from its memory) and
+/// removes the ones that were written. Returns how many.
+pub fn poll_write_buf<W, B>(
+    writer: Pin<&mut W>,
+    cx: &mut Context<'_>,
+    vec: &mut Vector<u8, B>,
+) -> Poll<io::Result<usize>>
+where
+    W: AsyncWrite + ?Sized,
+    B: Buffer<Element = u8> + ContiguousMemoryBuffer,
+{
+    let written = ready!(writer.poll_write(cx, vec.as_slice()))?;
+    vec.drain(..written);
+    Poll::Ready(Ok(written))
+}
+
+/// Writing into a vector never has to wait.
+impl<B: Buffer<Element = u8>> AsyncWrite for VectorWriter<'_, B> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(io::Write::write(self.get_mut(), buf))
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(io::Write::write_vectored(self.get_mut(), bufs))
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        true
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::task::Waker;
+
+    use crate::{
+        base_buffers::{HeapBuffer, InlineBuffer},
+        test_utils::panic::assert_panic,
+    };
+
+    use super::*;
+
+    /// Reader which gives its bytes in parts of at most 3.
+    struct SlowReader(&'static [u8]);
+
+    impl AsyncRead for SlowReader {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            let size = self.0.len().min(buf.remaining()).min(3);
+            buf.put_slice(&self.0[..size]);
+            self.0 = &self.0[size..];
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn reads_into_the_spare_capacity() {
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut reader = SlowReader(b"hello");
+        let mut vec = Vector::<u8, HeapBuffer<u8>>::new();
+
+        let mut poll = || poll_read_buf(Pin::new(&mut reader), &mut cx, &mut vec);
+        assert!(matches!(poll(), Poll::Ready(Ok(3))));
+        assert!(matches!(poll(), Poll::Ready(Ok(2))));
+        assert!(matches!(poll(), Poll::Ready(Ok(0))));
+        assert_eq!(vec.iter_copied().collect::<Vec<_>>(), b"hello");
+        assert_eq!(vec.capacity(), MIN_READ_SPACE);
+    }
+
+    /// Reader which replaces the `ReadBuf` with an empty one of its own.
+    struct SwappingReader;
+
+    impl AsyncRead for SwappingReader {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            *buf = ReadBuf::new(&mut []);
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn readers_replacing_the_read_buf_panic() {
+        let mut vec = Vector::<u8, InlineBuffer<u8, 4>>::new();
+        assert_panic(std::panic::AssertUnwindSafe(|| {
+            let mut cx = Context::from_waker(Waker::noop());
+            poll_read_buf(Pin::new(&mut SwappingReader), &mut cx, &mut vec)
+        }));
+        assert!(vec.is_empty());
+    }
+
+    #[test]
+    fn reading_into_a_full_vector_fails() {
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut vec = Vector::<u8, InlineBuffer<u8, 1>>::new();
+        vec.push(0);
+        let result = poll_read_buf(Pin::new(&mut SlowReader(b"a")), &mut cx, &mut vec);
+        assert!(matches!(result, Poll::Ready(Err(_))));
+    }
+
+    #[test]
+    fn writes_and_removes_what_was_written() {
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut vec = Vector::<u8, HeapBuffer<u8>>::new();
+        vec.extend_from_slice(b"hello");
+
+        let mut out = Vector::<u8, InlineBuffer<u8, 3>>::new();
+        let mut writer = out.writer();
+        let result = poll_write_buf(Pin::new(&mut writer), &mut cx, &mut vec);
+        assert!(matches!(result, Poll::Ready(Ok(3))));
+        assert_eq!(vec.iter_copied().collect::<Vec<_>>(), b"lo");
+        assert_eq!(out.iter_copied().collect::<Vec<_>>(), b"hel");
+    }
+}
@@ -1,3 +1,90 @@
 #[path = "1_vec.rs"]
 pub mod vec;
-pub use vec::Vector;
+pub use vec::{FixedVector, SmallVector, Vector};
+
+#[cfg(feature = "collections")]
+#[path = "2_pool.rs"]
+pub mod pool;
+#[cfg(feature = "collections")]
+pub use pool::{PooledVector, VectorPool};
+
+#[cfg(feature = "collections")]
+#[path = "3_scratch.rs"]
+pub mod scratch;
+#[cfg(feature = "collections")]
+pub use scratch::with_scratch;
+
+#[path = "4_raw_vec.rs"]
+pub mod raw_vec;
+pub use raw_vec::RawVector;
+
+#[cfg(feature = "collections")]
+#[path = "5_any_vec.rs"]
+pub mod any_vec;
+#[cfg(feature = "collections")]
+pub use any_vec::AnyVec;
+
+#[path = "6_macros.rs"]
+mod macros;
+
+#[path = "7_bytes.rs"]
+pub mod bytes;
+
+#[cfg(feature = "encoding")]
+#[path = "8_encoding.rs"]
+pub mod encoding;
+
+#[cfg(feature = "tokio")]
+#[path = "9_tokio_io.rs"]
+pub mod tokio_io;
+
+#[path = "a_heapless.rs"]
+pub mod heapless;
+
+#[cfg(feature = "serde")]
+#[path = "b_serde.rs"]
+mod serde_support;
+
+#[cfg(feature = "collections")]
+#[path = "c_pinned_vec.rs"]
+pub mod pinned_vec;
+#[cfg(feature = "collections")]
+pub use pinned_vec::PinnedVector;
+
+#[cfg(feature = "collections")]
+#[path = "d_intrusive_list.rs"]
+pub mod intrusive_list;
+#[cfg(feature = "collections")]
+pub use intrusive_list::IntrusiveList;
+
+#[cfg(feature = "collections")]
+#[path = "e_lru.rs"]
+pub mod lru;
+#[cfg(feature = "collections")]
+pub use lru::LruCache;
+
+#[cfg(feature = "collections")]
+#[path = "f_csr.rs"]
+pub mod csr;
+#[cfg(feature = "collections")]
+pub use csr::{CsrBuilder, CsrGraph};
+
+#[cfg(feature = "collections")]
+#[path = "g_nd_view.rs"]
+pub mod nd_view;
+#[cfg(feature = "collections")]
+pub use nd_view::{NdView, NdViewMut};
+
+#[cfg(feature = "ndarray")]
+#[path = "h_ndarray.rs"]
+mod ndarray_support;
+
+#[cfg(feature = "read-mostly")]
+#[path = "i_read_mostly.rs"]
+pub mod read_mostly;
+#[cfg(feature = "read-mostly")]
+pub use read_mostly::ReadMostlyVector;
+
+#[cfg(feature = "wasm-bindgen")]
+#[path = "j_wasm.rs"]
+pub mod wasm;
@@ -0,0 +1,384 @@
+//! Compatibility layer with the API of the [`heapless`] crate, to migrate
+//! existing code with minimal changes: replacing `heapless::Vec` by
+//! [`Vec`] from here keeps the same method names and result shapes (those of
+//! `heapless` 0.9).
+//!
+//! [`Vec`] is a thin wrapper over a [`FixedVector`], which can be taken out
+//! (see [`Vec::into_inner`]) to move to the rest of the crate gradually.
+//!
+//! `heapless::Deque` has no counterpart because the crate doesn't have a
+//! double-ended queue yet.
+//!
+//! [`heapless`]: https://docs.rs/heapless
+
+use std::{
+    array, fmt,
+    ops::{Deref, DerefMut, RangeBounds},
+};
+
+use crate::base_buffers::InlineBuffer;
+
+use super::vec::{Drain, FixedVector};
+
+/// Error when there isn't enough capacity for an operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CapacityError;
+
+impl fmt::Display for CapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("insufficient capacity")
+    }
+}
+
+impl std::error::Error for CapacityError {}
+
+/// Fixed-capacity vector with the API of `heapless::Vec`. Like it, it
+/// dereferences to a slice.
+///
+/// # Example
+/// ```
+/// # use buffers::collections::heapless::Vec;
+/// let mut vec = Vec::<u32, 2>::new();
+/// vec.push(1).unwrap();
+/// vec.push(2).unwrap();
+/// assert_eq!(vec.push(3), Err(3));
+/// assert!(vec.is_full());
+/// assert_eq!(*vec, [1, 2]);
+/// ```
+pub struct Vec<T, const N: usize>(FixedVector<T, N>);
+
+impl<T, const N: usize> Vec<T, N> {
+    /// Makes an empty vector.
+    pub fn new() -> Self {
+        Self(FixedVector::new())
+    }
+
+    /// Makes a vector by cloning the values of `other`, if they fit.
+    pub fn from_slice(other: &[T]) -> Result<Self, CapacityError>
+    where
+        T: Clone,
+    {
+        let mut vec = Self::new();
+        vec.extend_from_slice(other)?;
+        Ok(vec)
+    }
+
+    /// Makes a vector with the values of `src`.
+    ///
+    /// Arrays bigger than the vector (`M` bigger than `N`) don't compile:
+    /// ```compile_fail
+    /// # use buffers::collections::heapless::Vec;
+    /// let vec = Vec::<u32, 2>::from_array([1, 2, 3]);
+    /// ```
+    pub fn from_array<const M: usize>(src: [T; M]) -> Self {
+        let mut vec = Self::new();
+        vec.0.push_array(src);
+        vec
+    }
+
+    /// Moves the values into an array if there are exactly `M` of them.
+    /// Otherwise the vector is given back.
+    pub fn into_array<const M: usize>(mut self) -> Result<[T; M], Self> {
+        if self.len() != M {
+            return Err(self);
+        }
+        let mut values = self.0.drain(..);
+        Ok(array::from_fn(|_| values.next().unwrap()))
+    }
+
+    /// Gives back the underlying vector.
+    pub fn into_inner(self) -> FixedVector<T, N> {
+        self.0
+    }
+
+    /// Removes the values in `range` and iterates over them.
+    pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<'_, T, InlineBuffer<T, N>> {
+        self.0.drain(range)
+    }
+
+    /// All the values as a slice.
+    pub fn as_slice(&self) -> &[T] {
+        self.0.as_slice()
+    }
+
+    /// All the values as a mutable slice.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        self.0.as_mut_slice()
+    }
+
+    /// Maximum number of values (always `N`).
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Number of values it can still add.
+    pub const fn remaining_capacity(&self) -> usize {
+        N - self.0.len()
+    }
+
+    /// Removes all the values.
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// Appends all the values of `iter`.
+    ///
+    /// # Panics
+    /// Panics if they don't fit.
+    pub fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            if self.push(value).is_err() {
+                panic!("Vec::extend overflow");
+            }
+        }
+    }
+
+    /// Appends clones of the values of `other`, if all of them fit.
+    pub fn extend_from_slice(&mut self, other: &[T]) -> Result<(), CapacityError>
+    where
+        T: Clone,
+    {
+        if other.len() > N - self.len() {
+            return Err(CapacityError);
+        }
+        self.0.extend_from_slice(other);
+        Ok(())
+    }
+
+    /// Removes the last value, if any.
+    pub fn pop(&mut self) -> Option<T> {
+        self.0.pop()
+    }
+
+    /// Appends `item`, or gives it back if the vector is full.
+    pub fn push(&mut self, item: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(item);
+        }
+        self.0.push(item);
+        Ok(())
+    }
+
+    /// Removes the last value.
+    ///
+    /// # Safety
+    /// The vector must not be empty.
+    pub unsafe fn pop_unchecked(&mut self) -> T {
+        debug_assert!(!self.is_empty());
+        // SAFETY: The caller ensures that there is a value.
+        unsafe { self.0.pop().unwrap_unchecked() }
+    }
+
+    /// Appends `item`.
+    ///
+    /// # Safety
+    /// The vector must not be full.
+    pub unsafe fn push_unchecked(&mut self, item: T) {
+        debug_assert!(!self.is_full());
+        self.0.push(item);
+    }
+
+    /// Keeps the first `len` values and drops the rest.
+    pub fn truncate(&mut self, len: usize) {
+        self.0.truncate(len);
+    }
+
+    /// Changes the length to `new_len`, either dropping the last values or
+    /// appending clones of `value`. Fails (without changes) if it doesn't fit.
+    pub fn resize(&mut self, new_len: usize, value: T) -> Result<(), CapacityError>
+    where
+        T: Clone,
+    {
+        // An inline buffer can only fail because it doesn't fit.
+        self.0.try_resize(new_len, value).map_err(|_| CapacityError)
+    }
+
+    /// Same as [`Vec::resize`] with the default value.
+    pub fn resize_default(&mut self, new_len: usize) -> Result<(), CapacityError>
+    where
+        T: Clone + Default,
+    {
+        self.resize(new_len, T::default())
+    }
+
+    /// Forces the length of the vector.
+    ///
+    /// # Safety
+    /// Same as [`crate::collections::Vector::set_len`].
+    pub unsafe fn set_len(&mut self, new_len: usize) {
+        // SAFETY: Same requirements.
+        unsafe { self.0.set_len(new_len) };
+    }
+
+    /// Removes the value at `index`, replacing it by the last one.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        self.0.swap_remove(index)
+    }
+
+    /// Same as [`Vec::swap_remove`] without checking the bounds.
+    ///
+    /// # Safety
+    /// `index` must be less than the length.
+    pub unsafe fn swap_remove_unchecked(&mut self, index: usize) -> T {
+        debug_assert!(index < self.len());
+        self.0.swap_remove(index)
+    }
+
+    /// Whether it's at its capacity.
+    pub fn is_full(&self) -> bool {
+        self.len() == N
+    }
+
+    /// Whether it has no values.
+    pub const fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Whether the values start with `needle`.
+    pub fn starts_with(&self, needle: &[T]) -> bool
+    where
+        T: PartialEq,
+    {
+        self.as_slice().starts_with(needle)
+    }
+
+    /// Whether the values end with `needle`.
+    pub fn ends_with(&self, needle: &[T]) -> bool
+    where
+        T: PartialEq,
+    {
+        self.as_slice().ends_with(needle)
+    }
+
+    /// Inserts `element` at `index`, shifting the values after it, or gives it
+    /// back if the vector is full.
+    ///
+    /// # Panics
+    /// Panics if `index` is bigger than the length.
+    pub fn insert(&mut self, index: usize, element: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(element);
+        }
+        self.0.insert(index, element);
+        Ok(())
+    }
+
+    /// Removes the value at `index`, shifting the values after it.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> T {
+        self.0.remove(index)
+    }
+
+    /// Keeps only the values for which `f` returns `true`, in order.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, f: F) {
+        self.0.retain(f);
+    }
+
+    /// Same as [`Vec::retain`], but `f` can change the values.
+    pub fn retain_mut<F: FnMut(&mut T) -> bool>(&mut self, mut f: F) {
+        self.0.extract_if(|value| !f(value)).for_each(drop);
+    }
+}
+
+impl<T, const N: usize> Default for Vec<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> From<FixedVector<T, N>> for Vec<T, N> {
+    fn from(vec: FixedVector<T, N>) -> Self {
+        Self(vec)
+    }
+}
+
+impl<T, const N: usize> Deref for Vec<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, const N: usize> DerefMut for Vec<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for Vec<T, N> {
+    fn clone(&self) -> Self {
+        let mut vec = Self::new();
+        vec.0.extend_from_slice(self);
+        vec
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for Vec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_slice().fmt(f)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<T: defmt::Format, const N: usize> defmt::Format for Vec<T, N> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        self.0.format(f);
+    }
+}
+
+impl<T: PartialEq, const N: usize, const M: usize> PartialEq<Vec<T, M>> for Vec<T, N> {
+    fn eq(&self, other: &Vec<T, M>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for Vec<T, N> {}
+
+impl<T, const N: usize> Extend<T> for Vec<T, N> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        Vec::extend(self, iter);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capacity_errors_leave_the_vector_unchanged() {
+        let mut vec = Vec::<u32, 3>::from_slice(&[1, 2]).unwrap();
+        assert_eq!(vec.extend_from_slice(&[3, 4]), Err(CapacityError));
+        assert_eq!(vec.resize(4, 0), Err(CapacityError));
+        assert_eq!(vec.insert(0, 0), Ok(()));
+        assert_eq!(vec.insert(0, 9), Err(9));
+        assert_eq!(*vec, [0, 1, 2]);
+        assert!(Vec::<u32, 1>::from_slice(&[1, 2]).is_err());
+    }
+
+    #[test]
+    fn arrays_go_in_and_out() {
+        let vec = Vec::<String, 4>::from_array(["a".to_string(), "b".to_string()]);
+        let vec = vec.into_array::<3>().unwrap_err();
+        let array: [String; 2] = vec.into_array().unwrap();
+        assert_eq!(array, ["a", "b"]);
+    }
+
+    #[test]
+    fn values_can_be_changed_while_retaining() {
+        let mut vec = Vec::<u32, 4>::from_array([1, 2, 3, 4]);
+        vec.retain_mut(|value| {
+            *value *= 10;
+            *value != 20
+        });
+        assert_eq!(vec, Vec::<u32, 3>::from_array([10, 30, 40]));
+        vec.resize_default(4).unwrap();
+        assert!(vec.ends_with(&[40, 0]));
+    }
+}
@@ -0,0 +1,172 @@
+//! Integration with `serde`. Requires the `serde` feature.
+//!
+//! Vectors are serialized as sequences. Deserializing never panics: when the
+//! buffer cannot hold another element (eg. a [`super::FixedVector`] is full,
+//! or allocating fails) it returns an error. This makes it safe to parse
+//! untrusted input into fixed buffers (eg. with `postcard` on a
+//! microcontroller).
+
+use std::{fmt, marker::PhantomData, mem};
+
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::interface::{contiguous_memory::ContiguousMemoryBuffer, Buffer};
+
+use super::{heapless, FixedVector, Vector};
+
+/// Maximum number of bytes reserved upfront from the length given by the
+/// input, which may not be trustworthy.
+const MAX_PREALLOCATION: usize = 1024 * 1024;
+
+/// Internal utility (like serde's `size_hint::cautious`) that caps the number
+/// of `T` to reserve from a size hint to [`MAX_PREALLOCATION`] bytes.
+fn cautious<T>(hint: Option<usize>) -> usize {
+    match mem::size_of::<T>() {
+        0 => 0,
+        size => hint.unwrap_or(0).min(MAX_PREALLOCATION / size),
+    }
+}
+
+impl<T, B> Serialize for Vector<T, B>
+where
+    T: Serialize,
+    B: Buffer<Element = T> + ContiguousMemoryBuffer,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.as_slice())
+    }
+}
+
+impl<'de, T, B> Deserialize<'de> for Vector<T, B>
+where
+    T: Deserialize<'de>,
+    B: Buffer<Element = T> + Default,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(VectorVisitor(PhantomData))
+    }
+}
+
+/// Internal type. Builds a vector from a sequence.
+struct VectorVisitor<T, B>(PhantomData<fn() -> (T, B)>);
+
+impl<'de, T, B> Visitor<'de> for VectorVisitor<T, B>
+where
+    T: Deserialize<'de>,
+    B: Buffer<Element = T> + Default,
+{
+    type Value = Vector<T, B>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence that fits in the buffer")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut vec = Vector::new();
+        // It's only a hint: pushing tells whether they really fit.
+        let _ = vec.try_reserve_exact(cautious::<T>(seq.size_hint()));
+        while let Some(value) = seq.next_element()? {
+            if vec.try_push(value).is_err() {
+                // The hint may be anything, so it can't overflow.
+                let len = (vec.len() + 1).saturating_add(seq.size_hint().unwrap_or(0));
+                return Err(de::Error::invalid_length(len, &self));
+            }
+        }
+        Ok(vec)
+    }
+}
+
+impl<T: Serialize, const N: usize> Serialize for heapless::Vec<T, N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.as_slice())
+    }
+}
+
+impl<'de, T: Deserialize<'de>, const N: usize> Deserialize<'de> for heapless::Vec<T, N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        FixedVector::deserialize(deserializer).map(Self::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_test::{assert_de_tokens_error, assert_ser_tokens, assert_tokens, Token};
+
+    use serde::de::{value, DeserializeSeed, IntoDeserializer};
+
+    use crate::base_buffers::{HeapBuffer, InlineBuffer};
+
+    use super::*;
+
+    #[test]
+    fn vectors_are_sequences() {
+        let mut vec = Vector::<u32, HeapBuffer<u32>>::new();
+        vec.extend_from_slice(&[1, 2]);
+        let tokens = [
+            Token::Seq { len: Some(2) },
+            Token::U32(1),
+            Token::U32(2),
+            Token::SeqEnd,
+        ];
+        assert_ser_tokens(&vec, &tokens);
+    }
+
+    #[test]
+    fn fixed_vectors_reject_sequences_that_dont_fit() {
+        assert_tokens(
+            &heapless::Vec::<u8, 2>::from_slice(&[1, 2]).unwrap(),
+            &[
+                Token::Seq { len: Some(2) },
+                Token::U8(1),
+                Token::U8(2),
+                Token::SeqEnd,
+            ],
+        );
+        assert_de_tokens_error::<FixedVector<u8, 2>>(
+            &[
+                Token::Seq { len: Some(4) },
+                Token::U8(1),
+                Token::U8(2),
+                Token::U8(3),
+            ],
+            "invalid length 4, expected a sequence that fits in the buffer",
+        );
+    }
+
+    /// Endless sequence of zeros which claims to have `usize::MAX` left.
+    struct HostileSeq;
+
+    impl<'de> SeqAccess<'de> for HostileSeq {
+        type Error = value::Error;
+
+        fn next_element_seed<S: DeserializeSeed<'de>>(
+            &mut self,
+            seed: S,
+        ) -> Result<Option<S::Value>, value::Error> {
+            seed.deserialize(0u8.into_deserializer()).map(Some)
+        }
+
+        fn size_hint(&self) -> Option<usize> {
+            Some(usize::MAX)
+        }
+    }
+
+    #[test]
+    fn huge_size_hints_are_only_trusted_cautiously() {
+        assert_eq!(cautious::<u32>(Some(usize::MAX)), MAX_PREALLOCATION / 4);
+        assert_eq!(cautious::<()>(Some(usize::MAX)), 0);
+
+        let visitor = VectorVisitor::<u8, InlineBuffer<u8, 2>>(PhantomData);
+        let error = visitor.visit_seq(HostileSeq).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "invalid length {}, expected a sequence that fits in the buffer",
+                usize::MAX
+            )
+        );
+    }
+}
@@ -0,0 +1,221 @@
+use std::pin::Pin;
+
+use crate::interface::{
+    contiguous_memory::ContiguousMemoryBuffer, resize_error::ResizeError,
+    stable::StableElementsBuffer,
+};
+
+use super::Vector;
+
+/// Vector whose elements never move once pushed, so they can be pinned (eg.
+/// intrusive or self-referential values).
+///
+/// Only operations that keep the elements in place exist: pushing at the end,
+/// and dropping them in place (truncating). Anything that shifts, swaps or
+/// moves them out (like `insert`, `remove` or `swap_remove`) is missing on
+/// purpose, and [`PinnedVector::pop`] is only available for [`Unpin`] values.
+///
+/// It needs a [`StableElementsBuffer`], whose values stay in the same address
+/// even when the vector is moved.
+///
+/// # Example
+/// ```
+/// # use std::{marker::PhantomPinned, pin::Pin};
+/// # use buffers::{base_buffers::InlineBuffer, collections::PinnedVector};
+/// struct Node {
+///     value: u32,
+///     _pinned: PhantomPinned,
+/// }
+///
+/// let mut nodes = PinnedVector::<Node, Box<InlineBuffer<Node, 4>>>::new();
+/// nodes.push(Node { value: 1, _pinned: PhantomPinned });
+/// let address = nodes.get_pin(0).map(|node| &*node as *const Node);
+///
+/// let mut nodes = nodes; // Moving the vector doesn't move the elements.
+/// nodes.push(Node { value: 2, _pinned: PhantomPinned });
+/// assert_eq!(nodes.get_pin(0).map(|node| &*node as *const Node), address);
+///
+/// let node: Pin<&mut Node> = nodes.get_pin_mut(1).unwrap();
+/// // SAFETY: The value is not moved out.
+/// unsafe { node.get_unchecked_mut().value += 1 };
+/// assert_eq!(nodes.get(1).unwrap().value, 3);
+/// ```
+///
+/// Elements that aren't [`Unpin`] cannot be moved out:
+/// ```compile_fail
+/// # use std::marker::PhantomPinned;
+/// # use buffers::{base_buffers::InlineBuffer, collections::PinnedVector};
+/// let mut nodes = PinnedVector::<PhantomPinned, Box<InlineBuffer<PhantomPinned, 4>>>::new();
+/// nodes.push(PhantomPinned);
+/// let node = nodes.pop();
+/// ```
+pub struct PinnedVector<T, B: StableElementsBuffer<Element = T>> {
+    vec: Vector<T, B>,
+}
+
+impl<T, B: StableElementsBuffer<Element = T>> PinnedVector<T, B> {
+    /// Makes an empty vector on top of `buffer`.
+    pub fn from_buffer(buffer: B) -> Self {
+        Self {
+            vec: Vector::from_buffer(buffer),
+        }
+    }
+
+    /// Makes an empty vector with a default buffer.
+    pub fn new() -> Self
+    where
+        B: Default,
+    {
+        Self::from_buffer(B::default())
+    }
+
+    /// Number of elements.
+    pub const fn len(&self) -> usize {
+        self.vec.len()
+    }
+
+    /// Whether it has no elements.
+    pub const fn is_empty(&self) -> bool {
+        self.vec.is_empty()
+    }
+
+    /// Number of elements it can hold without growing.
+    pub fn capacity(&self) -> usize {
+        self.vec.capacity()
+    }
+
+    /// Number of elements it can still add without growing.
+    pub fn remaining_capacity(&self) -> usize {
+        self.vec.remaining_capacity()
+    }
+
+    /// Tries to add `value` at the end (growing if needed) and returns its
+    /// index. See [`Vector::try_push`].
+    pub fn try_push(&mut self, value: T) -> Result<usize, ResizeError> {
+        self.vec.try_push(value)
+    }
+
+    /// Adds `value` at the end and returns its index.
+    ///
+    /// # Panics
+    /// Panics if it cannot grow.
+    pub fn push(&mut self, value: T) -> usize {
+        self.vec.push(value)
+    }
+
+    /// Removes the last element and returns it, if any. Moving it out is only
+    /// allowed because it's [`Unpin`]: use [`PinnedVector::truncate`] to drop
+    /// it in place otherwise.
+    pub fn pop(&mut self) -> Option<T>
+    where
+        T: Unpin,
+    {
+        self.vec.pop()
+    }
+
+    /// Drops (in place) all the elements after the first `len`.
+    pub fn truncate(&mut self, len: usize) {
+        self.vec.truncate(len);
+    }
+
+    /// Drops (in place) all the elements.
+    pub fn clear(&mut self) {
+        self.vec.clear();
+    }
+}
+
+impl<T, B> PinnedVector<T, B>
+where
+    B: StableElementsBuffer<Element = T> + ContiguousMemoryBuffer,
+{
+    /// All the elements as a slice.
+    pub fn as_slice(&self) -> &[T] {
+        self.vec.as_slice()
+    }
+
+    /// Reference to the element at `index`, if any.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.as_slice().get(index)
+    }
+
+    /// Pinned reference to the element at `index`, if any.
+    pub fn get_pin(&self, index: usize) -> Option<Pin<&T>> {
+        let value = self.get(index)?;
+        // SAFETY: Elements never move nor get invalidated without being
+        // dropped (see the type documentation).
+        Some(unsafe { Pin::new_unchecked(value) })
+    }
+
+    /// Pinned mutable reference to the element at `index`, if any.
+    pub fn get_pin_mut(&mut self, index: usize) -> Option<Pin<&mut T>> {
+        let value = self.vec.as_mut_slice().get_mut(index)?;
+        // SAFETY: Same as `get_pin`.
+        Some(unsafe { Pin::new_unchecked(value) })
+    }
+
+    /// Iterates over pinned mutable references to all the elements, in order.
+    pub fn iter_pin_mut(&mut self) -> impl Iterator<Item = Pin<&mut T>> + '_ {
+        self.vec.as_mut_slice().iter_mut().map(|value| {
+            // SAFETY: Same as `get_pin`.
+            unsafe { Pin::new_unchecked(value) }
+        })
+    }
+}
+
+impl<T, B: StableElementsBuffer<Element = T> + Default> Default for PinnedVector<T, B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{marker::PhantomPinned, ptr};
+
+    use crate::base_buffers::InlineBuffer;
+
+    use super::*;
+
+    /// Value that points to itself once initialized.
+    struct SelfRef {
+        this: *const SelfRef,
+        _pinned: PhantomPinned,
+    }
+
+    impl SelfRef {
+        fn new() -> Self {
+            Self {
+                this: ptr::null(),
+                _pinned: PhantomPinned,
+            }
+        }
+
+        fn init(self: Pin<&mut Self>) {
+            let this = &*self as *const Self;
+            // SAFETY: The value is not moved out.
+            unsafe { self.get_unchecked_mut().this = this };
+        }
+
+        fn is_valid(&self) -> bool {
+            ptr::eq(self.this, self)
+        }
+    }
+
+    #[test]
+    fn self_references_stay_valid() {
+        let mut vec = PinnedVector::<SelfRef, Box<InlineBuffer<SelfRef, 3>>>::new();
+        vec.push(SelfRef::new());
+        vec.push(SelfRef::new());
+        vec.iter_pin_mut().for_each(SelfRef::init);
+
+        let mut moved = vec;
+        moved.push(SelfRef::new());
+        moved.get_pin_mut(2).unwrap().init();
+        assert!(moved.as_slice().iter().all(SelfRef::is_valid));
+
+        assert!(moved.try_push(SelfRef::new()).is_err());
+        moved.truncate(1);
+        assert_eq!(moved.len(), 1);
+        assert!(moved.get(0).unwrap().is_valid());
+    }
+}
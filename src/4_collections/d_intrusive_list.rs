@@ -0,0 +1,580 @@
+//! Doubly linked list stored in a buffer (like a slab), linking elements by
+//! index instead of by pointer.
+//!
+//! The nodes live in a [`SparseBuffer`] (like a [`SlabBuffer`]), which keeps
+//! track of which positions are filled. A list in an inline buffer never
+//! allocates, and removing an element only empties its position (which is
+//! reused by the next insertion).
+
+use std::cmp::max;
+
+use crate::{
+    composites::SlabBuffer,
+    interface::{
+        contiguous_memory::ContiguousMemoryBuffer, resize_error::ResizeError, sparse::SparseBuffer,
+    },
+    DefaultBuffer,
+};
+
+/// Index used when there is no element (eg. the previous of the first one).
+const NONE: usize = usize::MAX;
+
+/// Identifies an element of an [`IntrusiveList`] while it's in the list.
+///
+/// Once the element is removed, its key may be reused by a new element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key(usize);
+
+/// Node of an [`IntrusiveList`], as stored in its buffer. It's opaque: it only
+/// appears in the buffer's type.
+pub struct Slot<T> {
+    value: T,
+    prev: usize,
+    next: usize,
+}
+
+/// Doubly linked list whose nodes are stored in a buffer and linked by index.
+///
+/// Elements are identified by a [`Key`], which allows removing them (or
+/// inserting next to them) in O(1).
+///
+/// The buffer must know which of its positions are filled (a
+/// [`SparseBuffer`]), so new elements go to the first empty one.
+///
+/// # Example
+/// ```
+/// # use buffers::{base_buffers::InlineBuffer, collections::intrusive_list::{IntrusiveList, Slot}, composites::SlabBuffer};
+/// type Nodes = SlabBuffer<InlineBuffer<Slot<u32>, 4>, InlineBuffer<usize, 1>>;
+/// let mut list = IntrusiveList::<u32, Nodes>::new();
+/// let one = list.push_back(1);
+/// list.push_back(3);
+/// list.insert_after(one, 2);
+/// list.push_front(0);
+///
+/// assert_eq!(list.remove(one), Some(1));
+/// assert_eq!(list.iter().map(|(_, value)| *value).collect::<Vec<_>>(), [0, 2, 3]);
+/// ```
+pub struct IntrusiveList<T, B = SlabBuffer<DefaultBuffer<Slot<T>>>>
+where
+    B: SparseBuffer<Element = Slot<T>> + ContiguousMemoryBuffer,
+{
+    buffer: B,
+    head: usize,
+    tail: usize,
+    /// Every position before it is filled, so the first empty one is at or
+    /// after it.
+    free: usize,
+    len: usize,
+}
+
+impl<T, B> IntrusiveList<T, B>
+where
+    B: SparseBuffer<Element = Slot<T>> + ContiguousMemoryBuffer,
+{
+    /// Makes an empty list on top of `buffer` (which must be empty).
+    pub fn from_buffer(buffer: B) -> Self {
+        Self {
+            buffer,
+            head: NONE,
+            tail: NONE,
+            free: 0,
+            len: 0,
+        }
+    }
+
+    /// Makes an empty list with a default buffer.
+    pub fn new() -> Self
+    where
+        B: Default,
+    {
+        Self::from_buffer(B::default())
+    }
+
+    /// Number of elements.
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether it has no elements.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of elements it can hold without growing.
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    /// Number of elements it can still add without growing.
+    pub fn remaining_capacity(&self) -> usize {
+        self.capacity() - self.len
+    }
+
+    /// Tries to make space for at least `additional` more elements, growing
+    /// as the buffer prefers (see
+    /// [`crate::interface::Buffer::preferred_grow_target`]).
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), ResizeError> {
+        let required = self
+            .len
+            .checked_add(additional)
+            .ok_or(ResizeError::CapacityOverflow)?;
+        let capacity = self.capacity();
+        if required > capacity {
+            let preferred = self.buffer.preferred_grow_target(capacity, required);
+            // SAFETY: It's at least `required`, which is bigger than the
+            // current capacity.
+            unsafe { self.buffer.try_grow(max(preferred, required)) }?;
+        }
+        Ok(())
+    }
+
+    /// Whether `key` belongs to an element of the list.
+    pub fn contains(&self, key: Key) -> bool {
+        self.buffer.is_filled(key.0)
+    }
+
+    /// Reference to the element of `key`, if any.
+    pub fn get(&self, key: Key) -> Option<&T> {
+        self.node(key.0).map(|node| &node.value)
+    }
+
+    /// Mutable reference to the element of `key`, if any.
+    pub fn get_mut(&mut self, key: Key) -> Option<&mut T> {
+        self.node_mut(key.0).map(|node| &mut node.value)
+    }
+
+    /// Key of the first element, if any.
+    pub fn front(&self) -> Option<Key> {
+        (self.head != NONE).then_some(Key(self.head))
+    }
+
+    /// Key of the last element, if any.
+    pub fn back(&self) -> Option<Key> {
+        (self.tail != NONE).then_some(Key(self.tail))
+    }
+
+    /// Key of the element after the one of `key`, if any.
+    pub fn next(&self, key: Key) -> Option<Key> {
+        let (_, next) = self.links(key.0)?;
+        (next != NONE).then_some(Key(next))
+    }
+
+    /// Key of the element before the one of `key`, if any.
+    pub fn prev(&self, key: Key) -> Option<Key> {
+        let (prev, _) = self.links(key.0)?;
+        (prev != NONE).then_some(Key(prev))
+    }
+
+    /// Adds `value` at the start.
+    ///
+    /// # Panics
+    /// Panics if it needs to grow and it cannot.
+    pub fn push_front(&mut self, value: T) -> Key {
+        self.link(value, NONE, self.head)
+    }
+
+    /// Adds `value` at the end.
+    ///
+    /// # Panics
+    /// Panics if it needs to grow and it cannot.
+    pub fn push_back(&mut self, value: T) -> Key {
+        self.link(value, self.tail, NONE)
+    }
+
+    /// Adds `value` right after the element of `key`.
+    ///
+    /// # Panics
+    /// Panics if `key` doesn't belong to an element, or if it needs to grow and
+    /// it cannot.
+    pub fn insert_after(&mut self, key: Key, value: T) -> Key {
+        let (_, next) = self.links(key.0).expect("The key must be in the list");
+        self.link(value, key.0, next)
+    }
+
+    /// Adds `value` right before the element of `key`.
+    ///
+    /// # Panics
+    /// Panics if `key` doesn't belong to an element, or if it needs to grow and
+    /// it cannot.
+    pub fn insert_before(&mut self, key: Key, value: T) -> Key {
+        let (prev, _) = self.links(key.0).expect("The key must be in the list");
+        self.link(value, prev, key.0)
+    }
+
+    /// Removes the element of `key` and returns it, if any. Its position is
+    /// reused by the next insertion.
+    pub fn remove(&mut self, key: Key) -> Option<T> {
+        self.links(key.0)?;
+        self.unlink(key.0);
+        // SAFETY: It's filled (it has links).
+        let node = unsafe { self.buffer.take(key.0) };
+        self.free = self.free.min(key.0);
+        self.len -= 1;
+        Some(node.value)
+    }
+
+    /// Moves the element of `key` to the start (it keeps its key).
+    ///
+    /// # Panics
+    /// Panics if `key` doesn't belong to an element.
+    pub fn move_to_front(&mut self, key: Key) {
+        self.unlink(key.0);
+        let head = self.head;
+        self.relink(key.0, NONE, head);
+    }
+
+    /// Moves the element of `key` to the end (it keeps its key).
+    ///
+    /// # Panics
+    /// Panics if `key` doesn't belong to an element.
+    pub fn move_to_back(&mut self, key: Key) {
+        self.unlink(key.0);
+        let tail = self.tail;
+        self.relink(key.0, tail, NONE);
+    }
+
+    /// Removes the first element and returns it, if any.
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.remove(self.front()?)
+    }
+
+    /// Removes the last element and returns it, if any.
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.remove(self.back()?)
+    }
+
+    /// Removes all the elements (keeping the buffer).
+    ///
+    /// If dropping one of them panics, the rest are still dropped.
+    pub fn clear(&mut self) {
+        self.head = NONE;
+        self.tail = NONE;
+        self.free = 0;
+        self.len = 0;
+        ClearGuard(&mut self.buffer).drop_filled();
+    }
+
+    /// Iterates over the elements (and their keys) in order.
+    pub fn iter(&self) -> Iter<'_, T, B> {
+        Iter {
+            list: self,
+            front: self.head,
+            back: self.tail,
+            remaining: self.len,
+        }
+    }
+
+    /// Internal utility that gives the node at `index`, if it's filled.
+    fn node(&self, index: usize) -> Option<&Slot<T>> {
+        if !self.buffer.is_filled(index) {
+            return None;
+        }
+        // SAFETY: It's filled, so it's a valid position.
+        let ptr = unsafe { self.buffer.ptr(index) };
+        // SAFETY: It's filled, and the reference borrows the list.
+        Some(unsafe { &*ptr })
+    }
+
+    /// Internal utility that gives the node at `index` to change it, if it's
+    /// filled.
+    fn node_mut(&mut self, index: usize) -> Option<&mut Slot<T>> {
+        if !self.buffer.is_filled(index) {
+            return None;
+        }
+        // SAFETY: It's filled, so it's a valid position.
+        let ptr = unsafe { self.buffer.mut_ptr(index) };
+        // SAFETY: It's filled, and the reference borrows the list mutably.
+        Some(unsafe { &mut *ptr })
+    }
+
+    /// Internal utility that gives the links (previous and next) of the node
+    /// at `index`, if it's filled.
+    fn links(&self, index: usize) -> Option<(usize, usize)> {
+        self.node(index).map(|node| (node.prev, node.next))
+    }
+
+    /// Internal utility that gives mutable references to the links (previous
+    /// and next) of the node at `index`, which must be filled.
+    fn links_mut(&mut self, index: usize) -> (&mut usize, &mut usize) {
+        let node = self.node_mut(index).expect("Linked nodes are filled");
+        (&mut node.prev, &mut node.next)
+    }
+
+    /// Internal utility that gives the first empty position. There must be
+    /// one.
+    ///
+    /// All the positions before `free` are filled, so it's either `free` or
+    /// right after the run of filled positions that starts there.
+    fn vacant(&self) -> usize {
+        let index = match self.buffer.next_filled_run(self.free) {
+            Some(run) if run.start == self.free => run.end,
+            _ => self.free,
+        };
+        debug_assert!(index < self.capacity(), "There must be an empty position");
+        index
+    }
+
+    /// Internal utility that stores `value` between `prev` and `next` (which
+    /// must be consecutive) and links them to it.
+    fn link(&mut self, value: T, prev: usize, next: usize) -> Key {
+        if self.len == self.capacity() {
+            self.try_reserve(1)
+                .expect("Should have space for the new element");
+        }
+        let index = self.vacant();
+        // SAFETY: It's valid and empty.
+        unsafe { self.buffer.put(index, Slot { value, prev, next }) };
+        self.free = index + 1;
+        self.len += 1;
+        self.relink(index, prev, next);
+        Key(index)
+    }
+
+    /// Internal utility that links the neighbours of the (filled) node at
+    /// `index` to each other, leaving it out of the list. Its own links are
+    /// left as they were.
+    ///
+    /// # Panics
+    /// Panics if the node isn't filled.
+    fn unlink(&mut self, index: usize) {
+        let (prev, next) = self.links(index).expect("The key must be in the list");
+        match prev {
+            NONE => self.head = next,
+            prev => *self.links_mut(prev).1 = next,
+        }
+        match next {
+            NONE => self.tail = prev,
+            next => *self.links_mut(next).0 = prev,
+        }
+    }
+
+    /// Internal utility that puts the (filled) node at `index` between `prev`
+    /// and `next`, which must be consecutive.
+    fn relink(&mut self, index: usize, prev: usize, next: usize) {
+        let links = self.links_mut(index);
+        *links.0 = prev;
+        *links.1 = next;
+        match prev {
+            NONE => self.head = index,
+            prev => *self.links_mut(prev).1 = index,
+        }
+        match next {
+            NONE => self.tail = index,
+            next => *self.links_mut(next).0 = index,
+        }
+    }
+}
+
+impl<T, B> Default for IntrusiveList<T, B>
+where
+    B: SparseBuffer<Element = Slot<T>> + ContiguousMemoryBuffer + Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, B> Drop for IntrusiveList<T, B>
+where
+    B: SparseBuffer<Element = Slot<T>> + ContiguousMemoryBuffer,
+{
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// Internal guard which drops all the filled positions of a buffer. If
+/// dropping one of them panics, it keeps dropping the rest while unwinding.
+struct ClearGuard<'a, B: SparseBuffer>(&'a mut B);
+
+impl<B: SparseBuffer> ClearGuard<'_, B> {
+    fn drop_filled(&mut self) {
+        // Dropping a run empties all of it (even if a value panics), so the
+        // next one is always found from the start.
+        while let Some(run) = self.0.next_filled_run(0) {
+            // SAFETY: The positions of the run are valid and filled.
+            unsafe { self.0.manually_drop_range(run) };
+        }
+    }
+}
+
+impl<B: SparseBuffer> Drop for ClearGuard<'_, B> {
+    fn drop(&mut self) {
+        // Only does something if a value panicked while being dropped.
+        self.drop_filled();
+    }
+}
+
+/// Iterator over the elements of an [`IntrusiveList`] and their keys. See
+/// [`IntrusiveList::iter`].
+pub struct Iter<'a, T, B>
+where
+    B: SparseBuffer<Element = Slot<T>> + ContiguousMemoryBuffer,
+{
+    list: &'a IntrusiveList<T, B>,
+    front: usize,
+    back: usize,
+    remaining: usize,
+}
+
+impl<'a, T, B> Iter<'a, T, B>
+where
+    B: SparseBuffer<Element = Slot<T>> + ContiguousMemoryBuffer,
+{
+    /// Internal utility that gives the node at `index`, which must be filled.
+    fn node(&self, index: usize) -> &'a Slot<T> {
+        let list = self.list;
+        list.node(index).expect("Linked nodes are filled")
+    }
+}
+
+impl<'a, T, B> Iterator for Iter<'a, T, B>
+where
+    B: SparseBuffer<Element = Slot<T>> + ContiguousMemoryBuffer,
+{
+    type Item = (Key, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let index = self.front;
+        let node = self.node(index);
+        self.front = node.next;
+        self.remaining -= 1;
+        Some((Key(index), &node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T, B> DoubleEndedIterator for Iter<'_, T, B>
+where
+    B: SparseBuffer<Element = Slot<T>> + ContiguousMemoryBuffer,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let index = self.back;
+        let node = self.node(index);
+        self.back = node.prev;
+        self.remaining -= 1;
+        Some((Key(index), &node.value))
+    }
+}
+
+impl<T, B> ExactSizeIterator for Iter<'_, T, B> where
+    B: SparseBuffer<Element = Slot<T>> + ContiguousMemoryBuffer
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicI64, Ordering};
+
+    use crate::{
+        base_buffers::{HeapBuffer, InlineBuffer},
+        test_utils::life_counter::LifeCounter,
+    };
+
+    use super::*;
+
+    fn values<T: Clone, B>(list: &IntrusiveList<T, B>) -> Vec<T>
+    where
+        B: SparseBuffer<Element = Slot<T>> + ContiguousMemoryBuffer,
+    {
+        list.iter().map(|(_, value)| value.clone()).collect()
+    }
+
+    #[test]
+    fn removed_slots_are_reused() {
+        type Nodes = SlabBuffer<InlineBuffer<Slot<String>, 3>, InlineBuffer<usize, 1>>;
+        let mut list = IntrusiveList::<String, Nodes>::new();
+        let a = list.push_back("a".to_string());
+        let b = list.push_back("b".to_string());
+        let c = list.push_back("c".to_string());
+
+        assert_eq!(list.remove(b).as_deref(), Some("b"));
+        assert!(!list.contains(b));
+        assert_eq!(list.remove(b), None);
+        assert_eq!(list.next(a), Some(c));
+        assert_eq!(list.prev(c), Some(a));
+
+        // It's full, but the slot of `b` is free.
+        let d = list.insert_before(a, "d".to_string());
+        assert_eq!(d, b);
+        assert_eq!(values(&list), ["d", "a", "c"]);
+        assert_eq!(list.len(), 3);
+    }
+
+    #[test]
+    fn the_first_free_slot_is_used() {
+        let mut list = IntrusiveList::<u32>::new();
+        let keys: Vec<_> = (0..100).map(|value| list.push_back(value)).collect();
+        list.remove(keys[70]);
+        list.remove(keys[3]);
+        list.remove(keys[4]);
+
+        assert_eq!(list.push_back(100), keys[3]);
+        assert_eq!(list.push_back(101), keys[4]);
+        assert_eq!(list.push_back(102), keys[70]);
+        assert_eq!(list.push_back(103), Key(100));
+        assert_eq!(list.len(), 101);
+    }
+
+    #[test]
+    fn moved_elements_keep_their_keys() {
+        let mut list = IntrusiveList::<u32, SlabBuffer<HeapBuffer<Slot<u32>>>>::new();
+        let keys = [1, 2, 3].map(|value| list.push_back(value));
+
+        list.move_to_front(keys[1]);
+        assert_eq!(values(&list), [2, 1, 3]);
+        list.move_to_back(keys[0]);
+        assert_eq!(values(&list), [2, 3, 1]);
+        list.move_to_back(keys[0]);
+        assert_eq!(values(&list), [2, 3, 1]);
+        assert_eq!(list.get(keys[1]), Some(&2));
+        assert_eq!(list.back(), Some(keys[0]));
+    }
+
+    #[test]
+    fn both_ends_can_be_used() {
+        let mut list = IntrusiveList::<u32, SlabBuffer<HeapBuffer<Slot<u32>>>>::new();
+        list.push_back(2);
+        list.push_front(1);
+        let three = list.push_back(3);
+        *list.get_mut(three).unwrap() += 1;
+
+        let reversed: Vec<_> = list.iter().rev().map(|(_, &value)| value).collect();
+        assert_eq!(reversed, [4, 2, 1]);
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.pop_back(), Some(4));
+        assert_eq!(list.pop_back(), Some(2));
+        assert_eq!(list.pop_front(), None);
+        assert!(list.is_empty());
+        assert_eq!(list.front(), None);
+
+        list.push_back(5);
+        list.clear();
+        assert!(list.is_empty());
+        assert_eq!(list.iter().count(), 0);
+    }
+
+    #[test]
+    fn elements_left_in_the_holes_are_dropped() {
+        let counter = AtomicI64::new(0);
+        {
+            let mut list = IntrusiveList::<LifeCounter<'_>>::new();
+            let keys: Vec<_> = (0..10)
+                .map(|_| list.push_back(LifeCounter::new(&counter)))
+                .collect();
+            list.remove(keys[2]);
+            list.remove(keys[7]);
+            assert_eq!(counter.load(Ordering::SeqCst), 8);
+        }
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+    }
+}
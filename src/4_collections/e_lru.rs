@@ -0,0 +1,365 @@
+//! Least-recently-used cache made of an [`IntrusiveList`] (in recency order)
+//! and a hash index over it, each on top of its own buffer.
+
+use std::{
+    borrow::Borrow,
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+};
+
+use crate::{
+    composites::SlabBuffer,
+    interface::{contiguous_memory::ContiguousMemoryBuffer, sparse::SparseBuffer, Buffer},
+    DefaultBuffer,
+};
+
+use super::{
+    intrusive_list::{IntrusiveList, Key, Slot},
+    Vector,
+};
+
+/// Cache that holds up to a fixed number of entries. When it's full, putting a
+/// new one evicts the least recently used.
+///
+/// The entries are kept in an [`IntrusiveList`] (on top of `B`), from the most
+/// recently used to the least. To find them by key there is an open-addressing
+/// hash table (on top of `I`) with twice as many slots as entries (rounded up
+/// to a power of two).
+///
+/// With inline buffers it never allocates. The table needs space for its
+/// slots, eg. for 4 entries:
+/// ```
+/// # use buffers::{base_buffers::InlineBuffer, collections::{intrusive_list::{Key, Slot}, lru::LruCache}, composites::SlabBuffer};
+/// type Entries = SlabBuffer<InlineBuffer<Slot<(u32, &'static str)>, 4>, InlineBuffer<usize, 1>>;
+/// type Index = InlineBuffer<Option<Key>, 8>;
+///
+/// let mut cache = LruCache::<u32, &str, Entries, Index>::new(4);
+/// for (key, value) in [(1, "a"), (2, "b"), (3, "c"), (4, "d")] {
+///     cache.put(key, value);
+/// }
+/// assert_eq!(cache.get(&1), Some(&"a"));
+/// cache.put(5, "e"); // Evicts 2, which is now the least recently used.
+///
+/// assert_eq!(cache.get(&2), None);
+/// let keys: Vec<_> = cache.iter().map(|(key, _)| *key).collect();
+/// assert_eq!(keys, [5, 1, 4, 3]);
+/// ```
+pub struct LruCache<
+    K,
+    V,
+    B = SlabBuffer<DefaultBuffer<Slot<(K, V)>>>,
+    I = DefaultBuffer<Option<Key>>,
+    S = RandomState,
+> where
+    B: SparseBuffer<Element = Slot<(K, V)>> + ContiguousMemoryBuffer,
+    I: Buffer<Element = Option<Key>> + ContiguousMemoryBuffer,
+{
+    entries: IntrusiveList<(K, V), B>,
+    table: Vector<Option<Key>, I>,
+    capacity: usize,
+    hasher: S,
+}
+
+impl<K, V, B, I, S> LruCache<K, V, B, I, S>
+where
+    K: Hash + Eq,
+    B: SparseBuffer<Element = Slot<(K, V)>> + ContiguousMemoryBuffer,
+    I: Buffer<Element = Option<Key>> + ContiguousMemoryBuffer,
+    S: BuildHasher,
+{
+    /// Makes an empty cache for up to `capacity` entries on top of the given
+    /// buffers.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is 0, or if the buffers cannot hold the entries or
+    /// the slots of the table.
+    pub fn from_parts(capacity: usize, entries: B, table: I, hasher: S) -> Self {
+        assert!(capacity > 0, "The capacity of the cache must not be 0");
+        let mut entries = IntrusiveList::from_buffer(entries);
+        entries
+            .try_reserve(capacity)
+            .expect("The buffer cannot hold all the entries");
+
+        let slots = (capacity * 2).next_power_of_two();
+        let mut table = Vector::from_buffer(table);
+        table
+            .try_reserve_exact(slots)
+            .expect("The buffer cannot hold all the slots of the table");
+        for _ in 0..slots {
+            table.push(None);
+        }
+
+        Self {
+            entries,
+            table,
+            capacity,
+            hasher,
+        }
+    }
+
+    /// Makes an empty cache for up to `capacity` entries with default buffers.
+    ///
+    /// # Panics
+    /// Same as [`LruCache::from_parts`].
+    pub fn new(capacity: usize) -> Self
+    where
+        B: Default,
+        I: Default,
+        S: Default,
+    {
+        Self::from_parts(capacity, B::default(), I::default(), S::default())
+    }
+
+    /// Number of entries.
+    pub const fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether it has no entries.
+    pub const fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Maximum number of entries.
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of entries it can still add before evicting.
+    pub const fn remaining_capacity(&self) -> usize {
+        self.capacity - self.entries.len()
+    }
+
+    /// Value of `key`, if any, which becomes the most recently used.
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_mut(key).map(|value| &*value)
+    }
+
+    /// Mutable value of `key`, if any, which becomes the most recently used.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let entry = self.table[self.find(key).ok()?]?;
+        self.entries.move_to_front(entry);
+        self.entries.get_mut(entry).map(|(_, value)| value)
+    }
+
+    /// Value of `key`, if any, without changing how recently it was used.
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let entry = self.table[self.find(key).ok()?]?;
+        self.entries.get(entry).map(|(_, value)| value)
+    }
+
+    /// Whether there is an entry for `key` (without changing how recently it
+    /// was used).
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(key).is_ok()
+    }
+
+    /// Sets the value of `key`, which becomes the most recently used. Returns
+    /// the previous value, if any.
+    ///
+    /// If the cache is full, the least recently used entry is evicted first.
+    /// Use [`LruCache::push`] to get it back.
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        match self.find(&key) {
+            Ok(slot) => {
+                let entry = self.table[slot]?;
+                self.entries.move_to_front(entry);
+                let (_, old) = self.entries.get_mut(entry)?;
+                Some(std::mem::replace(old, value))
+            }
+            Err(_) => {
+                self.push(key, value);
+                None
+            }
+        }
+    }
+
+    /// Same as [`LruCache::put`], but returns the evicted entry (if any)
+    /// instead of the previous value, which is dropped.
+    pub fn push(&mut self, key: K, value: V) -> Option<(K, V)> {
+        if let Ok(slot) = self.find(&key) {
+            let entry = self.table[slot]?;
+            self.entries.move_to_front(entry);
+            *self.entries.get_mut(entry)? = (key, value);
+            return None;
+        }
+
+        let evicted = if self.len() == self.capacity {
+            self.evict()
+        } else {
+            None
+        };
+        // The key may have moved when evicting, so it's looked up again.
+        let slot = self.find(&key).unwrap_err();
+        self.table[slot] = Some(self.entries.push_front((key, value)));
+        evicted
+    }
+
+    /// Removes the least recently used entry and returns it, if any.
+    pub fn evict(&mut self) -> Option<(K, V)> {
+        let entry = self.entries.back()?;
+        let (key, _) = self.entries.get(entry)?;
+        let slot = self.find(key).ok()?;
+        self.remove_slot(slot)
+    }
+
+    /// Removes the entry of `key` and returns its value, if any.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let slot = self.find(key).ok()?;
+        self.remove_slot(slot).map(|(_, value)| value)
+    }
+
+    /// Removes all the entries.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.table.as_mut_slice().fill(None);
+    }
+
+    /// Iterates over the entries from the most recently used to the least.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&K, &V)> + ExactSizeIterator + '_ {
+        self.entries.iter().map(|(_, (key, value))| (key, value))
+    }
+
+    /// Internal utility that finds the slot of the table with the entry of
+    /// `key` (`Ok`) or the empty slot where it would go (`Err`).
+    fn find<Q>(&self, key: &Q) -> Result<usize, usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let table = self.table.as_slice();
+        let mask = table.len() - 1;
+        let mut slot = self.ideal_slot(key);
+        // There are always empty slots (the table is bigger than the cache).
+        while let Some(entry) = table[slot] {
+            if let Some((stored, _)) = self.entries.get(entry) {
+                if stored.borrow() == key {
+                    return Ok(slot);
+                }
+            }
+            slot = (slot + 1) & mask;
+        }
+        Err(slot)
+    }
+
+    /// Internal utility that gives the slot where `key` goes if there are no
+    /// collisions.
+    fn ideal_slot<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        let mask = self.table.len() - 1;
+        (self.hasher.hash_one(key) as usize) & mask
+    }
+
+    /// Internal utility that removes the entry of the (filled) `slot` from
+    /// both the list and the table.
+    ///
+    /// The following entries of the same run are shifted back so lookups
+    /// don't stop at the hole.
+    fn remove_slot(&mut self, mut slot: usize) -> Option<(K, V)> {
+        let entry = self.table[slot].take()?;
+        let removed = self.entries.remove(entry);
+
+        let mask = self.table.len() - 1;
+        let mut next = (slot + 1) & mask;
+        while let Some(entry) = self.table[next] {
+            let (key, _) = self.entries.get(entry)?;
+            let ideal = self.ideal_slot(key);
+            // It can fill the hole if the hole is between its ideal slot and
+            // where it is now (in probing order).
+            if (next.wrapping_sub(ideal) & mask) >= (next.wrapping_sub(slot) & mask) {
+                self.table[slot] = self.table[next].take();
+                slot = next;
+            }
+            next = (next + 1) & mask;
+        }
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::BuildHasherDefault;
+
+    use crate::base_buffers::HeapBuffer;
+
+    use super::*;
+
+    /// Hasher that puts everything in the same slot, to test collisions.
+    #[derive(Default)]
+    struct Colliding;
+
+    impl std::hash::Hasher for Colliding {
+        fn finish(&self) -> u64 {
+            0
+        }
+
+        fn write(&mut self, _bytes: &[u8]) {}
+    }
+
+    type CollidingCache = LruCache<
+        String,
+        u32,
+        SlabBuffer<HeapBuffer<Slot<(String, u32)>>>,
+        HeapBuffer<Option<Key>>,
+        BuildHasherDefault<Colliding>,
+    >;
+
+    #[test]
+    fn evicts_the_least_recently_used() {
+        let mut cache = LruCache::<u32, String>::new(2);
+        assert_eq!(cache.push(1, "a".to_string()), None);
+        assert_eq!(cache.push(2, "b".to_string()), None);
+        assert_eq!(cache.put(1, "A".to_string()).as_deref(), Some("a"));
+
+        assert_eq!(cache.push(3, "c".to_string()), Some((2, "b".to_string())));
+        assert_eq!(cache.peek(&1).map(String::as_str), Some("A"));
+        assert_eq!(cache.evict(), Some((1, "A".to_string())));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.remaining_capacity(), 1);
+        assert!(cache.contains(&3));
+    }
+
+    #[test]
+    fn collisions_are_found_after_removals() {
+        let mut cache = CollidingCache::new(4);
+        for (value, key) in ["a", "b", "c", "d"].into_iter().enumerate() {
+            cache.put(key.to_string(), value as u32);
+        }
+
+        assert_eq!(cache.remove("b"), Some(1));
+        assert_eq!(cache.get("d"), Some(&3));
+        assert_eq!(cache.get("c"), Some(&2));
+        cache.put("e".to_string(), 4);
+        cache.put("f".to_string(), 5); // Evicts "a"
+
+        let entries: Vec<_> = cache
+            .iter()
+            .map(|(key, &value)| (key.as_str(), value))
+            .collect();
+        assert_eq!(entries, [("f", 5), ("e", 4), ("c", 2), ("d", 3)]);
+        assert_eq!(cache.get("a"), None);
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.get("f"), None);
+    }
+}
@@ -0,0 +1,271 @@
+//! Graphs stored in compressed sparse row (CSR) form: the targets of all the
+//! edges in a single buffer, grouped by source, and the offset where each
+//! group starts in another.
+//!
+//! Nodes are numbered from 0. Use [`CsrBuilder`] to make a graph from its
+//! edges.
+
+use crate::{
+    interface::{contiguous_memory::ContiguousMemoryBuffer, Buffer},
+    DefaultBuffer,
+};
+
+use super::Vector;
+
+/// Directed graph in CSR form. The neighbors of a node are a slice, sorted
+/// and without repetitions.
+///
+/// # Example
+/// ```
+/// # use buffers::collections::csr::{CsrBuilder, CsrGraph};
+/// let mut builder: CsrBuilder = CsrBuilder::new();
+/// builder.extend([(0, 2), (1, 0), (0, 1), (0, 2)]);
+/// let graph: CsrGraph = builder.build();
+///
+/// assert_eq!(graph.node_count(), 3);
+/// assert_eq!(graph.edge_count(), 3);
+/// assert_eq!(graph.neighbors(0), [1, 2]);
+/// assert_eq!(graph.neighbors(2), []);
+/// ```
+pub struct CsrGraph<O = DefaultBuffer<usize>, E = DefaultBuffer<usize>>
+where
+    O: Buffer<Element = usize> + ContiguousMemoryBuffer,
+    E: Buffer<Element = usize> + ContiguousMemoryBuffer,
+{
+    /// `offsets[u]..offsets[u + 1]` are the edges of `u`.
+    offsets: Vector<usize, O>,
+    targets: Vector<usize, E>,
+}
+
+impl<O, E> CsrGraph<O, E>
+where
+    O: Buffer<Element = usize> + ContiguousMemoryBuffer,
+    E: Buffer<Element = usize> + ContiguousMemoryBuffer,
+{
+    /// Number of nodes.
+    pub fn node_count(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    /// Number of edges.
+    pub fn edge_count(&self) -> usize {
+        self.targets.len()
+    }
+
+    /// Targets of the edges that start at `node`, sorted.
+    ///
+    /// # Panics
+    /// Panics if `node` is not a node of the graph.
+    pub fn neighbors(&self, node: usize) -> &[usize] {
+        let start = self.offsets[node];
+        let end = self.offsets[node + 1];
+        &self.targets.as_slice()[start..end]
+    }
+
+    /// Number of edges that start at `node`.
+    ///
+    /// # Panics
+    /// Panics if `node` is not a node of the graph.
+    pub fn degree(&self, node: usize) -> usize {
+        self.offsets[node + 1] - self.offsets[node]
+    }
+
+    /// Iterates over all the edges (as `(source, target)`), sorted.
+    pub fn edges(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.node_count()).flat_map(move |node| {
+            self.neighbors(node)
+                .iter()
+                .map(move |&target| (node, target))
+        })
+    }
+
+    /// The offsets (where the edges of each node start, plus the total at the
+    /// end) and the targets of the edges, which make the CSR form.
+    pub fn as_parts(&self) -> (&[usize], &[usize]) {
+        (self.offsets.as_slice(), self.targets.as_slice())
+    }
+}
+
+/// Collects the edges of a [`CsrGraph`] (in any order) and makes it.
+pub struct CsrBuilder<P = DefaultBuffer<(usize, usize)>>
+where
+    P: Buffer<Element = (usize, usize)> + ContiguousMemoryBuffer,
+{
+    edges: Vector<(usize, usize), P>,
+    node_count: usize,
+}
+
+impl<P> CsrBuilder<P>
+where
+    P: Buffer<Element = (usize, usize)> + ContiguousMemoryBuffer,
+{
+    /// Makes a builder which keeps the edges in `buffer`.
+    pub fn from_buffer(buffer: P) -> Self {
+        Self {
+            edges: Vector::from_buffer(buffer),
+            node_count: 0,
+        }
+    }
+
+    /// Makes a builder with a default buffer.
+    pub fn new() -> Self
+    where
+        P: Default,
+    {
+        Self::from_buffer(P::default())
+    }
+
+    /// Makes sure that the graph has at least `count` nodes (even if some
+    /// don't have edges).
+    pub fn with_nodes(mut self, count: usize) -> Self {
+        self.node_count = self.node_count.max(count);
+        self
+    }
+
+    /// Adds an edge from `source` to `target`.
+    ///
+    /// # Panics
+    /// Panics if it cannot grow.
+    pub fn add_edge(&mut self, source: usize, target: usize) {
+        self.edges.push((source, target));
+        self.node_count = self.node_count.max(source.max(target) + 1);
+    }
+
+    /// Adds all the edges of `edges`.
+    ///
+    /// # Panics
+    /// Panics if it cannot grow.
+    pub fn extend<I: IntoIterator<Item = (usize, usize)>>(&mut self, edges: I) {
+        for (source, target) in edges {
+            self.add_edge(source, target);
+        }
+    }
+
+    /// Makes the graph with default buffers. See [`CsrBuilder::build_into`].
+    pub fn build<O, E>(self) -> CsrGraph<O, E>
+    where
+        O: Buffer<Element = usize> + ContiguousMemoryBuffer + Default,
+        E: Buffer<Element = usize> + ContiguousMemoryBuffer + Default,
+    {
+        self.build_into(O::default(), E::default())
+    }
+
+    /// Makes the graph using `offsets` and `targets` as its buffers. Repeated
+    /// edges are only kept once.
+    ///
+    /// The edges are grouped by source with a counting sort, so it takes
+    /// linear time (plus sorting the neighbors of each node).
+    ///
+    /// # Panics
+    /// Panics if the buffers cannot hold the offsets (one more than the number
+    /// of nodes) or the edges.
+    pub fn build_into<O, E>(self, offsets: O, targets: E) -> CsrGraph<O, E>
+    where
+        O: Buffer<Element = usize> + ContiguousMemoryBuffer,
+        E: Buffer<Element = usize> + ContiguousMemoryBuffer,
+    {
+        let node_count = self.node_count;
+        let edges = self.edges.as_slice();
+
+        let mut offsets = Vector::from_buffer(offsets);
+        offsets
+            .try_reserve_exact(node_count + 1)
+            .expect("The buffer cannot hold the offsets");
+        for _ in 0..=node_count {
+            offsets.push(0);
+        }
+        for &(source, _) in edges {
+            offsets[source + 1] += 1;
+        }
+        for node in 0..node_count {
+            offsets[node + 1] += offsets[node];
+        }
+
+        let mut targets = Vector::from_buffer(targets);
+        targets
+            .try_reserve_exact(edges.len())
+            .expect("The buffer cannot hold the edges");
+        for _ in 0..edges.len() {
+            targets.push(0);
+        }
+        // Each offset is used as the cursor of its node, which leaves it at
+        // the start of the next one.
+        for &(source, target) in edges {
+            targets[offsets[source]] = target;
+            offsets[source] += 1;
+        }
+        for node in (1..=node_count).rev() {
+            offsets[node] = offsets[node - 1];
+        }
+        offsets[0] = 0;
+
+        // Sorts the neighbors of each node and removes repetitions, moving
+        // them back to close the gaps.
+        let mut written = 0;
+        let mut start = 0;
+        for node in 0..node_count {
+            let end = offsets[node + 1];
+            targets.as_mut_slice()[start..end].sort_unstable();
+            let first = written;
+            for index in start..end {
+                let target = targets[index];
+                if written == first || targets[written - 1] != target {
+                    targets[written] = target;
+                    written += 1;
+                }
+            }
+            offsets[node + 1] = written;
+            start = end;
+        }
+        targets.truncate(written);
+
+        CsrGraph { offsets, targets }
+    }
+}
+
+impl<P> Default for CsrBuilder<P>
+where
+    P: Buffer<Element = (usize, usize)> + ContiguousMemoryBuffer + Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::base_buffers::{HeapBuffer, InlineBuffer};
+
+    use super::*;
+
+    #[test]
+    fn groups_sorts_and_compacts_the_edges() {
+        let mut builder = CsrBuilder::<HeapBuffer<_>>::new().with_nodes(5);
+        builder.extend([(3, 1), (0, 4), (3, 0), (0, 4), (3, 1), (1, 1)]);
+        let graph: CsrGraph<HeapBuffer<_>, HeapBuffer<_>> = builder.build();
+
+        assert_eq!(graph.node_count(), 5);
+        assert_eq!(
+            graph.as_parts(),
+            ([0, 1, 2, 2, 4, 4].as_slice(), [4, 1, 0, 1].as_slice())
+        );
+        assert_eq!(graph.degree(3), 2);
+        assert_eq!(
+            graph.edges().collect::<Vec<_>>(),
+            [(0, 4), (1, 1), (3, 0), (3, 1)]
+        );
+    }
+
+    #[test]
+    fn can_be_built_into_inline_buffers() {
+        let mut builder = CsrBuilder::<InlineBuffer<_, 4>>::new();
+        builder.extend([(1, 0), (0, 1)]);
+        let graph = builder.build_into(InlineBuffer::<_, 3>::new(), InlineBuffer::<_, 2>::new());
+        assert_eq!(graph.neighbors(1), [0]);
+
+        let empty = CsrBuilder::<InlineBuffer<_, 1>>::new();
+        let graph = empty.build_into(InlineBuffer::<_, 1>::new(), InlineBuffer::<_, 1>::new());
+        assert_eq!(graph.node_count(), 0);
+        assert_eq!(graph.edges().count(), 0);
+    }
+}
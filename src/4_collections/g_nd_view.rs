@@ -0,0 +1,452 @@
+//! N-dimensional views (eg. matrices or tensors) over the elements of a
+//! vector, or any slice.
+//!
+//! A view has a shape (its size in each dimension) and strides (how many
+//! elements there are between two consecutive positions of a dimension).
+//! Views made from a slice are row-major: the last index is the one that
+//! changes faster.
+
+use std::{
+    marker::PhantomData,
+    ops::{Index, IndexMut, Range},
+};
+
+use crate::interface::{contiguous_memory::ContiguousMemoryBuffer, Buffer};
+
+use super::Vector;
+
+/// Internal type. Shape and strides of a view.
+#[derive(Clone, Copy)]
+struct Layout<const D: usize> {
+    shape: [usize; D],
+    strides: [usize; D],
+}
+
+impl<const D: usize> Layout<D> {
+    /// Row-major layout of `shape` and the number of elements it has, if it
+    /// doesn't overflow.
+    fn row_major(shape: [usize; D]) -> Option<(Self, usize)> {
+        let mut strides = [0; D];
+        let mut len = 1usize;
+        for axis in (0..D).rev() {
+            strides[axis] = len;
+            len = len.checked_mul(shape[axis])?;
+        }
+        Some((Self { shape, strides }, len))
+    }
+
+    fn len(&self) -> usize {
+        self.shape.iter().product()
+    }
+
+    /// Offset of the element at `index`, if it's inside the shape.
+    fn offset(&self, index: [usize; D]) -> Option<usize> {
+        let mut offset = 0;
+        for ((index, size), stride) in index.into_iter().zip(self.shape).zip(self.strides) {
+            if index >= size {
+                return None;
+            }
+            offset += index * stride;
+        }
+        Some(offset)
+    }
+
+    /// Restricts `axis` to `range`. Returns the new layout and the offset
+    /// where it starts (0 if it's empty).
+    fn slice(&self, axis: usize, range: Range<usize>) -> (Self, usize) {
+        assert!(axis < D, "The axis {axis} doesn't exist (there are {D})");
+        assert!(
+            range.start <= range.end && range.end <= self.shape[axis],
+            "The range {range:?} is out of bounds (the size of the axis is {})",
+            self.shape[axis]
+        );
+        let mut layout = *self;
+        layout.shape[axis] = range.len();
+        let start = if layout.len() == 0 {
+            0
+        } else {
+            range.start * self.strides[axis]
+        };
+        (layout, start)
+    }
+
+    fn swap_axes(&mut self, a: usize, b: usize) {
+        self.shape.swap(a, b);
+        self.strides.swap(a, b);
+    }
+
+    fn cursor(&self) -> Cursor<D> {
+        Cursor {
+            layout: *self,
+            index: [0; D],
+            remaining: self.len(),
+        }
+    }
+}
+
+/// Internal type. Walks over the offsets of a layout in row-major order.
+struct Cursor<const D: usize> {
+    layout: Layout<D>,
+    index: [usize; D],
+    remaining: usize,
+}
+
+impl<const D: usize> Cursor<D> {
+    fn next_offset(&mut self) -> Option<usize> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let offset = self.layout.offset(self.index)?;
+        self.remaining -= 1;
+        for axis in (0..D).rev() {
+            self.index[axis] += 1;
+            if self.index[axis] < self.layout.shape[axis] {
+                break;
+            }
+            self.index[axis] = 0;
+        }
+        Some(offset)
+    }
+}
+
+/// Read-only N-dimensional view.
+///
+/// # Example
+/// ```
+/// # use buffers::{base_buffers::HeapBuffer, collections::Vector};
+/// let mut vec = Vector::<u32, HeapBuffer<u32>>::new();
+/// vec.extend_from_slice(&[1, 2, 3, 4, 5, 6]);
+///
+/// let matrix = vec.nd_view([2, 3]).unwrap();
+/// assert_eq!(matrix[[1, 0]], 4);
+///
+/// let column = matrix.slice(1, 2..3);
+/// assert_eq!(column.shape(), [2, 1]);
+/// assert_eq!(column.iter().copied().collect::<Vec<_>>(), [3, 6]);
+///
+/// let transposed = matrix.transposed();
+/// assert_eq!(transposed.iter().copied().collect::<Vec<_>>(), [1, 4, 2, 5, 3, 6]);
+/// ```
+pub struct NdView<'a, T, const D: usize> {
+    data: &'a [T],
+    layout: Layout<D>,
+}
+
+impl<'a, T, const D: usize> NdView<'a, T, D> {
+    /// Makes a row-major view of `data` with the given shape. Returns `None`
+    /// if `data` doesn't have enough elements (extra ones are ignored).
+    pub fn new(data: &'a [T], shape: [usize; D]) -> Option<Self> {
+        let (layout, len) = Layout::row_major(shape)?;
+        (len <= data.len()).then_some(Self { data, layout })
+    }
+
+    /// Size of each dimension.
+    pub fn shape(&self) -> [usize; D] {
+        self.layout.shape
+    }
+
+    /// Number of elements between consecutive positions of each dimension.
+    pub fn strides(&self) -> [usize; D] {
+        self.layout.strides
+    }
+
+    /// Number of elements.
+    pub fn len(&self) -> usize {
+        self.layout.len()
+    }
+
+    /// Whether it has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reference to the element at `index`, if it's inside the shape.
+    pub fn get(&self, index: [usize; D]) -> Option<&'a T> {
+        self.data.get(self.layout.offset(index)?)
+    }
+
+    /// View of the part where the index of `axis` is in `range`. The indices
+    /// of the new view start at 0.
+    ///
+    /// # Panics
+    /// Panics if `axis` doesn't exist or `range` is out of bounds.
+    pub fn slice(&self, axis: usize, range: Range<usize>) -> Self {
+        let (layout, start) = self.layout.slice(axis, range);
+        Self {
+            data: &self.data[start..],
+            layout,
+        }
+    }
+
+    /// Same view with the dimensions `a` and `b` swapped.
+    ///
+    /// # Panics
+    /// Panics if any of the axes doesn't exist.
+    pub fn swap_axes(mut self, a: usize, b: usize) -> Self {
+        self.layout.swap_axes(a, b);
+        self
+    }
+
+    /// Same view with the dimensions in reverse order (the transpose, for
+    /// matrices).
+    pub fn transposed(mut self) -> Self {
+        self.layout.shape.reverse();
+        self.layout.strides.reverse();
+        self
+    }
+
+    /// Iterates over all the elements in row-major order.
+    pub fn iter(&self) -> Iter<'a, T, D> {
+        Iter {
+            data: self.data,
+            cursor: self.layout.cursor(),
+        }
+    }
+}
+
+impl<T, const D: usize> Clone for NdView<'_, T, D> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, const D: usize> Copy for NdView<'_, T, D> {}
+
+impl<T, const D: usize> Index<[usize; D]> for NdView<'_, T, D> {
+    type Output = T;
+
+    fn index(&self, index: [usize; D]) -> &T {
+        self.get(index).expect("The index is out of bounds")
+    }
+}
+
+impl<'a, T, const D: usize> IntoIterator for NdView<'a, T, D> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, D>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Mutable N-dimensional view. See [`NdView`].
+pub struct NdViewMut<'a, T, const D: usize> {
+    data: &'a mut [T],
+    layout: Layout<D>,
+}
+
+impl<'a, T, const D: usize> NdViewMut<'a, T, D> {
+    /// Makes a row-major view of `data` with the given shape. Returns `None`
+    /// if `data` doesn't have enough elements (extra ones are ignored).
+    pub fn new(data: &'a mut [T], shape: [usize; D]) -> Option<Self> {
+        let (layout, len) = Layout::row_major(shape)?;
+        (len <= data.len()).then_some(Self { data, layout })
+    }
+
+    /// Read-only view of the same elements.
+    pub fn view(&self) -> NdView<'_, T, D> {
+        NdView {
+            data: self.data,
+            layout: self.layout,
+        }
+    }
+
+    /// Size of each dimension.
+    pub fn shape(&self) -> [usize; D] {
+        self.layout.shape
+    }
+
+    /// Number of elements between consecutive positions of each dimension.
+    pub fn strides(&self) -> [usize; D] {
+        self.layout.strides
+    }
+
+    /// Number of elements.
+    pub fn len(&self) -> usize {
+        self.layout.len()
+    }
+
+    /// Whether it has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reference to the element at `index`, if it's inside the shape.
+    pub fn get(&self, index: [usize; D]) -> Option<&T> {
+        self.data.get(self.layout.offset(index)?)
+    }
+
+    /// Mutable reference to the element at `index`, if it's inside the shape.
+    pub fn get_mut(&mut self, index: [usize; D]) -> Option<&mut T> {
+        self.data.get_mut(self.layout.offset(index)?)
+    }
+
+    /// Mutable view of the part where the index of `axis` is in `range`. See
+    /// [`NdView::slice`].
+    ///
+    /// # Panics
+    /// Panics if `axis` doesn't exist or `range` is out of bounds.
+    pub fn slice_mut(&mut self, axis: usize, range: Range<usize>) -> NdViewMut<'_, T, D> {
+        let (layout, start) = self.layout.slice(axis, range);
+        NdViewMut {
+            data: &mut self.data[start..],
+            layout,
+        }
+    }
+
+    /// Same view with the dimensions `a` and `b` swapped.
+    ///
+    /// # Panics
+    /// Panics if any of the axes doesn't exist.
+    pub fn swap_axes(mut self, a: usize, b: usize) -> Self {
+        self.layout.swap_axes(a, b);
+        self
+    }
+
+    /// Same view with the dimensions in reverse order (the transpose, for
+    /// matrices).
+    pub fn transposed(mut self) -> Self {
+        self.layout.shape.reverse();
+        self.layout.strides.reverse();
+        self
+    }
+
+    /// Iterates over all the elements in row-major order.
+    pub fn iter(&self) -> Iter<'_, T, D> {
+        self.view().iter()
+    }
+
+    /// Iterates over mutable references to all the elements in row-major
+    /// order.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T, D> {
+        IterMut {
+            data: self.data.as_mut_ptr(),
+            cursor: self.layout.cursor(),
+            _lifetime: PhantomData,
+        }
+    }
+}
+
+impl<T, const D: usize> Index<[usize; D]> for NdViewMut<'_, T, D> {
+    type Output = T;
+
+    fn index(&self, index: [usize; D]) -> &T {
+        self.get(index).expect("The index is out of bounds")
+    }
+}
+
+impl<T, const D: usize> IndexMut<[usize; D]> for NdViewMut<'_, T, D> {
+    fn index_mut(&mut self, index: [usize; D]) -> &mut T {
+        self.get_mut(index).expect("The index is out of bounds")
+    }
+}
+
+/// Iterator over the elements of an [`NdView`].
+pub struct Iter<'a, T, const D: usize> {
+    data: &'a [T],
+    cursor: Cursor<D>,
+}
+
+impl<'a, T, const D: usize> Iterator for Iter<'a, T, D> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.data.get(self.cursor.next_offset()?)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.cursor.remaining, Some(self.cursor.remaining))
+    }
+}
+
+impl<T, const D: usize> ExactSizeIterator for Iter<'_, T, D> {}
+
+/// Iterator over mutable references to the elements of an [`NdViewMut`].
+pub struct IterMut<'a, T, const D: usize> {
+    data: *mut T,
+    cursor: Cursor<D>,
+    _lifetime: PhantomData<&'a mut [T]>,
+}
+
+impl<'a, T, const D: usize> Iterator for IterMut<'a, T, D> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        let ptr = self.data.wrapping_add(self.cursor.next_offset()?);
+        // SAFETY: The offsets of the layout are inside the slice (checked when
+        // making the view) and the strides never make two positions share an
+        // element, so each one is only given once while the slice is borrowed.
+        Some(unsafe { &mut *ptr })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.cursor.remaining, Some(self.cursor.remaining))
+    }
+}
+
+impl<T, const D: usize> ExactSizeIterator for IterMut<'_, T, D> {}
+
+impl<T, B> Vector<T, B>
+where
+    B: Buffer<Element = T> + ContiguousMemoryBuffer,
+{
+    /// Row-major view of the elements with the given shape. Returns `None` if
+    /// there aren't enough of them.
+    pub fn nd_view<const D: usize>(&self, shape: [usize; D]) -> Option<NdView<'_, T, D>> {
+        NdView::new(self.as_slice(), shape)
+    }
+
+    /// Mutable row-major view of the elements with the given shape. Returns
+    /// `None` if there aren't enough of them.
+    pub fn nd_view_mut<const D: usize>(
+        &mut self,
+        shape: [usize; D],
+    ) -> Option<NdViewMut<'_, T, D>> {
+        NdViewMut::new(self.as_mut_slice(), shape)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subviews_index_into_the_same_elements() {
+        let data: Vec<u32> = (0..24).collect();
+        let tensor = NdView::new(&data, [2, 3, 4]).unwrap();
+        assert_eq!(tensor.strides(), [12, 4, 1]);
+        assert_eq!(tensor[[1, 2, 3]], 23);
+        assert_eq!(tensor.get([0, 3, 0]), None);
+
+        let part = tensor.slice(0, 1..2).slice(2, 1..3);
+        assert_eq!(part.shape(), [1, 3, 2]);
+        assert_eq!(
+            part.iter().copied().collect::<Vec<_>>(),
+            [13, 14, 17, 18, 21, 22]
+        );
+        assert_eq!(part.swap_axes(1, 2)[[0, 1, 2]], 22);
+
+        let empty = tensor.slice(1, 3..3);
+        assert!(empty.is_empty());
+        assert_eq!(empty.iter().count(), 0);
+
+        assert!(NdView::new(&data, [5, 5]).is_none());
+        assert!(NdView::new(&data, [usize::MAX, 2]).is_none());
+        assert_eq!(NdView::new(&data, []).unwrap()[[]], 0);
+    }
+
+    #[test]
+    fn mutable_views_write_through() {
+        let mut data = [0u32; 6];
+        let mut matrix = NdViewMut::new(&mut data, [2, 3]).unwrap();
+        matrix[[0, 1]] = 1;
+        for (value, new) in matrix.slice_mut(1, 1..3).iter_mut().zip(10..) {
+            *value += new;
+        }
+
+        let mut transposed = matrix.transposed();
+        transposed.iter_mut().for_each(|value| *value *= 2);
+        assert_eq!(transposed[[2, 0]], 22);
+        assert_eq!(data, [0, 22, 22, 0, 24, 26]);
+    }
+}
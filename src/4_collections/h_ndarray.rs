@@ -0,0 +1,128 @@
+//! Integration with `ndarray`. Requires the `ndarray` feature.
+//!
+//! Contiguous vectors can be seen as arrays of any shape without copying
+//! ([`Vector::array_view`] and [`Vector::array_view_mut`]), and owned arrays
+//! in standard layout can be turned into heap vectors reusing their
+//! allocation.
+
+use std::mem;
+
+use ndarray::{Array, ArrayView, ArrayViewMut, Dimension, ShapeError, StrideShape};
+
+use crate::{
+    base_buffers::HeapBuffer,
+    interface::{contiguous_memory::ContiguousMemoryBuffer, Buffer},
+};
+
+use super::Vector;
+
+impl<T, B> Vector<T, B>
+where
+    B: Buffer<Element = T> + ContiguousMemoryBuffer,
+{
+    /// View of the elements as an array with the given shape (no copies). It
+    /// fails if the number of elements doesn't match the shape.
+    ///
+    /// # Example
+    /// ```
+    /// # use buffers::collections::Vector;
+    /// let mut vec = Vector::<u32>::new();
+    /// vec.extend_from_slice(&[1, 2, 3, 4, 5, 6]);
+    ///
+    /// let matrix = vec.array_view((2, 3)).unwrap();
+    /// assert_eq!(matrix[[1, 0]], 4);
+    /// assert_eq!(matrix.t().row(2).to_vec(), [3, 6]);
+    /// assert!(vec.array_view((4, 2)).is_err());
+    /// ```
+    pub fn array_view<Sh, D>(&self, shape: Sh) -> Result<ArrayView<'_, T, D>, ShapeError>
+    where
+        Sh: Into<StrideShape<D>>,
+        D: Dimension,
+    {
+        ArrayView::from_shape(shape, self.as_slice())
+    }
+
+    /// Mutable view of the elements as an array with the given shape (no
+    /// copies). It fails if the number of elements doesn't match the shape.
+    pub fn array_view_mut<Sh, D>(&mut self, shape: Sh) -> Result<ArrayViewMut<'_, T, D>, ShapeError>
+    where
+        Sh: Into<StrideShape<D>>,
+        D: Dimension,
+    {
+        ArrayViewMut::from_shape(shape, self.as_mut_slice())
+    }
+}
+
+/// Takes the allocation of the array, with the elements in logical order.
+///
+/// It only works for arrays in standard (row-major) layout, otherwise their
+/// order in memory is different. Zero-sized elements aren't supported either.
+/// In both cases the array is given back unchanged.
+///
+/// If the array is a part of its allocation (eg. after slicing it), the
+/// elements that aren't in it are dropped and the rest moved to the start.
+///
+/// # Example
+/// ```
+/// # use buffers::{base_buffers::HeapBuffer, collections::Vector};
+/// # use ndarray::{array, s};
+/// let matrix = array![[1, 2], [3, 4], [5, 6]];
+/// let vec = Vector::<_, HeapBuffer<_>>::try_from(matrix.slice_move(s![1.., ..]));
+/// assert_eq!(vec.ok().unwrap().as_slice(), [3, 4, 5, 6]);
+///
+/// let transposed = array![[1, 2], [3, 4]].reversed_axes();
+/// assert!(Vector::<_, HeapBuffer<_>>::try_from(transposed).is_err());
+/// ```
+impl<T, D: Dimension> TryFrom<Array<T, D>> for Vector<T, HeapBuffer<T>> {
+    type Error = Array<T, D>;
+
+    fn try_from(array: Array<T, D>) -> Result<Self, Self::Error> {
+        if mem::size_of::<T>() == 0 || !array.is_standard_layout() {
+            return Err(array);
+        }
+        let len = array.len();
+        let (mut vec, offset) = array.into_raw_vec_and_offset();
+        let offset = offset.unwrap_or(0);
+        vec.truncate(offset + len);
+        vec.drain(..offset);
+        match Vector::try_from(vec) {
+            Ok(vec) => Ok(vec),
+            Err(_) => unreachable!("The elements are not zero-sized"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr2, s, Array2, Ix3, ShapeBuilder};
+
+    use super::*;
+
+    #[test]
+    fn views_share_the_elements() {
+        let mut vec = Vector::<u32, HeapBuffer<u32>>::new();
+        vec.extend_from_slice(&[0; 12]);
+        vec.array_view_mut((2, 2, 3))
+            .unwrap()
+            .indexed_iter_mut()
+            .for_each(|((a, b, c), value)| *value = (a * 100 + b * 10 + c) as u32);
+
+        assert_eq!(vec.as_slice()[..4], [0, 1, 2, 10]);
+        let view = vec.array_view::<_, Ix3>([3, 2, 2].f()).unwrap();
+        assert_eq!(view[[1, 0, 0]], 1);
+    }
+
+    #[test]
+    fn arrays_keep_their_allocation() {
+        let array = Array2::from_shape_fn((3, 4), |(row, column)| (row * 4 + column).to_string());
+        let ptr = array.as_ptr();
+        let vec = Vector::<_, HeapBuffer<_>>::try_from(array).ok().unwrap();
+        assert_eq!(vec.as_slice().as_ptr(), ptr);
+        assert_eq!(vec.len(), 12);
+        assert_eq!(vec.as_slice()[11], "11");
+
+        let sliced = arr2(&[["a"; 3]; 3]).slice_move(s![..2, ..]);
+        let vec = Vector::<_, HeapBuffer<_>>::try_from(sliced).ok().unwrap();
+        assert_eq!(vec.len(), 6);
+    }
+}
@@ -0,0 +1,174 @@
+//! Vector for data that is read all the time and rarely changed (eg.
+//! configuration or routing tables), in the style of RCU: readers get
+//! immutable snapshots without locking and writers publish new versions.
+
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use arc_swap::ArcSwap;
+
+use crate::{
+    interface::{contiguous_memory::ContiguousMemoryBuffer, resize_error::ResizeError, Buffer},
+    DefaultBuffer,
+};
+
+use super::vec::Vector;
+
+/// Vector shared between threads where reading never blocks.
+///
+/// Readers [`ReadMostlyVector::load`] the current version, which is frozen:
+/// it never changes while they hold it. Writers make the next version
+/// ([`ReadMostlyVector::update`] copies the current one) and publish it
+/// atomically, so readers see either the old or the new one, never a mix.
+/// Writers are serialized, so no update is lost. An old version is dropped
+/// when the last reader holding it lets it go.
+///
+/// # Example
+/// ```
+/// # use std::{sync::Arc, thread};
+/// # use buffers::collections::read_mostly::ReadMostlyVector;
+/// let routes = Arc::new(ReadMostlyVector::<u32>::new());
+///
+/// let reader = {
+///     let routes = Arc::clone(&routes);
+///     thread::spawn(move || {
+///         let snapshot = routes.load();
+///         assert!(snapshot.is_empty() || snapshot.as_slice() == [1, 2]);
+///     })
+/// };
+/// routes.update(|next| next.extend([1, 2])).unwrap();
+/// reader.join().unwrap();
+///
+/// assert_eq!(routes.load().as_slice(), [1, 2]);
+/// ```
+pub struct ReadMostlyVector<T, B: Buffer<Element = T> = DefaultBuffer<T>> {
+    current: ArcSwap<Vector<T, B>>,
+    writer: Mutex<()>,
+}
+
+impl<T, B: Buffer<Element = T>> ReadMostlyVector<T, B> {
+    /// Makes it with `vec` as its first version.
+    pub fn from_vector(vec: Vector<T, B>) -> Self {
+        Self {
+            current: ArcSwap::from_pointee(vec),
+            writer: Mutex::new(()),
+        }
+    }
+
+    /// Gives the current version, without locking. Versions published later
+    /// don't change it.
+    pub fn load(&self) -> Arc<Vector<T, B>> {
+        self.current.load_full()
+    }
+
+    /// Replaces the current version with `next` and gives back the old one.
+    /// Readers which already loaded it keep it.
+    pub fn publish(&self, next: Vector<T, B>) -> Arc<Vector<T, B>> {
+        let _writing = self.lock_writer();
+        self.current.swap(Arc::new(next))
+    }
+
+    /// Internal utility to serialize writers. A writer that panicked didn't
+    /// publish anything, so poisoning is ignored.
+    fn lock_writer(&self) -> MutexGuard<'_, ()> {
+        self.writer.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T, B> ReadMostlyVector<T, B>
+where
+    B: Buffer<Element = T> + Default,
+{
+    /// Makes it with an empty first version.
+    pub fn new() -> Self {
+        Self::from_vector(Vector::new())
+    }
+}
+
+impl<T, B> ReadMostlyVector<T, B>
+where
+    T: Clone,
+    B: ContiguousMemoryBuffer<Element = T> + Default,
+{
+    /// Copies the current version, changes the copy with `change` and
+    /// publishes it. Other writers wait until it's done.
+    ///
+    /// If the copy cannot be made, it returns the error and nothing is
+    /// published. If `change` panics, nothing is published either.
+    pub fn update<R>(&self, change: impl FnOnce(&mut Vector<T, B>) -> R) -> Result<R, ResizeError> {
+        let _writing = self.lock_writer();
+        let mut next = Vector::try_from_slice(self.current.load().as_slice())?;
+        let result = change(&mut next);
+        self.current.store(Arc::new(next));
+        Ok(result)
+    }
+}
+
+impl<T, B> Default for ReadMostlyVector<T, B>
+where
+    B: Buffer<Element = T> + Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicBool, Ordering},
+        thread,
+    };
+
+    use crate::test_utils::panic::assert_panic;
+
+    use super::*;
+
+    #[test]
+    fn readers_keep_their_version() {
+        let vec = ReadMostlyVector::<u32>::new();
+        vec.update(|next| next.push(1)).unwrap();
+        let first = vec.load();
+
+        vec.update(|next| next.push(2)).unwrap();
+        let old = vec.publish(Vector::new());
+
+        assert_eq!(first.as_slice(), [1]);
+        assert_eq!(old.as_slice(), [1, 2]);
+        assert!(vec.load().is_empty());
+    }
+
+    #[test]
+    fn panicking_updates_publish_nothing() {
+        let vec = ReadMostlyVector::<u32>::new();
+        vec.update(|next| next.push(1)).unwrap();
+        assert_panic(std::panic::AssertUnwindSafe(|| {
+            vec.update(|next| {
+                next.push(2);
+                panic!("Failing on purpose");
+            })
+        }));
+
+        vec.update(|next| next.push(3)).unwrap();
+        assert_eq!(vec.load().as_slice(), [1, 3]);
+    }
+
+    #[test]
+    fn readers_see_whole_versions() {
+        let vec = ReadMostlyVector::<u32>::new();
+        let done = AtomicBool::new(false);
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                while !done.load(Ordering::Relaxed) {
+                    let snapshot = vec.load();
+                    let expected: Vec<u32> = (0..snapshot.len() as u32).collect();
+                    assert_eq!(snapshot.as_slice(), expected);
+                }
+            });
+            for i in 0..50 {
+                vec.update(|next| next.push(i)).unwrap();
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+        assert_eq!(vec.load().len(), 50);
+    }
+}
@@ -0,0 +1,122 @@
+//! Interoperability of byte vectors with JavaScript's typed arrays, for
+//! `wasm32` targets. Requires the `wasm-bindgen` feature.
+//!
+//! The views are zero-copy: they point straight into the vector in the wasm
+//! memory. The functions which copy don't have the restrictions of a view.
+
+use std::mem::MaybeUninit;
+
+use js_sys::Uint8Array;
+
+use crate::{
+    base_buffers::SliceBuffer,
+    interface::{contiguous_memory::ContiguousMemoryBuffer, resize_error::ResizeError, Buffer},
+};
+
+use super::Vector;
+
+/// Gives a `Uint8Array` which shows the elements of `vec` without copying
+/// them.
+///
+/// # Safety
+/// The view points into the wasm memory, so it must not be used after:
+///   * `vec` is changed, moved or dropped.
+///   * The wasm memory grows (any allocation might make it grow), which
+///     detaches all the views.
+///
+/// # Example
+/// ```no_run
+/// # use buffers::collections::{wasm, Vector};
+/// let vec = Vector::<u8>::from_slice(b"hello");
+/// // SAFETY: Nothing allocates while the view is used.
+/// let view = unsafe { wasm::view(&vec) };
+/// assert_eq!(view.length(), 5);
+/// ```
+pub unsafe fn view<B>(vec: &Vector<u8, B>) -> Uint8Array
+where
+    B: Buffer<Element = u8> + ContiguousMemoryBuffer,
+{
+    // SAFETY: The caller ensures that the view isn't used after the slice
+    // is invalidated.
+    unsafe { Uint8Array::view(vec.as_slice()) }
+}
+
+/// Gives a `Uint8Array` which shows the spare capacity of `vec` without
+/// copying it, so JavaScript can write into it directly. Use
+/// [`Vector::set_len`] afterwards to append what was written.
+///
+/// # Safety
+/// Same as [`view`]. Also, the positions are uninitialized, so they must not
+/// be read before being written.
+///
+/// # Example
+/// ```no_run
+/// # use buffers::collections::{wasm, Vector};
+/// let mut vec = Vector::<u8>::new();
+/// vec.reserve(4);
+/// // SAFETY: Nothing allocates while the view is used.
+/// let view = unsafe { wasm::spare_capacity_view(&mut vec) };
+/// view.copy_from(&[1, 2]);
+/// // SAFETY: The first 2 spare positions were just written.
+/// unsafe { vec.set_len(2) };
+/// ```
+pub unsafe fn spare_capacity_view<B>(vec: &mut Vector<u8, B>) -> Uint8Array
+where
+    B: Buffer<Element = u8> + ContiguousMemoryBuffer,
+{
+    let spare = vec.spare_capacity_mut();
+    // SAFETY: The caller ensures that the view isn't used after the memory
+    // is invalidated, and that nothing uninitialized is read.
+    unsafe { Uint8Array::view_mut_raw(spare.as_mut_ptr().cast(), spare.len()) }
+}
+
+/// Copies the elements of `vec` into a new `Uint8Array` (owned by
+/// JavaScript).
+pub fn to_uint8_array<B>(vec: &Vector<u8, B>) -> Uint8Array
+where
+    B: Buffer<Element = u8> + ContiguousMemoryBuffer,
+{
+    Uint8Array::from(vec.as_slice())
+}
+
+/// Appends a copy of the contents of `array` to `vec`. If it cannot grow
+/// enough, it returns the error and nothing is appended.
+pub fn extend_from_uint8_array<B>(
+    vec: &mut Vector<u8, B>,
+    array: &Uint8Array,
+) -> Result<(), ResizeError>
+where
+    B: Buffer<Element = u8> + ContiguousMemoryBuffer,
+{
+    let additional = array.length() as usize;
+    vec.try_reserve_exact(additional)?;
+    let written = array
+        .copy_to_uninit(&mut vec.spare_capacity_mut()[..additional])
+        .len();
+    let len = vec.len();
+    // SAFETY: `copy_to_uninit` filled the first `written` spare positions.
+    unsafe { vec.set_len(len + written) };
+    Ok(())
+}
+
+/// Makes a vector on top of `storage` (eg. memory reserved for the data that
+/// comes from JavaScript) with a copy of the contents of `array`. If they
+/// don't fit, it returns the error.
+///
+/// # Example
+/// ```no_run
+/// # use std::mem::MaybeUninit;
+/// # use buffers::collections::wasm;
+/// let array = js_sys::Uint8Array::new_with_length(3);
+/// let mut storage = [MaybeUninit::uninit(); 16];
+/// let vec = wasm::from_uint8_array(&array, &mut storage).ok().unwrap();
+/// assert_eq!(vec.as_slice(), [0, 0, 0]);
+/// ```
+pub fn from_uint8_array<'a>(
+    array: &Uint8Array,
+    storage: &'a mut [MaybeUninit<u8>],
+) -> Result<Vector<u8, SliceBuffer<'a, u8>>, ResizeError> {
+    let mut vec = Vector::from_buffer(SliceBuffer::from_slice(storage));
+    extend_from_uint8_array(&mut vec, array)?;
+    Ok(vec)
+}
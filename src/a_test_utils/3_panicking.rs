@@ -0,0 +1,118 @@
+use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+
+/// Element that may panic when dropped or cloned. Like
+/// [`super::life_counter::LifeCounter`], it counts how many instances exist.
+///
+/// Useful to check that containers stay consistent when an element operation
+/// panics: a negative count means a double drop and a positive one (after
+/// dropping the container) means a leak.
+#[derive(Debug)]
+pub struct PanickingElement<'a> {
+    counter: &'a AtomicI64,
+    value: u32,
+    panic_on_drop: bool,
+    panic_on_clone: bool,
+    clones_left: Option<&'a AtomicUsize>,
+}
+
+impl<'a> PanickingElement<'a> {
+    /// Makes a new element which doesn't panic.
+    pub fn new(counter: &'a AtomicI64, value: u32) -> Self {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Self {
+            counter,
+            value,
+            panic_on_drop: false,
+            panic_on_clone: false,
+            clones_left: None,
+        }
+    }
+
+    /// Makes this element panic when dropped.
+    pub fn panicking_on_drop(mut self) -> Self {
+        self.panic_on_drop = true;
+        self
+    }
+
+    /// Makes this element panic when cloned.
+    pub fn panicking_on_clone(mut self) -> Self {
+        self.panic_on_clone = true;
+        self
+    }
+
+    /// Makes this element (and its clones) panic when cloned once `clones_left`
+    /// reaches 0. Each clone counts it down.
+    pub fn panicking_on_clone_after(mut self, clones_left: &'a AtomicUsize) -> Self {
+        self.clones_left = Some(clones_left);
+        self
+    }
+
+    /// Value given when it was made.
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+}
+
+impl Clone for PanickingElement<'_> {
+    fn clone(&self) -> Self {
+        if self.panic_on_clone {
+            panic!("Panicking element cloned");
+        }
+        if let Some(clones_left) = self.clones_left {
+            let counted_down =
+                clones_left.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+                    left.checked_sub(1)
+                });
+            if counted_down.is_err() {
+                panic!("Panicking element cloned too many times");
+            }
+        }
+        let mut clone = Self::new(self.counter, self.value);
+        clone.clones_left = self.clones_left;
+        clone
+    }
+}
+
+impl PartialEq for PanickingElement<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl Drop for PanickingElement<'_> {
+    fn drop(&mut self) {
+        self.counter.fetch_sub(1, Ordering::SeqCst);
+        // Panicking while unwinding would abort.
+        if self.panic_on_drop && !std::thread::panicking() {
+            panic!("Panicking element dropped");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PanickingElement;
+    use crate::test_utils::panic::assert_panic;
+    use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+
+    #[test]
+    fn counts_even_when_panicking() {
+        let counter = AtomicI64::new(0);
+        assert_panic(|| drop(PanickingElement::new(&counter, 1).panicking_on_drop()));
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+
+        let element = PanickingElement::new(&counter, 1).panicking_on_clone();
+        assert_panic(|| element.clone());
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn clones_count_down_until_panicking() {
+        let counter = AtomicI64::new(0);
+        let clones_left = AtomicUsize::new(1);
+        let element = PanickingElement::new(&counter, 1).panicking_on_clone_after(&clones_left);
+        let clone = element.clone();
+        assert_panic(|| clone.clone());
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,86 @@
+use crate::interface::{indirect_buffer::IndirectBuffer, resize_error::ResizeError, Buffer};
+
+/// Buffer that passes everything to an inner buffer, except resizing, which can
+/// be made to fail.
+///
+/// Useful to check that composites stay consistent when one of their inner
+/// buffers fails to grow.
+pub struct FaultyBuffer<B: Buffer> {
+    inner: B,
+    grow_limit: usize,
+    grow_error: Option<ResizeError>,
+    shrink_error: Option<ResizeError>,
+}
+
+impl<B: Buffer> FaultyBuffer<B> {
+    /// Makes a buffer which doesn't fail.
+    pub fn from(inner: B) -> Self {
+        Self {
+            inner,
+            grow_limit: usize::MAX,
+            grow_error: None,
+            shrink_error: None,
+        }
+    }
+
+    /// Makes this buffer fail to grow (without changing) with `error`.
+    pub fn failing_grow(self, error: ResizeError) -> Self {
+        self.failing_grow_beyond(0, error)
+    }
+
+    /// Makes this buffer fail to grow (without changing) with `error` when the
+    /// target is bigger than `limit`.
+    pub fn failing_grow_beyond(mut self, limit: usize, error: ResizeError) -> Self {
+        self.grow_limit = limit;
+        self.grow_error = Some(error);
+        self
+    }
+
+    /// Makes this buffer fail to shrink (without changing), as if it didn't
+    /// support it.
+    pub fn failing_shrink(self) -> Self {
+        self.failing_shrink_with(ResizeError::UnsupportedOperation)
+    }
+
+    /// Makes this buffer fail to shrink (without changing) with `error`.
+    pub fn failing_shrink_with(mut self, error: ResizeError) -> Self {
+        self.shrink_error = Some(error);
+        self
+    }
+}
+
+impl<B: Buffer + Default> Default for FaultyBuffer<B> {
+    fn default() -> Self {
+        Self::from(Default::default())
+    }
+}
+
+impl<B: Buffer> IndirectBuffer for FaultyBuffer<B> {
+    type InnerBuffer = B;
+    type InnerBufferRef<'a> = &'a B where Self: 'a;
+    type InnerBufferMutRef<'a> = &'a mut B where Self: 'a;
+
+    fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    fn inner_mut(&mut self) -> &mut B {
+        &mut self.inner
+    }
+
+    unsafe fn try_grow(&mut self, target: usize) -> Result<(), ResizeError> {
+        match &self.grow_error {
+            Some(error) if target > self.grow_limit => Err(error.clone()),
+            // SAFETY: Forwards call to underlying buffer.
+            _ => unsafe { self.inner.try_grow(target) },
+        }
+    }
+
+    unsafe fn try_shrink(&mut self, target: usize) -> Result<(), ResizeError> {
+        match &self.shrink_error {
+            Some(error) => Err(error.clone()),
+            // SAFETY: Forwards call to underlying buffer.
+            None => unsafe { self.inner.try_shrink(target) },
+        }
+    }
+}
@@ -0,0 +1,469 @@
+use std::{cell::RefCell, ops::Range, ops::RangeBounds, rc::Rc};
+
+use crate::interface::{
+    buffer::normalize_range, copy_value::CopyValueBuffer, growable::GrowableBuffer,
+    resize_error::ResizeError, shrinkable::ShrinkableBuffer, Buffer, BufferCaps, CapacityContract,
+};
+
+/// Operation done to a buffer, as recorded by [`RecordingBuffer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operation<T> {
+    /// A value was put into the position.
+    Put(usize, T),
+    /// The value of the position was taken.
+    Take(usize),
+    /// The value of the position was dropped.
+    Drop(usize),
+    /// The values in the range were dropped.
+    DropRange(Range<usize>),
+    /// The buffer grew to the target.
+    Grow(usize),
+    /// The buffer shrank to the target.
+    Shrink(usize),
+    /// The values in the range were moved right by the amount of positions.
+    ShiftRight(Range<usize>, usize),
+    /// The values in the range were moved left by the amount of positions.
+    ShiftLeft(Range<usize>, usize),
+}
+
+/// Sequence of the operations done to a buffer, in order.
+///
+/// It can be replayed against another buffer with
+/// [`super::replay::replay`], and serialized with the `serde` feature (eg. to
+/// keep the one that made a fuzzer fail). The operations can also be edited to
+/// find the smallest journal that still fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Journal<T> {
+    operations: Vec<Operation<T>>,
+}
+
+impl<T> Journal<T> {
+    /// Makes an empty journal.
+    pub fn new() -> Self {
+        Self {
+            operations: Vec::new(),
+        }
+    }
+
+    /// Makes an empty journal that can be shared with a [`RecordingBuffer`].
+    pub fn shared() -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self::new()))
+    }
+
+    /// Operations recorded so far.
+    pub fn operations(&self) -> &[Operation<T>] {
+        &self.operations
+    }
+
+    /// Adds an operation at the end.
+    pub fn push(&mut self, operation: Operation<T>) {
+        self.operations.push(operation);
+    }
+
+    /// Takes the operations out of the journal.
+    pub fn into_operations(self) -> Vec<Operation<T>> {
+        self.operations
+    }
+}
+
+impl<T> Default for Journal<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> From<Vec<Operation<T>>> for Journal<T> {
+    fn from(operations: Vec<Operation<T>>) -> Self {
+        Self { operations }
+    }
+}
+
+/// Buffer that passes everything to an inner buffer, recording every
+/// operation (with a clone of the values put) into a shared [`Journal`].
+///
+/// Useful to reproduce a failure deterministically: record what a collection
+/// does to its buffer, and replay it later against a fresh buffer. Values
+/// aren't recorded when they are changed through a reference, so it doesn't
+/// give references nor pointers (only copies, with [`CopyValueBuffer`]).
+///
+/// # Example
+/// ```
+/// # use buffers::{base_buffers::HeapBuffer, collections::Vector, test_utils::{recording::{Journal, Operation, RecordingBuffer}, replay::replay}};
+/// let journal = Journal::shared();
+/// let mut vec = Vector::from_buffer(RecordingBuffer::new(HeapBuffer::new(), journal.clone()));
+/// vec.push(1);
+/// vec.insert(0, 2);
+/// assert_eq!(journal.borrow().operations()[1], Operation::Put(0, 1));
+///
+/// let replayed = replay(&journal.borrow(), HeapBuffer::<u32>::new()).unwrap();
+/// assert!(replayed.is_filled(0) && replayed.is_filled(1));
+/// ```
+pub struct RecordingBuffer<B: Buffer> {
+    inner: B,
+    journal: Rc<RefCell<Journal<B::Element>>>,
+}
+
+impl<B: Buffer> RecordingBuffer<B> {
+    /// Makes a buffer which records the operations done to `inner` into
+    /// `journal`.
+    pub fn new(inner: B, journal: Rc<RefCell<Journal<B::Element>>>) -> Self {
+        Self { inner, journal }
+    }
+
+    /// Journal where the operations are recorded.
+    pub fn journal(&self) -> &Rc<RefCell<Journal<B::Element>>> {
+        &self.journal
+    }
+
+    /// Internal utility that adds the operation to the journal.
+    fn record(&self, operation: Operation<B::Element>) {
+        self.journal.borrow_mut().push(operation);
+    }
+}
+
+impl<B: Buffer> Buffer for RecordingBuffer<B>
+where
+    B::Element: Clone,
+{
+    type Element = B::Element;
+
+    const CAPACITY_CONTRACT: CapacityContract = B::CAPACITY_CONTRACT;
+
+    fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    fn allocated_bytes(&self) -> usize {
+        self.inner.allocated_bytes()
+    }
+
+    /// Slices of a contiguous inner buffer would be read and written without
+    /// being recorded, so it's never contiguous.
+    fn caps(&self) -> BufferCaps {
+        self.inner.caps() & (BufferCaps::GROW | BufferCaps::SHRINK)
+    }
+
+    unsafe fn take(&mut self, index: usize) -> Self::Element {
+        self.record(Operation::Take(index));
+        // SAFETY: Same requirements.
+        unsafe { self.inner.take(index) }
+    }
+
+    unsafe fn put(&mut self, index: usize, value: Self::Element) {
+        self.record(Operation::Put(index, value.clone()));
+        // SAFETY: Same requirements.
+        unsafe { self.inner.put(index, value) };
+    }
+
+    unsafe fn manually_drop(&mut self, index: usize) {
+        self.record(Operation::Drop(index));
+        // SAFETY: Same requirements.
+        unsafe { self.inner.manually_drop(index) };
+    }
+
+    unsafe fn manually_drop_range<R: RangeBounds<usize> + Clone>(&mut self, values_range: R) {
+        let range = normalize_range(values_range, self.capacity());
+        self.record(Operation::DropRange(range.clone()));
+        // SAFETY: Same requirements.
+        unsafe { self.inner.manually_drop_range(range) };
+    }
+
+    unsafe fn try_grow(&mut self, target: usize) -> Result<(), ResizeError> {
+        // SAFETY: Same requirements.
+        unsafe { self.inner.try_grow(target) }?;
+        // Failing leaves the buffer as it was, so it's not recorded.
+        self.record(Operation::Grow(target));
+        Ok(())
+    }
+
+    fn preferred_grow_target(&self, current: usize, required: usize) -> usize {
+        self.inner.preferred_grow_target(current, required)
+    }
+
+    unsafe fn try_shrink(&mut self, target: usize) -> Result<(), ResizeError> {
+        // SAFETY: Same requirements.
+        unsafe { self.inner.try_shrink(target) }?;
+        self.record(Operation::Shrink(target));
+        Ok(())
+    }
+
+    unsafe fn shift_right<R: RangeBounds<usize> + Clone>(&mut self, to_move: R, positions: usize) {
+        let range = normalize_range(to_move, self.capacity());
+        self.record(Operation::ShiftRight(range.clone(), positions));
+        // SAFETY: Same requirements.
+        unsafe { self.inner.shift_right(range, positions) };
+    }
+
+    unsafe fn shift_left<R: RangeBounds<usize> + Clone>(&mut self, to_move: R, positions: usize) {
+        let range = normalize_range(to_move, self.capacity());
+        self.record(Operation::ShiftLeft(range.clone(), positions));
+        // SAFETY: Same requirements.
+        unsafe { self.inner.shift_left(range, positions) };
+    }
+}
+
+impl<B: CopyValueBuffer> CopyValueBuffer for RecordingBuffer<B>
+where
+    B::Element: Copy,
+{
+    unsafe fn copy(&self, index: usize) -> Self::Element {
+        // SAFETY: Same requirements.
+        unsafe { self.inner.copy(index) }
+    }
+}
+
+impl<B: GrowableBuffer> GrowableBuffer for RecordingBuffer<B> where B::Element: Clone {}
+
+impl<B: ShrinkableBuffer> ShrinkableBuffer for RecordingBuffer<B> where B::Element: Clone {}
+
+#[cfg(feature = "serde")]
+mod serde_support {
+    //! Journals are serialized as sequences of operations, which are enums
+    //! with the same variants as [`Operation`].
+
+    use std::{fmt, marker::PhantomData};
+
+    use serde::{
+        de::{self, EnumAccess, SeqAccess, VariantAccess, Visitor},
+        ser::SerializeTupleVariant,
+        Deserialize, Deserializer, Serialize, Serializer,
+    };
+
+    use super::{Journal, Operation};
+
+    const NAME: &str = "Operation";
+    const VARIANTS: &[&str] = &[
+        "Put",
+        "Take",
+        "Drop",
+        "DropRange",
+        "Grow",
+        "Shrink",
+        "ShiftRight",
+        "ShiftLeft",
+    ];
+
+    impl<T: Serialize> Serialize for Operation<T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            match self {
+                Self::Put(index, value) => {
+                    let mut variant = serializer.serialize_tuple_variant(NAME, 0, "Put", 2)?;
+                    variant.serialize_field(index)?;
+                    variant.serialize_field(value)?;
+                    variant.end()
+                }
+                Self::Take(index) => serializer.serialize_newtype_variant(NAME, 1, "Take", index),
+                Self::Drop(index) => serializer.serialize_newtype_variant(NAME, 2, "Drop", index),
+                Self::DropRange(range) => {
+                    serializer.serialize_newtype_variant(NAME, 3, "DropRange", range)
+                }
+                Self::Grow(target) => serializer.serialize_newtype_variant(NAME, 4, "Grow", target),
+                Self::Shrink(target) => {
+                    serializer.serialize_newtype_variant(NAME, 5, "Shrink", target)
+                }
+                Self::ShiftRight(range, positions) => {
+                    let mut variant =
+                        serializer.serialize_tuple_variant(NAME, 6, "ShiftRight", 2)?;
+                    variant.serialize_field(range)?;
+                    variant.serialize_field(positions)?;
+                    variant.end()
+                }
+                Self::ShiftLeft(range, positions) => {
+                    let mut variant =
+                        serializer.serialize_tuple_variant(NAME, 7, "ShiftLeft", 2)?;
+                    variant.serialize_field(range)?;
+                    variant.serialize_field(positions)?;
+                    variant.end()
+                }
+            }
+        }
+    }
+
+    impl<'de, T: Deserialize<'de>> Deserialize<'de> for Operation<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_enum(NAME, VARIANTS, OperationVisitor(PhantomData))
+        }
+    }
+
+    /// Internal type. Index of the variant of an [`Operation`].
+    struct Variant(usize);
+
+    impl<'de> Deserialize<'de> for Variant {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_identifier(VariantVisitor)
+        }
+    }
+
+    struct VariantVisitor;
+
+    impl Visitor<'_> for VariantVisitor {
+        type Value = Variant;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("an operation")
+        }
+
+        fn visit_u64<E: de::Error>(self, index: u64) -> Result<Variant, E> {
+            match usize::try_from(index) {
+                Ok(index) if index < VARIANTS.len() => Ok(Variant(index)),
+                _ => Err(E::invalid_value(de::Unexpected::Unsigned(index), &self)),
+            }
+        }
+
+        fn visit_str<E: de::Error>(self, name: &str) -> Result<Variant, E> {
+            match VARIANTS.iter().position(|variant| *variant == name) {
+                Some(index) => Ok(Variant(index)),
+                None => Err(E::unknown_variant(name, VARIANTS)),
+            }
+        }
+    }
+
+    struct OperationVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: Deserialize<'de>> Visitor<'de> for OperationVisitor<T> {
+        type Value = Operation<T>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("an operation")
+        }
+
+        fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Self::Value, A::Error> {
+            let (Variant(index), variant) = data.variant()?;
+            Ok(match index {
+                0 => {
+                    let (index, value) = variant.tuple_variant(2, PairVisitor(PhantomData))?;
+                    Operation::Put(index, value)
+                }
+                1 => Operation::Take(variant.newtype_variant()?),
+                2 => Operation::Drop(variant.newtype_variant()?),
+                3 => Operation::DropRange(variant.newtype_variant()?),
+                4 => Operation::Grow(variant.newtype_variant()?),
+                5 => Operation::Shrink(variant.newtype_variant()?),
+                6 => {
+                    let (range, positions) = variant.tuple_variant(2, PairVisitor(PhantomData))?;
+                    Operation::ShiftRight(range, positions)
+                }
+                _ => {
+                    let (range, positions) = variant.tuple_variant(2, PairVisitor(PhantomData))?;
+                    Operation::ShiftLeft(range, positions)
+                }
+            })
+        }
+    }
+
+    /// Internal visitor for the variants with two fields.
+    struct PairVisitor<A, B>(PhantomData<(A, B)>);
+
+    impl<'de, A: Deserialize<'de>, B: Deserialize<'de>> Visitor<'de> for PairVisitor<A, B> {
+        type Value = (A, B);
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("two fields")
+        }
+
+        fn visit_seq<S: SeqAccess<'de>>(self, mut seq: S) -> Result<Self::Value, S::Error> {
+            let first = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+            let second = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+            Ok((first, second))
+        }
+    }
+
+    impl<T: Serialize> Serialize for Journal<T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(&self.operations)
+        }
+    }
+
+    impl<'de, T: Deserialize<'de>> Deserialize<'de> for Journal<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_seq(JournalVisitor(PhantomData))
+        }
+    }
+
+    struct JournalVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: Deserialize<'de>> Visitor<'de> for JournalVisitor<T> {
+        type Value = Journal<T>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a sequence of operations")
+        }
+
+        fn visit_seq<S: SeqAccess<'de>>(self, mut seq: S) -> Result<Self::Value, S::Error> {
+            let mut journal = Journal::new();
+            while let Some(operation) = seq.next_element()? {
+                journal.push(operation);
+            }
+            Ok(journal)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{base_buffers::HeapBuffer, collections::Vector};
+
+    use super::*;
+
+    #[test]
+    fn records_what_the_vector_does() {
+        let journal = Journal::shared();
+        let buffer = RecordingBuffer::new(HeapBuffer::<u8>::new(), journal.clone());
+        let mut vec = Vector::from_buffer(buffer);
+        vec.push(1);
+        vec.push(2);
+        vec.remove(0);
+        vec.clear();
+
+        assert_eq!(
+            journal.borrow().operations(),
+            [
+                Operation::Grow(1),
+                Operation::Put(0, 1),
+                Operation::Grow(2),
+                Operation::Put(1, 2),
+                Operation::Take(0),
+                Operation::ShiftLeft(1..2, 1),
+                Operation::DropRange(0..1),
+            ]
+        );
+    }
+
+    #[test]
+    fn is_never_contiguous() {
+        let buffer = RecordingBuffer::new(HeapBuffer::<u8>::new(), Journal::shared());
+        assert_eq!(buffer.caps(), BufferCaps::GROW | BufferCaps::SHRINK);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn journals_can_be_serialized() {
+        use serde_test::{assert_tokens, Token};
+
+        let journal = Journal::from(vec![Operation::Grow(2), Operation::Put(1, 'a')]);
+        assert_tokens(
+            &journal,
+            &[
+                Token::Seq { len: Some(2) },
+                Token::NewtypeVariant {
+                    name: "Operation",
+                    variant: "Grow",
+                },
+                Token::U64(2),
+                Token::TupleVariant {
+                    name: "Operation",
+                    variant: "Put",
+                    len: 2,
+                },
+                Token::U64(1),
+                Token::Char('a'),
+                Token::TupleVariantEnd,
+                Token::SeqEnd,
+            ],
+        );
+    }
+}
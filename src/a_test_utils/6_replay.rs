@@ -0,0 +1,262 @@
+use std::{fmt, ops::Range};
+
+use crate::interface::{resize_error::ResizeError, Buffer};
+
+use super::recording::{Journal, Operation};
+
+/// Error when replaying a journal: the operation at `step` cannot be done.
+#[derive(Debug, Clone)]
+pub struct ReplayError {
+    /// Index of the operation in the journal.
+    pub step: usize,
+    /// Why it cannot be done.
+    pub reason: ReplayErrorReason,
+}
+
+/// Why an operation of a journal cannot be replayed.
+#[derive(Debug, Clone)]
+pub enum ReplayErrorReason {
+    /// It uses a position which is not valid (not less than the capacity).
+    OutOfBounds,
+    /// It reads or drops a position which is empty.
+    EmptyPosition,
+    /// It writes into a position which is filled (or shrinks over one).
+    FilledPosition,
+    /// It resizes in the wrong direction.
+    InvalidTarget,
+    /// The buffer failed to resize.
+    Resize(ResizeError),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cannot replay step {}: {:?}", self.step, self.reason)
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+/// Buffer after replaying a journal. It knows which positions are filled, and
+/// drops their values when dropped.
+pub struct Replayed<B: Buffer> {
+    buffer: B,
+    filled: Vec<bool>,
+}
+
+impl<B: Buffer> Replayed<B> {
+    /// Buffer with the values.
+    pub fn buffer(&self) -> &B {
+        &self.buffer
+    }
+
+    /// Whether the position is filled.
+    pub fn is_filled(&self, index: usize) -> bool {
+        self.filled.get(index).copied().unwrap_or(false)
+    }
+
+    /// Internal utility that checks that all the positions in `range` are
+    /// valid and filled (or empty).
+    fn check(&self, range: Range<usize>, filled: bool) -> Result<(), ReplayErrorReason> {
+        if range.start > range.end || range.end > self.buffer.capacity() {
+            Err(ReplayErrorReason::OutOfBounds)
+        } else if self.filled[range]
+            .iter()
+            .any(|&position| position != filled)
+        {
+            Err(if filled {
+                ReplayErrorReason::EmptyPosition
+            } else {
+                ReplayErrorReason::FilledPosition
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Internal utility that checks that the position is valid and filled (or
+    /// empty).
+    fn check_position(&self, index: usize, filled: bool) -> Result<(), ReplayErrorReason> {
+        if index >= self.buffer.capacity() {
+            return Err(ReplayErrorReason::OutOfBounds);
+        }
+        self.check(index..index + 1, filled)
+    }
+
+    /// Internal utility that checks that the operation can be done and does
+    /// it.
+    fn apply(&mut self, operation: &Operation<B::Element>) -> Result<(), ReplayErrorReason>
+    where
+        B::Element: Clone,
+    {
+        match operation {
+            Operation::Put(index, value) => {
+                self.check_position(*index, false)?;
+                // SAFETY: The position is valid and empty.
+                unsafe { self.buffer.put(*index, value.clone()) };
+                self.filled[*index] = true;
+            }
+            Operation::Take(index) => {
+                self.check_position(*index, true)?;
+                self.filled[*index] = false;
+                // SAFETY: The position is valid and filled.
+                drop(unsafe { self.buffer.take(*index) });
+            }
+            Operation::Drop(index) => {
+                self.check_position(*index, true)?;
+                self.filled[*index] = false;
+                // SAFETY: The position is valid and filled.
+                unsafe { self.buffer.manually_drop(*index) };
+            }
+            Operation::DropRange(range) => {
+                self.check(range.clone(), true)?;
+                self.filled[range.clone()].fill(false);
+                // SAFETY: The positions are valid and filled.
+                unsafe { self.buffer.manually_drop_range(range.clone()) };
+            }
+            Operation::Grow(target) => {
+                if *target <= self.buffer.capacity() {
+                    return Err(ReplayErrorReason::InvalidTarget);
+                }
+                // SAFETY: It's bigger than the current capacity.
+                unsafe { self.buffer.try_grow(*target) }.map_err(ReplayErrorReason::Resize)?;
+                self.filled.resize(self.buffer.capacity(), false);
+            }
+            Operation::Shrink(target) => {
+                if *target >= self.buffer.capacity() {
+                    return Err(ReplayErrorReason::InvalidTarget);
+                }
+                self.check(*target..self.buffer.capacity(), false)?;
+                // SAFETY: It's smaller than the current capacity and the
+                // positions after it are empty.
+                unsafe { self.buffer.try_shrink(*target) }.map_err(ReplayErrorReason::Resize)?;
+                self.filled.truncate(self.buffer.capacity());
+            }
+            Operation::ShiftRight(range, positions) => {
+                let end = range.end.saturating_add(*positions);
+                self.check(range.clone(), true)?;
+                self.check(range.end..end, false)?;
+                // SAFETY: The positions are valid, and the ones after them are
+                // valid and empty.
+                unsafe { self.buffer.shift_right(range.clone(), *positions) };
+                self.filled[range.clone()].fill(false);
+                self.filled[range.start + positions..end].fill(true);
+            }
+            Operation::ShiftLeft(range, positions) => {
+                let start = range
+                    .start
+                    .checked_sub(*positions)
+                    .ok_or(ReplayErrorReason::OutOfBounds)?;
+                self.check(range.clone(), true)?;
+                self.check(start..range.start, false)?;
+                // SAFETY: The positions are valid, and the ones before them are
+                // valid and empty.
+                unsafe { self.buffer.shift_left(range.clone(), *positions) };
+                self.filled[range.clone()].fill(false);
+                self.filled[start..range.end - positions].fill(true);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<B: Buffer> Drop for Replayed<B> {
+    fn drop(&mut self) {
+        for (index, filled) in self.filled.iter().enumerate() {
+            if *filled {
+                // SAFETY: The position is filled.
+                unsafe { self.buffer.manually_drop(index) };
+            }
+        }
+    }
+}
+
+/// Replays the operations of a journal (eg. recorded with
+/// [`super::recording::RecordingBuffer`]) against `buffer`, to reproduce what
+/// happened to another buffer deterministically.
+///
+/// Every operation is checked before doing it, so a journal that was edited
+/// (eg. while minimizing a fuzz case) or recorded from a buggy collection
+/// returns an error instead of misusing the buffer. Either way, the values
+/// left in the buffer are dropped.
+///
+/// # Example
+/// ```
+/// # use buffers::{base_buffers::InlineBuffer, test_utils::{recording::{Journal, Operation}, replay::replay}};
+/// let journal = Journal::from(vec![Operation::Put(0, 'a'), Operation::Take(0)]);
+/// let replayed = replay(&journal, InlineBuffer::<char, 2>::new()).unwrap();
+/// assert!(!replayed.is_filled(0));
+///
+/// let journal = Journal::from(vec![Operation::Take(1)]);
+/// assert_eq!(replay(&journal, InlineBuffer::<char, 2>::new()).err().unwrap().step, 0);
+/// ```
+pub fn replay<B: Buffer>(
+    journal: &Journal<B::Element>,
+    buffer: B,
+) -> Result<Replayed<B>, ReplayError>
+where
+    B::Element: Clone,
+{
+    let mut replayed = Replayed {
+        filled: vec![false; buffer.capacity()],
+        buffer,
+    };
+    for (step, operation) in journal.operations().iter().enumerate() {
+        replayed
+            .apply(operation)
+            .map_err(|reason| ReplayError { step, reason })?;
+    }
+    Ok(replayed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicI64, Ordering};
+
+    use crate::{
+        base_buffers::{HeapBuffer, SharedSliceBuffer},
+        collections::Vector,
+        composites::{ExponentialGrowthBuffer, SvoBuffer},
+        test_utils::{
+            assert_copied_buffer_eq, panicking::PanickingElement, recording::RecordingBuffer,
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn replaying_reproduces_the_values() {
+        let journal = Journal::shared();
+        let buffer = RecordingBuffer::new(HeapBuffer::new(), journal.clone());
+        let mut vec = Vector::from_buffer(buffer);
+        vec.extend_from_slice(&[1, 2, 3, 4]);
+        vec.insert(1, 5);
+        vec.remove(3);
+        vec.truncate(3);
+        vec.shrink_to_fit();
+
+        let buffer = ExponentialGrowthBuffer::<SvoBuffer<2, HeapBuffer<u32>>>::default();
+        let replayed = replay(&journal.borrow(), buffer).unwrap();
+        let values: Vec<_> = vec.iter_copied().collect();
+        let expected = SharedSliceBuffer::new(&values);
+        // SAFETY: The first 3 positions of both are filled.
+        unsafe { assert_copied_buffer_eq(replayed.buffer(), &expected, 3) };
+        assert!(!replayed.is_filled(3));
+    }
+
+    #[test]
+    fn invalid_journals_drop_the_values() {
+        let counter = AtomicI64::new(0);
+        let journal = Journal::from(vec![
+            Operation::Grow(4),
+            Operation::Put(0, PanickingElement::new(&counter, 0)),
+            Operation::Put(1, PanickingElement::new(&counter, 1)),
+            Operation::ShiftRight(0..2, 1),
+            Operation::Put(1, PanickingElement::new(&counter, 2)),
+        ]);
+
+        let error = replay(&journal, HeapBuffer::new()).err().unwrap();
+        assert_eq!(error.step, 4);
+        assert!(matches!(error.reason, ReplayErrorReason::FilledPosition));
+        assert_eq!(counter.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,154 @@
+use std::fmt::Debug;
+
+use crate::interface::Buffer;
+
+/// Capacity that the checks try to use. Buffers with less check with what they
+/// have.
+const LAW_CAPACITY: usize = 8;
+
+/// Laws that every [`Buffer`] must follow, written as checks that panic when
+/// they are broken. They can be called from any test, on any buffer (there is
+/// a blanket implementation), or generated for a type with
+/// [`crate::buffer_laws_tests`].
+///
+/// Each check takes an empty buffer and the values to use (by position), and
+/// leaves the buffer empty.
+pub trait BufferLaws: Buffer + Sized
+where
+    Self::Element: PartialEq + Debug,
+{
+    /// Growing either succeeds with at least the target capacity or fails
+    /// without changing it. Either way, the capacity never decreases.
+    fn check_grow_is_monotonic(mut self, targets: &[usize]) {
+        for &target in targets {
+            let before = self.capacity();
+            if target <= before {
+                continue;
+            }
+            // SAFETY: It's bigger than the current capacity, and the buffer is
+            // empty.
+            match unsafe { self.try_grow(target) } {
+                Ok(()) => assert!(
+                    self.capacity() >= target,
+                    "Grew to {target} but the capacity is {}",
+                    self.capacity()
+                ),
+                Err(_) => assert_eq!(self.capacity(), before, "Failing to grow changed it"),
+            }
+        }
+    }
+
+    /// Values read from a position are the ones last written into it.
+    fn check_read_after_write(mut self, mut values: impl FnMut(usize) -> Self::Element) {
+        let len = reserve(&mut self);
+        for index in (0..len).rev() {
+            // SAFETY: The position is valid and empty.
+            unsafe { self.put(index, values(index)) };
+        }
+        for index in 0..len {
+            // SAFETY: The position is valid and it was just filled.
+            let value = unsafe { self.take(index) };
+            assert_eq!(value, values(index), "Wrong value at {index}");
+        }
+    }
+
+    /// Shifting moves the values without changing them, and shifting back
+    /// returns them to where they were.
+    fn check_shift_preserves_values(mut self, mut values: impl FnMut(usize) -> Self::Element) {
+        let capacity = reserve(&mut self);
+        let len = capacity / 2;
+        let positions = capacity - len;
+        for index in 0..len {
+            // SAFETY: The position is valid and empty.
+            unsafe { self.put(index, values(index)) };
+        }
+
+        // SAFETY: The values are valid, and the `positions` after them are
+        // valid and empty.
+        unsafe { self.shift_right(0..len, positions) };
+        for index in 0..len {
+            // SAFETY: The position was filled by shifting.
+            let value = unsafe { self.take(positions + index) };
+            assert_eq!(value, values(index), "Wrong value shifted to {index}");
+            // SAFETY: It was just emptied.
+            unsafe { self.put(positions + index, value) };
+        }
+
+        // SAFETY: The values are valid, and the `positions` before them are
+        // valid and empty.
+        unsafe { self.shift_left(positions..capacity, positions) };
+        // SAFETY: The values were shifted back to the start.
+        unsafe { self.manually_drop_range(0..len) };
+    }
+}
+
+impl<B: Buffer> BufferLaws for B where B::Element: PartialEq + Debug {}
+
+/// Internal utility that tries to grow an empty buffer to [`LAW_CAPACITY`],
+/// and returns the capacity that the checks may use.
+fn reserve<B: Buffer>(buffer: &mut B) -> usize {
+    if buffer.capacity() < LAW_CAPACITY {
+        // SAFETY: It's bigger than the current capacity, and the buffer is
+        // empty. If it fails, the capacity stays the same.
+        let _ = unsafe { buffer.try_grow(LAW_CAPACITY) };
+    }
+    buffer.capacity().min(LAW_CAPACITY)
+}
+
+/// Generates a test for each of the [`BufferLaws`] of a buffer type, in a
+/// module with the given name. The buffer is made with [`Default`] and the
+/// values with the given function (from the position).
+///
+/// # Example
+/// ```
+/// # use buffers::{base_buffers::HeapBuffer, buffer_laws_tests};
+/// buffer_laws_tests!(heap_laws, HeapBuffer<String>, |index| index.to_string());
+/// ```
+#[macro_export]
+macro_rules! buffer_laws_tests {
+    ($name:ident, $buffer:ty, $values:expr $(,)?) => {
+        #[cfg(test)]
+        mod $name {
+            #[allow(unused_imports)]
+            use super::*;
+            use $crate::test_utils::laws::BufferLaws;
+
+            #[test]
+            fn grow_is_monotonic() {
+                <$buffer as Default>::default().check_grow_is_monotonic(&[1, 2, 7, 8, 64]);
+            }
+
+            #[test]
+            fn read_after_write() {
+                <$buffer as Default>::default().check_read_after_write($values);
+            }
+
+            #[test]
+            fn shift_preserves_values() {
+                <$buffer as Default>::default().check_shift_preserves_values($values);
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        base_buffers::{HeapBuffer, InlineBuffer},
+        composites::{AtLeastBuffer, ExponentialGrowthBuffer, SvoBuffer, ZstoBuffer},
+        DefaultBuffer,
+    };
+
+    buffer_laws_tests!(heap, HeapBuffer<String>, |index| index.to_string());
+    buffer_laws_tests!(inline, InlineBuffer<u32, 5>, |index| index as u32);
+    buffer_laws_tests!(svo, SvoBuffer<3, HeapBuffer<u8>>, |index| index as u8);
+    buffer_laws_tests!(zsto, ZstoBuffer<HeapBuffer<()>>, |_| ());
+    buffer_laws_tests!(
+        exponential,
+        ExponentialGrowthBuffer<AtLeastBuffer<3, HeapBuffer<u64>>>,
+        |index| index as u64,
+    );
+    buffer_laws_tests!(default, DefaultBuffer<Box<u8>>, |index| Box::new(
+        index as u8
+    ));
+}
@@ -0,0 +1,184 @@
+use std::fmt::{self, Debug};
+
+use crate::interface::{copy_value::CopyValueBuffer, refs::RefBuffer};
+
+/// First position where two buffers hold different values, with both of
+/// them (`left` from the first buffer and `right` from the second one).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mismatch<L, R> {
+    /// Position where they differ.
+    pub index: usize,
+    /// Value of the first buffer.
+    pub left: L,
+    /// Value of the second buffer.
+    pub right: R,
+}
+
+impl<L: Debug, R: Debug> fmt::Display for Mismatch<L, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the buffers differ at {}: {:?} != {:?}",
+            self.index, self.left, self.right
+        )
+    }
+}
+
+/// Compares the first `len` positions of `a` and `b` (which may be different
+/// kinds of buffers) through their references, and gives the first one where
+/// they differ.
+///
+/// # Safety
+/// The first `len` positions of both buffers must be valid and filled.
+pub unsafe fn first_mismatch<'a, A, B>(
+    a: &'a A,
+    b: &'a B,
+    len: usize,
+) -> Option<Mismatch<A::ConstantReference<'a>, B::ConstantReference<'a>>>
+where
+    A: RefBuffer,
+    B: RefBuffer,
+    A::ConstantReference<'a>: PartialEq<B::ConstantReference<'a>>,
+{
+    (0..len).find_map(|index| {
+        // SAFETY: The caller ensures that the position is valid and filled.
+        let left = unsafe { a.index(index) };
+        // SAFETY: Same as before.
+        let right = unsafe { b.index(index) };
+        (left != right).then_some(Mismatch { index, left, right })
+    })
+}
+
+/// Same as [`first_mismatch`] but using copies of the values, for buffers
+/// which can't give references (eg. [`crate::composites::EncryptedBuffer`]).
+///
+/// # Safety
+/// The first `len` positions of both buffers must be valid and filled.
+pub unsafe fn first_copied_mismatch<A, B>(
+    a: &A,
+    b: &B,
+    len: usize,
+) -> Option<Mismatch<A::Element, A::Element>>
+where
+    A: CopyValueBuffer,
+    B: CopyValueBuffer<Element = A::Element>,
+    A::Element: Copy + PartialEq,
+{
+    (0..len).find_map(|index| {
+        // SAFETY: The caller ensures that the position is valid and filled.
+        let left = unsafe { a.copy(index) };
+        // SAFETY: Same as before.
+        let right = unsafe { b.copy(index) };
+        (left != right).then_some(Mismatch { index, left, right })
+    })
+}
+
+/// Asserts that the first `len` positions of `a` and `b` hold the same
+/// values. Otherwise it panics with the first position where they differ
+/// and both values.
+///
+/// # Safety
+/// The first `len` positions of both buffers must be valid and filled.
+///
+/// # Example
+/// ```
+/// # use buffers::{base_buffers::{InlineBuffer, SharedSliceBuffer}, interface::Buffer, test_utils::assert_buffer_eq};
+/// let mut inline = InlineBuffer::<u32, 4>::new();
+/// for index in 0..3 {
+///     // SAFETY: The position is valid and empty.
+///     unsafe { inline.put(index, index as u32 * 10) };
+/// }
+///
+/// // SAFETY: Both have 3 filled positions.
+/// unsafe { assert_buffer_eq(&inline, &SharedSliceBuffer::new(&[0, 10, 20]), 3) };
+/// # // SAFETY: They are filled.
+/// # unsafe { inline.manually_drop_range(..3) };
+/// ```
+#[track_caller]
+pub unsafe fn assert_buffer_eq<'a, A, B>(a: &'a A, b: &'a B, len: usize)
+where
+    A: RefBuffer,
+    B: RefBuffer,
+    A::ConstantReference<'a>: PartialEq<B::ConstantReference<'a>> + Debug,
+    B::ConstantReference<'a>: Debug,
+{
+    // SAFETY: Same requirements.
+    if let Some(mismatch) = unsafe { first_mismatch(a, b, len) } {
+        panic!("{mismatch} (comparing {len} positions)");
+    }
+}
+
+/// Same as [`assert_buffer_eq`] but using copies of the values (see
+/// [`first_copied_mismatch`]).
+///
+/// # Safety
+/// The first `len` positions of both buffers must be valid and filled.
+#[track_caller]
+pub unsafe fn assert_copied_buffer_eq<A, B>(a: &A, b: &B, len: usize)
+where
+    A: CopyValueBuffer,
+    B: CopyValueBuffer<Element = A::Element>,
+    A::Element: Copy + PartialEq + Debug,
+{
+    // SAFETY: Same requirements.
+    if let Some(mismatch) = unsafe { first_copied_mismatch(a, b, len) } {
+        panic!("{mismatch} (comparing {len} positions)");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        base_buffers::{HeapBuffer, SharedSliceBuffer},
+        interface::Buffer,
+        test_utils::panic::catch_panic_unwind_silent,
+    };
+
+    use super::*;
+
+    fn heap_buffer(values: &[u32]) -> HeapBuffer<u32> {
+        let mut buffer = HeapBuffer::new();
+        // SAFETY: It's empty, so any capacity is bigger.
+        unsafe { buffer.try_grow(values.len()) }.unwrap();
+        for (index, value) in values.iter().enumerate() {
+            // SAFETY: The position is valid and empty.
+            unsafe { buffer.put(index, *value) };
+        }
+        buffer
+    }
+
+    #[test]
+    fn mismatches_give_the_first_different_position() {
+        let heap = heap_buffer(&[1, 2, 3, 4]);
+        let slice = SharedSliceBuffer::new(&[1, 2, 5, 6]);
+
+        // SAFETY: The first 4 positions of both are filled.
+        let mismatch = unsafe { first_mismatch(&heap, &slice, 4) }.unwrap();
+        assert_eq!((mismatch.index, *mismatch.left, *mismatch.right), (2, 3, 5));
+        // SAFETY: Same as before.
+        let mismatch = unsafe { first_copied_mismatch(&heap, &slice, 4) };
+        assert_eq!(
+            mismatch.map(|m| (m.index, m.left, m.right)),
+            Some((2, 3, 5))
+        );
+        // SAFETY: Same as before.
+        assert!(unsafe { first_mismatch(&heap, &slice, 2) }.is_none());
+    }
+
+    #[test]
+    fn failing_assertions_explain_the_difference() {
+        let heap = heap_buffer(&[1, 2]);
+        let slice = SharedSliceBuffer::new(&[1, 3]);
+
+        let error = catch_panic_unwind_silent(|| {
+            // SAFETY: The first 2 positions of both are filled.
+            unsafe { assert_copied_buffer_eq(&heap, &slice, 2) };
+        })
+        .err()
+        .unwrap();
+        assert_eq!(
+            error.downcast_ref::<String>().unwrap(),
+            "the buffers differ at 1: 2 != 3 (comparing 2 positions)"
+        );
+    }
+}
@@ -3,3 +3,22 @@ pub mod life_counter;
 
 #[path = "2_panic.rs"]
 pub mod panic;
+
+#[path = "3_panicking.rs"]
+pub mod panicking;
+
+#[path = "4_faulty.rs"]
+pub mod faulty;
+
+#[path = "5_recording.rs"]
+pub mod recording;
+
+#[path = "6_replay.rs"]
+pub mod replay;
+
+#[path = "7_laws.rs"]
+pub mod laws;
+
+#[path = "8_compare.rs"]
+pub mod compare;
+pub use compare::{assert_buffer_eq, assert_copied_buffer_eq};
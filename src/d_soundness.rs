@@ -0,0 +1,302 @@
+//! Soundness suite for the unsafe interface.
+//!
+//! It exercises every unsafe method of every buffer with values that own heap
+//! memory, so any double drop, leak or read of an empty position is noticed.
+//! It's meant to be run with Miri (which also checks the aliasing model):
+//!
+//! ```sh
+//! cargo +nightly miri test soundness
+//! ```
+
+use std::{fmt::Debug, mem::MaybeUninit};
+
+use crate::{
+    base_buffers::{
+        erased::{ErasedBuffer, ErasedLayout},
+        HeapBuffer, InlineBuffer, SliceBuffer, ZstBuffer,
+    },
+    collections::Vector,
+    composites::{
+        conditional::{ConditionalBuffer, Selector},
+        either::EitherBuffer,
+        AtLeastBuffer, ExponentialGrowthBuffer, SvoBuffer, ZstoBuffer,
+    },
+    interface::{contiguous_memory::ContiguousMemoryBuffer, ptrs::PtrBuffer, Buffer},
+    DefaultBuffer,
+};
+
+/// Value used to fill the buffers. It owns heap memory on purpose.
+fn value(i: usize) -> String {
+    format!("value {i}")
+}
+
+/// Grows `buffer` so it has at least `capacity` positions.
+fn ensure_capacity<B: Buffer>(buffer: &mut B, capacity: usize) {
+    if buffer.capacity() < capacity {
+        // SAFETY: It's bigger than the current capacity.
+        unsafe { buffer.try_grow(capacity) }.expect("The buffer should be able to grow");
+    }
+}
+
+/// Fills `0..len` with `make`.
+fn fill<B: Buffer>(buffer: &mut B, len: usize, make: impl Fn(usize) -> B::Element) {
+    for i in 0..len {
+        // SAFETY: The caller ensures that `0..len` are valid and empty.
+        unsafe { buffer.put(i, make(i)) };
+    }
+}
+
+/// Uses all the methods that fill and empty positions.
+fn check_values<B>(mut buffer: B, make: impl Fn(usize) -> B::Element)
+where
+    B: Buffer,
+    B::Element: PartialEq + Debug,
+{
+    const LEN: usize = 6;
+    ensure_capacity(&mut buffer, LEN);
+    fill(&mut buffer, LEN, &make);
+
+    for i in 0..LEN {
+        // SAFETY: All positions were filled.
+        let taken = unsafe { buffer.take(i) };
+        assert_eq!(taken, make(i));
+        // SAFETY: It was just emptied.
+        unsafe { buffer.put(i, taken) };
+    }
+
+    // SAFETY: It's filled.
+    unsafe { buffer.manually_drop(LEN - 1) };
+    // SAFETY: All the rest are filled.
+    unsafe { buffer.manually_drop_range(..LEN - 1) };
+}
+
+/// Shifts values with overlapping source and destination ranges, in both
+/// directions and by different distances.
+fn check_shifts<B>(mut buffer: B, make: impl Fn(usize) -> B::Element)
+where
+    B: Buffer,
+    B::Element: PartialEq + Debug,
+{
+    ensure_capacity(&mut buffer, 6);
+    fill(&mut buffer, 4, &make);
+
+    // [0, 1, 2, 3, _, _] -> [0, _, _, 1, 2, 3]
+    // SAFETY: `1..4` is filled and `4..6` is empty.
+    unsafe { buffer.shift_right(1..4, 2) };
+    // [0, _, _, 1, 2, 3] -> [0, 1, 2, 3, _, _]
+    // SAFETY: `3..6` is filled and `1..3` is empty.
+    unsafe { buffer.shift_left(3..6, 2) };
+    // [0, 1, 2, 3, _, _] -> [_, 0, 1, 2, 3, _]
+    // SAFETY: `0..4` is filled and `4` is empty.
+    unsafe { buffer.shift_right(0..4, 1) };
+    // [_, 0, 1, 2, 3, _] -> [0, 1, 2, 3, _, _]
+    // SAFETY: `1..5` is filled and `0` is empty.
+    unsafe { buffer.shift_left(1..5, 1) };
+
+    for i in 0..4 {
+        // SAFETY: `0..4` are filled after shifting back.
+        let taken = unsafe { buffer.take(i) };
+        assert_eq!(taken, make(i));
+    }
+}
+
+/// Mixes pointers and references to the same buffer, which is where aliasing
+/// bugs show up.
+fn check_aliasing<B: ContiguousMemoryBuffer<Element = String>>(mut buffer: B) {
+    const LEN: usize = 3;
+    ensure_capacity(&mut buffer, LEN);
+    fill(&mut buffer, LEN, value);
+
+    // SAFETY: 0 is a valid position.
+    let first = unsafe { buffer.mut_ptr(0) };
+    // SAFETY: 1 is a valid position.
+    let second = unsafe { buffer.ptr(1) };
+    // SAFETY: `first` points to a filled position and nothing else is using it.
+    unsafe { (*first).push('!') };
+    // SAFETY: `second` points to a filled position, which is a different one.
+    assert_eq!(unsafe { &*second }, "value 1");
+
+    // Pointers are the same as long as the buffer doesn't change.
+    // SAFETY: 0 is a valid position.
+    assert_eq!(unsafe { buffer.ptr(0) }, first.cast_const());
+
+    // Calling `mut_ptr` again would invalidate `first` for inline buffers
+    // (it reborrows the whole buffer mutably), but contiguous memory allows
+    // deriving the rest of the pointers from it.
+    // SAFETY: 2 is a valid position, so it's in the same allocation.
+    let last = unsafe { first.add(2) };
+    // SAFETY: Both are filled and don't overlap.
+    unsafe { std::ptr::swap(first, last) };
+
+    // SAFETY: 0 is filled.
+    assert_eq!(unsafe { buffer.take(0) }, "value 2");
+    // SAFETY: 2 is filled.
+    assert_eq!(unsafe { buffer.take(2) }, "value 0!");
+    // SAFETY: 1 is filled.
+    unsafe { buffer.manually_drop(1) };
+}
+
+/// Runs all the checks that apply to any buffer of [`String`]s.
+fn check_buffer<B: Buffer<Element = String>>(make_buffer: impl Fn() -> B) {
+    check_values(make_buffer(), value);
+    check_shifts(make_buffer(), value);
+}
+
+/// Runs all the checks, including the ones for pointers.
+fn check_contiguous_buffer<B: ContiguousMemoryBuffer<Element = String>>(
+    make_buffer: impl Fn() -> B,
+) {
+    check_buffer(&make_buffer);
+    check_aliasing(make_buffer());
+}
+
+#[test]
+fn inline_buffer() {
+    check_contiguous_buffer(InlineBuffer::<String, 8>::new);
+}
+
+#[test]
+fn heap_buffer() {
+    check_contiguous_buffer(HeapBuffer::<String>::new);
+}
+
+#[cfg(feature = "allocator")]
+#[test]
+fn allocator_buffer() {
+    check_contiguous_buffer(crate::base_buffers::AllocatorBuffer::<String>::new);
+}
+
+#[test]
+fn slice_buffer() {
+    let mut storage = [const { MaybeUninit::<String>::uninit() }; 8];
+    check_values(SliceBuffer::from_slice(&mut storage), value);
+    check_shifts(SliceBuffer::from_slice(&mut storage), value);
+    check_aliasing(SliceBuffer::from_slice(&mut storage));
+}
+
+#[test]
+fn zst_buffer() {
+    check_values(ZstBuffer::<()>::new(), |_| ());
+    check_shifts(ZstBuffer::<()>::new(), |_| ());
+    check_values(DefaultBuffer::<()>::default(), |_| ());
+}
+
+#[test]
+fn erased_buffer() {
+    let mut buffer = ErasedBuffer::new(ErasedLayout::of::<String>());
+    // SAFETY: It's bigger than the current capacity.
+    unsafe { buffer.try_grow(2) }.unwrap();
+    for i in 0..2 {
+        // SAFETY: It stores strings and `0..2` are valid and empty.
+        unsafe { buffer.put_as(i, value(i)) };
+    }
+    // SAFETY: It's bigger than the current capacity.
+    unsafe { buffer.try_grow(6) }.unwrap();
+
+    // [0, 1, _, _, _, _] -> [_, _, _, 0, 1, _] -> [_, 0, 1, _, _, _]
+    // SAFETY: `0..2` is filled and `2..5` is empty.
+    unsafe { buffer.shift_right(0..2, 3) };
+    // SAFETY: `3..5` is filled and `1..3` is empty.
+    unsafe { buffer.shift_left(3..5, 2) };
+
+    let mut taken = MaybeUninit::<String>::uninit();
+    // SAFETY: 1 is filled with a string, which is moved into `taken`.
+    unsafe { buffer.take(1, taken.as_mut_ptr().cast()) };
+    // SAFETY: It was just written.
+    assert_eq!(unsafe { taken.assume_init() }, value(0));
+    // SAFETY: 2 is filled.
+    unsafe { buffer.manually_drop_range(2..3) };
+    // SAFETY: Everything is empty.
+    unsafe { buffer.try_shrink(1) }.unwrap();
+}
+
+#[test]
+fn boxed_buffer() {
+    check_buffer(|| Box::new(InlineBuffer::<String, 8>::new()));
+}
+
+#[test]
+fn svo_buffer_while_small() {
+    check_contiguous_buffer(SvoBuffer::<8, HeapBuffer<String>>::new);
+}
+
+#[test]
+fn svo_buffer_while_big() {
+    check_contiguous_buffer(SvoBuffer::<1, HeapBuffer<String>>::new);
+}
+
+#[test]
+fn svo_buffer_migrating_partially_filled() {
+    let mut buffer = SvoBuffer::<4, HeapBuffer<String>>::new();
+    fill(&mut buffer, 2, value);
+    ensure_capacity(&mut buffer, 16);
+
+    // The moved values must be usable in place.
+    // SAFETY: 1 is a valid position.
+    let second = unsafe { buffer.mut_ptr(1) };
+    // SAFETY: It's filled and nothing else is using it.
+    unsafe { (*second).push('!') };
+    for i in 0..2 {
+        // SAFETY: Both positions were filled before growing.
+        let taken = unsafe { buffer.take(i) };
+        assert!(taken.starts_with(&value(i)));
+    }
+}
+
+#[test]
+fn zsto_buffer() {
+    check_contiguous_buffer(ZstoBuffer::<HeapBuffer<String>>::default);
+    check_contiguous_buffer(DefaultBuffer::<String>::default);
+}
+
+#[test]
+fn growth_buffers() {
+    check_buffer(ExponentialGrowthBuffer::<HeapBuffer<String>>::default);
+    check_buffer(AtLeastBuffer::<8, HeapBuffer<String>>::default);
+}
+
+#[test]
+fn either_buffer() {
+    type Either = EitherBuffer<InlineBuffer<String, 8>, HeapBuffer<String>>;
+    check_contiguous_buffer(|| Either::First(InlineBuffer::new()));
+    check_contiguous_buffer(|| Either::Second(HeapBuffer::new()));
+}
+
+#[test]
+fn conditional_buffer() {
+    struct First;
+    impl Selector for First {
+        const SELECT_A: bool = true;
+    }
+    struct Second;
+    impl Selector for Second {
+        const SELECT_A: bool = false;
+    }
+
+    type Conditional<S> = ConditionalBuffer<InlineBuffer<String, 8>, HeapBuffer<String>, S>;
+    check_contiguous_buffer(|| Conditional::<First>::with_first(InlineBuffer::new()));
+    check_contiguous_buffer(|| Conditional::<Second>::with_second(HeapBuffer::new()));
+}
+
+#[cfg(feature = "array")]
+#[test]
+fn array_buffer() {
+    use crate::composites::ArrayBuffer;
+
+    let make = |i| [value(i), value(i + 100)];
+    check_values(ArrayBuffer::<2, HeapBuffer<String>>::default(), make);
+    check_shifts(ArrayBuffer::<2, HeapBuffer<String>>::default(), make);
+}
+
+#[test]
+fn vector_through_the_default_buffer() {
+    let mut vec = Vector::<String, SvoBuffer<2, HeapBuffer<String>>>::new();
+    for i in 0..8 {
+        vec.push(value(i));
+    }
+    vec.mut_index(0).push('!');
+    assert_eq!(*vec.index(0), "value 0!");
+    assert_eq!(vec.pop(), Some(value(7)));
+    vec.truncate(3);
+    assert_eq!(vec.len(), 3);
+}
@@ -0,0 +1,34 @@
+//! Re-exports of the most common types and traits, to import them at once:
+//! ```
+//! use buffers::prelude::*;
+//!
+//! let mut vec = Vector::<u32, SvoBuffer<4, HeapBuffer<u32>>>::new();
+//! vec.push(1);
+//! assert!(vec.capacity() >= 4);
+//! ```
+//!
+//! It has the buffer interface (including all the capability traits), the
+//! base buffers, the common composites and the vectors.
+
+pub use crate::interface::{
+    contiguous_memory::ContiguousMemoryBuffer, copy_value::CopyValueBuffer,
+    growable::GrowableBuffer, indirect_buffer::IndirectBuffer, ptrs::PtrBuffer, refs::RefBuffer,
+    shrinkable::ShrinkableBuffer, sparse::SparseBuffer, stable::StableElementsBuffer, Buffer,
+    BufferCaps, ResizeError,
+};
+
+#[cfg(feature = "allocator")]
+pub use crate::base_buffers::AllocatorBuffer;
+pub use crate::base_buffers::{
+    HeapBuffer, InlineBuffer, SharedSliceBuffer, SliceBuffer, ZstBuffer,
+};
+
+#[cfg(feature = "array")]
+pub use crate::composites::ArrayBuffer;
+pub use crate::composites::{AtLeastBuffer, ExponentialGrowthBuffer, SvoBuffer, ZstoBuffer};
+
+pub use crate::collections::{FixedVector, SmallVector, Vector};
+
+pub use crate::DefaultBuffer;
+#[cfg(feature = "allocator")]
+pub use crate::DefaultBufferIn;
@@ -0,0 +1,19 @@
+//! Synchronization primitives used by the concurrent structures.
+//!
+//! When checking the models (`--cfg loom` with the `loom` feature) they are
+//! the ones of `loom`, so it can explore every interleaving of their
+//! operations. See `g_loom.rs`.
+
+#[cfg(all(loom, feature = "loom"))]
+#[allow(unused_imports)]
+pub(crate) use loom::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Mutex,
+};
+
+#[cfg(not(all(loom, feature = "loom")))]
+#[allow(unused_imports)]
+pub(crate) use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Mutex,
+};
@@ -0,0 +1,105 @@
+//! Models of the concurrent structures, checked with `loom`: every test
+//! explores all the interleavings of the threads it spawns (bounded by the
+//! preemptions `loom` allows), so changes to the atomics or the locking stay
+//! correct.
+//!
+//! Run them with:
+//! ```text
+//! RUSTFLAGS="--cfg loom" cargo test --release --features loom --lib loom_models
+//! ```
+//! Anything outside of these models may not work in that configuration,
+//! since `loom`'s primitives can only be used inside a model.
+
+use std::alloc::Global;
+
+use loom::{sync::Arc, thread};
+
+use crate::{
+    base_buffers::{allocators::TrackingAllocator, AllocatorBuffer, HeapBuffer},
+    collections::{pool::VectorPool, Vector},
+};
+
+/// Vector that allocates through a shared budget.
+type Budgeted = Vector<u64, AllocatorBuffer<u64, &'static TrackingAllocator>>;
+
+/// Makes a budget of `limit` bytes that can be shared with the threads. It's
+/// leaked, which is fine for a test.
+fn budget(limit: usize) -> &'static TrackingAllocator {
+    Box::leak(Box::new(TrackingAllocator::with_limit(Global, limit)))
+}
+
+/// Makes a budgeted vector and tries to make room for `len` values.
+fn reserve(budget: &'static TrackingAllocator, len: usize) -> (Budgeted, bool) {
+    let mut vec = Vector::from_buffer(AllocatorBuffer::with_allocator(budget));
+    let reserved = vec.try_reserve_exact(len).is_ok();
+    (vec, reserved)
+}
+
+#[test]
+fn the_budget_is_never_exceeded() {
+    loom::model(|| {
+        // Room for 3 values at once.
+        let budget = budget(24);
+
+        let other = thread::spawn(move || {
+            let (vec, reserved) = reserve(budget, 2);
+            assert!(budget.used() <= budget.limit());
+            drop(vec);
+            reserved
+        });
+        let (vec, reserved) = reserve(budget, 2);
+        assert!(budget.used() <= budget.limit());
+        drop(vec);
+        let other_reserved = other.join().unwrap();
+
+        // Both fit one after the other, but not at once.
+        assert!(reserved || other_reserved);
+        assert!(budget.peak() <= 24);
+        assert_eq!(budget.used(), 0);
+    });
+}
+
+#[test]
+fn releasing_makes_room_for_others() {
+    loom::model(|| {
+        let budget = budget(16);
+        let (vec, reserved) = reserve(budget, 2);
+        assert!(reserved);
+
+        let other = thread::spawn(move || reserve(budget, 1).1);
+        drop(vec);
+        let other_reserved = other.join().unwrap();
+
+        // It fits unless it was tried before releasing.
+        assert!(other_reserved || budget.peak() == 16);
+        assert_eq!(budget.used(), 0);
+    });
+}
+
+#[test]
+fn pooled_vectors_are_never_lost_nor_shared() {
+    loom::model(|| {
+        let pool = Arc::new(VectorPool::<u32, HeapBuffer<u32>>::new());
+        pool.put_back(Vector::new());
+
+        let other = {
+            let pool = Arc::clone(&pool);
+            thread::spawn(move || {
+                let mut vec = pool.get();
+                vec.push(1);
+                assert_eq!(vec.as_slice(), [1]);
+            })
+        };
+        {
+            let mut vec = pool.get();
+            vec.push(2);
+            assert_eq!(vec.as_slice(), [2]);
+        }
+        other.join().unwrap();
+
+        // One was made if both were lent at the same time.
+        let idle = pool.idle();
+        assert!(idle == 1 || idle == 2);
+        assert!(pool.get().is_empty());
+    });
+}
@@ -1,5 +1,4 @@
 #![feature(dropck_eyepatch)]
-#![feature(maybe_uninit_uninit_array)]
 #![cfg_attr(feature = "allocator", feature(allocator_api))]
 #![cfg_attr(feature = "array", feature(maybe_uninit_array_assume_init))]
 #![deny(unsafe_op_in_unsafe_fn)]
@@ -30,13 +29,41 @@ pub mod never;
 #[path = "c_narrow_ref.rs"]
 pub mod narrow_ref;
 
+#[path = "e_prelude.rs"]
+pub mod prelude;
+
+#[path = "f_sync.rs"]
+mod sync;
+
+pub use collections::Vector;
+
+#[cfg(test)]
+#[path = "d_soundness.rs"]
+mod soundness;
+
+#[cfg(all(test, loom, feature = "loom"))]
+#[path = "g_loom.rs"]
+mod loom_models;
+
 /// Default buffer composition.
 ///
 /// It's meant to be used as a sensible default for most cases. Its composition
 /// may change, specially when improving performance. If it doesn't comfort your
 /// use case, make one which is! (that's what this library is about)
+///
+/// Note that it keeps some elements inline (to prevent allocations on small
+/// vectors), so it's quite bigger than a `Vec`. If size matters, use
+/// [`HeapBuffer`] directly, which has the same layout as `Vec`.
 pub type DefaultBuffer<T> = ZstoBuffer<SvoBuffer<256, HeapBuffer<T>>>;
 
+/// Same composition as [`DefaultBuffer`], but allocating with the given
+/// allocator. Make vectors with it using
+/// [`collections::Vector::new_in`] or [`collections::Vector::with_capacity_in`].
+///
+/// It requires the `allocator` feature.
+#[cfg(feature = "allocator")]
+pub type DefaultBufferIn<T, A> = ZstoBuffer<SvoBuffer<256, base_buffers::AllocatorBuffer<T, A>>>;
+
 // Force running README.md example code, so we can ensure it actually works :)
 #[doc = include_str!("../README.md")]
 #[cfg(doctest)]
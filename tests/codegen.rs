@@ -0,0 +1,91 @@
+//! Codegen checks: builds a small crate that pushes into vectors (in release
+//! mode) and looks at the assembly, to make sure the composite forwarders are
+//! inlined and only the cold growing path is left out of line.
+//!
+//! The exact instructions change between compiler versions, so it checks
+//! which functions `push` calls instead of comparing whole snapshots.
+#![cfg(target_arch = "x86_64")]
+
+use std::{env, fs, path::Path, process::Command};
+
+const FIXTURE: &str = r#"
+use buffers::{base_buffers::HeapBuffer, collections::Vector, DefaultBuffer};
+
+#[no_mangle]
+pub fn push_default(vec: &mut Vector<u32, DefaultBuffer<u32>>, value: u32) {
+    vec.push(value);
+}
+
+#[no_mangle]
+pub fn push_heap(vec: &mut Vector<u32, HeapBuffer<u32>>, value: u32) {
+    vec.push(value);
+}
+"#;
+
+/// Builds the fixture and gives its assembly.
+fn fixture_asm() -> String {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("codegen");
+    fs::create_dir_all(&dir).unwrap();
+    let manifest = format!(
+        "[package]\nname = \"codegen\"\nversion = \"0.0.0\"\nedition = \"2021\"\n\n\
+         [lib]\npath = \"lib.rs\"\n\n\
+         [dependencies]\nbuffers = {{ path = {:?} }}\n\n\
+         [profile.release]\ncodegen-units = 1\n\n\
+         [workspace]\n",
+        env!("CARGO_MANIFEST_DIR"),
+    );
+    fs::write(dir.join("Cargo.toml"), manifest).unwrap();
+    fs::write(dir.join("lib.rs"), FIXTURE).unwrap();
+
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+    let status = Command::new(cargo)
+        .current_dir(&dir)
+        .args(["rustc", "--quiet", "--offline", "--release", "--lib"])
+        .args(["--", "--emit", "asm"])
+        .env("CARGO_TARGET_DIR", dir.join("target"))
+        .env_remove("RUSTFLAGS")
+        .status()
+        .unwrap();
+    assert!(status.success(), "the fixture didn't build");
+
+    let deps = dir.join("target/release/deps");
+    let asm = fs::read_dir(deps)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.extension().is_some_and(|ext| ext == "s"))
+        .expect("no assembly was emitted");
+    fs::read_to_string(asm).unwrap()
+}
+
+/// Gives the targets of the calls made by `symbol`.
+fn calls<'a>(asm: &'a str, symbol: &str) -> Vec<&'a str> {
+    let label = format!("{symbol}:");
+    asm.lines()
+        .skip_while(|line| line.trim() != label)
+        .take_while(|line| !line.trim().starts_with(".cfi_endproc"))
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            parts
+                .next()
+                .filter(|op| op.starts_with("call"))
+                .and(parts.next())
+        })
+        .collect()
+}
+
+#[test]
+fn push_only_calls_the_cold_path() {
+    let asm = fixture_asm();
+    for symbol in ["push_default", "push_heap"] {
+        let calls = calls(&asm, symbol);
+        assert!(
+            calls.iter().any(|call| call.contains("grow_for_push")),
+            "{symbol} doesn't call the out of line growing path: {calls:?}"
+        );
+        let others: Vec<_> = calls
+            .iter()
+            .filter(|call| !call.contains("grow_for_push") && !call.contains("failed"))
+            .collect();
+        assert!(others.is_empty(), "{symbol} calls {others:?}");
+    }
+}